@@ -0,0 +1,48 @@
+import { Controller } from '@hotwired/stimulus'
+import { connectStreamSource, disconnectStreamSource } from '@hotwired/turbo'
+
+// Applies the turbo streams the server pushes over a WebSocket, reconnecting when it drops.
+export default class LiveController extends Controller {
+  declare readonly urlValue: string
+
+  static values = {
+    url: String
+  }
+
+  private socket?: WebSocket
+  private retry?: number
+
+  connect() {
+    this.open()
+  }
+
+  disconnect() {
+    window.clearTimeout(this.retry)
+    this.close()
+  }
+
+  private open() {
+    const url = new URL(this.urlValue, window.location.href)
+    url.protocol = url.protocol === 'https:' ? 'wss:' : 'ws:'
+
+    const socket = new WebSocket(url)
+    socket.addEventListener('close', () => {
+      // Sockets closed by `close` are not reopened.
+      if (this.socket !== socket) return
+
+      this.close()
+      this.retry = window.setTimeout(() => this.open(), 5000)
+    })
+    connectStreamSource(socket)
+    this.socket = socket
+  }
+
+  private close() {
+    const socket = this.socket
+    if (socket) {
+      this.socket = undefined
+      disconnectStreamSource(socket)
+      socket.close()
+    }
+  }
+}
@@ -0,0 +1,54 @@
+import { Controller } from '@hotwired/stimulus'
+
+export default class SortableController extends Controller<HTMLElement> {
+  declare readonly urlValue: string
+
+  static values = {
+    url: String
+  }
+
+  private dragged: HTMLElement | null = null
+
+  start(event: DragEvent) {
+    this.dragged = event.currentTarget as HTMLElement
+    this.dragged.classList.add("opacity-50")
+    event.dataTransfer?.setData("text/plain", this.dragged.dataset.sortableId ?? "")
+  }
+
+  over(event: DragEvent) {
+    event.preventDefault()
+
+    const target = event.currentTarget as HTMLElement
+    if (this.dragged === null || target === this.dragged) return
+
+    const { top, height } = target.getBoundingClientRect()
+    const after = event.clientY > top + height / 2
+    target.parentElement?.insertBefore(this.dragged, after ? target.nextSibling : target)
+  }
+
+  drop(event: DragEvent) {
+    event.preventDefault()
+  }
+
+  async end() {
+    if (this.dragged === null) return
+
+    this.dragged.classList.remove("opacity-50")
+    this.dragged = null
+
+    const ids = Array.from(this.element.querySelectorAll<HTMLElement>("[data-sortable-id]"))
+      .map((element) => element.dataset.sortableId)
+      .join(",")
+    const token = document.querySelector<HTMLMetaElement>('meta[name="csrf-token"]')?.content ?? ""
+
+    await fetch(this.urlValue, {
+      method: "PUT",
+      headers: {
+        "Accept": "application/json",
+        "Content-Type": "application/x-www-form-urlencoded",
+        "X-CSRF-Token": token
+      },
+      body: new URLSearchParams({ ids })
+    })
+  }
+}
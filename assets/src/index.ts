@@ -5,6 +5,8 @@ import { Application } from "@hotwired/stimulus";
 import FormatterController from "./controllers/formatter_controller.js";
 import ModalController from "./controllers/modal_controller.js";
 import CurrencyInputController from "./controllers/currency_input_controller.js";
+import SortableController from "./controllers/sortable_controller.js";
+import LiveController from "./controllers/live_controller.js";
 
 declare global {
   interface Window {
@@ -17,3 +19,5 @@ window.Stimulus = Application.start();
 window.Stimulus.register("modal", ModalController);
 window.Stimulus.register("formatter", FormatterController);
 window.Stimulus.register("currency-input", CurrencyInputController);
+window.Stimulus.register("sortable", SortableController);
+window.Stimulus.register("live", LiveController);
@@ -0,0 +1,13 @@
+CREATE TABLE transactions (
+    id uuid NOT NULL DEFAULT gen_random_uuid() PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users(id),
+    account_id uuid NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    envelope_id uuid REFERENCES envelopes(id) ON DELETE SET NULL,
+    amount numeric NOT NULL,
+    description text NOT NULL,
+    occurred_on date NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT now()
+);
+
+CREATE INDEX transactions_account_id_idx ON transactions (account_id);
+CREATE INDEX transactions_envelope_id_idx ON transactions (envelope_id);
@@ -0,0 +1 @@
+ALTER TABLE transactions ALTER COLUMN account_id DROP NOT NULL;
@@ -0,0 +1,9 @@
+CREATE TABLE envelope_transfers (
+    id uuid NOT NULL DEFAULT gen_random_uuid() PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users(id),
+    from_envelope_id uuid NOT NULL REFERENCES envelopes(id) ON DELETE CASCADE,
+    to_envelope_id uuid REFERENCES envelopes(id) ON DELETE CASCADE,
+    amount numeric NOT NULL CHECK (amount > 0),
+    created_at timestamp with time zone NOT NULL DEFAULT now()
+);
+CREATE INDEX envelope_transfers_user_id_created_at_idx ON envelope_transfers (user_id, created_at);
@@ -0,0 +1,12 @@
+CREATE TYPE "EnvelopePolicy" AS ENUM ('Append', 'Replace', 'CreateNew');
+
+ALTER TABLE goals
+    ADD COLUMN envelope_id uuid REFERENCES envelopes(id) ON DELETE SET NULL,
+    ADD COLUMN envelope_policy "EnvelopePolicy" NOT NULL DEFAULT 'Append';
+
+UPDATE goals SET envelope_id = (
+    SELECT envelopes.id FROM envelopes
+    WHERE envelopes.user_id = goals.user_id AND envelopes.name = goals.name
+    ORDER BY envelopes.amount DESC
+    LIMIT 1
+);
@@ -0,0 +1,31 @@
+CREATE TYPE "IncomeRecurrence" AS ENUM (
+    'Once',
+    'Weekly',
+    'Biweekly',
+    'SemiMonthly',
+    'Monthly',
+    'Quarterly',
+    'Yearly'
+);
+
+CREATE TABLE income_sources (
+    id uuid NOT NULL DEFAULT gen_random_uuid() PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users(id),
+    name text NOT NULL,
+    amount numeric NOT NULL,
+    recurrence "IncomeRecurrence" NOT NULL,
+    next_expected_on date NOT NULL
+);
+CREATE INDEX income_sources_user_id_idx ON income_sources (user_id);
+
+INSERT INTO income_sources (user_id, name, amount, recurrence, next_expected_on)
+SELECT
+    id,
+    'Monthly income',
+    (preferences ->> 'monthly_income')::numeric,
+    'Monthly',
+    (date_trunc('month', now()) + interval '1 month')::date
+FROM users
+WHERE (preferences ->> 'monthly_income')::numeric > 0;
+
+UPDATE users SET preferences = preferences - 'monthly_income' WHERE preferences ? 'monthly_income';
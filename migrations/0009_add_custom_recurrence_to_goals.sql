@@ -0,0 +1,9 @@
+ALTER TYPE "Recurrence" ADD VALUE 'EveryDays';
+ALTER TYPE "Recurrence" ADD VALUE 'EveryWeeks';
+ALTER TYPE "Recurrence" ADD VALUE 'EveryMonths';
+ALTER TYPE "Recurrence" ADD VALUE 'SemiMonthly';
+ALTER TYPE "Recurrence" ADD VALUE 'LastDayOfMonth';
+
+ALTER TABLE goals
+    ADD COLUMN recurrence_interval integer CHECK (recurrence_interval > 0),
+    ADD COLUMN recurrence_days integer[] CHECK (cardinality(recurrence_days) = 2);
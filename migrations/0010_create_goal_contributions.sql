@@ -0,0 +1,12 @@
+CREATE TYPE "ContributionSource" AS ENUM ('Accumulation', 'Acceleration', 'Manual', 'Reset');
+
+CREATE TABLE goal_contributions (
+    id uuid NOT NULL DEFAULT gen_random_uuid() PRIMARY KEY,
+    goal_id uuid NOT NULL REFERENCES goals(id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users(id),
+    source "ContributionSource" NOT NULL,
+    amount numeric NOT NULL,
+    accumulated_amount numeric NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT clock_timestamp()
+);
+CREATE INDEX goal_contributions_goal_id_created_at_idx ON goal_contributions (goal_id, created_at);
@@ -0,0 +1,12 @@
+ALTER TABLE goals
+    ADD COLUMN priority integer NOT NULL DEFAULT 0,
+    ADD COLUMN funding_weight numeric NOT NULL DEFAULT 1 CHECK (funding_weight > 0);
+
+UPDATE goals SET priority = ranked.priority
+FROM (
+    SELECT id, row_number() OVER (PARTITION BY user_id ORDER BY target_date, target) - 1 AS priority
+    FROM goals
+) AS ranked
+WHERE goals.id = ranked.id;
+
+CREATE INDEX goals_user_id_priority_idx ON goals (user_id, priority);
@@ -0,0 +1,10 @@
+CREATE TABLE dashboard_snapshots (
+    id uuid NOT NULL DEFAULT gen_random_uuid() PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    snapshot_date date NOT NULL,
+    per_diem numeric NOT NULL,
+    remaining_total numeric NOT NULL,
+    goals_accumulated_per_day numeric NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT clock_timestamp(),
+    UNIQUE (user_id, snapshot_date)
+);
@@ -0,0 +1,12 @@
+CREATE TABLE account_balances (
+    id uuid NOT NULL DEFAULT gen_random_uuid() PRIMARY KEY,
+    account_id uuid NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users(id),
+    amount numeric NOT NULL,
+    debt boolean NOT NULL,
+    recorded_at timestamp with time zone NOT NULL DEFAULT clock_timestamp()
+);
+CREATE INDEX account_balances_account_id_recorded_at_idx ON account_balances (account_id, recorded_at);
+
+INSERT INTO account_balances (account_id, user_id, amount, debt)
+SELECT id, user_id, amount, debt FROM accounts;
@@ -0,0 +1,2 @@
+ALTER TABLE accounts ADD COLUMN apr numeric CHECK (apr >= 0);
+ALTER TABLE accounts ADD COLUMN minimum_payment numeric CHECK (minimum_payment >= 0);
@@ -0,0 +1,2 @@
+ALTER TABLE accounts ADD COLUMN account_number text;
+CREATE UNIQUE INDEX accounts_user_id_account_number_idx ON accounts (user_id, account_number);
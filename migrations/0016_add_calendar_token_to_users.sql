@@ -0,0 +1,2 @@
+ALTER TABLE users ADD COLUMN calendar_token text;
+CREATE UNIQUE INDEX users_calendar_token_idx ON users (calendar_token);
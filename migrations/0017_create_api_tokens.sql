@@ -0,0 +1,12 @@
+CREATE TYPE "TokenScope" AS ENUM ('Read', 'Write');
+
+CREATE TABLE api_tokens (
+    id uuid NOT NULL DEFAULT gen_random_uuid() PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users(id),
+    name text NOT NULL,
+    scope "TokenScope" NOT NULL,
+    token_hash text NOT NULL,
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    last_used_at timestamp with time zone
+);
+CREATE UNIQUE INDEX api_tokens_token_hash_idx ON api_tokens (token_hash);
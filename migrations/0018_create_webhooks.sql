@@ -0,0 +1,30 @@
+CREATE TYPE "WebhookDeliveryStatus" AS ENUM ('Pending', 'Delivered', 'Failed');
+
+CREATE TABLE webhook_endpoints (
+    id uuid NOT NULL DEFAULT gen_random_uuid() PRIMARY KEY,
+    user_id uuid NOT NULL REFERENCES users(id),
+    url text NOT NULL,
+    secret text NOT NULL,
+    events text[] NOT NULL,
+    per_diem_threshold numeric,
+    per_diem_below boolean NOT NULL DEFAULT false,
+    created_at timestamp with time zone NOT NULL DEFAULT now()
+);
+CREATE INDEX webhook_endpoints_user_id_idx ON webhook_endpoints (user_id);
+
+CREATE TABLE webhook_deliveries (
+    id uuid NOT NULL DEFAULT gen_random_uuid() PRIMARY KEY,
+    endpoint_id uuid NOT NULL REFERENCES webhook_endpoints(id) ON DELETE CASCADE,
+    user_id uuid NOT NULL REFERENCES users(id),
+    event text NOT NULL,
+    payload jsonb NOT NULL,
+    status "WebhookDeliveryStatus" NOT NULL DEFAULT 'Pending',
+    attempts integer NOT NULL DEFAULT 0,
+    next_attempt_at timestamp with time zone NOT NULL DEFAULT now(),
+    response_status integer,
+    last_error text,
+    created_at timestamp with time zone NOT NULL DEFAULT now(),
+    delivered_at timestamp with time zone
+);
+CREATE INDEX webhook_deliveries_pending_idx ON webhook_deliveries (next_attempt_at) WHERE status = 'Pending';
+CREATE INDEX webhook_deliveries_user_id_created_at_idx ON webhook_deliveries (user_id, created_at);
@@ -0,0 +1,8 @@
+ALTER TABLE users ADD COLUMN issuer text;
+-- Every user so far signed in with Google.
+UPDATE users SET issuer = 'https://accounts.google.com';
+ALTER TABLE users ALTER COLUMN issuer SET NOT NULL;
+ALTER TABLE users DROP CONSTRAINT users_subject_key;
+ALTER TABLE users ADD CONSTRAINT users_issuer_subject_key UNIQUE (issuer, subject);
+-- Another issuer may vouch for the same address; it does not make the two the same user.
+ALTER TABLE users DROP CONSTRAINT users_email_key;
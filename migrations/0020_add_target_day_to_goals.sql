@@ -0,0 +1,3 @@
+-- The day of the month month-based recurrences aim for, so a goal on the 31st comes back to
+-- the 31st after a short month. Existing goals aim for their current target date's day.
+ALTER TABLE goals ADD COLUMN target_day integer CHECK (target_day BETWEEN 1 AND 31);
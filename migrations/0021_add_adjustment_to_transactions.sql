@@ -0,0 +1,6 @@
+-- Marks ledger entries that set a balance rather than record money spent or received, so
+-- spending totals can leave them out. Earlier account adjustments are marked by description.
+ALTER TABLE transactions ADD COLUMN adjustment boolean DEFAULT false NOT NULL;
+
+UPDATE transactions SET adjustment = true
+WHERE description = 'Balance adjustment' AND envelope_id IS NULL;
@@ -19,6 +19,44 @@ SET xmloption = content;
 SET client_min_messages = warning;
 SET row_security = off;
 
+--
+-- Name: ContributionSource; Type: TYPE; Schema: public; Owner: -
+--
+
+CREATE TYPE public."ContributionSource" AS ENUM (
+    'Accumulation',
+    'Acceleration',
+    'Manual',
+    'Reset'
+);
+
+
+--
+-- Name: EnvelopePolicy; Type: TYPE; Schema: public; Owner: -
+--
+
+CREATE TYPE public."EnvelopePolicy" AS ENUM (
+    'Append',
+    'Replace',
+    'CreateNew'
+);
+
+
+--
+-- Name: IncomeRecurrence; Type: TYPE; Schema: public; Owner: -
+--
+
+CREATE TYPE public."IncomeRecurrence" AS ENUM (
+    'Once',
+    'Weekly',
+    'Biweekly',
+    'SemiMonthly',
+    'Monthly',
+    'Quarterly',
+    'Yearly'
+);
+
+
 --
 -- Name: Recurrence; Type: TYPE; Schema: public; Owner: -
 --
@@ -29,7 +67,33 @@ CREATE TYPE public."Recurrence" AS ENUM (
     'Monthly',
     'Quarterly',
     'Yearly',
-    'Never'
+    'Never',
+    'EveryDays',
+    'EveryWeeks',
+    'EveryMonths',
+    'SemiMonthly',
+    'LastDayOfMonth'
+);
+
+
+--
+-- Name: TokenScope; Type: TYPE; Schema: public; Owner: -
+--
+
+CREATE TYPE public."TokenScope" AS ENUM (
+    'Read',
+    'Write'
+);
+
+
+--
+-- Name: WebhookDeliveryStatus; Type: TYPE; Schema: public; Owner: -
+--
+
+CREATE TYPE public."WebhookDeliveryStatus" AS ENUM (
+    'Pending',
+    'Delivered',
+    'Failed'
 );
 
 
@@ -37,6 +101,20 @@ SET default_tablespace = '';
 
 SET default_table_access_method = heap;
 
+--
+-- Name: account_balances; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.account_balances (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    account_id uuid NOT NULL,
+    user_id uuid NOT NULL,
+    amount numeric NOT NULL,
+    debt boolean NOT NULL,
+    recorded_at timestamp with time zone DEFAULT clock_timestamp() NOT NULL
+);
+
+
 --
 -- Name: accounts; Type: TABLE; Schema: public; Owner: -
 --
@@ -46,7 +124,57 @@ CREATE TABLE public.accounts (
     amount numeric NOT NULL,
     debt boolean NOT NULL,
     id uuid DEFAULT gen_random_uuid() CONSTRAINT accounts__id_not_null NOT NULL,
-    user_id uuid CONSTRAINT accounts__user_id_not_null NOT NULL
+    user_id uuid CONSTRAINT accounts__user_id_not_null NOT NULL,
+    apr numeric,
+    minimum_payment numeric,
+    account_number text,
+    CONSTRAINT accounts_apr_check CHECK ((apr >= (0)::numeric)),
+    CONSTRAINT accounts_minimum_payment_check CHECK ((minimum_payment >= (0)::numeric))
+);
+
+
+--
+-- Name: api_tokens; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.api_tokens (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    user_id uuid NOT NULL,
+    name text NOT NULL,
+    scope public."TokenScope" NOT NULL,
+    token_hash text NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    last_used_at timestamp with time zone
+);
+
+
+--
+-- Name: dashboard_snapshots; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.dashboard_snapshots (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    user_id uuid NOT NULL,
+    snapshot_date date NOT NULL,
+    per_diem numeric NOT NULL,
+    remaining_total numeric NOT NULL,
+    goals_accumulated_per_day numeric NOT NULL,
+    created_at timestamp with time zone DEFAULT clock_timestamp() NOT NULL
+);
+
+
+--
+-- Name: envelope_transfers; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.envelope_transfers (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    user_id uuid NOT NULL,
+    from_envelope_id uuid NOT NULL,
+    to_envelope_id uuid,
+    amount numeric NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    CONSTRAINT envelope_transfers_amount_check CHECK ((amount > (0)::numeric))
 );
 
 
@@ -62,6 +190,21 @@ CREATE TABLE public.envelopes (
 );
 
 
+--
+-- Name: goal_contributions; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.goal_contributions (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    goal_id uuid NOT NULL,
+    user_id uuid NOT NULL,
+    source public."ContributionSource" NOT NULL,
+    amount numeric NOT NULL,
+    accumulated_amount numeric NOT NULL,
+    created_at timestamp with time zone DEFAULT clock_timestamp() NOT NULL
+);
+
+
 --
 -- Name: goals; Type: TABLE; Schema: public; Owner: -
 --
@@ -74,7 +217,32 @@ CREATE TABLE public.goals (
     accumulated_amount numeric NOT NULL,
     start_date timestamp with time zone,
     id uuid DEFAULT gen_random_uuid() CONSTRAINT goals__id_not_null NOT NULL,
-    user_id uuid CONSTRAINT goals__user_id_not_null NOT NULL
+    user_id uuid CONSTRAINT goals__user_id_not_null NOT NULL,
+    envelope_id uuid,
+    envelope_policy public."EnvelopePolicy" DEFAULT 'Append'::public."EnvelopePolicy" NOT NULL,
+    recurrence_interval integer,
+    recurrence_days integer[],
+    priority integer DEFAULT 0 NOT NULL,
+    funding_weight numeric DEFAULT 1 NOT NULL,
+    target_day integer,
+    CONSTRAINT goals_funding_weight_check CHECK ((funding_weight > (0)::numeric)),
+    CONSTRAINT goals_recurrence_days_check CHECK ((cardinality(recurrence_days) = 2)),
+    CONSTRAINT goals_recurrence_interval_check CHECK ((recurrence_interval > 0)),
+    CONSTRAINT goals_target_day_check CHECK (((target_day >= 1) AND (target_day <= 31)))
+);
+
+
+--
+-- Name: income_sources; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.income_sources (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    user_id uuid NOT NULL,
+    name text NOT NULL,
+    amount numeric NOT NULL,
+    recurrence public."IncomeRecurrence" NOT NULL,
+    next_expected_on date NOT NULL
 );
 
 
@@ -90,6 +258,23 @@ CREATE TABLE public.sessions (
 );
 
 
+--
+-- Name: transactions; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.transactions (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    user_id uuid NOT NULL,
+    account_id uuid,
+    envelope_id uuid,
+    amount numeric NOT NULL,
+    description text NOT NULL,
+    occurred_on date NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    adjustment boolean DEFAULT false NOT NULL
+);
+
+
 --
 -- Name: users; Type: TABLE; Schema: public; Owner: -
 --
@@ -98,10 +283,56 @@ CREATE TABLE public.users (
     subject text NOT NULL,
     email text NOT NULL,
     preferences jsonb,
-    id uuid DEFAULT gen_random_uuid() CONSTRAINT users__id_not_null NOT NULL
+    id uuid DEFAULT gen_random_uuid() CONSTRAINT users__id_not_null NOT NULL,
+    calendar_token text,
+    issuer text NOT NULL
 );
 
 
+--
+-- Name: webhook_deliveries; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.webhook_deliveries (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    endpoint_id uuid NOT NULL,
+    user_id uuid NOT NULL,
+    event text NOT NULL,
+    payload jsonb NOT NULL,
+    status public."WebhookDeliveryStatus" DEFAULT 'Pending'::public."WebhookDeliveryStatus" NOT NULL,
+    attempts integer DEFAULT 0 NOT NULL,
+    next_attempt_at timestamp with time zone DEFAULT now() NOT NULL,
+    response_status integer,
+    last_error text,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    delivered_at timestamp with time zone
+);
+
+
+--
+-- Name: webhook_endpoints; Type: TABLE; Schema: public; Owner: -
+--
+
+CREATE TABLE public.webhook_endpoints (
+    id uuid DEFAULT gen_random_uuid() NOT NULL,
+    user_id uuid NOT NULL,
+    url text NOT NULL,
+    secret text NOT NULL,
+    events text[] NOT NULL,
+    per_diem_threshold numeric,
+    per_diem_below boolean DEFAULT false NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+--
+-- Name: account_balances account_balances_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.account_balances
+    ADD CONSTRAINT account_balances_pkey PRIMARY KEY (id);
+
+
 --
 -- Name: accounts accounts_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -110,6 +341,38 @@ ALTER TABLE ONLY public.accounts
     ADD CONSTRAINT accounts_pkey PRIMARY KEY (id);
 
 
+--
+-- Name: api_tokens api_tokens_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.api_tokens
+    ADD CONSTRAINT api_tokens_pkey PRIMARY KEY (id);
+
+
+--
+-- Name: dashboard_snapshots dashboard_snapshots_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.dashboard_snapshots
+    ADD CONSTRAINT dashboard_snapshots_pkey PRIMARY KEY (id);
+
+
+--
+-- Name: dashboard_snapshots dashboard_snapshots_user_id_snapshot_date_key; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.dashboard_snapshots
+    ADD CONSTRAINT dashboard_snapshots_user_id_snapshot_date_key UNIQUE (user_id, snapshot_date);
+
+
+--
+-- Name: envelope_transfers envelope_transfers_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.envelope_transfers
+    ADD CONSTRAINT envelope_transfers_pkey PRIMARY KEY (id);
+
+
 --
 -- Name: envelopes envelopes_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -118,6 +381,14 @@ ALTER TABLE ONLY public.envelopes
     ADD CONSTRAINT envelopes_pkey PRIMARY KEY (id);
 
 
+--
+-- Name: goal_contributions goal_contributions_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.goal_contributions
+    ADD CONSTRAINT goal_contributions_pkey PRIMARY KEY (id);
+
+
 --
 -- Name: goals goals_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -126,6 +397,14 @@ ALTER TABLE ONLY public.goals
     ADD CONSTRAINT goals_pkey PRIMARY KEY (id);
 
 
+--
+-- Name: income_sources income_sources_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.income_sources
+    ADD CONSTRAINT income_sources_pkey PRIMARY KEY (id);
+
+
 --
 -- Name: sessions sessions_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
@@ -135,11 +414,19 @@ ALTER TABLE ONLY public.sessions
 
 
 --
--- Name: users users_email_key; Type: CONSTRAINT; Schema: public; Owner: -
+-- Name: transactions transactions_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.transactions
+    ADD CONSTRAINT transactions_pkey PRIMARY KEY (id);
+
+
+--
+-- Name: users users_issuer_subject_key; Type: CONSTRAINT; Schema: public; Owner: -
 --
 
 ALTER TABLE ONLY public.users
-    ADD CONSTRAINT users_email_key UNIQUE (email);
+    ADD CONSTRAINT users_issuer_subject_key UNIQUE (issuer, subject);
 
 
 --
@@ -151,11 +438,126 @@ ALTER TABLE ONLY public.users
 
 
 --
--- Name: users users_subject_key; Type: CONSTRAINT; Schema: public; Owner: -
+-- Name: webhook_deliveries webhook_deliveries_pkey; Type: CONSTRAINT; Schema: public; Owner: -
 --
 
-ALTER TABLE ONLY public.users
-    ADD CONSTRAINT users_subject_key UNIQUE (subject);
+ALTER TABLE ONLY public.webhook_deliveries
+    ADD CONSTRAINT webhook_deliveries_pkey PRIMARY KEY (id);
+
+
+--
+-- Name: webhook_endpoints webhook_endpoints_pkey; Type: CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.webhook_endpoints
+    ADD CONSTRAINT webhook_endpoints_pkey PRIMARY KEY (id);
+
+
+--
+-- Name: account_balances_account_id_recorded_at_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX account_balances_account_id_recorded_at_idx ON public.account_balances USING btree (account_id, recorded_at);
+
+
+--
+-- Name: accounts_user_id_account_number_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE UNIQUE INDEX accounts_user_id_account_number_idx ON public.accounts USING btree (user_id, account_number);
+
+
+--
+-- Name: api_tokens_token_hash_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE UNIQUE INDEX api_tokens_token_hash_idx ON public.api_tokens USING btree (token_hash);
+
+
+--
+-- Name: envelope_transfers_user_id_created_at_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX envelope_transfers_user_id_created_at_idx ON public.envelope_transfers USING btree (user_id, created_at);
+
+
+--
+-- Name: goal_contributions_goal_id_created_at_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX goal_contributions_goal_id_created_at_idx ON public.goal_contributions USING btree (goal_id, created_at);
+
+
+--
+-- Name: goals_user_id_priority_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX goals_user_id_priority_idx ON public.goals USING btree (user_id, priority);
+
+
+--
+-- Name: income_sources_user_id_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX income_sources_user_id_idx ON public.income_sources USING btree (user_id);
+
+
+--
+-- Name: transactions_account_id_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX transactions_account_id_idx ON public.transactions USING btree (account_id);
+
+
+--
+-- Name: transactions_envelope_id_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX transactions_envelope_id_idx ON public.transactions USING btree (envelope_id);
+
+
+--
+-- Name: users_calendar_token_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE UNIQUE INDEX users_calendar_token_idx ON public.users USING btree (calendar_token);
+
+
+--
+-- Name: webhook_deliveries_pending_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX webhook_deliveries_pending_idx ON public.webhook_deliveries USING btree (next_attempt_at) WHERE (status = 'Pending'::public."WebhookDeliveryStatus");
+
+
+--
+-- Name: webhook_deliveries_user_id_created_at_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX webhook_deliveries_user_id_created_at_idx ON public.webhook_deliveries USING btree (user_id, created_at);
+
+
+--
+-- Name: webhook_endpoints_user_id_idx; Type: INDEX; Schema: public; Owner: -
+--
+
+CREATE INDEX webhook_endpoints_user_id_idx ON public.webhook_endpoints USING btree (user_id);
+
+
+--
+-- Name: account_balances account_balances_account_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.account_balances
+    ADD CONSTRAINT account_balances_account_id_fkey FOREIGN KEY (account_id) REFERENCES public.accounts(id) ON DELETE CASCADE;
+
+
+--
+-- Name: account_balances account_balances_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.account_balances
+    ADD CONSTRAINT account_balances_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
 
 
 --
@@ -166,6 +568,46 @@ ALTER TABLE ONLY public.accounts
     ADD CONSTRAINT accounts_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
 
 
+--
+-- Name: api_tokens api_tokens_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.api_tokens
+    ADD CONSTRAINT api_tokens_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
+
+
+--
+-- Name: dashboard_snapshots dashboard_snapshots_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.dashboard_snapshots
+    ADD CONSTRAINT dashboard_snapshots_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id) ON DELETE CASCADE;
+
+
+--
+-- Name: envelope_transfers envelope_transfers_from_envelope_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.envelope_transfers
+    ADD CONSTRAINT envelope_transfers_from_envelope_id_fkey FOREIGN KEY (from_envelope_id) REFERENCES public.envelopes(id) ON DELETE CASCADE;
+
+
+--
+-- Name: envelope_transfers envelope_transfers_to_envelope_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.envelope_transfers
+    ADD CONSTRAINT envelope_transfers_to_envelope_id_fkey FOREIGN KEY (to_envelope_id) REFERENCES public.envelopes(id) ON DELETE CASCADE;
+
+
+--
+-- Name: envelope_transfers envelope_transfers_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.envelope_transfers
+    ADD CONSTRAINT envelope_transfers_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
+
+
 --
 -- Name: envelopes envelopes_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -174,6 +616,30 @@ ALTER TABLE ONLY public.envelopes
     ADD CONSTRAINT envelopes_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
 
 
+--
+-- Name: goal_contributions goal_contributions_goal_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.goal_contributions
+    ADD CONSTRAINT goal_contributions_goal_id_fkey FOREIGN KEY (goal_id) REFERENCES public.goals(id) ON DELETE CASCADE;
+
+
+--
+-- Name: goal_contributions goal_contributions_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.goal_contributions
+    ADD CONSTRAINT goal_contributions_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
+
+
+--
+-- Name: goals goals_envelope_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.goals
+    ADD CONSTRAINT goals_envelope_id_fkey FOREIGN KEY (envelope_id) REFERENCES public.envelopes(id) ON DELETE SET NULL;
+
+
 --
 -- Name: goals goals_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -182,6 +648,14 @@ ALTER TABLE ONLY public.goals
     ADD CONSTRAINT goals_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
 
 
+--
+-- Name: income_sources income_sources_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.income_sources
+    ADD CONSTRAINT income_sources_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
+
+
 --
 -- Name: sessions sessions_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
 --
@@ -190,6 +664,54 @@ ALTER TABLE ONLY public.sessions
     ADD CONSTRAINT sessions_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
 
 
+--
+-- Name: transactions transactions_account_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.transactions
+    ADD CONSTRAINT transactions_account_id_fkey FOREIGN KEY (account_id) REFERENCES public.accounts(id) ON DELETE CASCADE;
+
+
+--
+-- Name: transactions transactions_envelope_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.transactions
+    ADD CONSTRAINT transactions_envelope_id_fkey FOREIGN KEY (envelope_id) REFERENCES public.envelopes(id) ON DELETE SET NULL;
+
+
+--
+-- Name: transactions transactions_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.transactions
+    ADD CONSTRAINT transactions_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
+
+
+--
+-- Name: webhook_deliveries webhook_deliveries_endpoint_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.webhook_deliveries
+    ADD CONSTRAINT webhook_deliveries_endpoint_id_fkey FOREIGN KEY (endpoint_id) REFERENCES public.webhook_endpoints(id) ON DELETE CASCADE;
+
+
+--
+-- Name: webhook_deliveries webhook_deliveries_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.webhook_deliveries
+    ADD CONSTRAINT webhook_deliveries_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
+
+
+--
+-- Name: webhook_endpoints webhook_endpoints_user_id_fkey; Type: FK CONSTRAINT; Schema: public; Owner: -
+--
+
+ALTER TABLE ONLY public.webhook_endpoints
+    ADD CONSTRAINT webhook_endpoints_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id);
+
+
 --
 -- PostgreSQL database dump complete
 --
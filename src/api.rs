@@ -0,0 +1,908 @@
+mod accounts;
+mod dashboard;
+mod envelopes;
+mod goals;
+mod income_sources;
+mod preferences;
+mod reports;
+mod transactions;
+
+use crate::{
+    SharedState,
+    authenticated::{UserExtension, bearer_token, live::publish_changes, token_permits},
+    errors::AppError,
+    models::{api_token::ApiToken, user::Session},
+};
+use anyhow::anyhow;
+use axum::{
+    Json, Router,
+    extract::{
+        FromRequest, FromRequestParts, Request, State,
+        rejection::{JsonRejection, PathRejection, QueryRejection},
+    },
+    http::StatusCode,
+    middleware::{Next, from_fn_with_state},
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use axum_extra::extract::SignedCookieJar;
+use rust_database_common::DatabasePoolError;
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use tracing::error;
+use utoipa::{
+    OpenApi, ToSchema,
+    openapi::{
+        ContentBuilder, Ref, ResponseBuilder,
+        security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    },
+};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorDetail {
+    /// JSON pointer to the offending value in the request body.
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    /// Stable, machine-readable reason, such as `not_found` or `validation_failed`.
+    code: String,
+    message: String,
+    details: Vec<ErrorDetail>,
+}
+
+/// The body of every error response, so that clients only need one decoder.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    error: ErrorBody,
+}
+
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    details: Vec<ErrorDetail>,
+}
+
+pub type ApiResponse = Result<Response, ApiError>;
+
+impl ApiError {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            details: Vec::new(),
+        }
+    }
+
+    pub fn unauthorized() -> Self {
+        Self::new(
+            StatusCode::UNAUTHORIZED,
+            "unauthorized",
+            "Sign in to use the API",
+        )
+    }
+
+    pub fn forbidden() -> Self {
+        Self::new(
+            StatusCode::FORBIDDEN,
+            "insufficient_scope",
+            "This token can only read",
+        )
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND, "not_found", "Not found")
+    }
+
+    pub fn invalid_record(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, "invalid_record", message)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(ErrorResponse {
+                error: ErrorBody {
+                    code: self.code.to_string(),
+                    message: self.message,
+                    details: self.details,
+                },
+            }),
+        )
+            .into_response()
+    }
+}
+
+impl From<AppError> for ApiError {
+    fn from(value: AppError) -> Self {
+        match value {
+            AppError::InvalidRecord(err) => Self::invalid_record(err.to_string()),
+            AppError::RecordNotFound(_) => Self::not_found(),
+            _ => {
+                error!("Unhandled error: {:?}", value);
+                Self::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "internal_error",
+                    "Sorry, something has gone wrong",
+                )
+            }
+        }
+    }
+}
+
+impl From<DatabasePoolError> for ApiError {
+    fn from(value: DatabasePoolError) -> Self {
+        AppError::from(value).into()
+    }
+}
+
+impl From<tokio_postgres::Error> for ApiError {
+    fn from(value: tokio_postgres::Error) -> Self {
+        AppError::from(value).into()
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(value: anyhow::Error) -> Self {
+        AppError::from(value).into()
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(value: JsonRejection) -> Self {
+        let code = match value {
+            JsonRejection::MissingJsonContentType(_) => "unsupported_media_type",
+            _ => "malformed_body",
+        };
+
+        Self::new(value.status(), code, value.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(value: PathRejection) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_path", value.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(value: QueryRejection) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_query", value.body_text())
+    }
+}
+
+/// A JSON request body whose rejections are answered with an `ApiError`.
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(ApiError))]
+pub struct ApiJson<T>(pub T);
+
+/// Path parameters whose rejections are answered with an `ApiError`.
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ApiError))]
+pub struct ApiPath<T>(pub T);
+
+/// Query parameters whose rejections are answered with an `ApiError`.
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub struct ApiQuery<T>(pub T);
+
+/// Checks a request body against `schema`, reporting every failing value, then deserializes it.
+pub fn validate<T: DeserializeOwned>(schema: &Value, body: Value) -> Result<T, ApiError> {
+    let validator =
+        jsonschema::validator_for(schema).map_err(|e| AppError::Unknown(anyhow!("{}", e)))?;
+    let details: Vec<_> = validator
+        .iter_errors(&body)
+        .map(|error| ErrorDetail {
+            field: error.instance_path().to_string(),
+            message: error.to_string(),
+        })
+        .collect();
+
+    if !details.is_empty() {
+        return Err(ApiError {
+            details,
+            ..ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "validation_failed",
+                "The request body is invalid",
+            )
+        });
+    }
+
+    serde_json::from_value(body).map_err(|e| ApiError::invalid_record(e.to_string()))
+}
+
+/// A non-negative amount, sent either as a JSON number or as a decimal string to keep its
+/// precision.
+pub fn amount_schema() -> Value {
+    json!({
+        "anyOf": [
+            { "type": "number", "minimum": 0 },
+            { "type": "string", "pattern": "^[0-9]+(\\.[0-9]+)?$" }
+        ]
+    })
+}
+
+/// Rates such as APRs, from 0 to 100 percent.
+pub fn percentage_schema() -> Value {
+    json!({
+        "anyOf": [
+            { "type": "number", "minimum": 0, "maximum": 100 },
+            { "type": "string", "pattern": "^(100(\\.0+)?|[0-9]{1,2}(\\.[0-9]+)?)$" }
+        ]
+    })
+}
+
+pub fn nullable(schema: Value) -> Value {
+    json!({ "anyOf": [schema, { "type": "null" }] })
+}
+
+/// Like the browser session check, but answers with a 401 instead of redirecting to the login
+/// page, and also takes personal access tokens. The API takes no CSRF token: JSON bodies and the
+/// PUT, PATCH and DELETE methods cannot be sent cross-site without a CORS preflight, which this
+/// app never grants.
+async fn authenticated(
+    State(state): State<SharedState>,
+    jar: SignedCookieJar,
+    mut request: Request,
+    next: Next,
+) -> ApiResponse {
+    let client = state.pool.get_client().await?;
+
+    if let Some(secret) = bearer_token(request.headers()) {
+        let token = ApiToken::authenticate(&client, secret)
+            .await
+            .map_err(|_| ApiError::unauthorized())?;
+        if !token_permits(token.scope, request.method()) {
+            return Err(ApiError::forbidden());
+        }
+
+        request.extensions_mut().insert(UserExtension {
+            id: token.user_id,
+            csrf: String::new(),
+        });
+
+        return Ok(next.run(request).await);
+    }
+
+    let session_id = jar.get("session_id").ok_or_else(ApiError::unauthorized)?;
+    let session = Session::get_by_id(&client, session_id.value())
+        .await
+        .map_err(|_| ApiError::unauthorized())?;
+
+    request.extensions_mut().insert(UserExtension {
+        id: session.user_id,
+        csrf: session.csrf,
+    });
+
+    Ok(next.run(request).await)
+}
+
+async fn not_found() -> ApiError {
+    ApiError::not_found()
+}
+
+async fn method_not_allowed() -> ApiError {
+    ApiError::new(
+        StatusCode::METHOD_NOT_ALLOWED,
+        "method_not_allowed",
+        "Method not allowed",
+    )
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Simple Budget API", version = "1"),
+    servers((url = "/api/v1")),
+    security(("session" = []), ("token" = [])),
+    components(schemas(ErrorResponse)),
+    tags(
+        (name = "accounts"),
+        (name = "envelopes"),
+        (name = "goals"),
+        (name = "transactions"),
+        (name = "income_sources"),
+        (name = "preferences"),
+        (name = "dashboard"),
+        (name = "reports")
+    )
+)]
+struct ApiDoc;
+
+/// Declares the session cookie and access tokens, and the error responses that every operation
+/// can give.
+fn document_common_responses(openapi: &mut utoipa::openapi::OpenApi) {
+    let components = openapi.components.get_or_insert_with(Default::default);
+    components.add_security_scheme(
+        "session",
+        SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("session_id"))),
+    );
+    components.add_security_scheme(
+        "token",
+        SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+    );
+
+    let error = |description: &str| {
+        ResponseBuilder::new()
+            .description(description)
+            .content(
+                "application/json",
+                ContentBuilder::new()
+                    .schema(Some(Ref::from_schema_name("ErrorResponse")))
+                    .build(),
+            )
+            .build()
+    };
+
+    for item in openapi.paths.paths.values_mut() {
+        for (operation, writes) in [
+            (&mut item.get, false),
+            (&mut item.post, true),
+            (&mut item.put, true),
+            (&mut item.patch, true),
+            (&mut item.delete, true),
+        ] {
+            let Some(operation) = operation else {
+                continue;
+            };
+            let responses = &mut operation.responses.responses;
+            responses
+                .entry("401".to_string())
+                .or_insert_with(|| error("Not signed in").into());
+            if writes {
+                responses
+                    .entry("403".to_string())
+                    .or_insert_with(|| error("The access token can only read").into());
+            }
+            responses
+                .entry("500".to_string())
+                .or_insert_with(|| error("Unexpected failure").into());
+        }
+    }
+}
+
+fn api_routes() -> OpenApiRouter<SharedState> {
+    let mut routes = OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .nest("/accounts", accounts::accounts_router())
+        .nest("/envelopes", envelopes::envelopes_router())
+        .nest("/goals", goals::goals_router())
+        .nest("/transactions", transactions::transactions_router())
+        .nest("/income_sources", income_sources::income_sources_router())
+        .routes(routes!(preferences::show, preferences::update))
+        .routes(routes!(dashboard::show))
+        .merge(reports::reports_router());
+    document_common_responses(routes.get_openapi_mut());
+
+    routes
+}
+
+pub fn api_router(state: SharedState) -> Router<SharedState> {
+    let (router, openapi) = api_routes().split_for_parts();
+
+    router
+        .route_layer(from_fn_with_state(state.clone(), publish_changes))
+        .route_layer(from_fn_with_state(state, authenticated))
+        .route(
+            "/openapi.json",
+            get(move || {
+                let openapi = openapi.clone();
+                async move { Json(openapi) }
+            }),
+        )
+        .fallback(not_found)
+        .method_not_allowed_fallback(method_not_allowed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{api_router, api_routes, validate};
+    use crate::{
+        models::api_token::{ApiToken, TokenScope},
+        test_utils::{api_request, state_for_tests},
+    };
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Method, Request, StatusCode},
+    };
+    use chrono::{Days, SecondsFormat, Utc};
+    use serde_json::{Value, json};
+    use std::collections::BTreeSet;
+    use tower::ServiceExt;
+
+    async fn error_for(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (
+            status,
+            serde_json::from_slice::<Value>(&body).unwrap()["error"].clone(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_json_objects() {
+        let (shared_state, _user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .nest("/api/v1", api_router(shared_state.clone()))
+            .with_state(shared_state);
+
+        let (status, error) = error_for(
+            &app,
+            Request::builder()
+                .uri("/api/v1/accounts")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error["code"], "unauthorized");
+
+        let (status, error) = error_for(
+            &app,
+            Request::builder()
+                .uri("/api/v1/unknown")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error["code"], "not_found");
+        assert_eq!(error["details"], json!([]));
+    }
+
+    #[test]
+    fn test_validate_reports_each_field() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "minLength": 2 },
+                "amount": super::amount_schema()
+            },
+            "required": ["name", "amount"]
+        });
+
+        let error = validate::<Value>(&schema, json!({ "name": "a", "amount": "-1" })).unwrap_err();
+        let mut fields: Vec<_> = error
+            .details
+            .iter()
+            .map(|detail| detail.field.as_str())
+            .collect();
+        fields.sort();
+
+        assert_eq!(error.code, "validation_failed");
+        assert_eq!(fields, ["/amount", "/name"]);
+        assert!(validate::<Value>(&schema, json!({ "name": "ab", "amount": 1.5 })).is_ok());
+    }
+
+    /// Rejects properties that the document leaves out, so that a field added to a response
+    /// without its schema changing is caught.
+    fn close_objects(schema: &mut Value) {
+        match schema {
+            Value::Object(object) => {
+                if object.contains_key("properties") && !object.contains_key("additionalProperties")
+                {
+                    object.insert("additionalProperties".to_string(), Value::Bool(false));
+                }
+                object.values_mut().for_each(close_objects);
+            }
+            Value::Array(array) => array.iter_mut().for_each(close_objects),
+            _ => {}
+        }
+    }
+
+    struct Conformance {
+        app: Router,
+        spec: Value,
+        exercised: BTreeSet<(String, String)>,
+    }
+
+    impl Conformance {
+        /// Sends a request and checks that its status and body are both documented for `path`.
+        async fn call(
+            &mut self,
+            method: Method,
+            path: &str,
+            uri: &str,
+            body: Option<Value>,
+        ) -> (StatusCode, Value) {
+            let (status, response) = api_request(&self.app, method.clone(), uri, body).await;
+            let method = method.as_str().to_lowercase();
+            let documented = &self.spec["paths"][path][&method]["responses"][status.as_str()];
+            assert!(
+                documented.is_object(),
+                "{method} {path} answered with undocumented status {status}: {response}"
+            );
+
+            match documented["content"]["application/json"]["schema"].as_object() {
+                Some(schema) => {
+                    let mut schema = schema.clone();
+                    schema.insert("components".to_string(), self.spec["components"].clone());
+                    let schema = Value::Object(schema);
+                    let validator = jsonschema::validator_for(&schema).unwrap();
+                    let errors: Vec<_> = validator
+                        .iter_errors(&response)
+                        .map(|error| format!("{}: {}", error.instance_path(), error))
+                        .collect();
+                    assert!(
+                        errors.is_empty(),
+                        "{method} {path} {status} does not match the document: {errors:?}"
+                    );
+                }
+                None => assert_eq!(response, Value::Null, "{method} {path} {status} has a body"),
+            }
+
+            self.exercised.insert((method, path.to_string()));
+            (status, response)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_responses_match_openapi_document() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let (router, openapi) = api_routes().split_for_parts();
+        let mut spec = serde_json::to_value(&openapi).unwrap();
+        close_objects(&mut spec["components"]);
+        let mut api = Conformance {
+            app: Router::new()
+                .nest("/api/v1", router)
+                .with_state(shared_state)
+                .layer(user_extension),
+            spec,
+            exercised: BTreeSet::new(),
+        };
+        let missing = "00000000-0000-0000-0000-000000000000";
+        let target_date = (Utc::now() + Days::new(60)).date_naive().to_string();
+        let today = Utc::now().date_naive().to_string();
+
+        let (_, account) = api
+            .call(
+                Method::POST,
+                "/accounts",
+                "/api/v1/accounts",
+                Some(json!({ "name": "Card", "amount": "120.50", "debt": true, "apr": 20 })),
+            )
+            .await;
+        let (_, envelope) = api
+            .call(
+                Method::POST,
+                "/envelopes",
+                "/api/v1/envelopes",
+                Some(json!({ "name": "Groceries", "amount": 400 })),
+            )
+            .await;
+        let (_, goal) = api
+            .call(
+                Method::POST,
+                "/goals",
+                "/api/v1/goals",
+                Some(json!({
+                    "name": "Rent",
+                    "target": 1500,
+                    "target_date": target_date,
+                    "recurrence": { "kind": "every_months", "interval": 1 },
+                    "envelope_id": envelope["id"]
+                })),
+            )
+            .await;
+
+        let (_, transaction) = api
+            .call(
+                Method::POST,
+                "/transactions",
+                "/api/v1/transactions",
+                Some(json!({
+                    "account_id": account["id"],
+                    "amount": -20,
+                    "description": "Market",
+                    "occurred_on": today
+                })),
+            )
+            .await;
+        let (_, income_source) = api
+            .call(
+                Method::POST,
+                "/income_sources",
+                "/api/v1/income_sources",
+                Some(json!({
+                    "name": "Salary",
+                    "amount": 2500,
+                    "recurrence": "monthly",
+                    "next_expected_on": target_date
+                })),
+            )
+            .await;
+
+        let spends = format!(
+            "/api/v1/envelopes/{}/spends",
+            envelope["id"].as_str().unwrap()
+        );
+        let transfers = format!(
+            "/api/v1/envelopes/{}/transfers",
+            envelope["id"].as_str().unwrap()
+        );
+        let spend = json!({
+            "amount": 12,
+            "description": "Market",
+            "account_id": account["id"],
+            "occurred_on": today
+        });
+        api.call(
+            Method::POST,
+            "/envelopes/{id}/spends",
+            &spends,
+            Some(spend.clone()),
+        )
+        .await;
+        api.call(
+            Method::POST,
+            "/envelopes/{id}/spends",
+            &spends,
+            Some(json!({})),
+        )
+        .await;
+        api.call(
+            Method::POST,
+            "/envelopes/{id}/spends",
+            &format!("/api/v1/envelopes/{missing}/spends"),
+            Some(spend),
+        )
+        .await;
+        api.call(Method::GET, "/envelopes/{id}/spends", &spends, None)
+            .await;
+        api.call(
+            Method::GET,
+            "/envelopes/{id}/spends",
+            &format!("/api/v1/envelopes/{missing}/spends"),
+            None,
+        )
+        .await;
+        api.call(
+            Method::POST,
+            "/envelopes/{id}/transfers",
+            &transfers,
+            Some(json!({ "amount": 5, "to_envelope_id": null })),
+        )
+        .await;
+        api.call(
+            Method::POST,
+            "/envelopes/{id}/transfers",
+            &transfers,
+            Some(json!({ "amount": 5 })),
+        )
+        .await;
+        api.call(
+            Method::POST,
+            "/envelopes/{id}/transfers",
+            &format!("/api/v1/envelopes/{missing}/transfers"),
+            Some(json!({ "amount": 5, "to_envelope_id": null })),
+        )
+        .await;
+
+        api.call(
+            Method::GET,
+            "/goals/{id}/contributions",
+            &format!(
+                "/api/v1/goals/{}/contributions",
+                goal["id"].as_str().unwrap()
+            ),
+            None,
+        )
+        .await;
+        api.call(
+            Method::GET,
+            "/goals/{id}/contributions",
+            &format!("/api/v1/goals/{missing}/contributions"),
+            None,
+        )
+        .await;
+        let at = (Utc::now() + Days::new(3)).to_rfc3339_opts(SecondsFormat::Secs, true);
+        for query in [
+            format!("at={at}"),
+            "at=2000-01-01T00:00:00Z".to_string(),
+            "at=soon".to_string(),
+        ] {
+            api.call(
+                Method::GET,
+                "/goals/simulation",
+                &format!("/api/v1/goals/simulation?{query}"),
+                None,
+            )
+            .await;
+        }
+
+        for (path, queries) in [
+            (
+                "/accounts/net_worth",
+                [
+                    "granularity=daily",
+                    "from=2024-02-01&to=2024-01-01",
+                    "granularity=hourly",
+                ],
+            ),
+            (
+                "/accounts/debt_plan",
+                ["extra=100", "extra=-5", "extra=some"],
+            ),
+            ("/forecast", ["months=1", "months=25", "months=soon"]),
+            ("/history", ["months=6", "months=37", "months=soon"]),
+        ] {
+            for query in queries {
+                api.call(Method::GET, path, &format!("/api/v1{path}?{query}"), None)
+                    .await;
+            }
+        }
+
+        for (resource, record, body) in [
+            (
+                "transactions",
+                transaction,
+                json!({
+                    "account_id": account["id"],
+                    "amount": -25,
+                    "description": "Market",
+                    "occurred_on": today
+                }),
+            ),
+            (
+                "income_sources",
+                income_source,
+                json!({
+                    "name": "Salary",
+                    "amount": 2600,
+                    "recurrence": "semi_monthly",
+                    "next_expected_on": target_date
+                }),
+            ),
+            (
+                "accounts",
+                account,
+                json!({ "name": "Checking", "amount": 80 }),
+            ),
+            (
+                "envelopes",
+                envelope,
+                json!({ "name": "Food", "amount": 450 }),
+            ),
+            (
+                "goals",
+                goal,
+                json!({
+                    "name": "Rent",
+                    "target": 1600,
+                    "target_date": target_date,
+                    "recurrence": { "kind": "never" }
+                }),
+            ),
+        ] {
+            let collection = format!("/{resource}");
+            let member = format!("/{resource}/{{id}}");
+            let uri = format!("/api/v1/{resource}/{}", record["id"].as_str().unwrap());
+            let missing_uri = format!("/api/v1/{resource}/{missing}");
+
+            api.call(
+                Method::GET,
+                &collection,
+                &format!("/api/v1{collection}"),
+                None,
+            )
+            .await;
+            api.call(
+                Method::POST,
+                &collection,
+                &format!("/api/v1{collection}"),
+                Some(json!({ "name": "" })),
+            )
+            .await;
+            api.call(Method::GET, &member, &uri, None).await;
+            api.call(Method::GET, &member, &missing_uri, None).await;
+            api.call(Method::PUT, &member, &uri, Some(body.clone()))
+                .await;
+            api.call(Method::PUT, &member, &uri, Some(json!({}))).await;
+            api.call(Method::PUT, &member, &missing_uri, Some(body))
+                .await;
+            api.call(Method::DELETE, &member, &uri, None).await;
+            api.call(Method::DELETE, &member, &uri, None).await;
+        }
+
+        api.call(Method::GET, "/preferences", "/api/v1/preferences", None)
+            .await;
+        api.call(
+            Method::PATCH,
+            "/preferences",
+            "/api/v1/preferences",
+            Some(json!({ "budget_period": { "kind": "biweekly", "anchor": "2024-01-05" } })),
+        )
+        .await;
+        api.call(
+            Method::PATCH,
+            "/preferences",
+            "/api/v1/preferences",
+            Some(json!({ "forecast_offset": 7 })),
+        )
+        .await;
+        api.call(Method::GET, "/dashboard", "/api/v1/dashboard", None)
+            .await;
+
+        let documented: BTreeSet<_> = api.spec["paths"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .flat_map(|(path, item)| {
+                item.as_object()
+                    .unwrap()
+                    .keys()
+                    .filter(|method| {
+                        ["get", "post", "put", "patch", "delete"].contains(&method.as_str())
+                    })
+                    .map(move |method| (method.clone(), path.clone()))
+            })
+            .collect();
+        assert_eq!(documented, api.exercised);
+    }
+
+    #[tokio::test]
+    async fn test_access_tokens() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+        let (_, read) = ApiToken::create(&client, user_extension.id, "read", TokenScope::Read)
+            .await
+            .unwrap();
+        let (_, write) = ApiToken::create(&client, user_extension.id, "write", TokenScope::Write)
+            .await
+            .unwrap();
+        let app = Router::new()
+            .nest("/api/v1", api_router(shared_state.clone()))
+            .with_state(shared_state);
+        let send = |method: Method, token: &str| {
+            Request::builder()
+                .method(method)
+                .uri("/api/v1/envelopes")
+                .header("Authorization", format!("Bearer {token}"))
+                .header("Content-Type", "application/json")
+                .body(Body::from(r#"{ "name": "Gifts", "amount": 50 }"#))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(send(Method::GET, &read)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let (status, error) = error_for(&app, send(Method::POST, &read)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(error["code"], "insufficient_scope");
+
+        let response = app
+            .clone()
+            .oneshot(send(Method::POST, &write))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let (status, error) = error_for(&app, send(Method::GET, "sb_revoked")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(error["code"], "unauthorized");
+    }
+
+    #[tokio::test]
+    async fn test_openapi_document_is_public() {
+        let (shared_state, _user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .nest("/api/v1", api_router(shared_state.clone()))
+            .with_state(shared_state);
+
+        let (status, document) = api_request(&app, Method::GET, "/api/v1/openapi.json", None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(document["openapi"], "3.1.0");
+        assert!(document["paths"]["/goals/{id}"]["put"].is_object());
+    }
+}
@@ -0,0 +1,626 @@
+use super::{
+    ApiJson, ApiPath, ApiQuery, ApiResponse, ErrorResponse, amount_schema, nullable,
+    percentage_schema, validate,
+};
+use crate::{
+    SharedState,
+    authenticated::UserExtension,
+    errors::AppError,
+    models::{
+        account::Account,
+        account_balance::AccountBalance,
+        debt_plan::{DebtPayoff, DebtPlan, PayoffStrategy, check_extra, plan},
+        net_worth::{AccountValue, Granularity, NetWorthPoint, net_worth_for, report_range},
+        user::User,
+    },
+};
+use anyhow::anyhow;
+use axum::{Extension, Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+fn schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "minLength": 2 },
+            "amount": amount_schema(),
+            "debt": { "type": "boolean" },
+            "apr": nullable(percentage_schema()),
+            "minimum_payment": nullable(amount_schema()),
+            "account_number": nullable(json!({ "type": "string", "maxLength": 34 }))
+        },
+        "required": [ "name", "amount" ],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct AccountRequest {
+    #[schema(min_length = 2)]
+    name: String,
+    amount: Decimal,
+    #[serde(default)]
+    debt: bool,
+    apr: Option<Decimal>,
+    minimum_payment: Option<Decimal>,
+    #[schema(max_length = 34)]
+    account_number: Option<String>,
+}
+
+impl AccountRequest {
+    /// Copies the request onto `account`; debt terms are only kept for debt accounts.
+    fn apply(self, account: &mut Account) {
+        account.name = self.name;
+        account.amount = self.amount;
+        account.debt = self.debt;
+        account.apr = self.apr.filter(|_| self.debt);
+        account.minimum_payment = self.minimum_payment.filter(|_| self.debt);
+        account.account_number = self
+            .account_number
+            .as_deref()
+            .map(str::trim)
+            .filter(|account_number| !account_number.is_empty())
+            .map(str::to_string);
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct AccountResponse {
+    id: Option<Uuid>,
+    name: String,
+    amount: Decimal,
+    debt: bool,
+    apr: Option<Decimal>,
+    minimum_payment: Option<Decimal>,
+    account_number: Option<String>,
+}
+
+impl From<Account> for AccountResponse {
+    fn from(account: Account) -> Self {
+        Self {
+            id: account.id,
+            name: account.name,
+            amount: account.amount,
+            debt: account.debt,
+            apr: account.apr,
+            minimum_payment: account.minimum_payment,
+            account_number: account.account_number,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "accounts",
+    responses((status = 200, body = Vec<AccountResponse>))
+)]
+async fn index(shared_state: State<SharedState>, user: Extension<UserExtension>) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let accounts: Vec<AccountResponse> = Account::get_all(&client, user.id)
+        .await?
+        .into_iter()
+        .map(AccountResponse::from)
+        .collect();
+
+    Ok(Json(accounts).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "accounts",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 200, body = AccountResponse),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn show(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let account = Account::get_one(&client, id, user.id).await?;
+
+    Ok(Json(AccountResponse::from(account)).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "accounts",
+    request_body = AccountRequest,
+    responses(
+        (status = 201, body = AccountResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn create(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: AccountRequest = validate(&schema(), body)?;
+    let mut account = Account {
+        id: None,
+        user_id: user.id,
+        name: String::new(),
+        amount: Decimal::ZERO,
+        debt: false,
+        apr: None,
+        minimum_payment: None,
+        account_number: None,
+    };
+    request.apply(&mut account);
+
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let account = account.create(&client).await?;
+    AccountBalance::record(
+        &client,
+        account.id.ok_or_else(|| anyhow!("account was not saved"))?,
+        user.id,
+    )
+    .await?;
+    client.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(AccountResponse::from(account))).into_response())
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    tag = "accounts",
+    params(("id" = Uuid, Path)),
+    request_body = AccountRequest,
+    responses(
+        (status = 200, body = AccountResponse),
+        (status = 404, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn update(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: AccountRequest = validate(&schema(), body)?;
+
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let timezone: Tz = User::get_by_id(&client, user.id)
+        .await?
+        .timezone()?
+        .parse()
+        .map_err(AppError::from)?;
+    let previous = Account::get_one(&client, id, user.id).await?;
+    let mut account = previous.clone();
+    request.apply(&mut account);
+    let balance = std::mem::replace(&mut account.amount, previous.amount);
+    account.update(&client).await?;
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    if !account.adjust_balance(&client, balance, today).await? && account.debt != previous.debt {
+        AccountBalance::record(&client, id, user.id).await?;
+    }
+    client.commit().await?;
+
+    Ok(Json(AccountResponse::from(account)).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "accounts",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 204),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn delete(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let account = Account::get_one(&client, id, user.id).await?;
+    account.delete(&client).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[schema(as = Granularity)]
+enum GranularityBody {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl From<GranularityBody> for Granularity {
+    fn from(granularity: GranularityBody) -> Self {
+        match granularity {
+            GranularityBody::Daily => Self::Daily,
+            GranularityBody::Weekly => Self::Weekly,
+            GranularityBody::Monthly => Self::Monthly,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct NetWorthQuery {
+    /// Six months before `to` when left out.
+    from: Option<NaiveDate>,
+    /// Today when left out.
+    to: Option<NaiveDate>,
+    /// `weekly` when left out.
+    granularity: Option<GranularityBody>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct AccountValueResponse {
+    account_id: Uuid,
+    name: String,
+    amount: Decimal,
+    debt: bool,
+}
+
+impl From<AccountValue> for AccountValueResponse {
+    fn from(value: AccountValue) -> Self {
+        Self {
+            account_id: value.account_id,
+            name: value.name,
+            amount: value.amount,
+            debt: value.debt,
+        }
+    }
+}
+
+/// Assets, debts and their difference at the end of a day, week or month.
+#[derive(Debug, Serialize, ToSchema)]
+struct NetWorthPointResponse {
+    date: NaiveDate,
+    assets: Decimal,
+    debts: Decimal,
+    net_worth: Decimal,
+    /// Accounts with a balance recorded by `date`.
+    accounts: Vec<AccountValueResponse>,
+}
+
+impl From<NetWorthPoint> for NetWorthPointResponse {
+    fn from(point: NetWorthPoint) -> Self {
+        Self {
+            date: point.date,
+            assets: point.assets,
+            debts: point.debts,
+            net_worth: point.net_worth,
+            accounts: point
+                .accounts
+                .into_iter()
+                .map(AccountValueResponse::from)
+                .collect(),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/net_worth",
+    tag = "accounts",
+    params(NetWorthQuery),
+    responses(
+        (status = 200, body = Vec<NetWorthPointResponse>),
+        (status = 400, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn net_worth(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiQuery(query): ApiQuery<NetWorthQuery>,
+) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let user = User::get_by_id(&client, user.id).await?;
+    let timezone: Tz = user.timezone()?.parse().map_err(AppError::from)?;
+    let granularity = query.granularity.map(Granularity::from).unwrap_or_default();
+    let (from, to) = report_range(
+        query.from,
+        query.to,
+        granularity,
+        Utc::now().with_timezone(&timezone).date_naive(),
+    )?;
+    let points: Vec<NetWorthPointResponse> = net_worth_for(&client, &user, from, to, granularity)
+        .await?
+        .into_iter()
+        .map(NetWorthPointResponse::from)
+        .collect();
+
+    Ok(Json(points).into_response())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct DebtPlanQuery {
+    /// Paid each month on top of the minimum payments; 0 when left out.
+    extra: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[schema(as = PayoffStrategy)]
+enum PayoffStrategyBody {
+    Avalanche,
+    Snowball,
+}
+
+impl From<PayoffStrategy> for PayoffStrategyBody {
+    fn from(strategy: PayoffStrategy) -> Self {
+        match strategy {
+            PayoffStrategy::Avalanche => Self::Avalanche,
+            PayoffStrategy::Snowball => Self::Snowball,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DebtPayoffResponse {
+    account_id: Option<Uuid>,
+    name: String,
+    balance: Decimal,
+    /// Null when the payments never clear the debt.
+    paid_off_on: Option<NaiveDate>,
+    months: Option<u32>,
+    interest: Decimal,
+    total_paid: Decimal,
+}
+
+impl From<DebtPayoff> for DebtPayoffResponse {
+    fn from(payoff: DebtPayoff) -> Self {
+        Self {
+            account_id: payoff.account_id,
+            name: payoff.name,
+            balance: payoff.balance,
+            paid_off_on: payoff.paid_off_on,
+            months: payoff.months,
+            interest: payoff.interest,
+            total_paid: payoff.total_paid,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DebtPlanResponse {
+    strategy: PayoffStrategyBody,
+    monthly_payment: Decimal,
+    /// In the order the debts are paid off.
+    payoffs: Vec<DebtPayoffResponse>,
+    total_interest: Decimal,
+    paid_off_on: Option<NaiveDate>,
+    months: Option<u32>,
+}
+
+impl From<DebtPlan> for DebtPlanResponse {
+    fn from(plan: DebtPlan) -> Self {
+        Self {
+            strategy: plan.strategy.into(),
+            monthly_payment: plan.monthly_payment,
+            payoffs: plan
+                .payoffs
+                .into_iter()
+                .map(DebtPayoffResponse::from)
+                .collect(),
+            total_interest: plan.total_interest,
+            paid_off_on: plan.paid_off_on,
+            months: plan.months,
+        }
+    }
+}
+
+/// The same debts paid off highest APR first and smallest balance first.
+#[derive(Debug, Serialize, ToSchema)]
+struct DebtPlansResponse {
+    avalanche: DebtPlanResponse,
+    snowball: DebtPlanResponse,
+}
+
+/// Plans paying off the user's debt accounts from today.
+#[utoipa::path(
+    get,
+    path = "/debt_plan",
+    tag = "accounts",
+    params(DebtPlanQuery),
+    responses(
+        (status = 200, body = DebtPlansResponse),
+        (status = 400, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn debt_plan(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiQuery(query): ApiQuery<DebtPlanQuery>,
+) -> ApiResponse {
+    let extra = check_extra(query.extra.unwrap_or(Decimal::ZERO))?;
+    let client = shared_state.pool.get_client().await?;
+    let timezone: Tz = User::get_by_id(&client, user.id)
+        .await?
+        .timezone()?
+        .parse()
+        .map_err(AppError::from)?;
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    let debts = Account::get_all(&client, user.id)
+        .await?
+        .into_iter()
+        .filter(|account| account.debt)
+        .collect::<Vec<_>>();
+
+    Ok(Json(DebtPlansResponse {
+        avalanche: plan(&debts, extra, today, PayoffStrategy::Avalanche)?.into(),
+        snowball: plan(&debts, extra, today, PayoffStrategy::Snowball)?.into(),
+    })
+    .into_response())
+}
+
+pub fn accounts_router() -> OpenApiRouter<SharedState> {
+    OpenApiRouter::new()
+        .routes(routes!(index, create))
+        .routes(routes!(show, update, delete))
+        .routes(routes!(net_worth))
+        .routes(routes!(debt_plan))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::accounts_router;
+    use crate::test_utils::{api_request, state_for_tests};
+    use axum::{
+        Router,
+        http::{Method, StatusCode},
+    };
+    use serde_json::{Value, json};
+
+    #[tokio::test]
+    async fn test_account_lifecycle() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .nest("/api/v1/accounts", Router::from(accounts_router()))
+            .with_state(shared_state)
+            .layer(user_extension);
+
+        let (status, account) = api_request(
+            &app,
+            Method::POST,
+            "/api/v1/accounts",
+            Some(json!({ "name": "Card", "amount": "120.50", "apr": 24.99, "debt": true })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(account["amount"], "120.50");
+        assert_eq!(account["apr"], "24.99");
+        let uri = format!("/api/v1/accounts/{}", account["id"].as_str().unwrap());
+
+        let (status, account) = api_request(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(json!({ "name": "Checking", "amount": 80, "apr": "5" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(account["name"], "Checking");
+        assert_eq!(account["debt"], false);
+        assert_eq!(account["apr"], Value::Null);
+
+        let (status, _error) = api_request(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(json!({ "name": "Card", "amount": 80, "apr": 250, "debt": true })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, accounts) = api_request(&app, Method::GET, "/api/v1/accounts", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(accounts.as_array().unwrap().len(), 1);
+
+        let (status, error) = api_request(
+            &app,
+            Method::POST,
+            "/api/v1/accounts",
+            Some(json!({ "name": "C", "amount": -1, "color": "red" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["error"]["code"], "validation_failed");
+        assert_eq!(error["error"]["details"].as_array().unwrap().len(), 3);
+
+        let (status, _) = api_request(&app, Method::DELETE, &uri, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, error) = api_request(&app, Method::GET, &uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error["error"]["code"], "not_found");
+
+        let (status, error) = api_request(&app, Method::GET, "/api/v1/accounts/nope", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["error"]["code"], "invalid_path");
+    }
+
+    #[tokio::test]
+    async fn test_reports() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .nest("/api/v1/accounts", Router::from(accounts_router()))
+            .with_state(shared_state)
+            .layer(user_extension);
+
+        for account in [
+            json!({ "name": "checking", "amount": 1500 }),
+            json!({ "name": "card", "amount": 2000, "apr": 22, "minimum_payment": 50, "debt": true }),
+            json!({ "name": "car", "amount": 5000, "apr": 5, "minimum_payment": 150, "debt": true }),
+        ] {
+            let (status, _) =
+                api_request(&app, Method::POST, "/api/v1/accounts", Some(account)).await;
+            assert_eq!(status, StatusCode::CREATED);
+        }
+
+        let (status, points) = api_request(
+            &app,
+            Method::GET,
+            "/api/v1/accounts/net_worth?granularity=monthly",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let latest = points.as_array().unwrap().last().unwrap();
+        assert_eq!(latest["net_worth"], "-5500");
+        assert_eq!(latest["accounts"].as_array().unwrap().len(), 3);
+
+        let (status, error) = api_request(
+            &app,
+            Method::GET,
+            "/api/v1/accounts/net_worth?from=2024-02-01&to=2024-01-01",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["error"]["code"], "invalid_record");
+
+        let (status, plans) = api_request(
+            &app,
+            Method::GET,
+            "/api/v1/accounts/debt_plan?extra=100",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(plans["avalanche"]["strategy"], "avalanche");
+        assert_eq!(plans["avalanche"]["payoffs"][0]["name"], "card");
+        assert_eq!(plans["snowball"]["payoffs"][0]["name"], "card");
+        assert_eq!(plans["avalanche"]["monthly_payment"], "300");
+
+        for extra in ["-5", "1000000000.01"] {
+            let (status, _) = api_request(
+                &app,
+                Method::GET,
+                &format!("/api/v1/accounts/debt_plan?extra={extra}"),
+                None,
+            )
+            .await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+}
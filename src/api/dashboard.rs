@@ -0,0 +1,114 @@
+use super::ApiResponse;
+use crate::{
+    SharedState,
+    authenticated::{
+        UserExtension,
+        dashboard::{DashboardSummary, DashboardTransfer},
+    },
+    models::user::User,
+};
+use axum::{Extension, Json, extract::State, response::IntoResponse};
+use chrono::{DateTime, FixedOffset};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, ToSchema)]
+struct TransferResponse {
+    from: Option<String>,
+    /// Null when the amount was released from the budget rather than moved to an envelope.
+    to: Option<String>,
+    amount: Decimal,
+    created_at: DateTime<FixedOffset>,
+}
+
+impl From<DashboardTransfer> for TransferResponse {
+    fn from(transfer: DashboardTransfer) -> Self {
+        Self {
+            from: transfer.from,
+            to: transfer.to,
+            amount: transfer.amount,
+            created_at: transfer.created_at.fixed_offset(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct DashboardResponse {
+    remaining_total: Decimal,
+    /// What will remain at the start of the forecast day, `forecast_offset` days from today.
+    forecast_remaining_total: Decimal,
+    forecast_offset: i64,
+    minutes_until_forecast: i64,
+    goals_accumulated_per_day: Decimal,
+    per_diem: Decimal,
+    /// How much more the per diem is than income per day.
+    per_diem_over_income: Decimal,
+    remaining_days: Decimal,
+    released_total: Decimal,
+    transfers: Vec<TransferResponse>,
+}
+
+impl From<DashboardSummary> for DashboardResponse {
+    fn from(summary: DashboardSummary) -> Self {
+        Self {
+            remaining_total: summary.remaining_total,
+            forecast_remaining_total: summary.tomorrow_remaining_total,
+            forecast_offset: summary.forecast_offset,
+            minutes_until_forecast: summary.remaining_minutes,
+            goals_accumulated_per_day: summary.goals_accumulated_per_day,
+            per_diem: summary.per_diem,
+            per_diem_over_income: summary.per_diem_diff_monthly,
+            remaining_days: summary.remaining_days.round_dp(1),
+            released_total: summary.released_total,
+            transfers: summary
+                .transfers
+                .into_iter()
+                .map(TransferResponse::from)
+                .collect(),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/dashboard",
+    tag = "dashboard",
+    responses((status = 200, body = DashboardResponse))
+)]
+pub async fn show(shared_state: State<SharedState>, user: Extension<UserExtension>) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let user = User::get_by_id(&client, user.id).await?;
+    let summary = DashboardSummary::measure(&user, &client).await?;
+
+    Ok(Json(DashboardResponse::from(summary)).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::show;
+    use crate::test_utils::{api_request, state_for_tests};
+    use axum::{
+        Router,
+        http::{Method, StatusCode},
+        routing::get,
+    };
+    use serde_json::Value;
+
+    #[tokio::test]
+    async fn test_dashboard() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .route("/api/v1/dashboard", get(show))
+            .with_state(shared_state)
+            .layer(user_extension);
+
+        let (status, dashboard) = api_request(&app, Method::GET, "/api/v1/dashboard", None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert!(dashboard["remaining_total"].is_string());
+        assert_eq!(dashboard["forecast_offset"], 1);
+        assert_eq!(dashboard["transfers"], Value::Array(vec![]));
+        assert!(dashboard["minutes_until_forecast"].as_i64().unwrap() > 0);
+    }
+}
@@ -0,0 +1,444 @@
+use super::{
+    ApiError, ApiJson, ApiPath, ApiResponse, ErrorResponse, amount_schema, nullable,
+    transactions::TransactionResponse, validate,
+};
+use crate::{
+    SharedState,
+    authenticated::UserExtension,
+    errors::AppError,
+    models::{
+        account::Account,
+        envelope::{Envelope, EnvelopeTransfer},
+        transaction::Transaction,
+        user::User,
+        webhook::{WebhookEndpoint, WebhookEvent},
+    },
+};
+use axum::{Extension, Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+fn schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "minLength": 2 },
+            "amount": amount_schema()
+        },
+        "required": [ "name", "amount" ],
+        "additionalProperties": false
+    })
+}
+
+fn spend_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "amount": amount_schema(),
+            "description": { "type": "string", "minLength": 2 },
+            "account_id": nullable(json!({ "type": "string", "format": "uuid" })),
+            "occurred_on": { "type": "string", "format": "date" }
+        },
+        "required": [ "amount", "description", "occurred_on" ],
+        "additionalProperties": false
+    })
+}
+
+fn transfer_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "amount": amount_schema(),
+            "to_envelope_id": nullable(json!({ "type": "string", "format": "uuid" }))
+        },
+        "required": [ "amount", "to_envelope_id" ],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct EnvelopeRequest {
+    #[schema(min_length = 2)]
+    name: String,
+    amount: Decimal,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct SpendRequest {
+    amount: Decimal,
+    #[schema(min_length = 2)]
+    description: String,
+    /// The account the money left, if any.
+    account_id: Option<Uuid>,
+    occurred_on: NaiveDate,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct TransferRequest {
+    amount: Decimal,
+    /// Null to release the amount from the budget instead of moving it to another envelope.
+    to_envelope_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct EnvelopeResponse {
+    id: Option<Uuid>,
+    name: String,
+    amount: Decimal,
+}
+
+impl From<Envelope> for EnvelopeResponse {
+    fn from(envelope: Envelope) -> Self {
+        Self {
+            id: envelope.id,
+            name: envelope.name,
+            amount: envelope.amount,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "envelopes",
+    responses((status = 200, body = Vec<EnvelopeResponse>))
+)]
+async fn index(shared_state: State<SharedState>, user: Extension<UserExtension>) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let envelopes: Vec<EnvelopeResponse> = Envelope::get_all(&client, user.id)
+        .await?
+        .into_iter()
+        .map(EnvelopeResponse::from)
+        .collect();
+
+    Ok(Json(envelopes).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "envelopes",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 200, body = EnvelopeResponse),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn show(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let envelope = Envelope::get_one(&client, id, user.id).await?;
+
+    Ok(Json(EnvelopeResponse::from(envelope)).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "envelopes",
+    request_body = EnvelopeRequest,
+    responses(
+        (status = 201, body = EnvelopeResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn create(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: EnvelopeRequest = validate(&schema(), body)?;
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let envelope = Envelope {
+        id: None,
+        user_id: user.id,
+        name: request.name,
+        amount: request.amount,
+    }
+    .create(&client)
+    .await?;
+    WebhookEndpoint::publish(
+        &client,
+        user.id,
+        WebhookEvent::EnvelopeCreated,
+        json!({ "envelope": envelope }),
+    )
+    .await?;
+    client.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(EnvelopeResponse::from(envelope))).into_response())
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    tag = "envelopes",
+    params(("id" = Uuid, Path)),
+    request_body = EnvelopeRequest,
+    responses(
+        (status = 200, body = EnvelopeResponse),
+        (status = 404, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn update(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: EnvelopeRequest = validate(&schema(), body)?;
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let timezone: Tz = User::get_by_id(&client, user.id)
+        .await?
+        .timezone()?
+        .parse()
+        .map_err(AppError::from)?;
+    let mut envelope = Envelope::get_one(&client, id, user.id).await?;
+
+    envelope.name = request.name;
+    envelope.update(&client).await?;
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    envelope
+        .adjust_balance(&client, request.amount, today)
+        .await?;
+    client.commit().await?;
+
+    Ok(Json(EnvelopeResponse::from(envelope)).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "envelopes",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 204),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn delete(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let envelope = Envelope::get_one(&client, id, user.id).await?;
+    envelope.delete(&client).await?;
+    WebhookEndpoint::publish(
+        &client,
+        user.id,
+        WebhookEvent::EnvelopeDeleted,
+        json!({ "envelope": envelope }),
+    )
+    .await?;
+    client.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// What was spent from the envelope, newest first; balance adjustments are left out.
+#[utoipa::path(
+    get,
+    path = "/{id}/spends",
+    tag = "envelopes",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 200, body = Vec<TransactionResponse>),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn spends(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    Envelope::get_one(&client, id, user.id).await?;
+    let spends: Vec<TransactionResponse> = Transaction::get_all_for_envelope(&client, id, user.id)
+        .await?
+        .into_iter()
+        .filter(|transaction| !transaction.adjustment)
+        .map(TransactionResponse::from)
+        .collect();
+
+    Ok(Json(spends).into_response())
+}
+
+/// Takes `amount` out of the envelope, and out of the account when one is given. Fails when
+/// the envelope holds less than `amount`.
+#[utoipa::path(
+    post,
+    path = "/{id}/spends",
+    tag = "envelopes",
+    params(("id" = Uuid, Path)),
+    request_body = SpendRequest,
+    responses(
+        (status = 201, body = TransactionResponse),
+        (status = 404, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn spend(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: SpendRequest = validate(&spend_schema(), body)?;
+    if request.amount.is_zero() {
+        return Err(ApiError::invalid_record("amount must be more than zero"));
+    }
+
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let envelope = Envelope::get_one(&client, id, user.id).await?;
+    if let Some(account_id) = request.account_id {
+        Account::get_one(&client, account_id, user.id)
+            .await
+            .map_err(|_| ApiError::invalid_record("account_id is not one of your accounts"))?;
+    }
+    let spend = envelope
+        .spend(
+            &client,
+            request.amount,
+            request.account_id,
+            request.description,
+            request.occurred_on,
+        )
+        .await?;
+    client.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(TransactionResponse::from(spend))).into_response())
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct EnvelopeTransferResponse {
+    id: Uuid,
+    from_envelope_id: Uuid,
+    to_envelope_id: Option<Uuid>,
+    amount: Decimal,
+    created_at: DateTime<Utc>,
+}
+
+impl From<EnvelopeTransfer> for EnvelopeTransferResponse {
+    fn from(transfer: EnvelopeTransfer) -> Self {
+        Self {
+            id: transfer.id,
+            from_envelope_id: transfer.from_envelope_id,
+            to_envelope_id: transfer.to_envelope_id,
+            amount: transfer.amount,
+            created_at: transfer.created_at,
+        }
+    }
+}
+
+/// Moves `amount` to another envelope, or releases it. Fails when the envelope holds less than
+/// `amount`.
+#[utoipa::path(
+    post,
+    path = "/{id}/transfers",
+    tag = "envelopes",
+    params(("id" = Uuid, Path)),
+    request_body = TransferRequest,
+    responses(
+        (status = 201, body = EnvelopeTransferResponse),
+        (status = 404, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn transfer(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: TransferRequest = validate(&transfer_schema(), body)?;
+    let client = shared_state.pool.get_client().await?;
+    let envelope = Envelope::get_one(&client, id, user.id).await?;
+    let transfer = envelope
+        .transfer(&client, request.to_envelope_id, request.amount)
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(EnvelopeTransferResponse::from(transfer)),
+    )
+        .into_response())
+}
+
+pub fn envelopes_router() -> OpenApiRouter<SharedState> {
+    OpenApiRouter::new()
+        .routes(routes!(index, create))
+        .routes(routes!(show, update, delete))
+        .routes(routes!(spends, spend))
+        .routes(routes!(transfer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::envelopes_router;
+    use crate::test_utils::{api_request, state_for_tests};
+    use axum::{
+        Router,
+        http::{Method, StatusCode},
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_envelope_lifecycle() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .nest("/api/v1/envelopes", Router::from(envelopes_router()))
+            .with_state(shared_state)
+            .layer(user_extension);
+
+        let (status, envelope) = api_request(
+            &app,
+            Method::POST,
+            "/api/v1/envelopes",
+            Some(json!({ "name": "Groceries", "amount": "400.00" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let uri = format!("/api/v1/envelopes/{}", envelope["id"].as_str().unwrap());
+
+        let (status, envelope) = api_request(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(json!({ "name": "Food", "amount": 450 })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(envelope["name"], "Food");
+        assert_eq!(envelope["amount"], "450");
+
+        let (status, envelope) = api_request(&app, Method::GET, &uri, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(envelope["name"], "Food");
+
+        let (status, error) =
+            api_request(&app, Method::PUT, &uri, Some(json!({ "name": "Food" }))).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["error"]["details"][0]["field"], "");
+
+        let (status, _) = api_request(&app, Method::DELETE, &uri, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, _) = api_request(&app, Method::DELETE, &uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,780 @@
+use super::{
+    ApiError, ApiJson, ApiPath, ApiQuery, ApiResponse, ErrorResponse, amount_schema,
+    envelopes::EnvelopeResponse, nullable, validate,
+};
+use crate::{
+    SharedState,
+    authenticated::UserExtension,
+    errors::AppError,
+    jobs::convert_goals::{ConversionReport, EnvelopeFill, GoalChange, simulate_convert_goals},
+    models::{
+        envelope::Envelope,
+        goal::{EnvelopePolicy, Goal, Recurrence},
+        goal_contribution::{ContributionSource, GoalContribution},
+    },
+    utilities::dates::FixedTime,
+};
+use anyhow::anyhow;
+use axum::{Extension, Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::{DateTime, Days, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+fn schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "minLength": 2 },
+            "target": amount_schema(),
+            "target_date": { "type": "string", "format": "date" },
+            "recurrence": {
+                "type": "object",
+                "properties": {
+                    "kind": {
+                        "enum": [
+                            "never", "daily", "weekly", "monthly", "quarterly", "yearly",
+                            "every_days", "every_weeks", "every_months", "semi_monthly",
+                            "last_day_of_month"
+                        ]
+                    },
+                    "interval": nullable(json!({ "type": "integer", "minimum": 1, "maximum": 999 })),
+                    "days": nullable(json!({
+                        "type": "array",
+                        "items": { "type": "integer", "minimum": 1, "maximum": 31 },
+                        "minItems": 2,
+                        "maxItems": 2
+                    }))
+                },
+                "required": [ "kind" ],
+                "additionalProperties": false
+            },
+            "envelope_id": nullable(json!({ "type": "string", "format": "uuid" })),
+            "envelope_policy": { "enum": ["append", "replace", "create_new"] },
+            "funding_weight": amount_schema()
+        },
+        "required": [ "name", "target", "target_date", "recurrence" ],
+        "additionalProperties": false
+    })
+}
+
+/// A recurrence with its interval or days, which are null for recurrences that have none.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[schema(as = Recurrence)]
+struct RecurrenceBody {
+    /// One of `never`, `daily`, `weekly`, `monthly`, `quarterly`, `yearly`, `every_days`,
+    /// `every_weeks`, `every_months`, `semi_monthly` or `last_day_of_month`.
+    kind: String,
+    /// How many days, weeks or months apart `every_*` goals recur.
+    #[schema(minimum = 1, maximum = 999)]
+    interval: Option<u32>,
+    /// The two days of the month that `semi_monthly` goals recur on.
+    #[schema(value_type = Option<Vec<u32>>, min_items = 2, max_items = 2)]
+    days: Option<[u32; 2]>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = EnvelopePolicy)]
+enum EnvelopePolicyBody {
+    Append,
+    Replace,
+    CreateNew,
+}
+
+impl From<EnvelopePolicyBody> for EnvelopePolicy {
+    fn from(policy: EnvelopePolicyBody) -> Self {
+        match policy {
+            EnvelopePolicyBody::Append => Self::Append,
+            EnvelopePolicyBody::Replace => Self::Replace,
+            EnvelopePolicyBody::CreateNew => Self::CreateNew,
+        }
+    }
+}
+
+impl From<&EnvelopePolicy> for EnvelopePolicyBody {
+    fn from(policy: &EnvelopePolicy) -> Self {
+        match policy {
+            EnvelopePolicy::Append => Self::Append,
+            EnvelopePolicy::Replace => Self::Replace,
+            EnvelopePolicy::CreateNew => Self::CreateNew,
+        }
+    }
+}
+
+impl RecurrenceBody {
+    fn recurrence(&self) -> Result<Recurrence, AppError> {
+        let interval = || {
+            self.interval.ok_or_else(|| {
+                AppError::InvalidRecord(anyhow!("{} goals need an interval", self.kind))
+            })
+        };
+
+        match self.kind.as_str() {
+            "every_days" => Ok(Recurrence::EveryDays(interval()?)),
+            "every_weeks" => Ok(Recurrence::EveryWeeks(interval()?)),
+            "every_months" => Ok(Recurrence::EveryMonths(interval()?)),
+            "semi_monthly" => {
+                let [first, second] = self.days.ok_or_else(|| {
+                    AppError::InvalidRecord(anyhow!("semi_monthly goals need two days"))
+                })?;
+
+                if first == second {
+                    return Err(AppError::InvalidRecord(anyhow!(
+                        "semi-monthly goals need two different days"
+                    )));
+                }
+
+                Ok(Recurrence::SemiMonthly(
+                    first.min(second),
+                    first.max(second),
+                ))
+            }
+            kind => {
+                Recurrence::from_str(kind).map_err(|e| AppError::InvalidRecord(anyhow!("{:#?}", e)))
+            }
+        }
+    }
+}
+
+impl From<&Recurrence> for RecurrenceBody {
+    fn from(recurrence: &Recurrence) -> Self {
+        Self {
+            kind: recurrence.name().to_string(),
+            interval: recurrence.interval(),
+            days: recurrence.days().map(|(first, second)| [first, second]),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct GoalRequest {
+    #[schema(min_length = 2)]
+    name: String,
+    target: Decimal,
+    target_date: NaiveDate,
+    recurrence: RecurrenceBody,
+    envelope_id: Option<Uuid>,
+    envelope_policy: Option<EnvelopePolicyBody>,
+    funding_weight: Option<Decimal>,
+}
+
+impl GoalRequest {
+    /// Copies the request onto `goal`, after checking that the linked envelope is the user's.
+    async fn apply(self, client: &impl GenericClient, goal: &mut Goal) -> Result<(), ApiError> {
+        if let Some(envelope_id) = self.envelope_id {
+            Envelope::get_one(client, envelope_id, goal.user_id)
+                .await
+                .map_err(|_| {
+                    ApiError::invalid_record("envelope_id is not one of your envelopes")
+                })?;
+        }
+        let funding_weight = self.funding_weight.unwrap_or(Decimal::ONE);
+        if funding_weight <= Decimal::ZERO {
+            return Err(ApiError::invalid_record(
+                "funding weight must be greater than zero",
+            ));
+        }
+
+        goal.name = self.name;
+        goal.target = self.target;
+        goal.set_target_date(NaiveDateTime::new(self.target_date, NaiveTime::MIN).and_utc());
+        goal.recurrence = self.recurrence.recurrence()?;
+        goal.envelope_id = self.envelope_id;
+        goal.envelope_policy = self
+            .envelope_policy
+            .map_or(EnvelopePolicy::Append, EnvelopePolicy::from);
+        goal.funding_weight = funding_weight;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct GoalResponse {
+    id: Option<Uuid>,
+    name: String,
+    target: Decimal,
+    target_date: NaiveDate,
+    recurrence: RecurrenceBody,
+    accumulated_amount: Decimal,
+    accumulated_per_day: Decimal,
+    start_date: Option<DateTime<Utc>>,
+    envelope_id: Option<Uuid>,
+    envelope_policy: EnvelopePolicyBody,
+    priority: i32,
+    funding_weight: Decimal,
+}
+
+impl TryFrom<Goal> for GoalResponse {
+    type Error = ApiError;
+
+    fn try_from(goal: Goal) -> Result<Self, ApiError> {
+        Ok(Self {
+            accumulated_per_day: goal.accumulated_per_day()?,
+            id: goal.id,
+            target: goal.target,
+            target_date: goal.target_date.date_naive(),
+            recurrence: RecurrenceBody::from(&goal.recurrence),
+            accumulated_amount: goal.accumulated_amount,
+            start_date: goal.start_date,
+            envelope_id: goal.envelope_id,
+            envelope_policy: EnvelopePolicyBody::from(&goal.envelope_policy),
+            priority: goal.priority,
+            funding_weight: goal.funding_weight,
+            name: goal.name,
+        })
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "goals",
+    responses((status = 200, body = Vec<GoalResponse>))
+)]
+async fn index(shared_state: State<SharedState>, user: Extension<UserExtension>) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let goals = Goal::get_all(&client, user.id)
+        .await?
+        .into_iter()
+        .map(GoalResponse::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(goals).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "goals",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 200, body = GoalResponse),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn show(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let goal = Goal::get_one(&client, id, user.id).await?;
+
+    Ok(Json(GoalResponse::try_from(goal)?).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "goals",
+    request_body = GoalRequest,
+    responses(
+        (status = 201, body = GoalResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn create(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: GoalRequest = validate(&schema(), body)?;
+    let client = shared_state.pool.get_client().await?;
+    let mut goal = Goal {
+        id: None,
+        user_id: user.id,
+        name: String::new(),
+        recurrence: Recurrence::Never,
+        target_date: Utc::now(),
+        target: Decimal::ZERO,
+        accumulated_amount: Decimal::ZERO,
+        start_date: None,
+        envelope_id: None,
+        envelope_policy: EnvelopePolicy::Append,
+        priority: 0,
+        funding_weight: Decimal::ONE,
+        target_day: None,
+    };
+    request.apply(&client, &mut goal).await?;
+    if goal.recurrence == Recurrence::Never {
+        goal.start_date = Some(Utc::now());
+    }
+    let goal = goal.create(&client).await?;
+
+    Ok((StatusCode::CREATED, Json(GoalResponse::try_from(goal)?)).into_response())
+}
+
+/// Replaces the goal and starts its accumulation over, as editing it in the browser does.
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    tag = "goals",
+    params(("id" = Uuid, Path)),
+    request_body = GoalRequest,
+    responses(
+        (status = 200, body = GoalResponse),
+        (status = 404, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn update(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: GoalRequest = validate(&schema(), body)?;
+    let client = shared_state.pool.get_client().await?;
+    let previous = Goal::get_one(&client, id, user.id).await?;
+    let mut goal = previous.clone();
+    request.apply(&client, &mut goal).await?;
+
+    goal.start_date = match (&goal.recurrence, &previous.recurrence) {
+        (Recurrence::Never, Recurrence::Never) => previous.start_date,
+        (Recurrence::Never, _) => Some(Utc::now()),
+        _ => None,
+    };
+    goal.accumulated_amount = Decimal::ZERO;
+
+    let goal = goal.update(&client).await?;
+    GoalContribution::record(&client, &previous, &goal, ContributionSource::Manual).await?;
+
+    Ok(Json(GoalResponse::try_from(goal)?).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "goals",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 204),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn delete(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let goal = Goal::get_one(&client, id, user.id).await?;
+    goal.delete(&client).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = ContributionSource)]
+enum ContributionSourceBody {
+    Accumulation,
+    Acceleration,
+    Manual,
+    Reset,
+}
+
+impl From<ContributionSource> for ContributionSourceBody {
+    fn from(source: ContributionSource) -> Self {
+        match source {
+            ContributionSource::Accumulation => Self::Accumulation,
+            ContributionSource::Acceleration => Self::Acceleration,
+            ContributionSource::Manual => Self::Manual,
+            ContributionSource::Reset => Self::Reset,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ContributionResponse {
+    id: Uuid,
+    source: ContributionSourceBody,
+    /// The signed change to the goal's accumulated amount.
+    amount: Decimal,
+    /// The goal's accumulated amount after the change.
+    accumulated_amount: Decimal,
+    created_at: DateTime<Utc>,
+}
+
+impl From<GoalContribution> for ContributionResponse {
+    fn from(contribution: GoalContribution) -> Self {
+        Self {
+            id: contribution.id,
+            source: contribution.source.into(),
+            amount: contribution.amount,
+            accumulated_amount: contribution.accumulated_amount,
+            created_at: contribution.created_at,
+        }
+    }
+}
+
+/// Every change to the goal's accumulated amount, newest first.
+#[utoipa::path(
+    get,
+    path = "/{id}/contributions",
+    tag = "goals",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 200, body = Vec<ContributionResponse>),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn contributions(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    Goal::get_one(&client, id, user.id).await?;
+    let contributions: Vec<ContributionResponse> =
+        GoalContribution::get_all_for_goal(&client, id, user.id)
+            .await?
+            .into_iter()
+            .map(ContributionResponse::from)
+            .collect();
+
+    Ok(Json(contributions).into_response())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct SimulationQuery {
+    /// When to simulate, in RFC 3339; a day from now when left out.
+    at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct EnvelopeFillResponse {
+    envelope: EnvelopeResponse,
+    /// Whether the envelope would be created for the goal.
+    created: bool,
+    /// What the envelope would gain.
+    amount: Decimal,
+}
+
+impl From<EnvelopeFill> for EnvelopeFillResponse {
+    fn from(fill: EnvelopeFill) -> Self {
+        Self {
+            envelope: fill.envelope.into(),
+            created: fill.created,
+            amount: fill.amount,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct GoalChangeResponse {
+    goal_id: Option<Uuid>,
+    name: String,
+    source: ContributionSourceBody,
+    amount: Decimal,
+    accumulated_amount: Decimal,
+}
+
+impl From<GoalChange> for GoalChangeResponse {
+    fn from(change: GoalChange) -> Self {
+        Self {
+            goal_id: change.goal_id,
+            name: change.name,
+            source: change.source.into(),
+            amount: change.amount,
+            accumulated_amount: change.accumulated_amount,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct SimulationResponse {
+    envelopes: Vec<EnvelopeFillResponse>,
+    /// Recurring goals that would move on to their next target date.
+    incremented_goals: Vec<GoalResponse>,
+    changes: Vec<GoalChangeResponse>,
+}
+
+impl TryFrom<ConversionReport> for SimulationResponse {
+    type Error = ApiError;
+
+    fn try_from(report: ConversionReport) -> Result<Self, ApiError> {
+        Ok(Self {
+            envelopes: report
+                .envelopes
+                .into_iter()
+                .map(EnvelopeFillResponse::from)
+                .collect(),
+            incremented_goals: report
+                .incremented_goals
+                .into_iter()
+                .map(GoalResponse::try_from)
+                .collect::<Result<_, _>>()?,
+            changes: report
+                .changes
+                .into_iter()
+                .map(GoalChangeResponse::from)
+                .collect(),
+        })
+    }
+}
+
+/// What the background job would do to the user's goals and envelopes at `at`, without
+/// changing anything.
+#[utoipa::path(
+    get,
+    path = "/simulation",
+    tag = "goals",
+    params(SimulationQuery),
+    responses(
+        (status = 200, body = SimulationResponse),
+        (status = 400, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn simulation(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiQuery(query): ApiQuery<SimulationQuery>,
+) -> ApiResponse {
+    let at = query.at.unwrap_or_else(|| Utc::now() + Days::new(1));
+    if at < Utc::now() {
+        return Err(ApiError::invalid_record(
+            "simulations must be in the future",
+        ));
+    }
+
+    let report = simulate_convert_goals(&shared_state.pool, user.id, &FixedTime(at)).await?;
+
+    Ok(Json(SimulationResponse::try_from(report)?).into_response())
+}
+
+pub fn goals_router() -> OpenApiRouter<SharedState> {
+    OpenApiRouter::new()
+        .routes(routes!(index, create))
+        .routes(routes!(show, update, delete))
+        .routes(routes!(contributions))
+        .routes(routes!(simulation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::goals_router;
+    use crate::{
+        models::{
+            envelope::Envelope,
+            goal::{EnvelopePolicy, Goal, Recurrence},
+        },
+        test_utils::{api_request, state_for_tests},
+    };
+    use axum::{
+        Router,
+        http::{Method, StatusCode},
+    };
+    use chrono::{Days, Duration, SecondsFormat, Utc};
+    use rust_decimal::Decimal;
+    use serde_json::{Value, json};
+
+    #[tokio::test]
+    async fn test_goal_lifecycle() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .nest("/api/v1/goals", Router::from(goals_router()))
+            .with_state(shared_state)
+            .layer(user_extension);
+        let target_date = (Utc::now() + Days::new(60)).date_naive().to_string();
+
+        let (status, goal) = api_request(
+            &app,
+            Method::POST,
+            "/api/v1/goals",
+            Some(json!({
+                "name": "Rent",
+                "target": "1500.00",
+                "target_date": target_date,
+                "recurrence": { "kind": "semi_monthly", "days": [15, 1] }
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(
+            goal["recurrence"],
+            json!({ "kind": "semi_monthly", "interval": null, "days": [1, 15] })
+        );
+        assert_eq!(goal["target_date"], target_date);
+        assert_eq!(goal["envelope_policy"], "append");
+        assert_eq!(goal["start_date"], Value::Null);
+        let uri = format!("/api/v1/goals/{}", goal["id"].as_str().unwrap());
+
+        let (status, goal) = api_request(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(json!({
+                "name": "Rent",
+                "target": 1600,
+                "target_date": target_date,
+                "recurrence": { "kind": "never" },
+                "funding_weight": "2.5"
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(goal["recurrence"]["kind"], "never");
+        assert_eq!(goal["funding_weight"], "2.5");
+        assert!(goal["start_date"].is_string());
+
+        let (status, error) = api_request(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(json!({
+                "name": "Rent",
+                "target": 1600,
+                "target_date": target_date,
+                "recurrence": { "kind": "every_weeks" }
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["error"]["code"], "invalid_record");
+
+        let (status, error) = api_request(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(json!({
+                "name": "Rent",
+                "target": 1600,
+                "target_date": target_date,
+                "recurrence": { "kind": "never" },
+                "envelope_id": "00000000-0000-0000-0000-000000000000"
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            error["error"]["message"],
+            "envelope_id is not one of your envelopes"
+        );
+
+        let (status, goals) = api_request(&app, Method::GET, "/api/v1/goals", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(goals[0]["target"], "1600");
+
+        let (status, _) = api_request(&app, Method::DELETE, &uri, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn test_contributions() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+        let goal = Goal {
+            id: None,
+            user_id,
+            name: "car".to_string(),
+            recurrence: Recurrence::Yearly,
+            target_date: Utc::now() + Duration::days(100),
+            target: Decimal::new(1000, 0),
+            accumulated_amount: Decimal::ZERO,
+            start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        goal.accelerate(&client, Decimal::new(25, 0)).await.unwrap();
+        let app = Router::new()
+            .nest("/api/v1/goals", Router::from(goals_router()))
+            .with_state(shared_state)
+            .layer(user_extension);
+
+        let (status, contributions) = api_request(
+            &app,
+            Method::GET,
+            &format!("/api/v1/goals/{}/contributions", goal.id.unwrap()),
+            None,
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(contributions.as_array().unwrap().len(), 1);
+        assert_eq!(contributions[0]["source"], "acceleration");
+        assert_eq!(contributions[0]["amount"], "25");
+    }
+
+    #[tokio::test]
+    async fn test_simulation_does_not_commit() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+        let goal = Goal {
+            id: None,
+            user_id,
+            name: "insurance".to_string(),
+            recurrence: Recurrence::Monthly,
+            target_date: Utc::now() + Duration::days(2),
+            target: Decimal::new(90, 0),
+            accumulated_amount: Decimal::ZERO,
+            start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        let app = Router::new()
+            .nest("/api/v1/goals", Router::from(goals_router()))
+            .with_state(shared_state)
+            .layer(user_extension);
+
+        let at = (Utc::now() + Duration::days(3)).to_rfc3339_opts(SecondsFormat::Secs, true);
+        let (status, report) = api_request(
+            &app,
+            Method::GET,
+            &format!("/api/v1/goals/simulation?at={at}"),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report["envelopes"][0]["created"], true);
+        assert_eq!(report["envelopes"][0]["amount"], "90");
+        assert_eq!(report["incremented_goals"][0]["name"], "insurance");
+
+        assert!(
+            Envelope::get_all(&client, user_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let goal = Goal::get_one(&client, goal.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(goal.accumulated_amount, Decimal::ZERO);
+
+        let (status, error) = api_request(
+            &app,
+            Method::GET,
+            "/api/v1/goals/simulation?at=2000-01-01T00:00:00Z",
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            error["error"]["message"],
+            "simulations must be in the future"
+        );
+    }
+}
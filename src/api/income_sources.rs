@@ -0,0 +1,306 @@
+use super::{ApiJson, ApiPath, ApiResponse, ErrorResponse, amount_schema, validate};
+use crate::{
+    SharedState,
+    authenticated::UserExtension,
+    models::income_source::{IncomeRecurrence, IncomeSource},
+};
+use axum::{Extension, Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+fn schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "minLength": 2 },
+            "amount": amount_schema(),
+            "recurrence": {
+                "enum": ["once", "weekly", "biweekly", "semi_monthly", "monthly", "quarterly", "yearly"]
+            },
+            "next_expected_on": { "type": "string", "format": "date" }
+        },
+        "required": [ "name", "amount", "recurrence", "next_expected_on" ],
+        "additionalProperties": false
+    })
+}
+
+/// How often the income pays out; semi-monthly income pays on the 15th and on the last day of
+/// the month.
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = IncomeRecurrence)]
+enum IncomeRecurrenceBody {
+    Once,
+    Weekly,
+    Biweekly,
+    SemiMonthly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl From<IncomeRecurrenceBody> for IncomeRecurrence {
+    fn from(recurrence: IncomeRecurrenceBody) -> Self {
+        match recurrence {
+            IncomeRecurrenceBody::Once => Self::Once,
+            IncomeRecurrenceBody::Weekly => Self::Weekly,
+            IncomeRecurrenceBody::Biweekly => Self::Biweekly,
+            IncomeRecurrenceBody::SemiMonthly => Self::SemiMonthly,
+            IncomeRecurrenceBody::Monthly => Self::Monthly,
+            IncomeRecurrenceBody::Quarterly => Self::Quarterly,
+            IncomeRecurrenceBody::Yearly => Self::Yearly,
+        }
+    }
+}
+
+impl From<IncomeRecurrence> for IncomeRecurrenceBody {
+    fn from(recurrence: IncomeRecurrence) -> Self {
+        match recurrence {
+            IncomeRecurrence::Once => Self::Once,
+            IncomeRecurrence::Weekly => Self::Weekly,
+            IncomeRecurrence::Biweekly => Self::Biweekly,
+            IncomeRecurrence::SemiMonthly => Self::SemiMonthly,
+            IncomeRecurrence::Monthly => Self::Monthly,
+            IncomeRecurrence::Quarterly => Self::Quarterly,
+            IncomeRecurrence::Yearly => Self::Yearly,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct IncomeSourceRequest {
+    #[schema(min_length = 2)]
+    name: String,
+    amount: Decimal,
+    recurrence: IncomeRecurrenceBody,
+    next_expected_on: NaiveDate,
+}
+
+impl IncomeSourceRequest {
+    fn apply(self, income_source: &mut IncomeSource) {
+        income_source.name = self.name;
+        income_source.amount = self.amount;
+        income_source.recurrence = self.recurrence.into();
+        income_source.next_expected_on = self.next_expected_on;
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct IncomeSourceResponse {
+    id: Option<Uuid>,
+    name: String,
+    amount: Decimal,
+    recurrence: IncomeRecurrenceBody,
+    next_expected_on: NaiveDate,
+}
+
+impl From<IncomeSource> for IncomeSourceResponse {
+    fn from(income_source: IncomeSource) -> Self {
+        Self {
+            id: income_source.id,
+            name: income_source.name,
+            amount: income_source.amount,
+            recurrence: income_source.recurrence.into(),
+            next_expected_on: income_source.next_expected_on,
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "income_sources",
+    responses((status = 200, body = Vec<IncomeSourceResponse>))
+)]
+async fn index(shared_state: State<SharedState>, user: Extension<UserExtension>) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let income_sources: Vec<IncomeSourceResponse> = IncomeSource::get_all(&client, user.id)
+        .await?
+        .into_iter()
+        .map(IncomeSourceResponse::from)
+        .collect();
+
+    Ok(Json(income_sources).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "income_sources",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 200, body = IncomeSourceResponse),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn show(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let income_source = IncomeSource::get_one(&client, id, user.id).await?;
+
+    Ok(Json(IncomeSourceResponse::from(income_source)).into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "income_sources",
+    request_body = IncomeSourceRequest,
+    responses(
+        (status = 201, body = IncomeSourceResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn create(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: IncomeSourceRequest = validate(&schema(), body)?;
+    let mut income_source = IncomeSource {
+        id: None,
+        user_id: user.id,
+        name: String::new(),
+        amount: Decimal::ZERO,
+        recurrence: IncomeRecurrence::Once,
+        next_expected_on: NaiveDate::MIN,
+    };
+    request.apply(&mut income_source);
+
+    let client = shared_state.pool.get_client().await?;
+    let income_source = income_source.create(&client).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(IncomeSourceResponse::from(income_source)),
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    tag = "income_sources",
+    params(("id" = Uuid, Path)),
+    request_body = IncomeSourceRequest,
+    responses(
+        (status = 200, body = IncomeSourceResponse),
+        (status = 404, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn update(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: IncomeSourceRequest = validate(&schema(), body)?;
+    let client = shared_state.pool.get_client().await?;
+    let mut income_source = IncomeSource::get_one(&client, id, user.id).await?;
+    request.apply(&mut income_source);
+    income_source.update(&client).await?;
+
+    Ok(Json(IncomeSourceResponse::from(income_source)).into_response())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "income_sources",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 204),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn delete(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let income_source = IncomeSource::get_one(&client, id, user.id).await?;
+    income_source.delete(&client).await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub fn income_sources_router() -> OpenApiRouter<SharedState> {
+    OpenApiRouter::new()
+        .routes(routes!(index, create))
+        .routes(routes!(show, update, delete))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::income_sources_router;
+    use crate::test_utils::{api_request, state_for_tests};
+    use axum::{
+        Router,
+        http::{Method, StatusCode},
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_income_source_lifecycle() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .nest(
+                "/api/v1/income_sources",
+                Router::from(income_sources_router()),
+            )
+            .with_state(shared_state)
+            .layer(user_extension);
+
+        let (status, income_source) = api_request(
+            &app,
+            Method::POST,
+            "/api/v1/income_sources",
+            Some(json!({
+                "name": "Salary",
+                "amount": "2500.00",
+                "recurrence": "semi_monthly",
+                "next_expected_on": "2024-03-15"
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(income_source["recurrence"], "semi_monthly");
+        let uri = format!(
+            "/api/v1/income_sources/{}",
+            income_source["id"].as_str().unwrap()
+        );
+
+        let (status, error) = api_request(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(json!({
+                "name": "Salary",
+                "amount": 2600,
+                "recurrence": "fortnightly",
+                "next_expected_on": "2024-03-15"
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["error"]["details"][0]["field"], "/recurrence");
+
+        let (status, income_sources) =
+            api_request(&app, Method::GET, "/api/v1/income_sources", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(income_sources[0]["amount"], "2500.00");
+
+        let (status, _) = api_request(&app, Method::DELETE, &uri, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+}
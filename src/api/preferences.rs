@@ -0,0 +1,277 @@
+use super::{ApiError, ApiJson, ApiResponse, ErrorResponse, nullable, validate};
+use crate::{
+    SharedState,
+    authenticated::UserExtension,
+    models::user::{GoalFunding, GoalHeader, Preferences, User},
+    utilities::dates::BudgetPeriod,
+};
+use axum::{Extension, Json, extract::State, response::IntoResponse};
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use utoipa::ToSchema;
+
+fn schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "timezone": nullable(json!({ "type": "string", "minLength": 1 })),
+            "goal_header": { "enum": ["accumulated", "days_remaining", "per_day"] },
+            "forecast_offset": { "type": "integer", "minimum": 1, "maximum": 3 },
+            "budget_period": {
+                "type": "object",
+                "properties": {
+                    "kind": { "enum": ["calendar_month", "semi_monthly", "biweekly", "weekly"] },
+                    "anchor": { "type": "string", "format": "date" }
+                },
+                "required": [ "kind" ],
+                "additionalProperties": false
+            },
+            "goal_funding": { "enum": ["sequential", "weighted"] }
+        },
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = GoalHeader)]
+enum GoalHeaderBody {
+    Accumulated,
+    DaysRemaining,
+    PerDay,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = GoalFunding)]
+enum GoalFundingBody {
+    Sequential,
+    Weighted,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+#[schema(as = BudgetPeriod)]
+enum BudgetPeriodBody {
+    CalendarMonth,
+    SemiMonthly,
+    Biweekly { anchor: NaiveDate },
+    Weekly,
+}
+
+/// Preferences to change; fields that are left out keep their current value.
+#[derive(Debug, Deserialize, ToSchema)]
+struct PreferencesRequest {
+    /// An IANA timezone name, or null for UTC.
+    #[serde(default, deserialize_with = "deserialize_present")]
+    #[schema(value_type = Option<String>)]
+    timezone: Option<Option<String>>,
+    goal_header: Option<GoalHeaderBody>,
+    #[schema(minimum = 1, maximum = 3)]
+    forecast_offset: Option<i64>,
+    budget_period: Option<BudgetPeriodBody>,
+    goal_funding: Option<GoalFundingBody>,
+}
+
+/// Tells a null `timezone`, which resets it to UTC, apart from a missing one.
+fn deserialize_present<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<String>>, D::Error> {
+    Option::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct PreferencesResponse {
+    timezone: String,
+    goal_header: GoalHeaderBody,
+    forecast_offset: i64,
+    budget_period: BudgetPeriodBody,
+    goal_funding: GoalFundingBody,
+}
+
+impl From<&Preferences> for PreferencesResponse {
+    fn from(preferences: &Preferences) -> Self {
+        Self {
+            timezone: preferences
+                .timezone
+                .clone()
+                .unwrap_or_else(|| "UTC".to_string()),
+            goal_header: match preferences.goal_header {
+                None | Some(GoalHeader::Accumulated) => GoalHeaderBody::Accumulated,
+                Some(GoalHeader::DaysRemaining) => GoalHeaderBody::DaysRemaining,
+                Some(GoalHeader::PerDay) => GoalHeaderBody::PerDay,
+            },
+            forecast_offset: preferences.forecast_offset.unwrap_or(1),
+            budget_period: match preferences.budget_period() {
+                BudgetPeriod::CalendarMonth => BudgetPeriodBody::CalendarMonth,
+                BudgetPeriod::SemiMonthly => BudgetPeriodBody::SemiMonthly,
+                BudgetPeriod::Biweekly { anchor } => BudgetPeriodBody::Biweekly { anchor },
+                BudgetPeriod::Weekly => BudgetPeriodBody::Weekly,
+            },
+            goal_funding: match preferences.goal_funding() {
+                GoalFunding::Sequential => GoalFundingBody::Sequential,
+                GoalFunding::Weighted => GoalFundingBody::Weighted,
+            },
+        }
+    }
+}
+
+fn preferences_of(user: &User) -> Preferences {
+    match &user.preferences {
+        Some(preferences) => preferences.0.clone(),
+        None => Preferences {
+            goal_header: None,
+            timezone: None,
+            forecast_offset: None,
+            budget_period: None,
+            goal_funding: None,
+        },
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/preferences",
+    tag = "preferences",
+    responses((status = 200, body = PreferencesResponse))
+)]
+pub async fn show(shared_state: State<SharedState>, user: Extension<UserExtension>) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let user = User::get_by_id(&client, user.id).await?;
+
+    Ok(Json(PreferencesResponse::from(&preferences_of(&user))).into_response())
+}
+
+#[utoipa::path(
+    patch,
+    path = "/preferences",
+    tag = "preferences",
+    request_body = PreferencesRequest,
+    responses(
+        (status = 200, body = PreferencesResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+pub async fn update(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: PreferencesRequest = validate(&schema(), body)?;
+    let client = shared_state.pool.get_client().await?;
+    let mut user = User::get_by_id(&client, user.id).await?;
+    let mut preferences = preferences_of(&user);
+
+    if let Some(timezone) = request.timezone {
+        if let Some(timezone) = &timezone {
+            timezone
+                .parse::<Tz>()
+                .map_err(|_| ApiError::invalid_record(format!("unknown timezone {timezone}")))?;
+        }
+        preferences.timezone = timezone;
+    }
+    if let Some(goal_header) = request.goal_header {
+        preferences.goal_header = Some(match goal_header {
+            GoalHeaderBody::Accumulated => GoalHeader::Accumulated,
+            GoalHeaderBody::DaysRemaining => GoalHeader::DaysRemaining,
+            GoalHeaderBody::PerDay => GoalHeader::PerDay,
+        });
+    }
+    if let Some(forecast_offset) = request.forecast_offset {
+        preferences.forecast_offset = Some(forecast_offset);
+    }
+    if let Some(budget_period) = request.budget_period {
+        preferences.budget_period = Some(match budget_period {
+            BudgetPeriodBody::CalendarMonth => BudgetPeriod::CalendarMonth,
+            BudgetPeriodBody::SemiMonthly => BudgetPeriod::SemiMonthly,
+            BudgetPeriodBody::Biweekly { anchor } => BudgetPeriod::Biweekly { anchor },
+            BudgetPeriodBody::Weekly => BudgetPeriod::Weekly,
+        });
+    }
+    if let Some(goal_funding) = request.goal_funding {
+        preferences.goal_funding = Some(match goal_funding {
+            GoalFundingBody::Sequential => GoalFunding::Sequential,
+            GoalFundingBody::Weighted => GoalFunding::Weighted,
+        });
+    }
+
+    user.preferences = Some(postgres_types::Json(preferences));
+    let user = user.update(&client).await?;
+
+    Ok(Json(PreferencesResponse::from(&preferences_of(&user))).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{show, update};
+    use crate::test_utils::{api_request, state_for_tests};
+    use axum::{
+        Router,
+        http::{Method, StatusCode},
+        routing::get,
+    };
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_update_preferences() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .route("/api/v1/preferences", get(show).patch(update))
+            .with_state(shared_state)
+            .layer(user_extension);
+
+        let (status, preferences) =
+            api_request(&app, Method::GET, "/api/v1/preferences", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            preferences["budget_period"],
+            json!({ "kind": "calendar_month" })
+        );
+
+        let (status, preferences) = api_request(
+            &app,
+            Method::PATCH,
+            "/api/v1/preferences",
+            Some(json!({
+                "timezone": "America/Chicago",
+                "budget_period": { "kind": "biweekly", "anchor": "2024-01-05" },
+                "goal_funding": "weighted"
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            preferences,
+            json!({
+                "timezone": "America/Chicago",
+                "goal_header": "accumulated",
+                "forecast_offset": 1,
+                "budget_period": { "kind": "biweekly", "anchor": "2024-01-05" },
+                "goal_funding": "weighted"
+            })
+        );
+
+        let (status, preferences) = api_request(
+            &app,
+            Method::PATCH,
+            "/api/v1/preferences",
+            Some(json!({ "timezone": null })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(preferences["timezone"], "UTC");
+        assert_eq!(preferences["goal_funding"], "weighted");
+
+        for body in [
+            json!({ "timezone": "Mars/Olympus" }),
+            json!({ "budget_period": { "kind": "biweekly" } }),
+            json!({ "forecast_offset": 7 }),
+        ] {
+            let (status, _) =
+                api_request(&app, Method::PATCH, "/api/v1/preferences", Some(body)).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+}
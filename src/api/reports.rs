@@ -0,0 +1,224 @@
+use super::{ApiError, ApiQuery, ApiResponse, ErrorResponse};
+use crate::{
+    SharedState,
+    authenticated::UserExtension,
+    errors::AppError,
+    models::{
+        dashboard_snapshot::DashboardSnapshot,
+        forecast::{ForecastDay, forecast_for},
+        user::User,
+    },
+    utilities::dates::TimeProvider,
+};
+use anyhow::anyhow;
+use axum::{Extension, Json, extract::State, response::IntoResponse};
+use chrono::{Months, NaiveDate, Utc};
+use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ForecastQuery {
+    /// How far ahead to look; 3 when left out.
+    #[param(minimum = 1, maximum = 24)]
+    months: Option<u32>,
+}
+
+/// The projected spendable balance at the end of one day.
+#[derive(Debug, Serialize, ToSchema)]
+struct ForecastDayResponse {
+    date: NaiveDate,
+    income: Decimal,
+    /// What goals set aside that day, including the shortfall paid in when a goal converts.
+    goals: Decimal,
+    /// The names of goals that convert to envelopes that day.
+    conversions: Vec<String>,
+    balance: Decimal,
+    negative: bool,
+}
+
+impl From<ForecastDay> for ForecastDayResponse {
+    fn from(day: ForecastDay) -> Self {
+        Self {
+            date: day.date,
+            income: day.income,
+            goals: day.goals,
+            conversions: day.conversions,
+            balance: day.balance,
+            negative: day.negative,
+        }
+    }
+}
+
+/// Walks the user's goals and income forward a day at a time from today.
+#[utoipa::path(
+    get,
+    path = "/forecast",
+    tag = "reports",
+    params(ForecastQuery),
+    responses(
+        (status = 200, body = Vec<ForecastDayResponse>),
+        (status = 400, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn forecast(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiQuery(query): ApiQuery<ForecastQuery>,
+) -> ApiResponse {
+    let months = query.months.unwrap_or(3);
+    if !(1..=24).contains(&months) {
+        return Err(ApiError::invalid_record("months must be between 1 and 24"));
+    }
+
+    let client = shared_state.pool.get_client().await?;
+    let user = User::get_by_id(&client, user.id).await?;
+    let days: Vec<ForecastDayResponse> = forecast_for(&client, &user, &TimeProvider {}, months)
+        .await?
+        .into_iter()
+        .map(ForecastDayResponse::from)
+        .collect();
+
+    Ok(Json(days).into_response())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct HistoryQuery {
+    /// How far back to look; 6 when left out.
+    #[param(minimum = 1, maximum = 36)]
+    months: Option<u32>,
+}
+
+/// The dashboard figures as they stood on one day.
+#[derive(Debug, Serialize, ToSchema)]
+struct SnapshotResponse {
+    date: NaiveDate,
+    per_diem: Decimal,
+    remaining_total: Decimal,
+    goals_accumulated_per_day: Decimal,
+}
+
+impl From<DashboardSnapshot> for SnapshotResponse {
+    fn from(snapshot: DashboardSnapshot) -> Self {
+        Self {
+            date: snapshot.snapshot_date,
+            per_diem: snapshot.per_diem,
+            remaining_total: snapshot.remaining_total,
+            goals_accumulated_per_day: snapshot.goals_accumulated_per_day,
+        }
+    }
+}
+
+/// The daily dashboard snapshots, oldest first.
+#[utoipa::path(
+    get,
+    path = "/history",
+    tag = "reports",
+    params(HistoryQuery),
+    responses(
+        (status = 200, body = Vec<SnapshotResponse>),
+        (status = 400, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn history(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiQuery(query): ApiQuery<HistoryQuery>,
+) -> ApiResponse {
+    let months = query.months.unwrap_or(6);
+    if !(1..=36).contains(&months) {
+        return Err(ApiError::invalid_record("months must be between 1 and 36"));
+    }
+
+    let client = shared_state.pool.get_client().await?;
+    let timezone: Tz = User::get_by_id(&client, user.id)
+        .await?
+        .timezone()?
+        .parse()
+        .map_err(AppError::from)?;
+    let since = Utc::now()
+        .with_timezone(&timezone)
+        .date_naive()
+        .checked_sub_months(Months::new(months))
+        .ok_or_else(|| anyhow!("could not subtract months"))?;
+    let snapshots: Vec<SnapshotResponse> = DashboardSnapshot::get_all(&client, user.id, since)
+        .await?
+        .into_iter()
+        .map(SnapshotResponse::from)
+        .collect();
+
+    Ok(Json(snapshots).into_response())
+}
+
+pub fn reports_router() -> OpenApiRouter<SharedState> {
+    OpenApiRouter::new()
+        .routes(routes!(forecast))
+        .routes(routes!(history))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reports_router;
+    use crate::{
+        models::dashboard_snapshot::DashboardSnapshot,
+        test_utils::{api_request, state_for_tests},
+    };
+    use axum::{
+        Router,
+        http::{Method, StatusCode},
+    };
+    use chrono::{Days, Utc};
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_reports() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+        let today = Utc::now().date_naive();
+        for (days_ago, per_diem) in [(2, 30), (1, 35)] {
+            DashboardSnapshot {
+                id: None,
+                user_id: user_extension.0.id,
+                snapshot_date: today - Days::new(days_ago),
+                per_diem: Decimal::new(per_diem, 0),
+                remaining_total: Decimal::new(per_diem * 10, 0),
+                goals_accumulated_per_day: Decimal::new(5, 0),
+                created_at: None,
+            }
+            .create(&client)
+            .await
+            .unwrap();
+        }
+        let app = Router::new()
+            .nest("/api/v1", Router::from(reports_router()))
+            .with_state(shared_state)
+            .layer(user_extension);
+
+        let (status, days) =
+            api_request(&app, Method::GET, "/api/v1/forecast?months=1", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(days.as_array().unwrap().len() >= 28);
+        assert_eq!(days[0]["negative"], false);
+
+        let (status, error) =
+            api_request(&app, Method::GET, "/api/v1/forecast?months=25", None).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(error["error"]["code"], "invalid_record");
+
+        let (status, snapshots) = api_request(&app, Method::GET, "/api/v1/history", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(snapshots.as_array().unwrap().len(), 2);
+        assert_eq!(snapshots[1]["per_diem"], "35");
+
+        let (status, error) =
+            api_request(&app, Method::GET, "/api/v1/history?months=soon", None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(error["error"]["code"], "invalid_query");
+    }
+}
@@ -0,0 +1,352 @@
+use super::{ApiError, ApiJson, ApiPath, ApiResponse, ErrorResponse, nullable, validate};
+use crate::{
+    SharedState,
+    authenticated::UserExtension,
+    models::{account::Account, envelope::Envelope, transaction::Transaction},
+};
+use axum::{Extension, Json, extract::State, http::StatusCode, response::IntoResponse};
+use chrono::NaiveDate;
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+fn schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "account_id": nullable(json!({ "type": "string", "format": "uuid" })),
+            "envelope_id": nullable(json!({ "type": "string", "format": "uuid" })),
+            "amount": {
+                "anyOf": [
+                    { "type": "number", "not": { "const": 0 } },
+                    { "type": "string", "pattern": "^-?[0-9]+(\\.[0-9]+)?$" }
+                ]
+            },
+            "description": { "type": "string", "minLength": 2 },
+            "occurred_on": { "type": "string", "format": "date" }
+        },
+        "required": [ "amount", "description", "occurred_on" ],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct TransactionRequest {
+    account_id: Option<Uuid>,
+    envelope_id: Option<Uuid>,
+    /// Negative for money going out.
+    amount: Decimal,
+    #[schema(min_length = 2)]
+    description: String,
+    occurred_on: NaiveDate,
+}
+
+impl TransactionRequest {
+    /// Copies the request onto `transaction`, after checking that it is tied to at least one of
+    /// the user's accounts or envelopes.
+    async fn apply(
+        self,
+        client: &impl GenericClient,
+        transaction: &mut Transaction,
+    ) -> Result<(), ApiError> {
+        if self.account_id.is_none() && self.envelope_id.is_none() {
+            return Err(ApiError::invalid_record(
+                "a transaction needs an account or an envelope",
+            ));
+        }
+        if self.amount.is_zero() {
+            return Err(ApiError::invalid_record("amount cannot be zero"));
+        }
+        if let Some(account_id) = self.account_id {
+            Account::get_one(client, account_id, transaction.user_id)
+                .await
+                .map_err(|_| ApiError::invalid_record("account_id is not one of your accounts"))?;
+        }
+        if let Some(envelope_id) = self.envelope_id {
+            Envelope::get_one(client, envelope_id, transaction.user_id)
+                .await
+                .map_err(|_| {
+                    ApiError::invalid_record("envelope_id is not one of your envelopes")
+                })?;
+        }
+
+        transaction.account_id = self.account_id;
+        transaction.envelope_id = self.envelope_id;
+        transaction.amount = self.amount;
+        transaction.description = self.description;
+        transaction.occurred_on = self.occurred_on;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(super) struct TransactionResponse {
+    id: Option<Uuid>,
+    account_id: Option<Uuid>,
+    envelope_id: Option<Uuid>,
+    amount: Decimal,
+    description: String,
+    occurred_on: NaiveDate,
+    /// Set on entries that moved a balance to a figure given by hand, which are not spending.
+    adjustment: bool,
+}
+
+impl From<Transaction> for TransactionResponse {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            id: transaction.id,
+            account_id: transaction.account_id,
+            envelope_id: transaction.envelope_id,
+            amount: transaction.amount,
+            description: transaction.description,
+            occurred_on: transaction.occurred_on,
+            adjustment: transaction.adjustment,
+        }
+    }
+}
+
+/// Newest first.
+#[utoipa::path(
+    get,
+    path = "/",
+    tag = "transactions",
+    responses((status = 200, body = Vec<TransactionResponse>))
+)]
+async fn index(shared_state: State<SharedState>, user: Extension<UserExtension>) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let transactions: Vec<TransactionResponse> = Transaction::get_all(&client, user.id)
+        .await?
+        .into_iter()
+        .map(TransactionResponse::from)
+        .collect();
+
+    Ok(Json(transactions).into_response())
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "transactions",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 200, body = TransactionResponse),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn show(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let client = shared_state.pool.get_client().await?;
+    let transaction = Transaction::get_one(&client, id, user.id).await?;
+
+    Ok(Json(TransactionResponse::from(transaction)).into_response())
+}
+
+/// Records the entry and moves its account and envelope balances by `amount`.
+#[utoipa::path(
+    post,
+    path = "/",
+    tag = "transactions",
+    request_body = TransactionRequest,
+    responses(
+        (status = 201, body = TransactionResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn create(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: TransactionRequest = validate(&schema(), body)?;
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let mut transaction = Transaction {
+        id: None,
+        user_id: user.id,
+        account_id: None,
+        envelope_id: None,
+        amount: Decimal::ZERO,
+        description: String::new(),
+        occurred_on: NaiveDate::MIN,
+        adjustment: false,
+    };
+    request.apply(&client, &mut transaction).await?;
+    let transaction = transaction.create(&client).await?;
+    client.commit().await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(TransactionResponse::from(transaction)),
+    )
+        .into_response())
+}
+
+/// Replaces the entry, moving balances back by its old amount and on by its new one.
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    tag = "transactions",
+    params(("id" = Uuid, Path)),
+    request_body = TransactionRequest,
+    responses(
+        (status = 200, body = TransactionResponse),
+        (status = 404, body = ErrorResponse),
+        (status = 422, body = ErrorResponse)
+    )
+)]
+async fn update(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+    ApiJson(body): ApiJson<Value>,
+) -> ApiResponse {
+    let request: TransactionRequest = validate(&schema(), body)?;
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let mut transaction = Transaction::get_one(&client, id, user.id).await?;
+    request.apply(&client, &mut transaction).await?;
+    let transaction = transaction.update(&client).await?;
+    client.commit().await?;
+
+    Ok(Json(TransactionResponse::from(transaction)).into_response())
+}
+
+/// Removes the entry and moves its balances back.
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "transactions",
+    params(("id" = Uuid, Path)),
+    responses(
+        (status = 204),
+        (status = 404, body = ErrorResponse)
+    )
+)]
+async fn delete(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    ApiPath(id): ApiPath<Uuid>,
+) -> ApiResponse {
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let transaction = Transaction::get_one(&client, id, user.id).await?;
+    transaction.delete(&client).await?;
+    client.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub fn transactions_router() -> OpenApiRouter<SharedState> {
+    OpenApiRouter::new()
+        .routes(routes!(index, create))
+        .routes(routes!(show, update, delete))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transactions_router;
+    use crate::{
+        models::account::Account,
+        test_utils::{api_request, state_for_tests},
+    };
+    use axum::{
+        Router,
+        http::{Method, StatusCode},
+    };
+    use rust_decimal::Decimal;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_transaction_lifecycle() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(500, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        let app = Router::new()
+            .nest("/api/v1/transactions", Router::from(transactions_router()))
+            .with_state(shared_state)
+            .layer(user_extension);
+
+        let (status, transaction) = api_request(
+            &app,
+            Method::POST,
+            "/api/v1/transactions",
+            Some(json!({
+                "account_id": account.id,
+                "amount": "-25.50",
+                "description": "market",
+                "occurred_on": "2024-03-01"
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(transaction["amount"], "-25.50");
+        assert_eq!(transaction["adjustment"], false);
+        let uri = format!(
+            "/api/v1/transactions/{}",
+            transaction["id"].as_str().unwrap()
+        );
+
+        let (status, transaction) = api_request(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(json!({
+                "account_id": account.id,
+                "amount": -40,
+                "description": "market",
+                "occurred_on": "2024-03-02"
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(transaction["occurred_on"], "2024-03-02");
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(account.amount, Decimal::new(460, 0));
+
+        let (status, error) = api_request(
+            &app,
+            Method::PUT,
+            &uri,
+            Some(json!({ "amount": 10, "description": "market", "occurred_on": "2024-03-02" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            error["error"]["message"],
+            "a transaction needs an account or an envelope"
+        );
+
+        let (status, _) = api_request(&app, Method::DELETE, &uri, None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(account.amount, Decimal::new(500, 0));
+
+        let (status, _) = api_request(&app, Method::GET, &uri, None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
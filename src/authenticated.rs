@@ -1,8 +1,15 @@
-use crate::{SharedState, models::user::Session};
+use crate::{
+    SharedState,
+    models::{
+        api_token::{ApiToken, TokenScope},
+        user::Session,
+    },
+    utilities::responses::{ResponseFormat, api_only, get_response_format},
+};
 use axum::{
     Extension, Router,
     extract::{Request, State},
-    http::{HeaderMap, Method, StatusCode},
+    http::{HeaderMap, Method, StatusCode, header},
     middleware::{self, Next},
     response::{IntoResponse, Redirect, Response},
     routing::get,
@@ -14,10 +21,15 @@ use axum_extra::extract::{
 use uuid::Uuid;
 
 pub mod accounts;
-mod dashboard;
+pub mod dashboard;
 mod envelopes;
+mod forecast;
 mod goals;
+mod history;
+mod income_sources;
+pub mod live;
 mod preferences;
+mod transactions;
 
 #[derive(Debug, Clone)]
 pub struct UserExtension {
@@ -25,6 +37,39 @@ pub struct UserExtension {
     pub csrf: String,
 }
 
+/// The secret of a personal access token sent as `Authorization: Bearer <secret>`.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+pub fn token_permits(scope: TokenScope, method: &Method) -> bool {
+    scope == TokenScope::Write || method.is_safe()
+}
+
+/// Keeps a route to signed-in browsers. A leaked token must not be able to set up access that
+/// outlives revoking it (new tokens, calendar feeds, webhooks), revoke the user's other
+/// tokens, or replace all of their data with an import.
+pub async fn session_only(request: Request, next: Next) -> Response {
+    if request.extensions().get::<TokenScope>().is_some() {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Keeps JSON requests away from pages whose JSON lives under `/api/v1`, before the handler
+/// changes anything.
+pub async fn pages_only(request: Request, next: Next) -> Response {
+    match get_response_format(request.headers()) {
+        Ok(ResponseFormat::Json) => api_only(),
+        _ => next.run(request).await,
+    }
+}
+
 async fn validate_csrf(
     user: Extension<UserExtension>,
     headers: HeaderMap,
@@ -33,6 +78,15 @@ async fn validate_csrf(
 ) -> Response {
     let method = request.method().to_owned();
 
+    // Tokens are never sent by a browser on its own, so they need no CSRF header.
+    if let Some(scope) = request.extensions().get::<TokenScope>() {
+        return if token_permits(*scope, &method) {
+            next.run(request).await
+        } else {
+            StatusCode::FORBIDDEN.into_response()
+        };
+    }
+
     match method {
         Method::POST | Method::PUT | Method::PATCH | Method::DELETE => {
             let Some(header) = headers.get("x-csrf-token") else {
@@ -54,6 +108,25 @@ async fn authenticated(
     mut request: Request,
     next: Next,
 ) -> Result<(SignedCookieJar, Response), StatusCode> {
+    if let Some(secret) = bearer_token(request.headers()) {
+        let client = state
+            .pool
+            .get_client()
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let token = ApiToken::authenticate(&client, secret)
+            .await
+            .map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+        request.extensions_mut().insert(UserExtension {
+            id: token.user_id,
+            csrf: String::new(),
+        });
+        request.extensions_mut().insert(token.scope);
+
+        return Ok((jar, next.run(request).await));
+    }
+
     let Some(session_id) = jar.get("session_id") else {
         let redirect_cookie = Cookie::build(("redirect_to", request.uri().path().to_owned()))
             .expires(None)
@@ -78,6 +151,8 @@ async fn authenticated(
             id: session.user_id,
             csrf: session.csrf.clone(),
         });
+        // The live socket outlives this request, so it keeps the session to check it again.
+        request.extensions_mut().insert(session);
 
         let cookie = Cookie::build(("session_id", session_id.to_string()))
             .expires(None)
@@ -110,7 +185,101 @@ pub fn authenticated_router(state: SharedState) -> Router<SharedState> {
         .nest("/goals", goals::goals_router())
         .nest("/preferences", preferences::preferences_router())
         .nest("/envelopes", envelopes::envelopes_router())
+        .nest(
+            "/transactions",
+            transactions::transactions_router().route_layer(middleware::from_fn(pages_only)),
+        )
+        .nest(
+            "/income_sources",
+            income_sources::income_sources_router().route_layer(middleware::from_fn(pages_only)),
+        )
+        .nest(
+            "/forecast",
+            forecast::forecast_router().route_layer(middleware::from_fn(pages_only)),
+        )
         .route("/", get(dashboard::index))
+        .route(
+            "/history",
+            get(history::index).route_layer(middleware::from_fn(pages_only)),
+        )
+        .route(
+            "/live",
+            get(live::connect).route_layer(middleware::from_fn(session_only)),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            live::publish_changes,
+        ))
         .route_layer(middleware::from_fn(validate_csrf))
         .route_layer(middleware::from_fn_with_state(state, authenticated))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{authenticated, preferences::preferences_router, session_only, validate_csrf};
+    use crate::{
+        models::api_token::{ApiToken, TokenScope},
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::Body,
+        http::{Method, Request, StatusCode},
+        middleware::{from_fn, from_fn_with_state},
+        routing::get,
+    };
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn test_tokens_skip_csrf_within_their_scope() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+        let (_, read) = ApiToken::create(&client, user_extension.id, "read", TokenScope::Read)
+            .await
+            .unwrap();
+        let (_, write) = ApiToken::create(&client, user_extension.id, "write", TokenScope::Write)
+            .await
+            .unwrap();
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }).post(|| async { "ok" }))
+            .route(
+                "/tokens",
+                get(|| async { "ok" })
+                    .post(|| async { "ok" })
+                    .route_layer(from_fn(session_only)),
+            )
+            .nest("/preferences", preferences_router())
+            .route_layer(from_fn(validate_csrf))
+            .route_layer(from_fn_with_state(shared_state.clone(), authenticated))
+            .layer(context_extension)
+            .with_state(shared_state);
+
+        let webhook = format!("/preferences/webhooks/{}", Uuid::new_v4());
+
+        for (method, uri, token, status) in [
+            (Method::GET, "/", read.as_str(), StatusCode::OK),
+            (Method::POST, "/", read.as_str(), StatusCode::FORBIDDEN),
+            (Method::POST, "/", write.as_str(), StatusCode::OK),
+            (Method::GET, "/", "sb_unknown", StatusCode::UNAUTHORIZED),
+            (Method::GET, "/tokens", read.as_str(), StatusCode::FORBIDDEN),
+            (Method::POST, "/tokens", write.as_str(), StatusCode::FORBIDDEN),
+            (Method::GET, "/preferences/calendar", read.as_str(), StatusCode::OK),
+            (Method::POST, "/preferences/calendar", write.as_str(), StatusCode::FORBIDDEN),
+            (Method::DELETE, "/preferences/calendar", write.as_str(), StatusCode::FORBIDDEN),
+            (Method::POST, "/preferences/import", write.as_str(), StatusCode::FORBIDDEN),
+            (Method::POST, "/preferences/webhooks", write.as_str(), StatusCode::FORBIDDEN),
+            (Method::DELETE, webhook.as_str(), write.as_str(), StatusCode::FORBIDDEN),
+        ] {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", format!("Bearer {token}"))
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+
+            assert_eq!(response.status(), status);
+        }
+    }
+}
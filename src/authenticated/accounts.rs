@@ -1,22 +1,29 @@
 mod create;
+mod debt_plans;
 mod delete;
 mod edit;
+mod imports;
 mod index;
+mod net_worth;
 mod new;
+mod ofx_imports;
 mod update;
 
-use super::UserExtension;
+use super::{UserExtension, pages_only};
+use crate::errors::AppError;
 use crate::{HandlebarsContext, Section, SharedState};
 use axum::{
     Extension, Router,
     extract::Request,
     middleware::{Next, from_fn},
     response::Response,
-    routing::get,
+    routing::{get, post},
 };
 use handlebars::to_json;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::str::FromStr;
 
 fn schema() -> serde_json::Value {
     json!({
@@ -24,7 +31,22 @@ fn schema() -> serde_json::Value {
         "properties": {
             "name": { "type": "string", "minLength": 2 },
             "amount": { "type": "number", "minimum": 0 },
-            "debt": { "anyOf": [{ "enum": [true] }, { "type": "null" }] }
+            "debt": { "anyOf": [{ "enum": [true] }, { "type": "null" }] },
+            "apr": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^((100(\\.0+)?)|([0-9]{1,2}(\\.[0-9]+)?))?$" },
+                    { "type": "null" }
+                ]
+            },
+            "minimum_payment": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^([0-9]+(\\.[0-9]+)?)?$" },
+                    { "type": "null" }
+                ]
+            },
+            "account_number": {
+                "anyOf": [{ "type": "string", "maxLength": 34 }, { "type": "null" }]
+            }
         },
         "required": [ "name", "amount" ],
         "additionalProperties": false
@@ -36,6 +58,40 @@ pub struct AccountForm {
     pub name: String,
     pub amount: f64,
     pub debt: Option<bool>,
+    pub apr: Option<String>,
+    pub minimum_payment: Option<String>,
+    pub account_number: Option<String>,
+}
+
+impl AccountForm {
+    /// Debt terms are only kept for debt accounts.
+    fn debt_term(&self, field: &Option<String>) -> Result<Option<Decimal>, AppError> {
+        if !self.debt.unwrap_or(false) {
+            return Ok(None);
+        }
+
+        field
+            .as_deref()
+            .filter(|value| !value.is_empty())
+            .map(|value| Decimal::from_str(value).map_err(|e| AppError::InvalidRecord(e.into())))
+            .transpose()
+    }
+
+    fn apr(&self) -> Result<Option<Decimal>, AppError> {
+        self.debt_term(&self.apr)
+    }
+
+    fn minimum_payment(&self) -> Result<Option<Decimal>, AppError> {
+        self.debt_term(&self.minimum_payment)
+    }
+
+    fn account_number(&self) -> Option<String> {
+        self.account_number
+            .as_deref()
+            .map(str::trim)
+            .filter(|account_number| !account_number.is_empty())
+            .map(str::to_string)
+    }
 }
 
 async fn initialize_context(
@@ -60,7 +116,35 @@ pub fn accounts_router() -> Router<SharedState> {
             "/{id}",
             get(edit::action).put(update::action).delete(delete::action),
         )
+        .route(
+            "/debt_plan",
+            get(debt_plans::index).route_layer(from_fn(pages_only)),
+        )
+        .route(
+            "/debt_plan/goals",
+            post(debt_plans::goals).route_layer(from_fn(pages_only)),
+        )
         .route("/new", get(new::action))
+        .route(
+            "/net_worth",
+            get(net_worth::action).route_layer(from_fn(pages_only)),
+        )
+        .route(
+            "/ofx",
+            get(ofx_imports::new)
+                .post(ofx_imports::create)
+                .route_layer(from_fn(pages_only)),
+        )
         .route("/{id}/delete", get(delete::modal))
+        .route(
+            "/{id}/import",
+            get(imports::new)
+                .post(imports::create)
+                .route_layer(from_fn(pages_only)),
+        )
+        .route(
+            "/{id}/import/preview",
+            post(imports::preview).route_layer(from_fn(pages_only)),
+        )
         .route_layer(from_fn(initialize_context))
 }
@@ -3,7 +3,10 @@ use crate::HandlebarsContext;
 use crate::authenticated::accounts::schema;
 use crate::errors::AppResponse;
 use crate::{
-    SharedState, authenticated::UserExtension, models::account::Account, utilities::responses,
+    SharedState,
+    authenticated::UserExtension,
+    models::{account::Account, account_balance::AccountBalance},
+    utilities::responses,
 };
 use anyhow::anyhow;
 use axum::{
@@ -37,6 +40,12 @@ pub async fn action(
             context.insert("name".to_string(), to_json(&form.name));
             context.insert("amount".to_string(), to_json(form.amount));
             context.insert("debt".to_string(), to_json(form.debt));
+            context.insert("apr".to_string(), to_json(&form.apr));
+            context.insert(
+                "minimum_payment".to_string(),
+                to_json(&form.minimum_payment),
+            );
+            context.insert("account_number".to_string(), to_json(&form.account_number));
 
             match response_format {
                 responses::ResponseFormat::Html => {
@@ -73,10 +82,21 @@ pub async fn action(
         amount: Decimal::from_f64(form.amount.to_owned())
             .ok_or_else(|| anyhow!("could not parse decimal"))?,
         debt: form.debt.unwrap_or(false),
+        apr: form.apr()?,
+        minimum_payment: form.minimum_payment()?,
+        account_number: form.account_number(),
         user_id: user.id,
     };
-    let client = &shared_state.pool.get_client().await?;
-    account.create(client).await?;
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let account = account.create(&client).await?;
+    AccountBalance::record(
+        &client,
+        account.id.ok_or_else(|| anyhow!("account was not saved"))?,
+        user.id,
+    )
+    .await?;
+    client.commit().await?;
 
     Ok(Redirect::to("/accounts").into_response())
 }
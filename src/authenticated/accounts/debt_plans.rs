@@ -0,0 +1,245 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::{
+        account::Account,
+        debt_plan::{PayoffStrategy, check_extra, plan},
+        goal::{EnvelopePolicy, Goal, Recurrence},
+        user::User,
+    },
+    utilities::{
+        dates::end_of_day,
+        responses::{ResponseFormat, api_only, generate_response, get_response_format},
+    },
+};
+use anyhow::anyhow;
+use axum::{
+    Extension, Form,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use chrono::{Days, Utc};
+use chrono_tz::Tz;
+use handlebars::to_json;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::str::FromStr;
+
+#[derive(Debug, Deserialize)]
+pub struct DebtPlanQuery {
+    extra: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DebtGoalsForm {
+    strategy: PayoffStrategy,
+    extra: Option<String>,
+}
+
+fn extra(extra: &Option<String>) -> Result<Decimal, AppError> {
+    let Some(extra) = extra.as_deref().filter(|extra| !extra.is_empty()) else {
+        return Ok(Decimal::ZERO);
+    };
+
+    check_extra(Decimal::from_str(extra).map_err(|e| AppError::InvalidRecord(e.into()))?)
+}
+
+pub async fn index(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    Query(query): Query<DebtPlanQuery>,
+) -> AppResponse {
+    let extra = extra(&query.extra)?;
+    let client = shared_state.pool.get_client().await?;
+    let user = User::get_by_id(&client, user.id).await?;
+    let timezone: Tz = user.timezone()?.parse()?;
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    let debts = Account::get_all(&client, user.id)
+        .await?
+        .into_iter()
+        .filter(|account| account.debt)
+        .collect::<Vec<_>>();
+
+    let avalanche = plan(&debts, extra, today, PayoffStrategy::Avalanche)?;
+    let snowball = plan(&debts, extra, today, PayoffStrategy::Snowball)?;
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            let mut context = context.clone();
+            context.insert("extra".to_string(), to_json(extra));
+            context.insert(
+                "incomplete".to_string(),
+                to_json(
+                    debts
+                        .iter()
+                        .filter(|account| {
+                            account.apr.is_none() || account.minimum_payment.is_none()
+                        })
+                        .map(|account| &account.name)
+                        .collect::<Vec<_>>(),
+                ),
+            );
+            context.insert("plans".to_string(), to_json([avalanche, snowball]));
+            context.insert("partial".to_string(), to_json("accounts/debt_plan"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+/// Creates a goal for each debt the plan pays off, saving up what the plan pays toward it by
+/// the payoff date.
+pub async fn goals(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Form(form): Form<DebtGoalsForm>,
+) -> AppResponse {
+    let extra = extra(&form.extra)?;
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let user = User::get_by_id(&client, user.id).await?;
+    let timezone: Tz = user.timezone()?.parse()?;
+    let now = Utc::now();
+    let today = now.with_timezone(&timezone).date_naive();
+    let debts = Account::get_all(&client, user.id).await?;
+
+    let mut goals = Vec::new();
+    for payoff in plan(&debts, extra, today, form.strategy)?.payoffs {
+        let Some(paid_off_on) = payoff.paid_off_on else {
+            continue;
+        };
+        let target_date = end_of_day(
+            timezone,
+            paid_off_on
+                .checked_sub_days(Days::new(1))
+                .ok_or_else(|| anyhow!("could not subtract days"))?,
+        )?;
+
+        let goal = Goal {
+            id: None,
+            user_id: user.id,
+            name: format!("Pay off {}", payoff.name),
+            recurrence: Recurrence::Never,
+            target_date,
+            target: payoff.total_paid,
+            accumulated_amount: Decimal::ZERO,
+            start_date: Some(now),
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        }
+        .create(&client)
+        .await?;
+        goals.push(goal);
+    }
+
+    client.commit().await?;
+
+    match get_response_format(&headers)? {
+        ResponseFormat::Html | ResponseFormat::Turbo => Ok(Redirect::to("/goals").into_response()),
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{goals, index};
+    use crate::{
+        models::{account::Account, goal::Goal, goal::Recurrence},
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::Body,
+        http::{Method, Request, StatusCode},
+        routing::{get, post},
+    };
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_debt_plan_creates_goals() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        for (name, amount, apr, minimum) in [("card", 2000, 22, 50), ("car", 5000, 5, 150)] {
+            Account {
+                id: None,
+                user_id,
+                name: name.to_string(),
+                amount: Decimal::new(amount, 0),
+                debt: true,
+                apr: Some(Decimal::new(apr, 0)),
+                minimum_payment: Some(Decimal::new(minimum, 0)),
+                account_number: None,
+            }
+            .create(&client)
+            .await
+            .unwrap();
+        }
+
+        let app = Router::new()
+            .route("/accounts/debt_plan", get(index))
+            .route("/accounts/debt_plan/goals", post(goals))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        for uri in ["/accounts/debt_plan?extra=100", "/accounts/debt_plan"] {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let request = Request::builder()
+            .uri("/accounts/debt_plan")
+            .header("Accept", "application/json")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/accounts/debt_plan/goals")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from("strategy=avalanche&extra=100"))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let goals = Goal::get_all(&client, user_id).await.unwrap();
+        assert_eq!(goals.len(), 2);
+        assert!(
+            goals
+                .iter()
+                .all(|goal| goal.recurrence == Recurrence::Never)
+        );
+        assert_eq!(goals[0].name, "Pay off card");
+        assert!(goals[0].target > Decimal::new(2000, 0));
+        assert!(goals[0].target_date < goals[1].target_date);
+
+        for extra in ["-5", "1000000000.01"] {
+            let request = Request::builder()
+                .uri(format!("/accounts/debt_plan?extra={extra}"))
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+}
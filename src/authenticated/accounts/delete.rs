@@ -107,6 +107,9 @@ mod tests {
             name: "Test Account".to_string(),
             amount: Decimal::new(100, 0),
             debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
         };
         let client = shared_state.pool.get_client().await.unwrap();
         let account = account.create(&client).await.unwrap();
@@ -139,6 +142,9 @@ mod tests {
             name: "Test Account".to_string(),
             amount: Decimal::new(100, 0),
             debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
         };
 
         let account = account.create(&client).await.unwrap();
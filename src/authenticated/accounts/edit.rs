@@ -28,6 +28,15 @@ pub async fn action(
     context.insert("id".to_string(), to_json(account.id));
     context.insert("name".to_string(), to_json(&account.name));
     context.insert("debt".to_string(), to_json(account.debt));
+    context.insert("apr".to_string(), to_json(account.apr));
+    context.insert(
+        "minimum_payment".to_string(),
+        to_json(account.minimum_payment),
+    );
+    context.insert(
+        "account_number".to_string(),
+        to_json(&account.account_number),
+    );
     context.insert("amount".to_string(), to_json(account.amount));
     match response_format {
         ResponseFormat::Html => {
@@ -0,0 +1,576 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::{
+        account::Account,
+        statement_import::{
+            StatementRow, closing_balance,
+            csv::{CsvOptions, DATE_FORMATS, parse},
+            import, mark_duplicates,
+        },
+        transaction::Transaction,
+    },
+    utilities::responses::{ResponseFormat, api_only, generate_response, get_response_format},
+};
+use anyhow::anyhow;
+use axum::{
+    Extension, Form,
+    extract::{Multipart, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use handlebars::to_json;
+use rust_database_common::GenericClient;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use uuid::Uuid;
+
+fn schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "csv": { "type": "string", "minLength": 1 },
+            "date_column": { "type": "string", "pattern": "^[1-9][0-9]*$" },
+            "description_column": { "type": "string", "pattern": "^[1-9][0-9]*$" },
+            "amount_column": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^([1-9][0-9]*)?$" },
+                    { "type": "null" }
+                ]
+            },
+            "balance_column": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^([1-9][0-9]*)?$" },
+                    { "type": "null" }
+                ]
+            },
+            "date_format": { "enum": DATE_FORMATS },
+            "delimiter": { "enum": [",", ";", "tab"] },
+            "decimal_separator": { "enum": [".", ","] },
+            "has_header": { "anyOf": [{ "enum": [true] }, { "type": "null" }] },
+            "flip_signs": { "anyOf": [{ "enum": [true] }, { "type": "null" }] }
+        },
+        "required": [
+            "csv",
+            "date_column",
+            "description_column",
+            "date_format",
+            "delimiter",
+            "decimal_separator"
+        ],
+        "anyOf": [
+            {
+                "properties": { "amount_column": { "type": "string", "minLength": 1 } },
+                "required": ["amount_column"]
+            },
+            {
+                "properties": { "balance_column": { "type": "string", "minLength": 1 } },
+                "required": ["balance_column"]
+            }
+        ],
+        "additionalProperties": false
+    })
+}
+
+/// Column numbers start at 1, as they would in a spreadsheet.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ImportForm {
+    #[serde(default)]
+    csv: String,
+    #[serde(default)]
+    date_column: String,
+    #[serde(default)]
+    description_column: String,
+    amount_column: Option<String>,
+    balance_column: Option<String>,
+    #[serde(default)]
+    date_format: String,
+    #[serde(default)]
+    delimiter: String,
+    #[serde(default)]
+    decimal_separator: String,
+    has_header: Option<bool>,
+    flip_signs: Option<bool>,
+}
+
+impl ImportForm {
+    /// Reads the upload form, taking the statement from its `file` field.
+    async fn from_multipart(mut multipart: Multipart) -> Result<Self, AppError> {
+        let mut fields = Map::new();
+
+        while let Some(field) = multipart
+            .next_field()
+            .await
+            .map_err(|e| AppError::InvalidRecord(e.into()))?
+        {
+            let name = match field.name() {
+                Some("file") => "csv".to_string(),
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let value = field
+                .text()
+                .await
+                .map_err(|e| AppError::InvalidRecord(e.into()))?;
+            let value = match name.as_str() {
+                "has_header" | "flip_signs" => Value::Bool(value == "true"),
+                _ => Value::String(value),
+            };
+
+            fields.insert(name, value);
+        }
+
+        serde_json::from_value(Value::Object(fields)).map_err(|e| AppError::InvalidRecord(e.into()))
+    }
+
+    fn options(&self) -> Result<CsvOptions, AppError> {
+        let column = |value: &str| -> Result<usize, AppError> {
+            value
+                .parse::<usize>()
+                .ok()
+                .and_then(|column| column.checked_sub(1))
+                .ok_or_else(|| AppError::InvalidRecord(anyhow!("invalid column {value}")))
+        };
+        let optional_column = |value: &Option<String>| {
+            value
+                .as_deref()
+                .filter(|value| !value.is_empty())
+                .map(column)
+                .transpose()
+        };
+
+        Ok(CsvOptions {
+            date_column: column(&self.date_column)?,
+            description_column: column(&self.description_column)?,
+            amount_column: optional_column(&self.amount_column)?,
+            balance_column: optional_column(&self.balance_column)?,
+            date_format: self.date_format.clone(),
+            delimiter: match self.delimiter.as_str() {
+                ";" => b';',
+                "tab" => b'\t',
+                _ => b',',
+            },
+            decimal_comma: self.decimal_separator == ",",
+            has_header: self.has_header.unwrap_or(false),
+            flip_signs: self.flip_signs.unwrap_or(false),
+        })
+    }
+
+    /// Validates the form and reads the statement, flagging rows already on the account.
+    async fn rows(
+        &self,
+        client: &impl GenericClient,
+        account: &Account,
+    ) -> Result<Result<Vec<StatementRow>, String>, AppError> {
+        if let Err(validation_errors) =
+            jsonschema::validate(&schema(), &serde_json::to_value(self)?)
+        {
+            return Ok(Err(validation_errors.to_string()));
+        }
+
+        let mut rows = match parse(&self.csv, &self.options()?) {
+            Ok(rows) => rows,
+            Err(e) => return Ok(Err(e.to_string())),
+        };
+        let account_id = account.id.ok_or_else(|| anyhow!("account was not saved"))?;
+        let existing =
+            Transaction::get_all_for_account(client, account_id, account.user_id).await?;
+        mark_duplicates(&mut rows, &existing);
+
+        Ok(Ok(rows))
+    }
+}
+
+fn form_context(
+    context: &HandlebarsContext,
+    account: &Account,
+    form: &ImportForm,
+    errors: Option<String>,
+) -> HandlebarsContext {
+    let mut context = context.clone();
+
+    context.insert("account".to_string(), to_json(account));
+    context.insert("form".to_string(), to_json(form));
+    context.insert("date_formats".to_string(), to_json(DATE_FORMATS));
+    context.insert("errors".to_string(), to_json(errors));
+
+    context
+}
+
+/// Renders `partial` again with the errors, or just the errors for Turbo and JSON requests.
+pub(super) fn invalid(
+    shared_state: &SharedState,
+    response_format: &ResponseFormat,
+    context: HandlebarsContext,
+    partial: &str,
+) -> AppResponse {
+    match response_format {
+        ResponseFormat::Html => {
+            let mut context = context;
+            context.insert("partial".to_string(), to_json(partial));
+
+            Ok(generate_response(
+                response_format,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::BAD_REQUEST,
+            ))
+        }
+        ResponseFormat::Turbo => Ok(generate_response(
+            response_format,
+            shared_state
+                .handlebars
+                .render("accounts/_form.turbo", &context)?,
+            StatusCode::BAD_REQUEST,
+        )),
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+pub async fn new(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    user: Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let account = Account::get_one(&client, id, user.id).await?;
+    let form = ImportForm {
+        date_column: "1".to_string(),
+        description_column: "2".to_string(),
+        amount_column: Some("3".to_string()),
+        date_format: DATE_FORMATS[0].to_string(),
+        delimiter: ",".to_string(),
+        decimal_separator: ".".to_string(),
+        has_header: Some(true),
+        ..Default::default()
+    };
+
+    let mut context = form_context(&context, &account, &form, None);
+    context.insert("partial".to_string(), to_json("accounts/import"));
+
+    Ok(generate_response(
+        &ResponseFormat::Html,
+        shared_state.handlebars.render("layout", &context)?,
+        StatusCode::OK,
+    ))
+}
+
+/// Shows what an upload would import without saving anything.
+pub async fn preview(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    user: Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    multipart: Multipart,
+) -> AppResponse {
+    let response_format = get_response_format(&headers)?;
+    let client = shared_state.pool.get_client().await?;
+    let account = Account::get_one(&client, id, user.id).await?;
+    let form = ImportForm::from_multipart(multipart).await?;
+
+    let rows = match form.rows(&client, &account).await? {
+        Ok(rows) => rows,
+        Err(errors) => {
+            let context = form_context(&context, &account, &form, Some(errors));
+            return invalid(&shared_state, &response_format, context, "accounts/import");
+        }
+    };
+    let duplicates = rows.iter().filter(|row| row.duplicate).count();
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            let mut context = form_context(&context, &account, &form, None);
+            context.insert("rows".to_string(), to_json(&rows));
+            context.insert("duplicates".to_string(), to_json(duplicates));
+            context.insert("importing".to_string(), to_json(rows.len() - duplicates));
+            context.insert("partial".to_string(), to_json("accounts/import_preview"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+pub async fn create(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    user: Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    Form(form): Form<ImportForm>,
+) -> AppResponse {
+    let response_format = get_response_format(&headers)?;
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let account = Account::get_one(&client, id, user.id).await?;
+
+    let rows = match form.rows(&client, &account).await? {
+        Ok(rows) => rows,
+        Err(errors) => {
+            let context = form_context(&context, &account, &form, Some(errors));
+            return invalid(&shared_state, &response_format, context, "accounts/import");
+        }
+    };
+    // The closing balance is as of the statement's last row.
+    let balance = closing_balance(&rows).zip(rows.iter().map(|row| row.occurred_on).max());
+    import(&client, &account, &rows, balance).await?;
+    client.commit().await?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            Ok(Redirect::to("/accounts").into_response())
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create, new, preview};
+    use crate::{
+        models::{
+            account::Account,
+            account_balance::AccountBalance,
+            net_worth::{Granularity, net_worth_for},
+            transaction::Transaction,
+            user::User,
+        },
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Method, Request, StatusCode},
+        routing::{get, post},
+    };
+    use chrono::{Datelike, Days, Months, Utc};
+    use rust_decimal::Decimal;
+    use std::str::from_utf8;
+    use tower::ServiceExt;
+
+    const STATEMENT: &str = "Date,Description,Amount,Balance\n\
+        2024-03-01,Paycheck,2000.00,2100.00\n\
+        2024-03-02,Groceries,-45.10,2054.90\n";
+
+    fn multipart(fields: &[(&str, &str)]) -> Body {
+        let mut body = String::new();
+        for (name, value) in fields {
+            let filename = if *name == "file" {
+                "; filename=\"statement.csv\""
+            } else {
+                ""
+            };
+            body.push_str(&format!(
+                "--boundary\r\nContent-Disposition: form-data; name=\"{name}\"{filename}\r\n\r\n{value}\r\n"
+            ));
+        }
+        body.push_str("--boundary--\r\n");
+
+        Body::from(body)
+    }
+
+    #[tokio::test]
+    async fn test_preview_and_import_statement() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(100, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        let account_id = account.id.unwrap();
+
+        let app = Router::new()
+            .route("/accounts/{id}/import", get(new).post(create))
+            .route("/accounts/{id}/import/preview", post(preview))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .uri(format!("/accounts/{account_id}/import"))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let fields = [
+            ("file", STATEMENT),
+            ("date_column", "1"),
+            ("description_column", "2"),
+            ("amount_column", "3"),
+            ("balance_column", ""),
+            ("date_format", "%Y-%m-%d"),
+            ("delimiter", ","),
+            ("decimal_separator", "."),
+            ("has_header", "true"),
+        ];
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/accounts/{account_id}/import/preview"))
+            .header("content-type", "multipart/form-data; boundary=boundary")
+            .body(multipart(&fields))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(from_utf8(&body).unwrap().contains("Groceries"));
+        assert!(
+            Transaction::get_all_for_account(&client, account_id, user_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let form = form_body(&[
+            ("csv", STATEMENT),
+            ("date_column", "1"),
+            ("description_column", "2"),
+            ("amount_column", "3"),
+            ("date_format", "%Y-%m-%d"),
+            ("delimiter", ","),
+            ("decimal_separator", "."),
+            ("has_header", "true"),
+        ]);
+        for _ in 0..2 {
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri(format!("/accounts/{account_id}/import"))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(form.clone()))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        }
+        assert_eq!(
+            Transaction::get_all_for_account(&client, account_id, user_id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let account = Account::get_one(&client, account_id, user_id)
+            .await
+            .unwrap();
+        assert_eq!(account.amount, Decimal::new(205490, 2));
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/accounts/{account_id}/import/preview"))
+            .header("content-type", "multipart/form-data; boundary=boundary")
+            .body(multipart(&[("file", STATEMENT), ("date_column", "0")]))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_imported_statement_shows_in_net_worth() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(100, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        let account_id = account.id.unwrap();
+        AccountBalance::record(&client, account_id, user_id)
+            .await
+            .unwrap();
+
+        let app = Router::new()
+            .route("/accounts/{id}/import", post(create))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let last_month = (Utc::now().date_naive() - Months::new(1))
+            .with_day(1)
+            .unwrap();
+        let day = |day| last_month.with_day(day).unwrap();
+        let statement = format!(
+            "Date,Description,Amount\n{},Paycheck,2000.00\n{},Groceries,-45.10\n",
+            day(5),
+            day(20)
+        );
+        let form = form_body(&[
+            ("csv", &statement),
+            ("date_column", "1"),
+            ("description_column", "2"),
+            ("amount_column", "3"),
+            ("date_format", "%Y-%m-%d"),
+            ("delimiter", ","),
+            ("decimal_separator", "."),
+            ("has_header", "true"),
+        ]);
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/accounts/{account_id}/import"))
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from(form))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let user = User::get_by_id(&client, user_id).await.unwrap();
+        let today = Utc::now().date_naive() + Days::new(1);
+        let points = net_worth_for(&client, &user, day(1), today, Granularity::Daily)
+            .await
+            .unwrap();
+        let on = |date| {
+            points
+                .iter()
+                .find(|point| point.date == date)
+                .unwrap()
+                .net_worth
+        };
+        assert_eq!(on(day(4)), Decimal::ZERO);
+        assert_eq!(on(day(5)), Decimal::new(2000, 0));
+        assert_eq!(on(day(20)), Decimal::new(195490, 2));
+        assert_eq!(on(today), Decimal::new(205490, 2));
+    }
+
+    fn form_body(fields: &[(&str, &str)]) -> String {
+        fields
+            .iter()
+            .map(|(name, value)| {
+                let value = value
+                    .bytes()
+                    .map(|byte| match byte {
+                        b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' | b'_' => {
+                            (byte as char).to_string()
+                        }
+                        _ => format!("%{byte:02X}"),
+                    })
+                    .collect::<String>();
+                format!("{name}={value}")
+            })
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+}
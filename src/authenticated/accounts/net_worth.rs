@@ -0,0 +1,230 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::{
+        net_worth::{Granularity, NetWorthPoint, net_worth_for, report_range},
+        user::User,
+    },
+    utilities::{
+        charts::{HEIGHT, Scale, WIDTH},
+        responses::{ResponseFormat, api_only, generate_response, get_response_format},
+    },
+};
+use axum::{
+    Extension,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+};
+use chrono::{NaiveDate, Utc};
+use chrono_tz::Tz;
+use handlebars::to_json;
+use rust_decimal::prelude::ToPrimitive;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Debug, Deserialize)]
+pub struct NetWorthQuery {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    granularity: Option<Granularity>,
+}
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    Query(query): Query<NetWorthQuery>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let user = User::get_by_id(&client, user.id).await?;
+    let timezone: Tz = user.timezone()?.parse()?;
+
+    let granularity = query.granularity.unwrap_or_default();
+    let (from, to) = report_range(
+        query.from,
+        query.to,
+        granularity,
+        Utc::now().with_timezone(&timezone).date_naive(),
+    )?;
+
+    let points = net_worth_for(&client, &user, from, to, granularity).await?;
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            let mut context = context.clone();
+            context.insert("from".to_string(), to_json(from));
+            context.insert("to".to_string(), to_json(to));
+            context.insert("granularity".to_string(), to_json(granularity));
+            context.insert("chart".to_string(), chart(&points));
+            context.insert("accounts".to_string(), breakdown(&points));
+            context.insert("latest".to_string(), to_json(points.last()));
+            context.insert(
+                "points".to_string(),
+                to_json(points.iter().rev().collect::<Vec<_>>()),
+            );
+            context.insert("partial".to_string(), to_json("accounts/net_worth"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+fn chart(points: &[NetWorthPoint]) -> serde_json::Value {
+    let net_worth = points
+        .iter()
+        .map(|point| point.net_worth.to_f64().unwrap_or_default())
+        .collect::<Vec<_>>();
+    let scale = Scale::new(&[&net_worth]);
+
+    json!({
+        "width": WIDTH,
+        "height": HEIGHT,
+        "zero": scale.y(0.0),
+        "net_worth": scale.points(&net_worth),
+    })
+}
+
+/// Each account's value at the start and end of the range, for accounts seen at the end.
+fn breakdown(points: &[NetWorthPoint]) -> serde_json::Value {
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        return json!([]);
+    };
+
+    last.accounts
+        .iter()
+        .map(|account| {
+            let start = first
+                .accounts
+                .iter()
+                .find(|start| start.account_id == account.account_id)
+                .map(|start| start.amount);
+
+            json!({
+                "name": account.name,
+                "debt": account.debt,
+                "start": start,
+                "end": account.amount,
+                "change": start.map(|start| account.amount - start),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::action;
+    use crate::{
+        models::{
+            account::Account,
+            net_worth::{Granularity, net_worth_for},
+            user::User,
+        },
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::Body,
+        http::{Method, Request, StatusCode},
+        routing::{get, post},
+    };
+    use chrono::{Days, Utc};
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_updates_show_in_net_worth() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .route(
+                "/accounts",
+                post(crate::authenticated::accounts::create::action),
+            )
+            .route(
+                "/accounts/{id}",
+                post(crate::authenticated::accounts::update::action),
+            )
+            .route("/accounts/net_worth", get(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension.clone())
+            .layer(context_extension);
+
+        for body in [
+            "name=checking&amount=1500",
+            "name=card&amount=400&debt=true",
+        ] {
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri("/accounts")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        }
+
+        let client = shared_state.pool.get_client().await.unwrap();
+        let card = Account::get_all(&client, user_extension.0.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|account| account.debt)
+            .unwrap();
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/accounts/{}", card.id.unwrap()))
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from("name=card&amount=250&debt=true"))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let today = Utc::now().date_naive();
+        let request = Request::builder()
+            .uri(format!(
+                "/accounts/net_worth?from={}&to={}&granularity=daily",
+                today - Days::new(2),
+                today + Days::new(1)
+            ))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let user = User::get_by_id(&client, user_extension.0.id).await.unwrap();
+        let points = net_worth_for(
+            &client,
+            &user,
+            today - Days::new(2),
+            today + Days::new(1),
+            Granularity::Daily,
+        )
+        .await
+        .unwrap();
+        assert_eq!(points.len(), 4);
+        assert_eq!(points[0].accounts.len(), 0);
+        assert_eq!(points[3].net_worth, Decimal::new(1250, 0));
+        assert_eq!(points[3].accounts.len(), 2);
+
+        let request = Request::builder()
+            .uri("/accounts/net_worth?granularity=monthly")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = Request::builder()
+            .uri("/accounts/net_worth?from=2024-02-01&to=2024-01-01")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
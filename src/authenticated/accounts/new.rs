@@ -20,6 +20,9 @@ pub async fn action(
     context.insert("name".to_string(), to_json(""));
     context.insert("amount".to_string(), to_json(""));
     context.insert("debt".to_string(), to_json(""));
+    context.insert("apr".to_string(), to_json(""));
+    context.insert("minimum_payment".to_string(), to_json(""));
+    context.insert("account_number".to_string(), to_json(""));
 
     let response_format = get_response_format(&headers)?;
 
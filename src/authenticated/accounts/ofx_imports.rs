@@ -0,0 +1,259 @@
+use super::imports::invalid;
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::{
+        account::Account,
+        statement_import::{ImportSummary, import, mark_duplicates, ofx::parse},
+        transaction::Transaction,
+    },
+    utilities::responses::{ResponseFormat, api_only, generate_response, get_response_format},
+};
+use anyhow::anyhow;
+use axum::{
+    Extension,
+    extract::{Multipart, State},
+    http::{HeaderMap, StatusCode},
+};
+use handlebars::to_json;
+use serde::Serialize;
+
+/// What happened to one statement in an uploaded file. Statements for account numbers that
+/// match no account are skipped.
+#[derive(Debug, Serialize)]
+pub struct StatementResult {
+    account_number: String,
+    account: Option<Account>,
+    transactions: usize,
+    summary: Option<ImportSummary>,
+}
+
+pub async fn new(
+    shared_state: State<SharedState>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let mut context = context.clone();
+    context.insert("errors".to_string(), to_json(None::<String>));
+    context.insert("partial".to_string(), to_json("accounts/ofx_import"));
+
+    Ok(generate_response(
+        &ResponseFormat::Html,
+        shared_state.handlebars.render("layout", &context)?,
+        StatusCode::OK,
+    ))
+}
+
+async fn read_file(mut multipart: Multipart) -> Result<String, AppError> {
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::InvalidRecord(e.into()))?
+    {
+        if field.name() == Some("file") {
+            let bytes = field
+                .bytes()
+                .await
+                .map_err(|e| AppError::InvalidRecord(e.into()))?;
+
+            return Ok(String::from_utf8_lossy(&bytes).into_owned());
+        }
+    }
+
+    Ok(String::new())
+}
+
+/// Imports every statement in an OFX or QFX file into the account with its account number.
+pub async fn create(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    user: Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    multipart: Multipart,
+) -> AppResponse {
+    let response_format = get_response_format(&headers)?;
+    let file = read_file(multipart).await?;
+
+    let statements = match parse(&file) {
+        Ok(statements) => statements,
+        Err(e) => {
+            let mut context = context.clone();
+            context.insert("errors".to_string(), to_json(e.to_string()));
+            return invalid(
+                &shared_state,
+                &response_format,
+                context,
+                "accounts/ofx_import",
+            );
+        }
+    };
+
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let mut results = Vec::with_capacity(statements.len());
+
+    for mut statement in statements {
+        let account =
+            Account::get_by_account_number(&client, &statement.account_number, user.id).await?;
+        let summary = match &account {
+            Some(account) => {
+                let account_id = account.id.ok_or_else(|| anyhow!("account was not saved"))?;
+                let existing =
+                    Transaction::get_all_for_account(&client, account_id, user.id).await?;
+                mark_duplicates(&mut statement.rows, &existing);
+
+                // Banks report what is owed on a card as a negative balance.
+                let balance = statement
+                    .ledger_balance
+                    .map(|balance| if account.debt { -balance } else { balance })
+                    .zip(statement.balance_on);
+
+                Some(import(&client, account, &statement.rows, balance).await?)
+            }
+            None => None,
+        };
+
+        results.push(StatementResult {
+            account_number: statement.account_number,
+            account,
+            transactions: statement.rows.len(),
+            summary,
+        });
+    }
+
+    client.commit().await?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            let mut context = context.clone();
+            context.insert("results".to_string(), to_json(&results));
+            context.insert("partial".to_string(), to_json("accounts/ofx_summary"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create, new};
+    use crate::{
+        models::{account::Account, transaction::Transaction},
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Method, Request, StatusCode},
+        routing::get,
+    };
+    use rust_decimal::Decimal;
+    use std::str::from_utf8;
+    use tower::ServiceExt;
+
+    fn upload(file: &str) -> Body {
+        Body::from(format!(
+            "--boundary\r\nContent-Disposition: form-data; name=\"file\"; filename=\"statement.ofx\"\r\n\r\n{file}\r\n--boundary--\r\n"
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_import_ofx_statements() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        for (name, debt, account_number) in [
+            ("checking", false, "000123456789"),
+            ("card", true, "4111111111111111"),
+        ] {
+            Account {
+                id: None,
+                user_id,
+                name: name.to_string(),
+                amount: Decimal::ZERO,
+                debt,
+                apr: None,
+                minimum_payment: None,
+                account_number: Some(account_number.to_string()),
+            }
+            .create(&client)
+            .await
+            .unwrap();
+        }
+
+        let app = Router::new()
+            .route("/accounts/ofx", get(new).post(create))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .uri("/accounts/ofx")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for _ in 0..2 {
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri("/accounts/ofx")
+                .header("content-type", "multipart/form-data; boundary=boundary")
+                .body(upload(include_str!("../../../fixtures/ofx/checking.ofx")))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            assert!(from_utf8(&body).unwrap().contains("checking"));
+        }
+
+        let checking = Account::get_by_account_number(&client, "000123456789", user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(checking.amount, Decimal::new(233490, 2));
+        assert_eq!(
+            Transaction::get_all_for_account(&client, checking.id.unwrap(), user_id)
+                .await
+                .unwrap()
+                .iter()
+                .filter(|transaction| !transaction.adjustment)
+                .count(),
+            3
+        );
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/accounts/ofx")
+            .header("content-type", "multipart/form-data; boundary=boundary")
+            .body(upload(include_str!(
+                "../../../fixtures/ofx/credit_card.qfx"
+            )))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(from_utf8(&body).unwrap().contains("4111111111111111"));
+
+        let card = Account::get_by_account_number(&client, "4111111111111111", user_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(card.amount, Decimal::new(51235, 2));
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/accounts/ofx")
+            .header("content-type", "multipart/form-data; boundary=boundary")
+            .body(upload("Date,Description,Amount\n"))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -3,7 +3,7 @@ use crate::{
     HandlebarsContext, SharedState,
     authenticated::UserExtension,
     errors::AppResponse,
-    models::account::Account,
+    models::{account::Account, account_balance::AccountBalance, user::User},
     utilities::responses::{self, generate_response, get_response_format},
 };
 use anyhow::anyhow;
@@ -13,6 +13,8 @@ use axum::{
     http::{HeaderMap, StatusCode},
     response::{IntoResponse, Redirect},
 };
+use chrono::Utc;
+use chrono_tz::Tz;
 use handlebars::to_json;
 use rust_decimal::{Decimal, prelude::FromPrimitive};
 use uuid::Uuid;
@@ -25,7 +27,7 @@ pub async fn action(
     Extension(context): Extension<HandlebarsContext>,
     Form(form): Form<AccountForm>,
 ) -> AppResponse {
-    let client = shared_state.pool.get_client().await?;
+    let mut client = shared_state.pool.get_client().await?;
     let json = serde_json::to_value(&form)?;
     let valid = jsonschema::validate(&schema(), &json);
     let response_format = responses::get_response_format(&headers)?;
@@ -40,6 +42,12 @@ pub async fn action(
             context.insert("name".to_string(), to_json(&form.name));
             context.insert("amount".to_string(), to_json(form.amount));
             context.insert("debt".to_string(), to_json(form.debt));
+            context.insert("apr".to_string(), to_json(&form.apr));
+            context.insert(
+                "minimum_payment".to_string(),
+                to_json(&form.minimum_payment),
+            );
+            context.insert("account_number".to_string(), to_json(&form.account_number));
 
             match response_format {
                 responses::ResponseFormat::Html => {
@@ -68,12 +76,26 @@ pub async fn action(
         }
     }
 
-    let mut account = Account::get_one(&client, id, user.id).await?;
+    let client = client.transaction().await?;
+    let timezone: Tz = User::get_by_id(&client, user.id)
+        .await?
+        .timezone()?
+        .parse()?;
+    let previous = Account::get_one(&client, id, user.id).await?;
+    let mut account = previous.clone();
     account.name = form.name.clone();
-    account.amount =
-        Decimal::from_f64(form.amount).ok_or_else(|| anyhow!("could not parse decimal"))?;
     account.debt = form.debt.unwrap_or(false);
+    account.apr = form.apr()?;
+    account.minimum_payment = form.minimum_payment()?;
+    account.account_number = form.account_number();
     account.update(&client).await?;
+    let balance =
+        Decimal::from_f64(form.amount).ok_or_else(|| anyhow!("could not parse decimal"))?;
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    if !account.adjust_balance(&client, balance, today).await? && account.debt != previous.debt {
+        AccountBalance::record(&client, id, user.id).await?;
+    }
+    client.commit().await?;
     match get_response_format(&headers)? {
         responses::ResponseFormat::Html | responses::ResponseFormat::Turbo => {
             Ok(Redirect::to("/accounts").into_response())
@@ -88,7 +110,10 @@ pub async fn action(
 
 #[cfg(test)]
 mod tests {
-    use crate::{models::account::Account, test_utils::state_for_tests};
+    use crate::{
+        models::{account::Account, transaction::Transaction},
+        test_utils::state_for_tests,
+    };
     use axum::http::{Method, Request, StatusCode};
     use rust_decimal::Decimal;
     use tower::ServiceExt;
@@ -104,6 +129,9 @@ mod tests {
             name: "Test Account".to_string(),
             amount: Decimal::new(100, 0),
             debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
         };
 
         let client = shared_state.pool.get_client().await.unwrap();
@@ -140,5 +168,13 @@ mod tests {
         assert_eq!(account.name, "Updated Account");
         assert_eq!(account.amount, Decimal::new(200, 0));
         assert!(account.debt);
+
+        // Owing 100 more on a debt account is money going out of it.
+        let transactions = Transaction::get_all_for_account(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].amount, Decimal::new(-100, 0));
+        assert_eq!(transactions[0].description, "Balance adjustment");
     }
 }
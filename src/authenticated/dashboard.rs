@@ -1,7 +1,9 @@
 use super::UserExtension;
 use crate::HandlebarsContext;
-use crate::errors::AppResponse;
-use crate::models::goal::Goal;
+use crate::errors::{AppError, AppResponse};
+use crate::models::dashboard_snapshot::DashboardSnapshot;
+use crate::models::envelope::{Envelope, EnvelopeTransfer};
+use crate::models::income_source::IncomeSource;
 use crate::models::user::Preferences;
 use crate::utilities::dates::{TimeProvider, TimeUtilities};
 use crate::utilities::responses::{ResponseFormat, generate_response, get_response_format};
@@ -13,12 +15,14 @@ use axum::{
     http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
-use chrono::{Duration, Local, NaiveTime};
+use chrono::{DateTime, Duration, Local, NaiveTime, Utc};
 use chrono_tz::Tz;
 use handlebars::to_json;
 use rust_database_common::GenericClient;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::FromPrimitive;
+use serde_json::json;
+use std::collections::HashMap;
 
 pub async fn index(
     shared_state: State<SharedState>,
@@ -54,6 +58,8 @@ pub async fn index(
                 "remaining_minutes": context.get("remaining_minutes"),
                 "forecast_offset": context.get("forecast_offset"),
                 "per_diem": context.get("per_diem"),
+                "released_total": context.get("released_total"),
+                "transfers": context.get("transfers"),
             });
 
             Ok(generate_response(
@@ -65,91 +71,190 @@ pub async fn index(
     }
 }
 
+pub struct DashboardTransfer {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub amount: Decimal,
+    pub created_at: DateTime<Tz>,
+}
+
+/// The figures behind the dashboard, measured now for the user's current budget period.
+pub struct DashboardSummary {
+    pub remaining_total: Decimal,
+    pub tomorrow_remaining_total: Decimal,
+    pub goals_accumulated_per_day: Decimal,
+    pub remaining_days: Decimal,
+    pub remaining_minutes: i64,
+    pub forecast_offset: i64,
+    pub per_diem: Decimal,
+    pub per_diem_diff_monthly: Decimal,
+    pub released_total: Decimal,
+    pub transfers: Vec<DashboardTransfer>,
+}
+
+impl DashboardSummary {
+    pub async fn measure(user: &User, client: &impl GenericClient) -> Result<Self, AppError> {
+        let preferences = match &user.preferences {
+            Some(preferences) => &preferences.0,
+            None => &Preferences {
+                goal_header: None,
+                timezone: Some(String::from("UTC")),
+                forecast_offset: None,
+                budget_period: None,
+                goal_funding: None,
+            },
+        };
+
+        let timezone = preferences.timezone.clone().unwrap_or(String::from("UTC"));
+        let timezone: Tz = timezone.parse()?;
+        let time_provider = TimeProvider {};
+        let time_utilities = &TimeUtilities {
+            timezone,
+            period: preferences.budget_period(),
+        };
+
+        let income_per_period = IncomeSource::total_for_period(
+            client,
+            user.id,
+            time_utilities.period,
+            time_utilities.start_of_period(&time_provider)?.date_naive(),
+        )
+        .await?;
+        let length_of_period = time_utilities
+            .length_of_period(&time_provider)?
+            .num_seconds();
+        let income_per_day = income_per_period
+            / Decimal::from_i64(length_of_period)
+                .ok_or(anyhow!("could not convert time to decimal"))?
+            * Decimal::new(86400, 0);
+
+        let snapshot = DashboardSnapshot::measure(client, user, &time_provider).await?;
+        let goals_accumulated = snapshot.goals_accumulated_per_day;
+        let remaining_total = snapshot.remaining_total;
+        let forecast_offset = preferences.forecast_offset.unwrap_or(1);
+        let now = Local::now().with_timezone(&timezone);
+        let tomorrow = (now + Duration::days(forecast_offset))
+            .with_time(
+                NaiveTime::from_hms_opt(0, 0, 0)
+                    .ok_or_else(|| anyhow!("could not construct datetime"))?,
+            )
+            .single()
+            .ok_or_else(|| anyhow!("more than one possible time"))?;
+        let duration_until_tomorrow = tomorrow - now;
+        let seconds_until_tomorrow = duration_until_tomorrow.num_seconds() as f64;
+        let seconds_until_tomorrow = Decimal::from_f64(seconds_until_tomorrow / 86400.0)
+            .ok_or(anyhow!("could not parse decimal"))?;
+        let tomorrow_remaining_total = remaining_total - goals_accumulated * seconds_until_tomorrow;
+        let remaining_days_in_seconds = time_utilities
+            .remaining_length_of_period(&time_provider)?
+            .num_seconds();
+        let remaining_days_in_seconds = Decimal::from_i64(remaining_days_in_seconds)
+            .ok_or(anyhow!("could not parse decimal"))?;
+        let per_diem = snapshot.per_diem;
+
+        let remaining_days = remaining_days_in_seconds / Decimal::new(86400, 0);
+
+        let per_diem_diff_monthly = per_diem - income_per_day;
+
+        let envelope_names: HashMap<_, _> = Envelope::get_all(client, user.id)
+            .await?
+            .into_iter()
+            .filter_map(|envelope| envelope.id.map(|id| (id, envelope.name)))
+            .collect();
+        let transfers = EnvelopeTransfer::get_since(
+            client,
+            user.id,
+            time_utilities
+                .start_of_period(&time_provider)?
+                .with_timezone(&Utc),
+        )
+        .await?;
+        let released_total: Decimal = transfers
+            .iter()
+            .filter(|transfer| transfer.to_envelope_id.is_none())
+            .map(|transfer| transfer.amount)
+            .sum();
+        let transfers = transfers
+            .iter()
+            .map(|transfer| DashboardTransfer {
+                from: envelope_names.get(&transfer.from_envelope_id).cloned(),
+                to: transfer
+                    .to_envelope_id
+                    .and_then(|id| envelope_names.get(&id).cloned()),
+                amount: transfer.amount,
+                created_at: transfer.created_at.with_timezone(&timezone),
+            })
+            .collect();
+
+        Ok(Self {
+            remaining_total,
+            tomorrow_remaining_total,
+            goals_accumulated_per_day: goals_accumulated,
+            remaining_days,
+            remaining_minutes: duration_until_tomorrow.num_minutes(),
+            forecast_offset,
+            per_diem,
+            per_diem_diff_monthly,
+            released_total,
+            transfers,
+        })
+    }
+}
+
 pub async fn generate_dashboard_context_for(
     context: &mut HandlebarsContext,
     user: &User,
     client: &impl GenericClient,
-) -> Result<()> {
-    let preferences = match &user.preferences {
-        Some(preferences) => &preferences.0,
-        None => &Preferences {
-            goal_header: None,
-            timezone: Some(String::from("UTC")),
-            forecast_offset: None,
-            monthly_income: Some(Decimal::ZERO),
-        },
-    };
-
-    let timezone = preferences.timezone.clone().unwrap_or(String::from("UTC"));
-    let timezone: Tz = timezone.parse()?;
-    let time_provider = TimeProvider {};
-    let time_utilities = &TimeUtilities { timezone };
-
-    let monthly_income = preferences.monthly_income.unwrap_or(Decimal::ZERO);
-    let length_of_month = time_utilities
-        .length_of_month(&time_provider)?
-        .num_seconds();
-    let monthly_income_per_day = monthly_income
-        / Decimal::from_i64(length_of_month).ok_or(anyhow!("could not convert time to decimal"))?
-        * Decimal::new(86400, 0);
-
-    let goals = Goal::get_all(client, user.id).await.unwrap_or(vec![]);
-    let goals_accumulated = goals
+) -> Result<(), AppError> {
+    let summary = DashboardSummary::measure(user, client).await?;
+
+    let transfers: Vec<_> = summary
+        .transfers
         .iter()
-        .map(|g| g.accumulated_per_day())
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .reduce(|memo, d| memo + d)
-        .unwrap_or(Decimal::ZERO);
-    let remaining_total = user.total_balance(client).await?;
-    let forecast_offset = preferences.forecast_offset.unwrap_or(1);
-    let now = Local::now().with_timezone(&timezone);
-    let tomorrow = (now + Duration::days(forecast_offset))
-        .with_time(
-            NaiveTime::from_hms_opt(0, 0, 0)
-                .ok_or_else(|| anyhow!("could not construct datetime"))?,
-        )
-        .single()
-        .ok_or_else(|| anyhow!("more than one possible time"))?;
-    let duration_until_tomorrow = tomorrow - now;
-    let seconds_until_tomorrow = duration_until_tomorrow.num_seconds() as f64;
-    let seconds_until_tomorrow = Decimal::from_f64(seconds_until_tomorrow / 86400.0)
-        .ok_or(anyhow!("could not parse decimal"))?;
-    let tomorrow_remaining_total = remaining_total - goals_accumulated * seconds_until_tomorrow;
-    let remaining_days_in_seconds = time_utilities
-        .remaining_length_of_month(&time_provider)?
-        .num_seconds();
-    let remaining_days_in_seconds =
-        Decimal::from_i64(remaining_days_in_seconds).ok_or(anyhow!("could not parse decimal"))?;
-    let per_diem = remaining_total / remaining_days_in_seconds * Decimal::new(86400, 0);
-    let forecast_offset = Decimal::from_i64(preferences.forecast_offset.unwrap_or(1))
-        .ok_or(anyhow!("could not parse decimal"))?;
-
-    let remaining_days = remaining_days_in_seconds / Decimal::new(86400, 0);
-    let remaining_days = remaining_days.round_dp(1).to_string();
-
-    let per_diem_diff_monthly = per_diem - monthly_income_per_day;
+        .map(|transfer| {
+            json!({
+                "from": transfer.from,
+                "to": transfer.to,
+                "amount": transfer.amount,
+                "created_at": transfer.created_at.format("%b %-d").to_string(),
+            })
+        })
+        .collect();
 
     context.insert(
         "tomorrow_remaining_total".to_string(),
-        to_json(tomorrow_remaining_total),
+        to_json(summary.tomorrow_remaining_total),
     );
     context.insert(
         "goals_accumulated_per_day".to_string(),
-        to_json(goals_accumulated),
+        to_json(summary.goals_accumulated_per_day),
+    );
+    context.insert(
+        "remaining_days".to_string(),
+        to_json(summary.remaining_days.round_dp(1).to_string()),
     );
-    context.insert("remaining_days".to_string(), to_json(remaining_days));
     context.insert(
         "remaining_minutes".to_string(),
-        to_json(duration_until_tomorrow.num_minutes()),
+        to_json(summary.remaining_minutes),
     );
 
-    context.insert("remaining_total".to_string(), to_json(remaining_total));
-    context.insert("forecast_offset".to_string(), to_json(forecast_offset));
-    context.insert("per_diem".to_string(), to_json(per_diem));
+    context.insert(
+        "remaining_total".to_string(),
+        to_json(summary.remaining_total),
+    );
+    context.insert(
+        "forecast_offset".to_string(),
+        to_json(Decimal::from(summary.forecast_offset)),
+    );
+    context.insert("per_diem".to_string(), to_json(summary.per_diem));
     context.insert(
         "per_diem_diff_monthly".to_string(),
-        to_json(per_diem_diff_monthly),
+        to_json(summary.per_diem_diff_monthly),
+    );
+    context.insert("transfers".to_string(), to_json(transfers));
+    context.insert(
+        "released_total".to_string(),
+        to_json(summary.released_total),
     );
 
     Ok(())
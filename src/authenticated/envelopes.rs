@@ -2,17 +2,20 @@ mod create;
 mod delete;
 mod edit;
 mod index;
+mod merges;
 mod new;
+mod spends;
+mod transfers;
 mod update;
 
-use super::UserExtension;
+use super::{UserExtension, pages_only};
 use crate::{HandlebarsContext, Section, SharedState};
 use axum::{
     Extension, Router,
     extract::Request,
     middleware::{Next, from_fn},
     response::Response,
-    routing::get,
+    routing::{get, post},
 };
 use handlebars::to_json;
 use serde::{Deserialize, Serialize};
@@ -36,6 +39,51 @@ pub struct EnvelopeForm {
     pub amount: f64,
 }
 
+fn spend_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "amount": { "type": "number", "exclusiveMinimum": 0 },
+            "description": { "type": "string", "minLength": 2 },
+            "account_id": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^([0-9a-fA-F-]{36})?$" },
+                    { "type": "null" }
+                ]
+            },
+            "occurred_on": { "type": "string", "format": "date" }
+        },
+        "required": [ "amount", "description", "occurred_on" ],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SpendForm {
+    pub amount: f64,
+    pub description: String,
+    pub account_id: Option<String>,
+    pub occurred_on: chrono::NaiveDate,
+}
+
+fn transfer_schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "amount": { "type": "number", "exclusiveMinimum": 0 },
+            "to_envelope_id": { "type": "string", "pattern": "^([0-9a-fA-F-]{36})?$" }
+        },
+        "required": [ "amount", "to_envelope_id" ],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TransferForm {
+    pub amount: f64,
+    pub to_envelope_id: String,
+}
+
 async fn initialize_context(
     Extension(user_extension): Extension<UserExtension>,
     Extension(context): Extension<HandlebarsContext>,
@@ -60,6 +108,25 @@ pub fn envelopes_router() -> Router<SharedState> {
             get(edit::action).put(update::action).delete(delete::action),
         )
         .route("/new", get(new::action))
+        .route("/merges", post(merges::action))
         .route("/{id}/delete", get(delete::modal))
+        .route(
+            "/{id}/spends",
+            get(spends::index)
+                .post(spends::action)
+                .route_layer(from_fn(pages_only)),
+        )
+        .route(
+            "/{id}/spends/new",
+            get(spends::modal).route_layer(from_fn(pages_only)),
+        )
+        .route(
+            "/{id}/transfers",
+            post(transfers::action).route_layer(from_fn(pages_only)),
+        )
+        .route(
+            "/{id}/transfers/new",
+            get(transfers::modal).route_layer(from_fn(pages_only)),
+        )
         .route_layer(from_fn(initialize_context))
 }
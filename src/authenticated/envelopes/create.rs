@@ -1,7 +1,12 @@
 use super::{EnvelopeForm, schema};
 use crate::errors::AppResponse;
 use crate::{
-    HandlebarsContext, SharedState, authenticated::UserExtension, models::envelope::Envelope,
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    models::{
+        envelope::Envelope,
+        webhook::{WebhookEndpoint, WebhookEvent},
+    },
     utilities::responses,
 };
 use anyhow::anyhow;
@@ -14,6 +19,7 @@ use axum::{
 use handlebars::to_json;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::FromPrimitive;
+use serde_json::json;
 
 pub async fn action(
     shared_state: State<SharedState>,
@@ -22,7 +28,7 @@ pub async fn action(
     Extension(context): Extension<HandlebarsContext>,
     Form(form): Form<EnvelopeForm>,
 ) -> AppResponse {
-    let client = shared_state.pool.get_client().await?;
+    let mut client = shared_state.pool.get_client().await?;
     let json = serde_json::to_value(&form)?;
     let valid = jsonschema::validate(&schema(), &json);
 
@@ -74,7 +80,16 @@ pub async fn action(
         user_id: user.id,
     };
 
-    envelope.create(&client).await?;
+    let client = client.transaction().await?;
+    let envelope = envelope.create(&client).await?;
+    WebhookEndpoint::publish(
+        &client,
+        user.id,
+        WebhookEvent::EnvelopeCreated,
+        json!({ "envelope": envelope }),
+    )
+    .await?;
+    client.commit().await?;
 
     Ok(Redirect::to("/envelopes").into_response())
 }
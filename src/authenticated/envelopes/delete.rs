@@ -2,7 +2,10 @@ use crate::{
     HandlebarsContext, SharedState,
     authenticated::UserExtension,
     errors::AppResponse,
-    models::envelope::Envelope,
+    models::{
+        envelope::Envelope,
+        webhook::{WebhookEndpoint, WebhookEvent},
+    },
     utilities::responses::{ResponseFormat, generate_response, get_response_format},
 };
 use axum::{
@@ -12,6 +15,7 @@ use axum::{
     response::IntoResponse,
 };
 use handlebars::to_json;
+use serde_json::json;
 use uuid::Uuid;
 
 pub async fn modal(
@@ -59,9 +63,18 @@ pub async fn action(
     Extension(user): Extension<UserExtension>,
     Extension(context): Extension<HandlebarsContext>,
 ) -> AppResponse {
-    let client = shared_state.pool.get_client().await?;
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
     let envelope = Envelope::get_one(&client, id, user.id).await?;
     envelope.delete(&client).await?;
+    WebhookEndpoint::publish(
+        &client,
+        user.id,
+        WebhookEvent::EnvelopeDeleted,
+        json!({ "envelope": envelope }),
+    )
+    .await?;
+    client.commit().await?;
     let response_format = get_response_format(&headers)?;
 
     match response_format {
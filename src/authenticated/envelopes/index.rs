@@ -11,6 +11,9 @@ use axum::{
     http::{HeaderMap, StatusCode},
 };
 use handlebars::to_json;
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::collections::HashSet;
 
 pub async fn action(
     shared_state: State<SharedState>,
@@ -25,7 +28,29 @@ pub async fn action(
 
     match response_format {
         ResponseFormat::Html | ResponseFormat::Turbo => {
-            context.insert("envelopes".to_string(), to_json(envelopes));
+            let spent = Envelope::spent_by_envelope(&client, user.id).await?;
+            let rows: Vec<_> = envelopes
+                .iter()
+                .map(|envelope| {
+                    json!({
+                        "id": envelope.id,
+                        "name": envelope.name,
+                        "amount": envelope.amount,
+                        "spent": envelope
+                            .id
+                            .and_then(|id| spent.get(&id).copied())
+                            .unwrap_or(Decimal::ZERO),
+                    })
+                })
+                .collect();
+
+            let mut names = HashSet::with_capacity(envelopes.len());
+            let has_duplicates = !envelopes
+                .iter()
+                .all(|envelope| names.insert(&envelope.name));
+
+            context.insert("envelopes".to_string(), to_json(rows));
+            context.insert("has_duplicates".to_string(), to_json(has_duplicates));
             context.insert("partial".to_string(), to_json("envelopes/index"));
 
             Ok(generate_response(
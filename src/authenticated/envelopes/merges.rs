@@ -0,0 +1,102 @@
+use crate::{
+    SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::{envelope::Envelope, user::User},
+    utilities::responses::{ResponseFormat, generate_response, get_response_format},
+};
+use axum::{
+    Extension, Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use chrono::Utc;
+use chrono_tz::Tz;
+use serde_json::json;
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+) -> AppResponse {
+    let response_format = get_response_format(&headers)?;
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+
+    let timezone: Tz = User::get_by_id(&client, user.id)
+        .await?
+        .timezone()?
+        .parse()?;
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    let merged = Envelope::merge_duplicates(&client, user.id, today).await?;
+
+    client.commit().await?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            Ok(Redirect::to("/envelopes").into_response())
+        }
+        ResponseFormat::Json => Ok(generate_response(
+            &response_format,
+            Json(json!({ "merged": merged })),
+            StatusCode::OK,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::state_for_tests;
+    use axum::Router;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use axum::routing::post;
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_merge_duplicates_json() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        for _ in 0..2 {
+            Envelope {
+                id: None,
+                user_id,
+                name: "rent".to_string(),
+                amount: Decimal::new(100, 0),
+            }
+            .create(&client)
+            .await
+            .unwrap();
+        }
+
+        let app = Router::new()
+            .route("/envelopes/merges", post(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/envelopes/merges")
+            .header("Accept", "application/json")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = to_bytes(body, usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+        assert_eq!(parts.status, StatusCode::OK);
+        assert_eq!(body["merged"], 1);
+
+        let envelopes = Envelope::get_all(&client, user_id).await.unwrap();
+        assert_eq!(envelopes.len(), 1);
+        assert_eq!(envelopes[0].amount, Decimal::new(200, 0));
+    }
+}
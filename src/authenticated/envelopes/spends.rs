@@ -0,0 +1,374 @@
+use super::{SpendForm, spend_schema};
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::{account::Account, envelope::Envelope, transaction::Transaction, user::User},
+    utilities::responses::{ResponseFormat, api_only, generate_response, get_response_format},
+};
+use anyhow::anyhow;
+use axum::{
+    Extension, Form,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use chrono::Utc;
+use chrono_tz::Tz;
+use handlebars::to_json;
+use rust_decimal::{Decimal, prelude::FromPrimitive};
+use serde_json::json;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+pub async fn index(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let envelope = Envelope::get_one(&client, id, user.id).await?;
+    let spends: Vec<_> = Transaction::get_all_for_envelope(&client, id, user.id)
+        .await?
+        .into_iter()
+        .filter(|transaction| !transaction.adjustment)
+        .collect();
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            let accounts: HashMap<_, _> = Account::get_all(&client, user.id)
+                .await?
+                .into_iter()
+                .map(|account| (account.id, account.name))
+                .collect();
+            let rows: Vec<_> = spends
+                .iter()
+                .map(|spend| {
+                    json!({
+                        "occurred_on": spend.occurred_on,
+                        "description": spend.description,
+                        "amount": -spend.amount,
+                        "account": spend.account_id.and_then(|id| accounts.get(&Some(id))),
+                    })
+                })
+                .collect();
+
+            let mut context = context.clone();
+            context.insert("spent".to_string(), to_json(envelope.spent(&client).await?));
+            context.insert("envelope".to_string(), to_json(envelope));
+            context.insert("spends".to_string(), to_json(rows));
+            context.insert("partial".to_string(), to_json("envelopes/spends"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+pub async fn modal(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let envelope = Envelope::get_one(&client, id, user.id).await?;
+    let accounts = Account::get_all(&client, user.id).await?;
+    let timezone: Tz = User::get_by_id(&client, user.id)
+        .await?
+        .timezone()?
+        .parse()?;
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html => {
+            let mut context = context.clone();
+            context.insert("envelope".to_string(), to_json(envelope));
+            context.insert("accounts".to_string(), to_json(accounts));
+            context.insert(
+                "occurred_on".to_string(),
+                to_json(
+                    Utc::now()
+                        .with_timezone(&timezone)
+                        .date_naive()
+                        .format("%Y-%m-%d")
+                        .to_string(),
+                ),
+            );
+
+            Ok(generate_response(
+                &response_format,
+                shared_state
+                    .handlebars
+                    .render("envelopes/spend", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Turbo => Ok(StatusCode::NOT_ACCEPTABLE.into_response()),
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+fn invalid(
+    shared_state: &SharedState,
+    response_format: &ResponseFormat,
+    context: &HandlebarsContext,
+    errors: String,
+) -> AppResponse {
+    let mut context = context.clone();
+    context.insert("errors".to_string(), to_json(errors));
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => Ok(generate_response(
+            &ResponseFormat::Turbo,
+            shared_state
+                .handlebars
+                .render("envelopes/_form.turbo", &context)?,
+            StatusCode::BAD_REQUEST,
+        )),
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    Form(form): Form<SpendForm>,
+) -> AppResponse {
+    let mut client = shared_state.pool.get_client().await?;
+    let envelope = Envelope::get_one(&client, id, user.id).await?;
+    let response_format = get_response_format(&headers)?;
+
+    let json = serde_json::to_value(&form)?;
+    if let Err(validation_errors) = jsonschema::validate(&spend_schema(), &json) {
+        return invalid(
+            &shared_state,
+            &response_format,
+            &context,
+            validation_errors.to_string(),
+        );
+    }
+
+    let account_id = match form.account_id.as_deref() {
+        None | Some("") => None,
+        Some(account_id) => {
+            Some(Uuid::parse_str(account_id).map_err(|e| AppError::InvalidRecord(e.into()))?)
+        }
+    };
+
+    let client = client.transaction().await?;
+    if let Some(account_id) = account_id {
+        Account::get_one(&client, account_id, user.id).await?;
+    }
+
+    match envelope
+        .spend(
+            &client,
+            Decimal::from_f64(form.amount).ok_or_else(|| anyhow!("could not parse decimal"))?,
+            account_id,
+            form.description.clone(),
+            form.occurred_on,
+        )
+        .await
+    {
+        Ok(_) => {}
+        Err(AppError::InvalidRecord(err)) => {
+            return invalid(&shared_state, &response_format, &context, err.to_string());
+        }
+        Err(err) => return Err(err),
+    }
+
+    let envelope = Envelope::get_one(&client, id, user.id).await?;
+    let spent = envelope.spent(&client).await?;
+
+    client.commit().await?;
+
+    match response_format {
+        ResponseFormat::Html => Ok(Redirect::to("/envelopes").into_response()),
+        ResponseFormat::Turbo => {
+            let mut context = context.clone();
+            context.insert("envelope".to_string(), to_json(&envelope));
+            context.insert("spent".to_string(), to_json(spent));
+
+            Ok(generate_response(
+                &response_format,
+                shared_state
+                    .handlebars
+                    .render("envelopes/spends.turbo", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::user::Preferences;
+    use crate::test_utils::state_for_tests;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::body::to_bytes;
+    use axum::http::Request;
+    use axum::routing::{get, post};
+    use postgres_types::Json;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_spend_from_envelope() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(500, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let envelope = Envelope {
+            id: None,
+            user_id,
+            name: "groceries".to_string(),
+            amount: Decimal::new(100, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/envelopes/{id}/spends", post(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/envelopes/{}/spends", envelope.id.unwrap()))
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("Accept", "text/vnd.turbo-stream.html")
+            .body(Body::from(format!(
+                "amount=42&description=market&account_id={}&occurred_on=2024-03-01",
+                account.id.unwrap()
+            )))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let envelope = Envelope::get_one(&client, envelope.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(envelope.amount, Decimal::new(58, 0));
+        assert_eq!(account.amount, Decimal::new(458, 0));
+    }
+
+    #[tokio::test]
+    async fn test_spend_more_than_remaining() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let envelope = Envelope {
+            id: None,
+            user_id,
+            name: "groceries".to_string(),
+            amount: Decimal::new(10, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/envelopes/{id}/spends", post(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/envelopes/{}/spends", envelope.id.unwrap()))
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("Accept", "text/vnd.turbo-stream.html")
+            .body(Body::from(
+                "amount=42&description=market&account_id=&occurred_on=2024-03-01",
+            ))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let envelope = Envelope::get_one(&client, envelope.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(envelope.amount, Decimal::new(10, 0));
+    }
+
+    #[tokio::test]
+    async fn test_spend_modal_dates_in_users_timezone() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let mut user = User::get_by_id(&client, user_id).await.unwrap();
+        let mut preferences = Preferences::default();
+        preferences.timezone = Some("Pacific/Kiritimati".to_string());
+        user.preferences = Some(Json(preferences));
+        user.update(&client).await.unwrap();
+
+        let envelope = Envelope {
+            id: None,
+            user_id,
+            name: "groceries".to_string(),
+            amount: Decimal::new(100, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/envelopes/{id}/spends/new", get(modal))
+            .with_state(shared_state)
+            .layer(user_extension)
+            .layer(context_extension);
+        let request = Request::builder()
+            .uri(format!("/envelopes/{}/spends/new", envelope.id.unwrap()))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let today = Utc::now()
+            .with_timezone(&chrono_tz::Pacific::Kiritimati)
+            .date_naive()
+            .format("%Y-%m-%d")
+            .to_string();
+        assert!(String::from_utf8(body.to_vec()).unwrap().contains(&today));
+    }
+}
@@ -0,0 +1,248 @@
+use super::{TransferForm, transfer_schema};
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::envelope::Envelope,
+    utilities::responses::{ResponseFormat, api_only, generate_response, get_response_format},
+};
+use anyhow::anyhow;
+use axum::{
+    Extension, Form,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use handlebars::to_json;
+use rust_decimal::{Decimal, prelude::FromPrimitive};
+use serde_json::json;
+use uuid::Uuid;
+
+pub async fn modal(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let envelope = Envelope::get_one(&client, id, user.id).await?;
+    let destinations: Vec<_> = Envelope::get_all(&client, user.id)
+        .await?
+        .into_iter()
+        .filter(|destination| destination.id != envelope.id)
+        .collect();
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html => {
+            let mut context = context.clone();
+            context.insert("envelope".to_string(), to_json(envelope));
+            context.insert("destinations".to_string(), to_json(destinations));
+
+            Ok(generate_response(
+                &response_format,
+                shared_state
+                    .handlebars
+                    .render("envelopes/transfer", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Turbo => Ok(StatusCode::NOT_ACCEPTABLE.into_response()),
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    Form(form): Form<TransferForm>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let envelope = Envelope::get_one(&client, id, user.id).await?;
+    let response_format = get_response_format(&headers)?;
+
+    let json = serde_json::to_value(&form)?;
+    let transfer = match jsonschema::validate(&transfer_schema(), &json) {
+        Err(validation_errors) => Err(validation_errors.to_string()),
+        Ok(_) => {
+            let to = match form.to_envelope_id.as_str() {
+                "" => None,
+                to => Some(Uuid::parse_str(to).map_err(|e| AppError::InvalidRecord(e.into()))?),
+            };
+            let amount =
+                Decimal::from_f64(form.amount).ok_or_else(|| anyhow!("could not parse decimal"))?;
+
+            match envelope.transfer(&client, to, amount).await {
+                Err(AppError::InvalidRecord(error)) => Err(error.to_string()),
+                result => Ok(result?),
+            }
+        }
+    };
+
+    let transfer = match transfer {
+        Ok(transfer) => transfer,
+        Err(errors) => {
+            let mut context = context.clone();
+            context.insert("errors".to_string(), to_json(errors));
+
+            return match response_format {
+                ResponseFormat::Html | ResponseFormat::Turbo => Ok(generate_response(
+                    &ResponseFormat::Turbo,
+                    shared_state
+                        .handlebars
+                        .render("envelopes/_form.turbo", &context)?,
+                    StatusCode::BAD_REQUEST,
+                )),
+                ResponseFormat::Json => Ok(api_only()),
+            };
+        }
+    };
+
+    match response_format {
+        ResponseFormat::Html => Ok(Redirect::to("/envelopes").into_response()),
+        ResponseFormat::Turbo => {
+            let spent = Envelope::spent_by_envelope(&client, user.id).await?;
+            let mut rows = Vec::new();
+            for changed in [Some(transfer.from_envelope_id), transfer.to_envelope_id]
+                .into_iter()
+                .flatten()
+            {
+                let changed = Envelope::get_one(&client, changed, user.id).await?;
+                rows.push(json!({
+                    "id": changed.id,
+                    "name": changed.name,
+                    "amount": changed.amount,
+                    "spent": changed
+                        .id
+                        .and_then(|id| spent.get(&id).copied())
+                        .unwrap_or(Decimal::ZERO),
+                }));
+            }
+
+            let mut context = context.clone();
+            context.insert("envelopes".to_string(), to_json(rows));
+
+            Ok(generate_response(
+                &response_format,
+                shared_state
+                    .handlebars
+                    .render("envelopes/transfers.turbo", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::state_for_tests;
+    use axum::Router;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use axum::routing::post;
+    use std::str::from_utf8;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_transfer_turbo_stream() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let from = Envelope {
+            id: None,
+            user_id,
+            name: "groceries".to_string(),
+            amount: Decimal::new(100, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        let to = Envelope {
+            id: None,
+            user_id,
+            name: "dining".to_string(),
+            amount: Decimal::ZERO,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/envelopes/{id}/transfers", post(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/envelopes/{}/transfers", from.id.unwrap()))
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("Accept", "text/vnd.turbo-stream.html")
+            .body(Body::from(format!(
+                "amount=25&to_envelope_id={}",
+                to.id.unwrap()
+            )))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        let (parts, body) = response.into_parts();
+        let bytes = to_bytes(body, usize::MAX).await.unwrap();
+        let body = from_utf8(&bytes).unwrap();
+
+        assert_eq!(parts.status, StatusCode::OK);
+        assert!(body.contains(&format!("envelope_{}", from.id.unwrap())));
+        assert!(body.contains(&format!("envelope_{}", to.id.unwrap())));
+
+        let to = Envelope::get_one(&client, to.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(to.amount, Decimal::new(25, 0));
+    }
+
+    #[tokio::test]
+    async fn test_transfer_more_than_available() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let from = Envelope {
+            id: None,
+            user_id,
+            name: "groceries".to_string(),
+            amount: Decimal::new(10, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/envelopes/{id}/transfers", post(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri(format!("/envelopes/{}/transfers", from.id.unwrap()))
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("Accept", "text/vnd.turbo-stream.html")
+            .body(Body::from("amount=25&to_envelope_id="))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let from = Envelope::get_one(&client, from.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(from.amount, Decimal::new(10, 0));
+    }
+}
@@ -3,7 +3,7 @@ use crate::{
     HandlebarsContext, SharedState,
     authenticated::UserExtension,
     errors::AppResponse,
-    models::envelope::Envelope,
+    models::{envelope::Envelope, user::User},
     utilities::responses::{self, generate_response, get_response_format},
 };
 use anyhow::anyhow;
@@ -13,6 +13,8 @@ use axum::{
     http::{HeaderMap, StatusCode},
     response::{IntoResponse, Redirect},
 };
+use chrono::Utc;
+use chrono_tz::Tz;
 use handlebars::to_json;
 use rust_decimal::{Decimal, prelude::FromPrimitive};
 use uuid::Uuid;
@@ -65,13 +67,21 @@ pub async fn action(
             }
         }
     }
-    let client = shared_state.pool.get_client().await?;
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let timezone: Tz = User::get_by_id(&client, user.id)
+        .await?
+        .timezone()?
+        .parse()?;
     let mut envelope = Envelope::get_one(&client, id, user.id).await?;
 
     envelope.name = form.name.clone();
-    envelope.amount =
-        Decimal::from_f64(form.amount).ok_or_else(|| anyhow!("could not parse decimal"))?;
     envelope.update(&client).await?;
+    let balance =
+        Decimal::from_f64(form.amount).ok_or_else(|| anyhow!("could not parse decimal"))?;
+    let today = Utc::now().with_timezone(&timezone).date_naive();
+    envelope.adjust_balance(&client, balance, today).await?;
+    client.commit().await?;
 
     match get_response_format(&headers)? {
         responses::ResponseFormat::Html | responses::ResponseFormat::Turbo => {
@@ -87,7 +97,10 @@ pub async fn action(
 
 #[cfg(test)]
 mod tests {
-    use crate::{models::envelope::Envelope, test_utils::state_for_tests};
+    use crate::{
+        models::{envelope::Envelope, transaction::Transaction},
+        test_utils::state_for_tests,
+    };
     use axum::http::{Method, Request, StatusCode};
     use rust_decimal::Decimal;
     use tower::ServiceExt;
@@ -135,5 +148,15 @@ mod tests {
 
         assert_eq!(envelope.name, "Updated Envelope");
         assert_eq!(envelope.amount, Decimal::new(200, 0));
+        assert_eq!(envelope.spent(&client).await.unwrap(), Decimal::ZERO);
+
+        let transactions =
+            Transaction::get_all_for_envelope(&client, envelope.id.unwrap(), user_id)
+                .await
+                .unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].amount, Decimal::new(199, 0));
+        assert_eq!(transactions[0].account_id, None);
+        assert!(transactions[0].adjustment);
     }
 }
@@ -0,0 +1,34 @@
+mod index;
+
+use super::UserExtension;
+use crate::HandlebarsContext;
+use crate::{Section, SharedState};
+use axum::{
+    Extension, Router,
+    extract::Request,
+    middleware::{Next, from_fn},
+    response::Response,
+    routing::get,
+};
+use handlebars::to_json;
+
+async fn initialize_context(
+    Extension(user_extension): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let mut context = context.clone();
+
+    context.insert("section".to_string(), to_json(Section::Forecast));
+    context.insert("csrf".to_string(), to_json(user_extension.csrf));
+
+    request.extensions_mut().insert(context);
+    next.run(request).await
+}
+
+pub fn forecast_router() -> Router<SharedState> {
+    Router::new()
+        .route("/", get(index::action))
+        .route_layer(from_fn(initialize_context))
+}
@@ -0,0 +1,213 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::{
+        forecast::{ForecastDay, forecast_for},
+        user::User,
+    },
+    utilities::{
+        charts::{HEIGHT, Scale, WIDTH},
+        dates::TimeProvider,
+        responses::{ResponseFormat, api_only, generate_response, get_response_format},
+    },
+};
+use anyhow::anyhow;
+use axum::{
+    Extension,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+};
+use handlebars::to_json;
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Debug, Deserialize)]
+pub struct ForecastQuery {
+    months: Option<u32>,
+}
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    Query(query): Query<ForecastQuery>,
+) -> AppResponse {
+    let months = query.months.unwrap_or(3);
+    if !(1..=24).contains(&months) {
+        return Err(AppError::InvalidRecord(anyhow!(
+            "months must be between 1 and 24"
+        )));
+    }
+
+    let client = shared_state.pool.get_client().await?;
+    let user = User::get_by_id(&client, user.id).await?;
+    let days = forecast_for(&client, &user, &TimeProvider {}, months).await?;
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            let mut context = context.clone();
+            context.insert("months".to_string(), to_json(months));
+            context.insert("chart".to_string(), chart(&days));
+            context.insert(
+                "lowest".to_string(),
+                to_json(days.iter().min_by_key(|day| day.balance)),
+            );
+            context.insert(
+                "negative_days".to_string(),
+                to_json(days.iter().filter(|day| day.negative).count()),
+            );
+            context.insert(
+                "notable_days".to_string(),
+                to_json(
+                    days.iter()
+                        .filter(|day| {
+                            day.negative
+                                || day.income > Decimal::ZERO
+                                || !day.conversions.is_empty()
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+            );
+            context.insert("partial".to_string(), to_json("forecast/index"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+/// The projected balance as an SVG polyline, with the zero line and negative days marked.
+fn chart(days: &[ForecastDay]) -> serde_json::Value {
+    let balances = days
+        .iter()
+        .map(|day| day.balance.to_f64().unwrap_or_default())
+        .collect::<Vec<_>>();
+    let scale = Scale::new(&[&balances]);
+
+    let negative = days
+        .iter()
+        .zip(balances.iter())
+        .enumerate()
+        .filter(|(_, (day, _))| day.negative)
+        .map(|(index, (day, balance))| {
+            json!({
+                "x": scale.x(index),
+                "y": scale.y(*balance),
+                "date": day.date,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "width": WIDTH,
+        "height": HEIGHT,
+        "points": scale.points(&balances),
+        "zero": scale.y(0.0),
+        "negative": negative,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::action;
+    use crate::{
+        models::{
+            account::Account,
+            forecast::forecast_for,
+            goal::{EnvelopePolicy, Goal, Recurrence},
+            user::User,
+        },
+        test_utils::state_for_tests,
+        utilities::dates::TimeProvider,
+    };
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use chrono::{Duration, Utc};
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_forecast_flags_negative_days() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(500, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        Goal {
+            id: None,
+            user_id,
+            name: "car".to_string(),
+            recurrence: Recurrence::Never,
+            target_date: Utc::now() + Duration::days(40),
+            target: Decimal::new(1000, 0),
+            accumulated_amount: Decimal::ZERO,
+            start_date: Some(Utc::now()),
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/forecast", get(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let user = User::get_by_id(&client, user_id).await.unwrap();
+        let days = forecast_for(&client, &user, &TimeProvider {}, 2)
+            .await
+            .unwrap();
+        assert!(days.len() >= 59);
+        assert!(!days[0].negative);
+        assert!(days.last().unwrap().negative);
+
+        let request = Request::builder()
+            .uri("/forecast")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(
+            String::from_utf8(body.to_vec())
+                .unwrap()
+                .contains("polyline")
+        );
+
+        let request = Request::builder()
+            .uri("/forecast?months=30")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -3,22 +3,33 @@ mod delete;
 mod edit;
 mod index;
 mod new;
+mod priorities;
 mod resets;
+mod simulations;
 mod update;
 
-use super::UserExtension;
+use super::{UserExtension, pages_only};
 use crate::HandlebarsContext;
+use crate::errors::AppError;
+use crate::models::goal::{EnvelopePolicy, Goal, Recurrence};
+use crate::models::user::{GoalHeader, User};
 use crate::{Section, SharedState};
+use anyhow::anyhow;
 use axum::{
     Extension, Router,
     extract::Request,
     middleware::{Next, from_fn},
     response::Response,
-    routing::{get, post},
+    routing::{get, post, put},
 };
+use chrono::Utc;
 use handlebars::to_json;
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::str::FromStr;
+use uuid::Uuid;
 
 fn schema() -> serde_json::Value {
     json!({
@@ -26,8 +37,44 @@ fn schema() -> serde_json::Value {
         "properties": {
             "name": { "type": "string", "minLength": 2 },
             "target": { "type": "number", "minimum": 0 },
-            "recurrence": { "enum": ["never", "daily", "weekly", "monthly", "quarterly", "yearly"] },
-            "target_date": { "type": "string", "format": "date" }
+            "recurrence": {
+                "enum": [
+                    "never", "daily", "weekly", "monthly", "quarterly", "yearly",
+                    "every_days", "every_weeks", "every_months", "semi_monthly", "last_day_of_month"
+                ]
+            },
+            "recurrence_interval": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^([1-9][0-9]{0,2})?$" },
+                    { "type": "null" }
+                ]
+            },
+            "recurrence_first_day": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^(0?[1-9]|[12][0-9]|3[01])?$" },
+                    { "type": "null" }
+                ]
+            },
+            "recurrence_second_day": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^(0?[1-9]|[12][0-9]|3[01])?$" },
+                    { "type": "null" }
+                ]
+            },
+            "target_date": { "type": "string", "format": "date" },
+            "envelope_id": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^([0-9a-fA-F-]{36})?$" },
+                    { "type": "null" }
+                ]
+            },
+            "envelope_policy": { "enum": ["append", "replace", "create_new", null] },
+            "funding_weight": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^([0-9]+(\\.[0-9]+)?)?$" },
+                    { "type": "null" }
+                ]
+            }
         },
         "required": [ "name", "target", "recurrence", "target_date" ],
         "additionalProperties": false
@@ -40,6 +87,128 @@ pub struct GoalForm {
     target: f64,
     target_date: chrono::NaiveDate,
     recurrence: String,
+    recurrence_interval: Option<String>,
+    recurrence_first_day: Option<String>,
+    recurrence_second_day: Option<String>,
+    envelope_id: Option<String>,
+    envelope_policy: Option<String>,
+    funding_weight: Option<String>,
+}
+
+impl GoalForm {
+    fn recurrence(&self) -> Result<Recurrence, AppError> {
+        let number = |field: &Option<String>, label: &str| -> Result<u32, AppError> {
+            field
+                .as_deref()
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| AppError::InvalidRecord(anyhow!("{} is required", label)))?
+                .parse::<u32>()
+                .map_err(|e| AppError::InvalidRecord(e.into()))
+        };
+
+        match self.recurrence.as_str() {
+            "every_days" => Ok(Recurrence::EveryDays(number(
+                &self.recurrence_interval,
+                "interval",
+            )?)),
+            "every_weeks" => Ok(Recurrence::EveryWeeks(number(
+                &self.recurrence_interval,
+                "interval",
+            )?)),
+            "every_months" => Ok(Recurrence::EveryMonths(number(
+                &self.recurrence_interval,
+                "interval",
+            )?)),
+            "semi_monthly" => {
+                let first = number(&self.recurrence_first_day, "first day")?;
+                let second = number(&self.recurrence_second_day, "second day")?;
+
+                if first == second {
+                    return Err(AppError::InvalidRecord(anyhow!(
+                        "semi-monthly goals need two different days"
+                    )));
+                }
+
+                Ok(Recurrence::SemiMonthly(
+                    first.min(second),
+                    first.max(second),
+                ))
+            }
+            recurrence => Recurrence::from_str(recurrence)
+                .map_err(|e| AppError::InvalidRecord(anyhow!("{:#?}", e))),
+        }
+    }
+
+    fn envelope_id(&self) -> Result<Option<Uuid>, AppError> {
+        match self.envelope_id.as_deref() {
+            None | Some("") => Ok(None),
+            Some(id) => Ok(Some(
+                Uuid::parse_str(id).map_err(|e| AppError::InvalidRecord(e.into()))?,
+            )),
+        }
+    }
+
+    fn funding_weight(&self) -> Result<Option<Decimal>, AppError> {
+        let Some(weight) = self
+            .funding_weight
+            .as_deref()
+            .filter(|weight| !weight.is_empty())
+        else {
+            return Ok(None);
+        };
+
+        let weight = Decimal::from_str(weight).map_err(|e| AppError::InvalidRecord(e.into()))?;
+        if weight <= Decimal::ZERO {
+            return Err(AppError::InvalidRecord(anyhow!(
+                "funding weight must be greater than zero"
+            )));
+        }
+
+        Ok(Some(weight))
+    }
+
+    fn envelope_policy(&self) -> Result<Option<EnvelopePolicy>, AppError> {
+        self.envelope_policy
+            .as_deref()
+            .map(|policy| {
+                EnvelopePolicy::from_str(policy)
+                    .map_err(|e| AppError::InvalidRecord(anyhow!("{:#?}", e)))
+            })
+            .transpose()
+    }
+}
+
+/// The goals table: each goal with the figure shown in its header column.
+pub async fn generate_goals_context_for(
+    context: &mut HandlebarsContext,
+    user: &User,
+    client: &impl GenericClient,
+) -> Result<(), AppError> {
+    let goal_header = match &user.preferences {
+        Some(preferences) => preferences.0.goal_header.clone(),
+        None => Some(GoalHeader::Accumulated),
+    };
+    let goals = Goal::get_all(client, user.id).await?;
+    let mut accumulations: Vec<Decimal> = Vec::new();
+    let mut days_remaining: Vec<i64> = Vec::new();
+    let mut per_days: Vec<Decimal> = Vec::new();
+
+    for goal in &goals {
+        accumulations.push(goal.accumulated_amount);
+        per_days.push(goal.accumulated_per_day()?);
+        days_remaining.push((goal.target_date - Utc::now()).num_days());
+    }
+
+    context.insert(
+        "goal_header".to_string(),
+        to_json(goal_header.or(Some(GoalHeader::Accumulated))),
+    );
+    context.insert("goals".to_string(), to_json(&goals));
+    context.insert("accumulations".to_string(), to_json(&accumulations));
+    context.insert("days_remaining".to_string(), to_json(&days_remaining));
+    context.insert("per_days".to_string(), to_json(&per_days));
+
+    Ok(())
 }
 
 async fn initialize_context(
@@ -66,6 +235,11 @@ pub fn goals_router() -> Router<SharedState> {
             get(edit::action).put(update::action).delete(delete::action),
         )
         .route("/new", get(new::action))
+        .route("/priorities", put(priorities::action))
+        .route(
+            "/simulation",
+            get(simulations::action).route_layer(from_fn(pages_only)),
+        )
         .route("/resets/{recurrence}", post(resets::action))
         .route("/{id}/delete", get(delete::modal))
         .route_layer(from_fn(initialize_context))
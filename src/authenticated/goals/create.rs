@@ -3,7 +3,10 @@ use crate::{
     HandlebarsContext, SharedState,
     authenticated::UserExtension,
     errors::AppResponse,
-    models::goal::{Goal, Recurrence},
+    models::{
+        envelope::Envelope,
+        goal::{EnvelopePolicy, Goal, Recurrence},
+    },
     utilities::responses,
 };
 use anyhow::anyhow;
@@ -16,7 +19,6 @@ use axum::{
 use chrono::{NaiveDateTime, NaiveTime, Utc};
 use handlebars::to_json;
 use rust_decimal::{Decimal, prelude::FromPrimitive};
-use std::str::FromStr;
 
 pub async fn action(
     shared_state: State<SharedState>,
@@ -39,9 +41,32 @@ pub async fn action(
             context.insert("target".to_string(), to_json(form.target));
             context.insert("target_date".to_string(), to_json(form.target_date));
             context.insert("recurrence".to_string(), to_json(&form.recurrence));
+            context.insert(
+                "recurrence_interval".to_string(),
+                to_json(&form.recurrence_interval),
+            );
+            context.insert(
+                "recurrence_first_day".to_string(),
+                to_json(&form.recurrence_first_day),
+            );
+            context.insert(
+                "recurrence_second_day".to_string(),
+                to_json(&form.recurrence_second_day),
+            );
+            context.insert("envelope_id".to_string(), to_json(&form.envelope_id));
+            context.insert(
+                "envelope_policy".to_string(),
+                to_json(&form.envelope_policy),
+            );
+            context.insert("funding_weight".to_string(), to_json(&form.funding_weight));
 
             match response_format {
                 responses::ResponseFormat::Html => {
+                    let client = shared_state.pool.get_client().await?;
+                    context.insert(
+                        "envelopes".to_string(),
+                        to_json(Envelope::get_all(&client, user.id).await?),
+                    );
                     context.insert("partial".to_string(), to_json("goals/new"));
                     return Ok(responses::generate_response(
                         &responses::ResponseFormat::Html,
@@ -69,12 +94,18 @@ pub async fn action(
         }
     }
 
-    let recurrence = Recurrence::from_str(&form.recurrence).map_err(|e| anyhow!("{:#?}", e))?;
+    let recurrence = form.recurrence()?;
     let start_date = match recurrence {
         Recurrence::Never => Some(Utc::now()),
         _ => None,
     };
 
+    let client = shared_state.pool.get_client().await?;
+    let envelope_id = form.envelope_id()?;
+    if let Some(envelope_id) = envelope_id {
+        Envelope::get_one(&client, envelope_id, user.id).await?;
+    }
+
     let goal = Goal {
         id: None,
         name: form.name.to_owned(),
@@ -85,8 +116,12 @@ pub async fn action(
         accumulated_amount: Decimal::ZERO,
         recurrence,
         start_date,
+        envelope_id,
+        envelope_policy: form.envelope_policy()?.unwrap_or(EnvelopePolicy::Append),
+        priority: 0,
+        funding_weight: form.funding_weight()?.unwrap_or(Decimal::ONE),
+        target_day: None,
     };
-    let client = shared_state.pool.get_client().await?;
     goal.create(&client).await?;
 
     Ok(Redirect::to("/goals").into_response())
@@ -246,4 +281,49 @@ mod tests {
             .to_rfc3339();
         assert!(chrono::DateTime::parse_from_rfc3339(&start_date_str).is_ok());
     }
+
+    #[tokio::test]
+    async fn test_create_goal_semi_monthly() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+        let user_id = user_extension.0.id;
+
+        let app = Router::new()
+            .route("/goals/create", post(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let form_data = "name=rent&target=1200&target_date=2024-09-15&recurrence=semi_monthly\
+            &recurrence_interval=&recurrence_first_day=31&recurrence_second_day=15";
+        let request = Request::builder()
+            .method("POST")
+            .uri("/goals/create")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from(form_data))
+            .unwrap();
+
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let goal: Goal = client
+            .query_one("SELECT * FROM goals WHERE user_id = $1", &[&user_id])
+            .await
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(goal.recurrence, Recurrence::SemiMonthly(15, 31));
+
+        let form_data = "name=rent&target=1200&target_date=2024-09-15&recurrence=semi_monthly\
+            &recurrence_first_day=15&recurrence_second_day=15";
+        let request = Request::builder()
+            .method("POST")
+            .uri("/goals/create")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from(form_data))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
 }
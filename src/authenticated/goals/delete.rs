@@ -89,7 +89,7 @@ mod tests {
     use std::str::from_utf8;
 
     use super::*;
-    use crate::models::goal::{Goal, Recurrence};
+    use crate::models::goal::{EnvelopePolicy, Goal, Recurrence};
     use crate::test_utils::state_for_tests;
     use axum::Router;
     use axum::body::{Body, to_bytes};
@@ -110,6 +110,11 @@ mod tests {
             target_date: Utc::now(),
             accumulated_amount: Decimal::ZERO,
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let client = shared_state.pool.get_client().await.unwrap();
 
@@ -145,6 +150,11 @@ mod tests {
             target_date: Utc::now(),
             accumulated_amount: Decimal::ZERO,
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
 
         let client = shared_state.pool.get_client().await.unwrap();
@@ -2,7 +2,7 @@ use crate::{
     HandlebarsContext, SharedState,
     authenticated::UserExtension,
     errors::AppResponse,
-    models::goal::Goal,
+    models::{envelope::Envelope, goal::Goal, goal_contribution::GoalContribution, user::User},
     utilities::responses::{ResponseFormat, generate_response, get_response_format},
 };
 use axum::{
@@ -11,7 +11,9 @@ use axum::{
     http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
+use chrono_tz::Tz;
 use handlebars::to_json;
+use serde_json::json;
 use uuid::Uuid;
 
 pub async fn action(
@@ -32,10 +34,52 @@ pub async fn action(
         "target_date".to_string(),
         to_json(goal.target_date.format("%Y-%m-%d").to_string()),
     );
+    context.insert("recurrence".to_string(), to_json(goal.recurrence.name()));
     context.insert(
-        "recurrence".to_string(),
-        to_json(format!("{:?}", goal.recurrence).to_lowercase()),
+        "recurrence_interval".to_string(),
+        to_json(goal.recurrence.interval()),
     );
+    let days = goal.recurrence.days();
+    context.insert(
+        "recurrence_first_day".to_string(),
+        to_json(days.map(|(first, _)| first)),
+    );
+    context.insert(
+        "recurrence_second_day".to_string(),
+        to_json(days.map(|(_, second)| second)),
+    );
+    context.insert("envelope_id".to_string(), to_json(goal.envelope_id));
+    context.insert(
+        "envelope_policy".to_string(),
+        to_json(goal.envelope_policy.to_string()),
+    );
+    context.insert("funding_weight".to_string(), to_json(goal.funding_weight));
+    context.insert(
+        "envelopes".to_string(),
+        to_json(Envelope::get_all(&client, user.id).await?),
+    );
+
+    let timezone: Tz = User::get_by_id(&client, user.id)
+        .await?
+        .timezone()?
+        .parse()?;
+    let contributions: Vec<_> = GoalContribution::get_all_for_goal(&client, id, user.id)
+        .await?
+        .iter()
+        .map(|contribution| {
+            json!({
+                "created_at": contribution
+                    .created_at
+                    .with_timezone(&timezone)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string(),
+                "source": contribution.source.to_string(),
+                "amount": contribution.amount,
+                "accumulated_amount": contribution.accumulated_amount,
+            })
+        })
+        .collect();
+    context.insert("contributions".to_string(), to_json(contributions));
     match response_format {
         ResponseFormat::Html => {
             context.insert("partial".to_string(), to_json("goals/edit"));
@@ -54,3 +98,66 @@ pub async fn action(
         )),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::action;
+    use crate::{
+        models::goal::{EnvelopePolicy, Goal, Recurrence},
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use chrono::{Duration, Utc};
+    use rust_decimal::Decimal;
+    use std::str::from_utf8;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_edit_shows_contributions() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let goal = Goal {
+            id: None,
+            user_id: user_extension.0.id,
+            name: "laptop".to_string(),
+            recurrence: Recurrence::SemiMonthly(1, 15),
+            target_date: Utc::now() + Duration::days(10),
+            target: Decimal::new(500, 0),
+            accumulated_amount: Decimal::ZERO,
+            start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        goal.accelerate(&client, Decimal::new(40, 0)).await.unwrap();
+
+        let app = Router::new()
+            .route("/goals/{id}", get(action))
+            .with_state(shared_state)
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .uri(format!("/goals/{}", goal.id.unwrap()))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = from_utf8(&body).unwrap();
+        assert!(body.contains("Acceleration"));
+        assert!(body.contains("name=\"recurrence_first_day\""));
+    }
+}
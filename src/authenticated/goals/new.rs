@@ -1,6 +1,8 @@
 use crate::{
     HandlebarsContext, SharedState,
+    authenticated::UserExtension,
     errors::AppResponse,
+    models::envelope::Envelope,
     utilities::responses::{ResponseFormat, generate_response, get_response_format},
 };
 use axum::{
@@ -13,8 +15,10 @@ use handlebars::to_json;
 pub async fn action(
     shared_state: State<SharedState>,
     headers: HeaderMap,
+    user: Extension<UserExtension>,
     Extension(context): Extension<HandlebarsContext>,
 ) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
     let mut context = context.clone();
     context.insert("id".to_string(), to_json(""));
     context.insert("name".to_string(), to_json(""));
@@ -22,6 +26,16 @@ pub async fn action(
     context.insert("target".to_string(), to_json(""));
     context.insert("target_date".to_string(), to_json(""));
     context.insert("recurrence".to_string(), to_json(""));
+    context.insert("recurrence_interval".to_string(), to_json(""));
+    context.insert("recurrence_first_day".to_string(), to_json(""));
+    context.insert("recurrence_second_day".to_string(), to_json(""));
+    context.insert("envelope_id".to_string(), to_json(""));
+    context.insert("envelope_policy".to_string(), to_json("append"));
+    context.insert("funding_weight".to_string(), to_json("1"));
+    context.insert(
+        "envelopes".to_string(),
+        to_json(Envelope::get_all(&client, user.id).await?),
+    );
 
     let response_format = get_response_format(&headers)?;
 
@@ -0,0 +1,122 @@
+use crate::{
+    SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::goal::Goal,
+    utilities::responses::{ResponseFormat, generate_response, get_response_format},
+};
+use axum::{
+    Extension, Form, Json,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct PrioritiesForm {
+    /// Comma-separated goal IDs, highest priority first.
+    ids: String,
+}
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Form(form): Form<PrioritiesForm>,
+) -> AppResponse {
+    let ids = form
+        .ids
+        .split(',')
+        .filter(|id| !id.is_empty())
+        .map(Uuid::parse_str)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::InvalidRecord(e.into()))?;
+
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    Goal::reorder(&client, user.id, &ids).await?;
+    let goals = Goal::get_all(&client, user.id).await?;
+    client.commit().await?;
+
+    match get_response_format(&headers)? {
+        ResponseFormat::Html | ResponseFormat::Turbo => Ok(Redirect::to("/goals").into_response()),
+        ResponseFormat::Json => Ok(generate_response(
+            &ResponseFormat::Json,
+            Json(goals),
+            StatusCode::OK,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::action;
+    use crate::{
+        models::goal::{EnvelopePolicy, Goal, Recurrence},
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::Body,
+        http::{Request, StatusCode},
+        routing::put,
+    };
+    use chrono::{Duration, Utc};
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_reorder_goals() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let mut ids = Vec::new();
+        for name in ["first", "second", "third"] {
+            let goal = Goal {
+                id: None,
+                user_id,
+                name: name.to_string(),
+                recurrence: Recurrence::Monthly,
+                target_date: Utc::now() + Duration::days(30),
+                target: Decimal::new(100, 0),
+                accumulated_amount: Decimal::ZERO,
+                start_date: None,
+                envelope_id: None,
+                envelope_policy: EnvelopePolicy::Append,
+                priority: 0,
+                funding_weight: Decimal::ONE,
+                target_day: None,
+            }
+            .create(&client)
+            .await
+            .unwrap();
+            ids.push(goal.id.unwrap());
+        }
+
+        let app = Router::new()
+            .route("/goals/priorities", put(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .method("PUT")
+            .uri("/goals/priorities")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from(format!("ids={},{}", ids[2], ids[0])))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let names: Vec<String> = Goal::get_all(&client, user_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|goal| goal.name)
+            .collect();
+        assert_eq!(names, ["third", "first", "second"]);
+    }
+}
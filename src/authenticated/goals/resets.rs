@@ -4,6 +4,7 @@ use crate::{
     errors::AppResponse,
     models::{
         goal::{Goal, Recurrence},
+        goal_contribution::{ContributionSource, GoalContribution},
         user::{GoalHeader, User},
     },
     utilities::responses::{self, ResponseFormat, generate_response},
@@ -41,8 +42,10 @@ pub async fn action(
 
     for goal in &mut goals {
         if goal.recurrence.eq(&Recurrence::Monthly) {
+            let previous = goal.clone();
             goal.accumulated_amount = Decimal::ZERO;
             goal.update(&client).await?;
+            GoalContribution::record(&client, &previous, goal, ContributionSource::Reset).await?;
         }
 
         accumulations.push(goal.accumulated_amount);
@@ -88,7 +91,7 @@ pub async fn action(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::goal::{Goal, Recurrence};
+    use crate::models::goal::{EnvelopePolicy, Goal, Recurrence};
     use crate::models::user::{GoalHeader, Preferences};
     use crate::test_utils::state_for_tests;
     use axum::Router;
@@ -117,6 +120,11 @@ mod tests {
             target_date: Utc::now() + Duration::days(30),
             accumulated_amount: Decimal::new(500, 0), // This should be reset to 0
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let mut goal = goal.create(&client).await.unwrap();
         // Set accumulated amount after creation since create() sets it to ZERO
@@ -163,6 +171,11 @@ mod tests {
             target_date: Utc::now() + Duration::days(30),
             accumulated_amount: Decimal::new(750, 0),
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let mut monthly_goal = monthly_goal.create(&client).await.unwrap();
         // Set accumulated amount after creation since create() sets it to ZERO
@@ -178,6 +191,11 @@ mod tests {
             target_date: Utc::now() + Duration::days(7),
             accumulated_amount: Decimal::new(100, 0),
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let mut weekly_goal = weekly_goal.create(&client).await.unwrap();
         // Set accumulated amount after creation since create() sets it to ZERO
@@ -228,7 +246,8 @@ mod tests {
             goal_header: Some(GoalHeader::PerDay),
             timezone: None,
             forecast_offset: None,
-            monthly_income: None,
+            budget_period: None,
+            goal_funding: None,
         };
 
         client
@@ -249,6 +268,11 @@ mod tests {
             target_date: Utc::now() + Duration::days(15),
             accumulated_amount: Decimal::new(150, 0),
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let mut goal = goal.create(&client).await.unwrap();
         // Set accumulated amount after creation since create() sets it to ZERO
@@ -293,6 +317,11 @@ mod tests {
             target_date: Utc::now() + Duration::days(1),
             accumulated_amount: Decimal::new(25, 0),
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let mut daily_goal = daily_goal.create(&client).await.unwrap();
         // Set accumulated amount after creation since create() sets it to ZERO
@@ -308,6 +337,11 @@ mod tests {
             target_date: Utc::now() + Duration::days(365),
             accumulated_amount: Decimal::new(2500, 0),
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let mut yearly_goal = yearly_goal.create(&client).await.unwrap();
         // Set accumulated amount after creation since create() sets it to ZERO
@@ -0,0 +1,199 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    jobs::convert_goals::simulate_convert_goals,
+    models::user::User,
+    utilities::{
+        dates::FixedTime,
+        responses::{ResponseFormat, api_only, generate_response, get_response_format},
+    },
+};
+use anyhow::anyhow;
+use axum::{
+    Extension,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+};
+use chrono::{Days, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use handlebars::to_json;
+use serde::Deserialize;
+use serde_json::json;
+
+const AT_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+#[derive(Debug, Deserialize)]
+pub struct SimulationQuery {
+    /// Local date and time to simulate, as sent by a `datetime-local` input.
+    at: Option<String>,
+}
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    Query(query): Query<SimulationQuery>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let timezone: Tz = User::get_by_id(&client, user.id)
+        .await?
+        .timezone()?
+        .parse()?;
+    drop(client);
+
+    let at = match query.at.as_deref().filter(|at| !at.is_empty()) {
+        Some(at) => {
+            let local = NaiveDateTime::parse_from_str(at, AT_FORMAT)
+                .map_err(|e| AppError::InvalidRecord(e.into()))?;
+            timezone
+                .from_local_datetime(&local)
+                .earliest()
+                .ok_or_else(|| AppError::InvalidRecord(anyhow!("{} does not exist", at)))?
+                .with_timezone(&Utc)
+        }
+        None => Utc::now() + Days::new(1),
+    };
+
+    if at < Utc::now() {
+        return Err(AppError::InvalidRecord(anyhow!(
+            "simulations must be in the future"
+        )));
+    }
+
+    let report = simulate_convert_goals(&shared_state.pool, user.id, &FixedTime(at)).await?;
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            let mut context = context.clone();
+            context.insert(
+                "at".to_string(),
+                to_json(at.with_timezone(&timezone).format(AT_FORMAT).to_string()),
+            );
+            context.insert("envelopes".to_string(), to_json(&report.envelopes));
+            context.insert(
+                "incremented_goals".to_string(),
+                to_json(
+                    report
+                        .incremented_goals
+                        .iter()
+                        .map(|goal| {
+                            json!({
+                                "name": goal.name,
+                                "target": goal.target,
+                                "target_date": goal
+                                    .target_date
+                                    .with_timezone(&timezone)
+                                    .format("%Y-%m-%d")
+                                    .to_string(),
+                            })
+                        })
+                        .collect::<Vec<_>>(),
+                ),
+            );
+            context.insert("changes".to_string(), to_json(&report.changes));
+            context.insert("partial".to_string(), to_json("goals/simulation"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::action;
+    use crate::{
+        models::{
+            envelope::Envelope,
+            goal::{EnvelopePolicy, Goal, Recurrence},
+        },
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use chrono::{Duration, Utc};
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_simulation_does_not_commit() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let goal = Goal {
+            id: None,
+            user_id,
+            name: "insurance".to_string(),
+            recurrence: Recurrence::Monthly,
+            target_date: Utc::now() + Duration::days(2),
+            target: Decimal::new(90, 0),
+            accumulated_amount: Decimal::ZERO,
+            start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/goals/simulation", get(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let at = (Utc::now() + Duration::days(3)).format("%Y-%m-%dT%H:%M");
+        let request = Request::builder()
+            .uri(format!("/goals/simulation?at={}", at))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert!(
+            String::from_utf8(body.to_vec())
+                .unwrap()
+                .contains("insurance")
+        );
+
+        assert!(
+            Envelope::get_all(&client, user_id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let goal = Goal::get_one(&client, goal.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(goal.accumulated_amount, Decimal::ZERO);
+
+        let request = Request::builder()
+            .uri("/goals/simulation")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = Request::builder()
+            .uri("/goals/simulation?at=2000-01-01T00:00")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -3,7 +3,11 @@ use crate::{
     HandlebarsContext, SharedState,
     authenticated::UserExtension,
     errors::AppResponse,
-    models::goal::{Goal, Recurrence},
+    models::{
+        envelope::Envelope,
+        goal::{Goal, Recurrence},
+        goal_contribution::{ContributionSource, GoalContribution},
+    },
     utilities::responses::{self, generate_response, get_response_format},
 };
 use anyhow::anyhow;
@@ -16,7 +20,6 @@ use axum::{
 use chrono::{NaiveDateTime, NaiveTime};
 use handlebars::to_json;
 use rust_decimal::{Decimal, prelude::FromPrimitive};
-use std::str::FromStr;
 use uuid::Uuid;
 
 pub async fn action(
@@ -44,9 +47,31 @@ pub async fn action(
             context.insert("target".to_string(), to_json(form.target));
             context.insert("target_date".to_string(), to_json(form.target_date));
             context.insert("recurrence".to_string(), to_json(&form.recurrence));
+            context.insert(
+                "recurrence_interval".to_string(),
+                to_json(&form.recurrence_interval),
+            );
+            context.insert(
+                "recurrence_first_day".to_string(),
+                to_json(&form.recurrence_first_day),
+            );
+            context.insert(
+                "recurrence_second_day".to_string(),
+                to_json(&form.recurrence_second_day),
+            );
+            context.insert("envelope_id".to_string(), to_json(&form.envelope_id));
+            context.insert(
+                "envelope_policy".to_string(),
+                to_json(&form.envelope_policy),
+            );
+            context.insert("funding_weight".to_string(), to_json(&form.funding_weight));
 
             match response_format {
                 responses::ResponseFormat::Html => {
+                    context.insert(
+                        "envelopes".to_string(),
+                        to_json(Envelope::get_all(&client, user.id).await?),
+                    );
                     context.insert("partial".to_string(), to_json("goals/form"));
                     return Ok(responses::generate_response(
                         &responses::ResponseFormat::Html,
@@ -73,8 +98,9 @@ pub async fn action(
     }
 
     let mut goal = Goal::get_one(&client, id, user.id).await?;
+    let previous = goal.clone();
 
-    let new_recurrence = Recurrence::from_str(&form.recurrence).unwrap();
+    let new_recurrence = form.recurrence()?;
 
     goal.name = form.name.to_owned();
     goal.target = Decimal::from_f64(form.target.to_owned())
@@ -92,11 +118,25 @@ pub async fn action(
         }
     }
 
+    if form.envelope_id.is_some() {
+        goal.envelope_id = form.envelope_id()?;
+        if let Some(envelope_id) = goal.envelope_id {
+            Envelope::get_one(&client, envelope_id, user.id).await?;
+        }
+    }
+    if let Some(envelope_policy) = form.envelope_policy()? {
+        goal.envelope_policy = envelope_policy;
+    }
+    if let Some(funding_weight) = form.funding_weight()? {
+        goal.funding_weight = funding_weight;
+    }
+
     goal.recurrence = new_recurrence;
-    goal.target_date = NaiveDateTime::new(form.target_date, NaiveTime::MIN).and_utc();
+    goal.set_target_date(NaiveDateTime::new(form.target_date, NaiveTime::MIN).and_utc());
     goal.accumulated_amount = Decimal::ZERO;
 
     goal.update(&client).await?;
+    GoalContribution::record(&client, &previous, &goal, ContributionSource::Manual).await?;
 
     match get_response_format(&headers)? {
         responses::ResponseFormat::Html | responses::ResponseFormat::Turbo => {
@@ -115,7 +155,7 @@ mod tests {
     use rust_database_common::GenericClient;
     #[tokio::test]
     async fn test_update_goal_recurrence_monthly_to_never_sets_start_date() {
-        use crate::models::goal::{Goal, Recurrence};
+        use crate::models::goal::{EnvelopePolicy, Goal, Recurrence};
         use chrono::{TimeZone, Utc};
         use rust_decimal::Decimal;
         let (shared_state, user_extension, context_extension) =
@@ -133,6 +173,11 @@ mod tests {
             accumulated_amount: Decimal::ZERO,
             recurrence: Recurrence::Monthly,
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let created = goal.create(&client).await.unwrap();
         goal.id = created.id;
@@ -178,7 +223,7 @@ mod tests {
         assert!(chrono::DateTime::parse_from_rfc3339(&start_date_str).is_ok());
     }
     use crate::{
-        models::goal::{Goal, Recurrence},
+        models::goal::{EnvelopePolicy, Goal, Recurrence},
         test_utils::state_for_tests,
     };
     use axum::http::{Method, Request, StatusCode};
@@ -200,6 +245,11 @@ mod tests {
             target_date: Utc::now(),
             recurrence: Recurrence::Weekly,
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let client = shared_state.pool.get_client().await.unwrap();
 
@@ -0,0 +1,221 @@
+use super::UserExtension;
+use crate::errors::{AppError, AppResponse};
+use crate::models::dashboard_snapshot::DashboardSnapshot;
+use crate::utilities::charts::{HEIGHT, Scale, WIDTH};
+use crate::utilities::responses::{
+    ResponseFormat, api_only, generate_response, get_response_format,
+};
+use crate::{HandlebarsContext, Section, SharedState, models::user::User};
+use anyhow::anyhow;
+use axum::{
+    Extension,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+};
+use chrono::{Datelike, Months, Utc};
+use chrono_tz::Tz;
+use handlebars::to_json;
+use rust_decimal::{Decimal, prelude::ToPrimitive};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    months: Option<u32>,
+}
+
+/// Averages of the daily snapshots taken in one calendar month.
+#[derive(Debug, Serialize)]
+struct MonthSummary {
+    month: String,
+    days: usize,
+    per_diem: Decimal,
+    goals_accumulated_per_day: Decimal,
+    remaining_total: Decimal,
+    per_diem_change: Option<Decimal>,
+    improving: bool,
+}
+
+pub async fn index(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    user: Extension<UserExtension>,
+    Extension(mut context): Extension<HandlebarsContext>,
+    Query(query): Query<HistoryQuery>,
+) -> AppResponse {
+    let months = query.months.unwrap_or(6);
+    if !(1..=36).contains(&months) {
+        return Err(AppError::InvalidRecord(anyhow!(
+            "months must be between 1 and 36"
+        )));
+    }
+
+    let client = shared_state.pool.get_client().await?;
+    let csrf = user.csrf.clone();
+    let user = User::get_by_id(&client, user.id).await?;
+    let timezone: Tz = user.timezone()?.parse()?;
+    let since = Utc::now()
+        .with_timezone(&timezone)
+        .date_naive()
+        .checked_sub_months(Months::new(months))
+        .ok_or_else(|| anyhow!("could not subtract months"))?;
+    let snapshots = DashboardSnapshot::get_all(&client, user.id, since).await?;
+
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            context.insert("csrf".to_string(), to_json(csrf));
+            context.insert("section".to_string(), to_json(Section::Reports));
+            context.insert("months".to_string(), to_json(months));
+            context.insert("chart".to_string(), chart(&snapshots));
+            context.insert(
+                "monthly".to_string(),
+                to_json(summarize_by_month(&snapshots)),
+            );
+            context.insert("partial".to_string(), to_json("history"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+fn chart(snapshots: &[DashboardSnapshot]) -> serde_json::Value {
+    let series = |value: fn(&DashboardSnapshot) -> Decimal| {
+        snapshots
+            .iter()
+            .map(|snapshot| value(snapshot).to_f64().unwrap_or_default())
+            .collect::<Vec<_>>()
+    };
+    let per_diem = series(|snapshot| snapshot.per_diem);
+    let goals = series(|snapshot| snapshot.goals_accumulated_per_day);
+    let scale = Scale::new(&[&per_diem, &goals]);
+
+    json!({
+        "width": WIDTH,
+        "height": HEIGHT,
+        "zero": scale.y(0.0),
+        "per_diem": scale.points(&per_diem),
+        "goals": scale.points(&goals),
+        "first": snapshots.first().map(|snapshot| snapshot.snapshot_date),
+        "last": snapshots.last().map(|snapshot| snapshot.snapshot_date),
+    })
+}
+
+/// Snapshots are expected in date order, as `DashboardSnapshot::get_all` returns them. Months
+/// are returned newest first.
+fn summarize_by_month(snapshots: &[DashboardSnapshot]) -> Vec<MonthSummary> {
+    let mut summaries: Vec<MonthSummary> = Vec::new();
+
+    for month in snapshots.chunk_by(|a, b| {
+        (a.snapshot_date.year(), a.snapshot_date.month())
+            == (b.snapshot_date.year(), b.snapshot_date.month())
+    }) {
+        let days = Decimal::from(month.len());
+        let per_diem = month.iter().map(|s| s.per_diem).sum::<Decimal>() / days;
+        let per_diem_change = summaries
+            .last()
+            .map(|previous| per_diem - previous.per_diem);
+
+        summaries.push(MonthSummary {
+            month: month[0].snapshot_date.format("%B %Y").to_string(),
+            days: month.len(),
+            per_diem,
+            goals_accumulated_per_day: month
+                .iter()
+                .map(|s| s.goals_accumulated_per_day)
+                .sum::<Decimal>()
+                / days,
+            remaining_total: month[month.len() - 1].remaining_total,
+            per_diem_change,
+            improving: per_diem_change.is_some_and(|change| change > Decimal::ZERO),
+        });
+    }
+
+    summaries.reverse();
+    summaries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{index, summarize_by_month};
+    use crate::{models::dashboard_snapshot::DashboardSnapshot, test_utils::state_for_tests};
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use chrono::{Days, NaiveDate, Utc};
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    fn snapshot(user_id: Uuid, date: NaiveDate, per_diem: i64) -> DashboardSnapshot {
+        DashboardSnapshot {
+            id: None,
+            user_id,
+            snapshot_date: date,
+            per_diem: Decimal::new(per_diem, 0),
+            remaining_total: Decimal::new(per_diem * 10, 0),
+            goals_accumulated_per_day: Decimal::new(5, 0),
+            created_at: None,
+        }
+    }
+
+    #[test]
+    fn test_summarize_by_month() {
+        let user_id = Uuid::new_v4();
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        let summaries = summarize_by_month(&[
+            snapshot(user_id, date(1, 30), 40),
+            snapshot(user_id, date(1, 31), 60),
+            snapshot(user_id, date(2, 1), 70),
+        ]);
+
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].month, "February 2024");
+        assert_eq!(summaries[0].per_diem_change, Some(Decimal::new(20, 0)));
+        assert!(summaries[0].improving);
+        assert_eq!(summaries[1].per_diem, Decimal::new(50, 0));
+        assert_eq!(summaries[1].remaining_total, Decimal::new(600, 0));
+        assert_eq!(summaries[1].per_diem_change, None);
+    }
+
+    #[tokio::test]
+    async fn test_history_lists_snapshots() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let today = Utc::now().date_naive();
+        for (days_ago, per_diem) in [(2, 30), (1, 35)] {
+            snapshot(user_id, today - Days::new(days_ago), per_diem)
+                .create(&client)
+                .await
+                .unwrap();
+        }
+
+        let app = Router::new()
+            .route("/history", get(index))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .uri("/history")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("polyline"));
+        assert!(body.contains("35"));
+    }
+}
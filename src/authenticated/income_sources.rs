@@ -0,0 +1,83 @@
+mod create;
+mod delete;
+mod edit;
+mod index;
+mod new;
+mod update;
+
+use super::UserExtension;
+use crate::{
+    HandlebarsContext, Section, SharedState, errors::AppError,
+    models::income_source::IncomeRecurrence,
+};
+use anyhow::anyhow;
+use axum::{
+    Extension, Router,
+    extract::Request,
+    middleware::{Next, from_fn},
+    response::Response,
+    routing::get,
+};
+use chrono::NaiveDate;
+use handlebars::to_json;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::str::FromStr;
+
+fn schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "minLength": 2 },
+            "amount": { "type": "number", "minimum": 0 },
+            "recurrence": {
+                "enum": ["once", "weekly", "biweekly", "semi_monthly", "monthly", "quarterly", "yearly"]
+            },
+            "next_expected_on": { "type": "string", "format": "date" }
+        },
+        "required": [ "name", "amount", "recurrence", "next_expected_on" ],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IncomeSourceForm {
+    name: String,
+    amount: f64,
+    recurrence: String,
+    next_expected_on: NaiveDate,
+}
+
+impl IncomeSourceForm {
+    fn recurrence(&self) -> Result<IncomeRecurrence, AppError> {
+        IncomeRecurrence::from_str(&self.recurrence)
+            .map_err(|e| AppError::InvalidRecord(anyhow!("{:#?}", e)))
+    }
+}
+
+async fn initialize_context(
+    Extension(user_extension): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let mut context = context.clone();
+
+    context.insert("section".to_string(), to_json(Section::Income));
+    context.insert("csrf".to_string(), to_json(user_extension.csrf));
+
+    request.extensions_mut().insert(context);
+    next.run(request).await
+}
+
+pub fn income_sources_router() -> Router<SharedState> {
+    Router::new()
+        .route("/", get(index::action).post(create::action))
+        .route(
+            "/{id}",
+            get(edit::action).put(update::action).delete(delete::action),
+        )
+        .route("/new", get(new::action))
+        .route("/{id}/delete", get(delete::modal))
+        .route_layer(from_fn(initialize_context))
+}
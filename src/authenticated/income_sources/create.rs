@@ -0,0 +1,155 @@
+use super::{IncomeSourceForm, schema};
+use crate::{
+    HandlebarsContext, SharedState, authenticated::UserExtension, errors::AppResponse,
+    models::income_source::IncomeSource, utilities::responses,
+};
+use anyhow::anyhow;
+use axum::{
+    Extension, Form,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use handlebars::to_json;
+use rust_decimal::{Decimal, prelude::FromPrimitive};
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    headers: HeaderMap,
+    Extension(context): Extension<HandlebarsContext>,
+    Form(form): Form<IncomeSourceForm>,
+) -> AppResponse {
+    let json = serde_json::to_value(&form)?;
+    let valid = jsonschema::validate(&schema(), &json);
+    let response_format = responses::get_response_format(&headers)?;
+
+    match valid {
+        Ok(_) => {}
+        Err(validation_errors) => {
+            let mut context = context.clone();
+
+            context.insert("errors".to_string(), to_json(validation_errors.to_string()));
+            context.insert("name".to_string(), to_json(&form.name));
+            context.insert("amount".to_string(), to_json(form.amount));
+            context.insert("recurrence".to_string(), to_json(&form.recurrence));
+            context.insert(
+                "next_expected_on".to_string(),
+                to_json(form.next_expected_on),
+            );
+
+            match response_format {
+                responses::ResponseFormat::Html => {
+                    context.insert("partial".to_string(), to_json("income_sources/new"));
+                    return Ok(responses::generate_response(
+                        &responses::ResponseFormat::Html,
+                        shared_state.handlebars.render("layout", &context)?,
+                        StatusCode::BAD_REQUEST,
+                    ));
+                }
+                responses::ResponseFormat::Turbo => {
+                    return Ok(responses::generate_response(
+                        &response_format,
+                        shared_state
+                            .handlebars
+                            .render("income_sources/_form.turbo", &context)?,
+                        StatusCode::BAD_REQUEST,
+                    ));
+                }
+                responses::ResponseFormat::Json => {
+                    return Ok(responses::api_only());
+                }
+            }
+        }
+    }
+
+    let client = shared_state.pool.get_client().await?;
+    IncomeSource {
+        id: None,
+        user_id: user.id,
+        name: form.name.to_owned(),
+        amount: Decimal::from_f64(form.amount).ok_or_else(|| anyhow!("could not parse decimal"))?,
+        recurrence: form.recurrence()?,
+        next_expected_on: form.next_expected_on,
+    }
+    .create(&client)
+    .await?;
+
+    match response_format {
+        responses::ResponseFormat::Html | responses::ResponseFormat::Turbo => {
+            Ok(Redirect::to("/income_sources").into_response())
+        }
+        responses::ResponseFormat::Json => Ok(responses::api_only()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::income_source::IncomeRecurrence, test_utils::state_for_tests};
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::Request;
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_create_income_source() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let app = Router::new()
+            .route("/income_sources", post(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/income_sources")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from(
+                "name=paycheck&amount=1200&recurrence=biweekly&next_expected_on=2024-01-05",
+            ))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers().get("location").unwrap(),
+            "/income_sources"
+        );
+
+        let income_sources = IncomeSource::get_all(&client, user_id).await.unwrap();
+        assert_eq!(income_sources.len(), 1);
+        assert_eq!(income_sources[0].recurrence, IncomeRecurrence::Biweekly);
+        assert_eq!(income_sources[0].amount, Decimal::new(1200, 0));
+    }
+
+    #[tokio::test]
+    async fn test_create_income_source_validation_error() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+
+        let app = Router::new()
+            .route("/income_sources", post(action))
+            .with_state(shared_state)
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .method("POST")
+            .uri("/income_sources")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("Accept", "text/vnd.turbo-stream.html")
+            .body(Body::from(
+                "name=paycheck&amount=1200&recurrence=hourly&next_expected_on=2024-01-05",
+            ))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
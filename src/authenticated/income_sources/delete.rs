@@ -0,0 +1,83 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::income_source::IncomeSource,
+    utilities::responses::{ResponseFormat, api_only, generate_response, get_response_format},
+};
+use axum::{
+    Extension,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use handlebars::to_json;
+use uuid::Uuid;
+
+pub async fn modal(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let income_source = IncomeSource::get_one(&client, id, user.id).await?;
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html => {
+            let mut context = context.clone();
+            context.insert(
+                "prompt".to_string(),
+                to_json("Are you sure you want to delete this income source?"),
+            );
+            context.insert(
+                "action".to_string(),
+                to_json(format!("/income_sources/{}", id)),
+            );
+            context.insert("entity".to_string(), to_json(income_source.name));
+            context.insert("partial".to_string(), to_json("delete_confirmation"));
+            Ok(generate_response(
+                &response_format,
+                shared_state
+                    .handlebars
+                    .render("delete_confirmation", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Turbo => Ok(StatusCode::NOT_ACCEPTABLE.into_response()),
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let income_source = IncomeSource::get_one(&client, id, user.id).await?;
+
+    income_source.delete(&client).await?;
+
+    let response_format = get_response_format(&headers)?;
+    match response_format {
+        ResponseFormat::Html => Ok(StatusCode::NOT_ACCEPTABLE.into_response()),
+        ResponseFormat::Json => Ok(api_only()),
+        ResponseFormat::Turbo => {
+            let mut context = context.clone();
+            context.insert("income_source".to_string(), to_json(&income_source));
+
+            Ok(generate_response(
+                &response_format,
+                shared_state
+                    .handlebars
+                    .render("income_sources/delete", &context)?,
+                StatusCode::OK,
+            ))
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::income_source::IncomeSource,
+    utilities::responses::{ResponseFormat, api_only, generate_response, get_response_format},
+};
+use axum::{
+    Extension,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use handlebars::to_json;
+use uuid::Uuid;
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    user: Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let income_source = IncomeSource::get_one(&client, id, user.id).await?;
+    let mut context = context.clone();
+    let response_format = get_response_format(&headers)?;
+    context.insert("id".to_string(), to_json(income_source.id));
+    context.insert("name".to_string(), to_json(&income_source.name));
+    context.insert("amount".to_string(), to_json(income_source.amount));
+    context.insert(
+        "recurrence".to_string(),
+        to_json(income_source.recurrence.to_string()),
+    );
+    context.insert(
+        "next_expected_on".to_string(),
+        to_json(income_source.next_expected_on),
+    );
+
+    match response_format {
+        ResponseFormat::Html => {
+            context.insert("partial".to_string(), to_json("income_sources/edit"));
+
+            Ok(generate_response(
+                &response_format,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Turbo => Ok(StatusCode::BAD_REQUEST.into_response()),
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::{income_source::IncomeSource, user::User},
+    utilities::{
+        dates::{TimeProvider, Times},
+        responses::{ResponseFormat, api_only, generate_response, get_response_format},
+    },
+};
+use axum::{
+    Extension,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use chrono_tz::Tz;
+use handlebars::to_json;
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::str::FromStr;
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    user: Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let mut context = context.clone();
+    let income_sources = IncomeSource::get_all(&client, user.id).await?;
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            let user = User::get_by_id(&client, user.id).await?;
+            let timezone = Tz::from_str(&user.timezone()?)?;
+            let period = user.budget_period();
+            let today = TimeProvider {}.now().with_timezone(&timezone).date_naive();
+            let (start, next) = period.bounds(today)?;
+
+            let mut period_total = Decimal::ZERO;
+            let mut rows = Vec::with_capacity(income_sources.len());
+            for income_source in &income_sources {
+                let per_period = income_source.amount_for_period(period, start, next);
+                period_total += per_period;
+
+                rows.push(json!({
+                    "id": income_source.id,
+                    "name": income_source.name,
+                    "amount": income_source.amount,
+                    "recurrence": income_source.recurrence.to_string(),
+                    "next_expected_on": income_source.next_expected_after(today)?,
+                    "per_period": per_period,
+                }));
+            }
+
+            context.insert("income_sources".to_string(), to_json(rows));
+            context.insert("period_total".to_string(), to_json(period_total));
+            context.insert("partial".to_string(), to_json("income_sources/index"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
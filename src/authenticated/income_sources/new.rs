@@ -0,0 +1,45 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    errors::AppResponse,
+    utilities::responses::{ResponseFormat, api_only, generate_response, get_response_format},
+};
+use axum::{
+    Extension,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use handlebars::to_json;
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let mut context = context.clone();
+    context.insert("name".to_string(), to_json(""));
+    context.insert("amount".to_string(), to_json(""));
+    context.insert("recurrence".to_string(), to_json("monthly"));
+    context.insert("next_expected_on".to_string(), to_json(""));
+
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html => {
+            context.insert("partial".to_string(), to_json("income_sources/new"));
+
+            Ok(generate_response(
+                &response_format,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Turbo => Ok(generate_response(
+            &response_format,
+            shared_state
+                .handlebars
+                .render("income_sources/new", &context)?,
+            StatusCode::OK,
+        )),
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
@@ -0,0 +1,87 @@
+use super::{IncomeSourceForm, schema};
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::income_source::IncomeSource,
+    utilities::responses::{self, get_response_format},
+};
+use anyhow::anyhow;
+use axum::{
+    Extension, Form,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use handlebars::to_json;
+use rust_decimal::{Decimal, prelude::FromPrimitive};
+use uuid::Uuid;
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Extension(context): Extension<HandlebarsContext>,
+    Form(form): Form<IncomeSourceForm>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let json = serde_json::to_value(&form)?;
+    let valid = jsonschema::validate(&schema(), &json);
+    let response_format = responses::get_response_format(&headers)?;
+
+    match valid {
+        Ok(_) => {}
+        Err(validation_errors) => {
+            let mut context = context.clone();
+
+            context.insert("errors".to_string(), to_json(validation_errors.to_string()));
+            context.insert("id".to_string(), to_json(id));
+            context.insert("name".to_string(), to_json(&form.name));
+            context.insert("amount".to_string(), to_json(form.amount));
+            context.insert("recurrence".to_string(), to_json(&form.recurrence));
+            context.insert(
+                "next_expected_on".to_string(),
+                to_json(form.next_expected_on),
+            );
+
+            match response_format {
+                responses::ResponseFormat::Html => {
+                    context.insert("partial".to_string(), to_json("income_sources/edit"));
+                    return Ok(responses::generate_response(
+                        &responses::ResponseFormat::Html,
+                        shared_state.handlebars.render("layout", &context)?,
+                        StatusCode::BAD_REQUEST,
+                    ));
+                }
+                responses::ResponseFormat::Turbo => {
+                    return Ok(responses::generate_response(
+                        &response_format,
+                        shared_state
+                            .handlebars
+                            .render("income_sources/_form.turbo", &context)?,
+                        StatusCode::BAD_REQUEST,
+                    ));
+                }
+                responses::ResponseFormat::Json => {
+                    return Ok(responses::api_only());
+                }
+            }
+        }
+    }
+
+    let mut income_source = IncomeSource::get_one(&client, id, user.id).await?;
+    income_source.name = form.name.clone();
+    income_source.amount =
+        Decimal::from_f64(form.amount).ok_or_else(|| anyhow!("could not parse decimal"))?;
+    income_source.recurrence = form.recurrence()?;
+    income_source.next_expected_on = form.next_expected_on;
+    income_source.update(&client).await?;
+
+    match get_response_format(&headers)? {
+        responses::ResponseFormat::Html | responses::ResponseFormat::Turbo => {
+            Ok(Redirect::to("/income_sources").into_response())
+        }
+        responses::ResponseFormat::Json => Ok(responses::api_only()),
+    }
+}
@@ -0,0 +1,330 @@
+use super::{UserExtension, dashboard::generate_dashboard_context_for, goals};
+use crate::{
+    HandlebarsContext, SharedState,
+    errors::AppError,
+    models::user::{Session, User},
+};
+use axum::{
+    Extension,
+    extract::{
+        Request, State,
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code},
+    },
+    http::{HeaderMap, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use tokio::{
+    sync::broadcast::{self, Receiver, Sender, error::RecvError},
+    time::sleep,
+};
+use tracing::error;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LiveUpdate {
+    User(Uuid),
+    /// Sent by the background jobs, which change every user's figures as time passes.
+    Everyone,
+}
+
+impl LiveUpdate {
+    fn concerns(&self, user_id: Uuid) -> bool {
+        match self {
+            Self::User(id) => *id == user_id,
+            Self::Everyone => true,
+        }
+    }
+}
+
+/// Tells open dashboards that their user's data has changed. Only sockets connected to this
+/// process are told, which is every socket while the app runs as a single instance.
+#[derive(Clone, Debug)]
+pub struct LiveUpdates(Sender<LiveUpdate>);
+
+impl Default for LiveUpdates {
+    fn default() -> Self {
+        Self(broadcast::channel(64).0)
+    }
+}
+
+impl LiveUpdates {
+    pub fn publish(&self, update: LiveUpdate) {
+        // Sending only fails when nobody is listening.
+        let _ = self.0.send(update);
+    }
+
+    pub fn subscribe(&self) -> Receiver<LiveUpdate> {
+        self.0.subscribe()
+    }
+}
+
+/// Publishes an update for the user after any request that may have changed their data.
+pub async fn publish_changes(
+    State(shared_state): State<SharedState>,
+    Extension(user): Extension<UserExtension>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let changes = !request.method().is_safe();
+    let response = next.run(request).await;
+
+    if changes && (response.status().is_success() || response.status().is_redirection()) {
+        shared_state.live.publish(LiveUpdate::User(user.id));
+    }
+
+    response
+}
+
+/// Browsers send cookies with cross-site WebSocket handshakes, so only same-origin pages may
+/// connect.
+fn same_origin(headers: &HeaderMap) -> bool {
+    let Some(origin) = headers.get(header::ORIGIN) else {
+        return true;
+    };
+
+    match (origin.to_str(), headers.get(header::HOST)) {
+        (Ok(origin), Some(host)) => origin
+            .split_once("://")
+            .is_some_and(|(_, origin)| origin.as_bytes() == host.as_bytes()),
+        _ => false,
+    }
+}
+
+pub async fn connect(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(session): Extension<Session>,
+    Extension(context): Extension<HandlebarsContext>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    if !same_origin(&headers) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    let updates = shared_state.live.subscribe();
+    let State(shared_state) = shared_state;
+
+    upgrade
+        .on_upgrade(move |socket| stream(socket, shared_state, context, user.id, session, updates))
+}
+
+/// Whether the session the socket was opened with still signs its user in.
+async fn signed_in(shared_state: &SharedState, session: &Session) -> bool {
+    let Some(id) = session.id else {
+        return false;
+    };
+    let Ok(client) = shared_state.pool.get_client().await else {
+        return false;
+    };
+
+    Session::get_by_id(&client, &id.to_string()).await.is_ok()
+}
+
+/// Sends fresh fragments on connecting, so a page that reconnects catches up, and again after
+/// each update for the user. The session is checked before every push, and the socket is
+/// closed once it has ended.
+async fn stream(
+    mut socket: WebSocket,
+    shared_state: SharedState,
+    context: HandlebarsContext,
+    user_id: Uuid,
+    session: Session,
+    mut updates: Receiver<LiveUpdate>,
+) {
+    let expired = sleep(
+        (session.expiration - Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    );
+    tokio::pin!(expired);
+
+    loop {
+        if !signed_in(&shared_state, &session).await {
+            let frame = CloseFrame {
+                code: close_code::POLICY,
+                reason: "session ended".into(),
+            };
+            let _ = socket.send(Message::Close(Some(frame))).await;
+            return;
+        }
+
+        match render(&shared_state, context.clone(), user_id).await {
+            Ok(fragments) => {
+                if socket.send(Message::Text(fragments.into())).await.is_err() {
+                    return;
+                }
+            }
+            Err(err) => error!("could not render live update for {}: {:#?}", user_id, err),
+        }
+
+        loop {
+            tokio::select! {
+                () = &mut expired, if !expired.is_elapsed() => break,
+                update = updates.recv() => match update {
+                    Ok(update) if update.concerns(user_id) => break,
+                    Ok(_) => {}
+                    // Whatever was missed may have concerned this user.
+                    Err(RecvError::Lagged(_)) => break,
+                    Err(RecvError::Closed) => return,
+                },
+                message = socket.recv() => match message {
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+                    Some(Ok(_)) => {}
+                },
+            }
+        }
+    }
+}
+
+/// Turbo streams for the dashboard figures and the goals table; pages without one of them
+/// ignore its stream.
+pub async fn render(
+    shared_state: &SharedState,
+    mut context: HandlebarsContext,
+    user_id: Uuid,
+) -> Result<String, AppError> {
+    let client = shared_state.pool.get_client().await?;
+    let user = User::get_by_id(&client, user_id).await?;
+
+    generate_dashboard_context_for(&mut context, &user, &client).await?;
+    goals::generate_goals_context_for(&mut context, &user, &client).await?;
+
+    Ok(shared_state.handlebars.render("live.turbo", &context)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LiveUpdate, publish_changes, render, same_origin, signed_in};
+    use crate::{
+        models::{
+            goal::{EnvelopePolicy, Goal, Recurrence},
+            user::Session,
+        },
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::Body,
+        http::{HeaderMap, Method, Request, StatusCode, header},
+        middleware::from_fn_with_state,
+        routing::get,
+    };
+    use chrono::{Duration, Utc};
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_same_origin() {
+        let headers = |origin: Option<&str>| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::HOST, "budget.example.com".parse().unwrap());
+            if let Some(origin) = origin {
+                headers.insert(header::ORIGIN, origin.parse().unwrap());
+            }
+            headers
+        };
+
+        assert!(same_origin(&headers(None)));
+        assert!(same_origin(&headers(Some("https://budget.example.com"))));
+        assert!(!same_origin(&headers(Some("https://evil.example.com"))));
+        assert!(!same_origin(&headers(Some("null"))));
+    }
+
+    #[test]
+    fn test_updates_concern_their_user() {
+        let (user_id, other_id) = (Uuid::new_v4(), Uuid::new_v4());
+
+        assert!(LiveUpdate::User(user_id).concerns(user_id));
+        assert!(!LiveUpdate::User(other_id).concerns(user_id));
+        assert!(LiveUpdate::Everyone.concerns(user_id));
+    }
+
+    #[tokio::test]
+    async fn test_sockets_outlive_only_current_sessions() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+        let session = |expiration| Session {
+            id: None,
+            user_id: user_extension.id,
+            expiration,
+            csrf: "live".to_string(),
+        };
+
+        let mut current = session(Utc::now() + Duration::hours(1));
+        current.create(&client).await.unwrap();
+        let mut expired = session(Utc::now() - Duration::hours(1));
+        expired.create(&client).await.unwrap();
+
+        assert!(signed_in(&shared_state, &current).await);
+        assert!(!signed_in(&shared_state, &expired).await);
+        assert!(!signed_in(&shared_state, &session(Utc::now() + Duration::hours(1))).await);
+    }
+
+    #[tokio::test]
+    async fn test_changes_are_published() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let mut updates = shared_state.live.subscribe();
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async { "ok" })
+                    .post(|| async { "ok" })
+                    .put(|| async { StatusCode::BAD_REQUEST }),
+            )
+            .route_layer(from_fn_with_state(shared_state.clone(), publish_changes))
+            .layer(user_extension.clone())
+            .with_state(shared_state);
+
+        for method in [Method::GET, Method::PUT, Method::POST] {
+            let request = Request::builder()
+                .method(method)
+                .uri("/")
+                .body(Body::empty())
+                .unwrap();
+            app.clone().oneshot(request).await.unwrap();
+        }
+
+        assert_eq!(
+            updates.try_recv().unwrap(),
+            LiveUpdate::User(user_extension.id)
+        );
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_render_dashboard_and_goals() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+        let goal = Goal {
+            id: None,
+            user_id: user_extension.id,
+            name: "Vacation".to_string(),
+            recurrence: Recurrence::Never,
+            target: Decimal::new(1200, 0),
+            target_date: Utc::now() + Duration::days(90),
+            accumulated_amount: Decimal::ZERO,
+            start_date: Some(Utc::now()),
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let fragments = render(&shared_state, context_extension.0, user_extension.id)
+            .await
+            .unwrap();
+
+        assert!(fragments.contains(r#"<turbo-stream action="update" target="dashboard">"#));
+        assert!(fragments.contains(r#"<turbo-stream action="replace" target="goals_index">"#));
+        assert!(fragments.contains(&format!(r#"id="goal_{}""#, goal.id.unwrap())));
+        assert!(fragments.contains("Minutes until today"));
+    }
+}
@@ -1,15 +1,28 @@
+mod calendar;
+mod export;
+mod import;
 mod index;
+mod ledger;
+mod tokens;
 mod update;
+mod webhooks;
 
-use super::UserExtension;
-use crate::{HandlebarsContext, Section, SharedState, models::user::GoalHeader};
+use super::{UserExtension, session_only};
+use crate::{
+    HandlebarsContext, Section, SharedState,
+    errors::AppError,
+    models::user::{GoalFunding, GoalHeader},
+    utilities::dates::BudgetPeriod,
+};
+use anyhow::anyhow;
 use axum::{
     Extension, Router,
     extract::Request,
     middleware::{Next, from_fn},
     response::Response,
-    routing::get,
+    routing::{delete, get, post},
 };
+use chrono::NaiveDate;
 use handlebars::to_json;
 use serde::Deserialize;
 
@@ -18,7 +31,49 @@ pub struct PreferencesForm {
     timezone: Option<String>,
     goal_header: Option<GoalHeader>,
     forecast_offset: Option<i64>,
-    monthly_income: Option<f64>,
+    budget_period: Option<String>,
+    budget_period_anchor: Option<String>,
+    goal_funding: Option<GoalFunding>,
+}
+
+impl PreferencesForm {
+    fn budget_period(&self) -> Result<Option<BudgetPeriod>, AppError> {
+        let Some(budget_period) = self.budget_period.as_deref() else {
+            return Ok(None);
+        };
+
+        match budget_period {
+            "calendar_month" => Ok(Some(BudgetPeriod::CalendarMonth)),
+            "semi_monthly" => Ok(Some(BudgetPeriod::SemiMonthly)),
+            "weekly" => Ok(Some(BudgetPeriod::Weekly)),
+            "biweekly" => {
+                let anchor = self
+                    .budget_period_anchor
+                    .as_deref()
+                    .filter(|anchor| !anchor.is_empty())
+                    .ok_or_else(|| {
+                        AppError::InvalidRecord(anyhow!("biweekly periods need an anchor date"))
+                    })?;
+                let anchor = NaiveDate::parse_from_str(anchor, "%Y-%m-%d")
+                    .map_err(|e| AppError::InvalidRecord(e.into()))?;
+
+                Ok(Some(BudgetPeriod::Biweekly { anchor }))
+            }
+            _ => Err(AppError::InvalidRecord(anyhow!(
+                "unknown budget period {}",
+                budget_period
+            ))),
+        }
+    }
+}
+
+fn budget_period_context(budget_period: BudgetPeriod) -> (&'static str, Option<NaiveDate>) {
+    match budget_period {
+        BudgetPeriod::CalendarMonth => ("calendar_month", None),
+        BudgetPeriod::SemiMonthly => ("semi_monthly", None),
+        BudgetPeriod::Biweekly { anchor } => ("biweekly", Some(anchor)),
+        BudgetPeriod::Weekly => ("weekly", None),
+    }
 }
 
 async fn initialize_context(
@@ -39,5 +94,41 @@ async fn initialize_context(
 pub fn preferences_router() -> Router<SharedState> {
     Router::new()
         .route("/", get(index::action).put(update::action))
+        .route(
+            "/calendar",
+            get(calendar::show).merge(
+                post(calendar::create)
+                    .delete(calendar::delete)
+                    .route_layer(from_fn(session_only)),
+            ),
+        )
+        .route("/export", get(export::action))
+        .route(
+            "/import",
+            get(import::new)
+                .post(import::create)
+                .route_layer(from_fn(session_only)),
+        )
+        .route("/ledger", get(ledger::new))
+        .route("/ledger/download", get(ledger::action))
+        .route(
+            "/tokens",
+            get(tokens::index)
+                .post(tokens::create)
+                .route_layer(from_fn(session_only)),
+        )
+        .route(
+            "/tokens/{id}",
+            delete(tokens::delete).route_layer(from_fn(session_only)),
+        )
+        .route(
+            "/webhooks",
+            get(webhooks::index).merge(post(webhooks::create).route_layer(from_fn(session_only))),
+        )
+        .route("/webhooks/deliveries", get(webhooks::deliveries))
+        .route(
+            "/webhooks/{id}",
+            delete(webhooks::delete).route_layer(from_fn(session_only)),
+        )
         .route_layer(from_fn(initialize_context))
 }
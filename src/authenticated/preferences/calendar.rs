@@ -0,0 +1,182 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::user::User,
+    utilities::responses::{ResponseFormat, generate_response, get_response_format},
+};
+use axum::{
+    Extension, Json,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Redirect},
+};
+use handlebars::to_json;
+use rand::{
+    distr::{Alphanumeric, SampleString},
+    rng,
+};
+use serde_json::json;
+
+/// Where calendar apps subscribe to the feed, on the host the page was requested from.
+fn feed_url(headers: &HeaderMap, calendar_token: Option<&str>) -> Option<String> {
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .unwrap_or("localhost");
+
+    calendar_token.map(|token| format!("https://{host}/calendar/{token}.ics"))
+}
+
+async fn respond(
+    shared_state: &SharedState,
+    headers: &HeaderMap,
+    mut context: HandlebarsContext,
+    user: &User,
+) -> AppResponse {
+    let response_format = get_response_format(headers)?;
+    let feed_url = feed_url(headers, user.calendar_token.as_deref());
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            context.insert(
+                "webcal_url".to_string(),
+                to_json(
+                    feed_url
+                        .as_deref()
+                        .map(|url| url.replacen("https://", "webcal://", 1)),
+                ),
+            );
+            context.insert("feed_url".to_string(), to_json(feed_url));
+            context.insert("partial".to_string(), to_json("preferences/calendar"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(generate_response(
+            &response_format,
+            Json(json!({ "feed_url": feed_url })),
+            StatusCode::OK,
+        )),
+    }
+}
+
+pub async fn show(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let user = User::get_by_id(&client, user.id).await?;
+
+    respond(&shared_state, &headers, context, &user).await
+}
+
+/// Creates the feed, or replaces its token so that the old URL stops working.
+pub async fn create(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let calendar_token = Alphanumeric.sample_string(&mut rng(), 32);
+    let user = User::get_by_id(&client, user.id)
+        .await?
+        .set_calendar_token(&client, Some(&calendar_token))
+        .await?;
+
+    match get_response_format(&headers)? {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            Ok(Redirect::to("/preferences/calendar").into_response())
+        }
+        ResponseFormat::Json => respond(&shared_state, &headers, context, &user).await,
+    }
+}
+
+pub async fn delete(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let user = User::get_by_id(&client, user.id)
+        .await?
+        .set_calendar_token(&client, None)
+        .await?;
+
+    match get_response_format(&headers)? {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            Ok(Redirect::to("/preferences/calendar").into_response())
+        }
+        ResponseFormat::Json => respond(&shared_state, &headers, context, &user).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create, delete, show};
+    use crate::test_utils::state_for_tests;
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Method, Request, StatusCode},
+        routing::get,
+    };
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    async fn feed_url(app: &Router, method: Method) -> Value {
+        let request = Request::builder()
+            .method(method)
+            .uri("/preferences/calendar")
+            .header("Accept", "application/json")
+            .header("Host", "budget.example.com")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<Value>(&body).unwrap()["feed_url"].clone()
+    }
+
+    #[tokio::test]
+    async fn test_create_rotate_and_revoke_feed() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .route(
+                "/preferences/calendar",
+                get(show).post(create).delete(delete),
+            )
+            .with_state(shared_state)
+            .layer(user_extension)
+            .layer(context_extension);
+
+        assert_eq!(feed_url(&app, Method::GET).await, Value::Null);
+
+        let created = feed_url(&app, Method::POST).await;
+        let created = created.as_str().unwrap();
+        assert!(created.starts_with("https://budget.example.com/calendar/"));
+        assert!(created.ends_with(".ics"));
+
+        let rotated = feed_url(&app, Method::POST).await;
+        assert_ne!(rotated.as_str().unwrap(), created);
+        assert_eq!(feed_url(&app, Method::GET).await, rotated);
+
+        assert_eq!(feed_url(&app, Method::DELETE).await, Value::Null);
+        assert_eq!(feed_url(&app, Method::GET).await, Value::Null);
+
+        let request = Request::builder()
+            .uri("/preferences/calendar")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::{
+    SharedState, authenticated::UserExtension, errors::AppResponse, models::archive::export,
+};
+use axum::{Extension, Json, extract::State, http::header, response::IntoResponse};
+
+/// Downloads everything the user owns as a versioned JSON archive.
+pub async fn action(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let archive = export(&client, user.id).await?;
+    let filename = format!(
+        "simple-budget-{}.json",
+        archive.exported_at.format("%Y-%m-%d")
+    );
+
+    Ok((
+        [(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{filename}\""),
+        )],
+        Json(archive),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::action;
+    use crate::test_utils::state_for_tests;
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_export_downloads_archive() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .route("/preferences/export", get(action))
+            .with_state(shared_state)
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .uri("/preferences/export")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            response.headers()["content-disposition"]
+                .to_str()
+                .unwrap()
+                .starts_with("attachment; filename=\"simple-budget-")
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let archive: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(archive["version"], 2);
+        assert_eq!(archive["tables"]["accounts"], Value::Array(Vec::new()));
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::archive::{Archive, restore, schema, upgrade},
+    utilities::responses::{ResponseFormat, generate_response, get_response_format},
+};
+use axum::{
+    Extension, Json,
+    extract::{Multipart, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use handlebars::to_json;
+use serde_json::Value;
+
+pub async fn new(
+    shared_state: State<SharedState>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let mut context = context.clone();
+    context.insert("errors".to_string(), to_json(None::<String>));
+    context.insert("partial".to_string(), to_json("preferences/import"));
+
+    Ok(generate_response(
+        &ResponseFormat::Html,
+        shared_state.handlebars.render("layout", &context)?,
+        StatusCode::OK,
+    ))
+}
+
+/// Reads the uploaded archive and whether to replace the user's data with it.
+async fn read_upload(mut multipart: Multipart) -> Result<(Vec<u8>, bool), AppError> {
+    let mut file = Vec::new();
+    let mut replace = false;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::InvalidRecord(e.into()))?
+    {
+        match field.name() {
+            Some("file") => {
+                file = field
+                    .bytes()
+                    .await
+                    .map_err(|e| AppError::InvalidRecord(e.into()))?
+                    .to_vec();
+            }
+            Some("replace") => {
+                replace = field
+                    .text()
+                    .await
+                    .map_err(|e| AppError::InvalidRecord(e.into()))?
+                    == "true";
+            }
+            _ => {}
+        }
+    }
+
+    Ok((file, replace))
+}
+
+pub async fn create(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    user: Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    multipart: Multipart,
+) -> AppResponse {
+    let response_format = get_response_format(&headers)?;
+    let (file, replace) = read_upload(multipart).await?;
+
+    let archive = serde_json::from_slice::<Value>(&file)
+        .map_err(|e| e.to_string())
+        .and_then(|mut archive| {
+            upgrade(&mut archive);
+            jsonschema::validate(&schema(), &archive).map_err(|e| e.to_string())?;
+            serde_json::from_value::<Archive>(archive).map_err(|e| e.to_string())
+        });
+    let archive = match archive {
+        Ok(archive) => archive,
+        Err(errors) => {
+            let mut context = context.clone();
+            context.insert("errors".to_string(), to_json(errors));
+
+            return match response_format {
+                ResponseFormat::Html | ResponseFormat::Turbo => {
+                    context.insert("partial".to_string(), to_json("preferences/import"));
+                    Ok(generate_response(
+                        &ResponseFormat::Html,
+                        shared_state.handlebars.render("layout", &context)?,
+                        StatusCode::BAD_REQUEST,
+                    ))
+                }
+                ResponseFormat::Json => Ok(generate_response(
+                    &response_format,
+                    serde_json::to_string(&context)?,
+                    StatusCode::BAD_REQUEST,
+                )),
+            };
+        }
+    };
+
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let summary = restore(&client, user.id, archive, replace).await?;
+    client.commit().await?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => Ok(Redirect::to("/").into_response()),
+        ResponseFormat::Json => Ok(generate_response(
+            &response_format,
+            Json(summary),
+            StatusCode::CREATED,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::create;
+    use crate::{
+        models::{account::Account, archive::export},
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Method, Request, StatusCode},
+        routing::post,
+    };
+    use rust_decimal::Decimal;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    fn upload(file: &str, replace: bool) -> Body {
+        let mut body = format!(
+            "--boundary\r\nContent-Disposition: form-data; name=\"file\"; filename=\"archive.json\"\r\n\r\n{file}\r\n"
+        );
+        if replace {
+            body.push_str(
+                "--boundary\r\nContent-Disposition: form-data; name=\"replace\"\r\n\r\ntrue\r\n",
+            );
+        }
+        body.push_str("--boundary--\r\n");
+
+        Body::from(body)
+    }
+
+    #[tokio::test]
+    async fn test_import_archive() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+        Account {
+            id: None,
+            user_id,
+            name: "savings".to_string(),
+            amount: Decimal::new(250, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        let archive = serde_json::to_string(&export(&client, user_id).await.unwrap()).unwrap();
+
+        let app = Router::new()
+            .route("/preferences/import", post(create))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        for (replace, accounts) in [(false, 2), (true, 1)] {
+            let request = Request::builder()
+                .method(Method::POST)
+                .uri("/preferences/import")
+                .header("content-type", "multipart/form-data; boundary=boundary")
+                .header("Accept", "application/json")
+                .body(upload(&archive, replace))
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+
+            let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let summary: Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(summary["replaced"], replace);
+            assert_eq!(summary["rows"]["accounts"], 1);
+            assert_eq!(
+                Account::get_all(&client, user_id).await.unwrap().len(),
+                accounts
+            );
+        }
+
+        let invalid = archive.replace("\"version\":2", "\"version\":3");
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri("/preferences/import")
+            .header("content-type", "multipart/form-data; boundary=boundary")
+            .body(upload(&invalid, true))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(Account::get_all(&client, user_id).await.unwrap().len(), 1);
+    }
+}
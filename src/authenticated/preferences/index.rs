@@ -1,3 +1,4 @@
+use super::budget_period_context;
 use crate::{
     HandlebarsContext, SharedState,
     authenticated::UserExtension,
@@ -26,9 +27,17 @@ pub async fn action(
     let mut context = context.clone();
 
     context.insert("timezone".to_string(), to_json(&preferences.timezone));
+
+    let (budget_period, budget_period_anchor) = budget_period_context(preferences.budget_period());
+    context.insert("budget_period".to_string(), to_json(budget_period));
+    context.insert(
+        "budget_period_anchor".to_string(),
+        to_json(budget_period_anchor),
+    );
+
     context.insert(
-        "monthly_income".to_string(),
-        to_json(preferences.monthly_income),
+        "goal_funding".to_string(),
+        to_json(preferences.goal_funding()),
     );
 
     match response_format {
@@ -0,0 +1,225 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::{
+        ledger::{Ledger, LedgerFormat, is_valid_commodity},
+        user::User,
+    },
+    utilities::responses::{ResponseFormat, generate_response},
+};
+use anyhow::anyhow;
+use axum::{
+    Extension,
+    extract::{Query, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use chrono::{Datelike, NaiveDate, Utc};
+use chrono_tz::Tz;
+use handlebars::to_json;
+use serde::Deserialize;
+
+const DEFAULT_COMMODITY: &str = "USD";
+
+#[derive(Debug, Deserialize)]
+pub struct LedgerQuery {
+    format: Option<LedgerFormat>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    commodity: Option<String>,
+}
+
+/// The range defaults to the year so far in the user's timezone.
+async fn range(
+    shared_state: &SharedState,
+    user: &UserExtension,
+    query: &LedgerQuery,
+) -> Result<(NaiveDate, NaiveDate), AppError> {
+    let client = shared_state.pool.get_client().await?;
+    let user = User::get_by_id(&client, user.id).await?;
+    let timezone: Tz = user.timezone()?.parse()?;
+
+    let to = match query.to {
+        Some(to) => to,
+        None => Utc::now().with_timezone(&timezone).date_naive(),
+    };
+    let from = match query.from {
+        Some(from) => from,
+        None => NaiveDate::from_ymd_opt(to.year(), 1, 1)
+            .ok_or_else(|| anyhow!("could not find the start of the year"))?,
+    };
+
+    Ok((from, to))
+}
+
+pub async fn new(
+    shared_state: State<SharedState>,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    Query(query): Query<LedgerQuery>,
+) -> AppResponse {
+    let (from, to) = range(&shared_state, &user, &query).await?;
+
+    let mut context = context.clone();
+    context.insert("from".to_string(), to_json(from));
+    context.insert("to".to_string(), to_json(to));
+    context.insert(
+        "format".to_string(),
+        to_json(query.format.unwrap_or_default()),
+    );
+    context.insert(
+        "commodity".to_string(),
+        to_json(query.commodity.as_deref().unwrap_or(DEFAULT_COMMODITY)),
+    );
+    context.insert("partial".to_string(), to_json("preferences/ledger"));
+
+    Ok(generate_response(
+        &ResponseFormat::Html,
+        shared_state.handlebars.render("layout", &context)?,
+        StatusCode::OK,
+    ))
+}
+
+/// Downloads a hledger or Beancount journal of the user's accounts, envelopes and goals.
+pub async fn action(
+    shared_state: State<SharedState>,
+    Extension(user): Extension<UserExtension>,
+    Query(query): Query<LedgerQuery>,
+) -> AppResponse {
+    let (from, to) = range(&shared_state, &user, &query).await?;
+    let format = query.format.unwrap_or_default();
+    let commodity = query
+        .commodity
+        .as_deref()
+        .map(str::trim)
+        .filter(|commodity| !commodity.is_empty())
+        .unwrap_or(DEFAULT_COMMODITY)
+        .to_uppercase();
+
+    if from > to {
+        return Err(AppError::InvalidRecord(anyhow!(
+            "the start of the range must be before the end"
+        )));
+    }
+    if !is_valid_commodity(&commodity) {
+        return Err(AppError::InvalidRecord(anyhow!(
+            "{commodity} is not a valid commodity"
+        )));
+    }
+
+    let client = shared_state.pool.get_client().await?;
+    let journal = Ledger::load(&client, user.id, from, to, commodity)
+        .await?
+        .render(format);
+    let filename = format!("simple-budget-{from}-{to}.{}", format.extension());
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "text/plain; charset=utf-8".to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        journal,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{action, new};
+    use crate::{
+        models::{account::Account, transaction::Transaction},
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Request, StatusCode},
+        routing::get,
+    };
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use std::str::from_utf8;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_download_journal() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(100, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        Transaction {
+            id: None,
+            user_id,
+            account_id: account.id,
+            envelope_id: None,
+            amount: Decimal::new(-2550, 2),
+            description: "Books".to_string(),
+            occurred_on: NaiveDate::from_ymd_opt(2024, 2, 10).unwrap(),
+            adjustment: false,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/preferences/ledger", get(new))
+            .route("/preferences/ledger/download", get(action))
+            .with_state(shared_state)
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let request = Request::builder()
+            .uri("/preferences/ledger")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = Request::builder()
+            .uri("/preferences/ledger/download?format=beancount&from=2024-02-01&to=2024-02-29&commodity=eur")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["content-disposition"],
+            "attachment; filename=\"simple-budget-2024-02-01-2024-02-29.beancount\""
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let journal = from_utf8(&body).unwrap();
+        assert!(journal.contains("  Assets:Checking  100.00 EUR\n"));
+        assert!(journal.contains("2024-02-10 * \"Books\"\n  Assets:Checking  -25.50 EUR\n"));
+
+        for query in [
+            "from=2024-03-01&to=2024-02-01",
+            "commodity=%24",
+            "format=ledger",
+        ] {
+            let request = Request::builder()
+                .uri(format!("/preferences/ledger/download?{query}"))
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{query}");
+        }
+    }
+}
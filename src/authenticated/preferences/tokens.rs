@@ -0,0 +1,263 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::{
+        api_token::{ApiToken, TokenScope},
+        user::User,
+    },
+    utilities::responses::{ResponseFormat, generate_response, get_response_format},
+};
+use axum::{
+    Extension, Form, Json,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use chrono_tz::Tz;
+use handlebars::to_json;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+fn schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string", "minLength": 2 },
+            "scope": { "enum": ["Read", "Write"] },
+        },
+        "required": [ "name", "scope" ],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TokenForm {
+    name: String,
+    scope: TokenScope,
+}
+
+/// Renders the user's tokens. `secret` belongs to a token that was just created, and is the only
+/// time it can be shown.
+async fn respond(
+    shared_state: &SharedState,
+    headers: &HeaderMap,
+    mut context: HandlebarsContext,
+    user_id: Uuid,
+    secret: Option<String>,
+    status: StatusCode,
+) -> AppResponse {
+    let response_format = get_response_format(headers)?;
+    let client = shared_state.pool.get_client().await?;
+    let tokens = ApiToken::get_all(&client, user_id).await?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            let user = User::get_by_id(&client, user_id).await?;
+            let timezone: Tz = user.timezone()?.parse()?;
+            let tokens = tokens
+                .iter()
+                .map(|token| {
+                    json!({
+                        "id": token.id,
+                        "name": token.name,
+                        "scope": token.scope,
+                        "created_at": token.created_at.with_timezone(&timezone).format("%b %-d, %Y").to_string(),
+                        "last_used_at": token.last_used_at.map(|last_used_at| {
+                            last_used_at.with_timezone(&timezone).format("%b %-d, %Y %-I:%M %p").to_string()
+                        }),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            context.entry("errors".to_string()).or_default();
+            context.entry("name".to_string()).or_default();
+            context.insert("tokens".to_string(), to_json(tokens));
+            context.insert("secret".to_string(), to_json(secret));
+            context.insert("partial".to_string(), to_json("preferences/tokens"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                status,
+            ))
+        }
+        ResponseFormat::Json => Ok(generate_response(
+            &response_format,
+            Json(json!({ "tokens": tokens, "secret": secret })),
+            status,
+        )),
+    }
+}
+
+pub async fn index(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    respond(
+        &shared_state,
+        &headers,
+        context,
+        user.id,
+        None,
+        StatusCode::OK,
+    )
+    .await
+}
+
+pub async fn create(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(mut context): Extension<HandlebarsContext>,
+    Form(form): Form<TokenForm>,
+) -> AppResponse {
+    if let Err(errors) = jsonschema::validate(&schema(), &serde_json::to_value(&form)?) {
+        context.insert("errors".to_string(), to_json(errors.to_string()));
+        context.insert("name".to_string(), to_json(&form.name));
+
+        return respond(
+            &shared_state,
+            &headers,
+            context,
+            user.id,
+            None,
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+    }
+
+    let client = shared_state.pool.get_client().await?;
+    let (_, secret) = ApiToken::create(&client, user.id, &form.name, form.scope).await?;
+
+    respond(
+        &shared_state,
+        &headers,
+        context,
+        user.id,
+        Some(secret),
+        StatusCode::CREATED,
+    )
+    .await
+}
+
+/// Revokes a token; requests that send it are refused from then on.
+pub async fn delete(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    ApiToken::get_one(&client, id, user.id)
+        .await?
+        .delete(&client)
+        .await?;
+
+    match get_response_format(&headers)? {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            Ok(Redirect::to("/preferences/tokens").into_response())
+        }
+        ResponseFormat::Json => {
+            respond(
+                &shared_state,
+                &headers,
+                context,
+                user.id,
+                None,
+                StatusCode::OK,
+            )
+            .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create, delete, index};
+    use crate::test_utils::state_for_tests;
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Method, Request, StatusCode},
+        routing::{delete as delete_route, get},
+    };
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    async fn send(app: &Router, method: Method, uri: &str, form: &str) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Accept", "application/json")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(Body::from(form.to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_create_and_revoke_tokens() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let app = Router::new()
+            .route("/preferences/tokens", get(index).post(create))
+            .route("/preferences/tokens/{id}", delete_route(delete))
+            .with_state(shared_state)
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let (status, body) = send(&app, Method::GET, "/preferences/tokens", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["tokens"], Value::Array(vec![]));
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/preferences/tokens",
+            "name=Scripts&scope=Read",
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(body["secret"].as_str().unwrap().starts_with("sb_"));
+        assert_eq!(body["tokens"][0]["name"], "Scripts");
+        assert_eq!(body["tokens"][0]["scope"], "Read");
+        assert!(body["tokens"][0].get("token_hash").is_none());
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/preferences/tokens",
+            "name=S&scope=Read",
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["tokens"].as_array().unwrap().len(), 1);
+
+        let (status, body) = send(&app, Method::GET, "/preferences/tokens", "").await;
+        assert_eq!(body["secret"], Value::Null);
+        let uri = format!(
+            "/preferences/tokens/{}",
+            body["tokens"][0]["id"].as_str().unwrap()
+        );
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = send(&app, Method::DELETE, &uri, "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["tokens"], Value::Array(vec![]));
+
+        let request = Request::builder()
+            .uri("/preferences/tokens")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
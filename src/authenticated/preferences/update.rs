@@ -5,14 +5,12 @@ use crate::{
     errors::AppResponse,
     models::user::{Preferences, User},
 };
-use anyhow::anyhow;
 use axum::{
     Extension, Form,
     extract::State,
     response::{Html, IntoResponse},
 };
 use postgres_types::Json;
-use rust_decimal::{Decimal, prelude::FromPrimitive};
 
 pub async fn action(
     shared_state: State<SharedState>,
@@ -29,7 +27,8 @@ pub async fn action(
             goal_header: None,
             timezone: None,
             forecast_offset: None,
-            monthly_income: Some(Decimal::ZERO),
+            budget_period: None,
+            goal_funding: None,
         },
     };
 
@@ -57,15 +56,13 @@ pub async fn action(
         }
     };
 
-    match &form.monthly_income {
-        None => {}
-        Some(monthly_income) => {
-            preferences.monthly_income = Some(
-                Decimal::from_f64(*monthly_income)
-                    .ok_or_else(|| anyhow!("could not parse decimal"))?,
-            )
-        }
-    };
+    if let Some(budget_period) = form.budget_period()? {
+        preferences.budget_period = Some(budget_period);
+    }
+
+    if let Some(goal_funding) = form.goal_funding {
+        preferences.goal_funding = Some(goal_funding);
+    }
 
     user.preferences = Some(Json(preferences.clone()));
     user.update(&client).await?;
@@ -0,0 +1,357 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::{
+        user::User,
+        webhook::{WebhookDelivery, WebhookDeliveryStatus, WebhookEndpoint, WebhookEvent},
+    },
+    utilities::responses::{ResponseFormat, generate_response, get_response_format},
+};
+use anyhow::anyhow;
+use axum::{
+    Extension, Form, Json,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use chrono_tz::Tz;
+use handlebars::to_json;
+use openidconnect::url::Url;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::json;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Each event is its own checkbox; a checked box sends its name with any value.
+#[derive(Debug, Deserialize)]
+pub struct WebhookForm {
+    url: String,
+    goal_converted: Option<String>,
+    envelope_created: Option<String>,
+    envelope_deleted: Option<String>,
+    per_diem_below_threshold: Option<String>,
+    per_diem_threshold: Option<String>,
+}
+
+impl WebhookForm {
+    fn url(&self) -> Result<Url, AppError> {
+        let url = Url::parse(self.url.trim())
+            .map_err(|_| AppError::InvalidRecord(anyhow!("{} is not a URL", self.url)))?;
+
+        match url.scheme() {
+            "http" | "https" => Ok(url),
+            scheme => Err(AppError::InvalidRecord(anyhow!(
+                "webhooks cannot be sent over {scheme}"
+            ))),
+        }
+    }
+
+    fn events(&self) -> Result<Vec<WebhookEvent>, AppError> {
+        let events: Vec<WebhookEvent> = [
+            (&self.goal_converted, WebhookEvent::GoalConverted),
+            (&self.envelope_created, WebhookEvent::EnvelopeCreated),
+            (&self.envelope_deleted, WebhookEvent::EnvelopeDeleted),
+            (
+                &self.per_diem_below_threshold,
+                WebhookEvent::PerDiemBelowThreshold,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(checked, event)| checked.as_ref().map(|_| event))
+        .collect();
+
+        if events.is_empty() {
+            return Err(AppError::InvalidRecord(anyhow!(
+                "choose at least one event"
+            )));
+        }
+
+        Ok(events)
+    }
+
+    fn per_diem_threshold(&self) -> Result<Option<Decimal>, AppError> {
+        if self.per_diem_below_threshold.is_none() {
+            return Ok(None);
+        }
+
+        let threshold = self
+            .per_diem_threshold
+            .as_deref()
+            .map(str::trim)
+            .filter(|threshold| !threshold.is_empty())
+            .ok_or_else(|| AppError::InvalidRecord(anyhow!("per diem alerts need a threshold")))?;
+
+        Decimal::from_str(threshold)
+            .ok()
+            .filter(|threshold| !threshold.is_sign_negative())
+            .map(Some)
+            .ok_or_else(|| AppError::InvalidRecord(anyhow!("{threshold} is not an amount")))
+    }
+}
+
+async fn respond(
+    shared_state: &SharedState,
+    headers: &HeaderMap,
+    mut context: HandlebarsContext,
+    user_id: Uuid,
+    status: StatusCode,
+) -> AppResponse {
+    let response_format = get_response_format(headers)?;
+    let client = shared_state.pool.get_client().await?;
+    let endpoints = WebhookEndpoint::get_all(&client, user_id).await?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            context.entry("errors".to_string()).or_default();
+            context.entry("url".to_string()).or_default();
+            context.insert("endpoints".to_string(), to_json(endpoints));
+            context.insert("partial".to_string(), to_json("preferences/webhooks"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                status,
+            ))
+        }
+        ResponseFormat::Json => Ok(generate_response(
+            &response_format,
+            Json(json!({ "endpoints": endpoints })),
+            status,
+        )),
+    }
+}
+
+pub async fn index(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    respond(&shared_state, &headers, context, user.id, StatusCode::OK).await
+}
+
+pub async fn create(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(mut context): Extension<HandlebarsContext>,
+    Form(form): Form<WebhookForm>,
+) -> AppResponse {
+    let endpoint = form
+        .url()
+        .and_then(|url| Ok((url, form.events()?, form.per_diem_threshold()?)));
+
+    let (url, events, per_diem_threshold) = match endpoint {
+        Ok(endpoint) => endpoint,
+        Err(AppError::InvalidRecord(err)) => {
+            context.insert("errors".to_string(), to_json(err.to_string()));
+            context.insert("url".to_string(), to_json(&form.url));
+
+            return respond(
+                &shared_state,
+                &headers,
+                context,
+                user.id,
+                StatusCode::BAD_REQUEST,
+            )
+            .await;
+        }
+        Err(err) => return Err(err),
+    };
+
+    let client = shared_state.pool.get_client().await?;
+    WebhookEndpoint::create(&client, user.id, url.as_str(), &events, per_diem_threshold).await?;
+
+    match get_response_format(&headers)? {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            Ok(Redirect::to("/preferences/webhooks").into_response())
+        }
+        ResponseFormat::Json => {
+            respond(
+                &shared_state,
+                &headers,
+                context,
+                user.id,
+                StatusCode::CREATED,
+            )
+            .await
+        }
+    }
+}
+
+pub async fn delete(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    WebhookEndpoint::get_one(&client, id, user.id)
+        .await?
+        .delete(&client)
+        .await?;
+
+    match get_response_format(&headers)? {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            Ok(Redirect::to("/preferences/webhooks").into_response())
+        }
+        ResponseFormat::Json => {
+            respond(&shared_state, &headers, context, user.id, StatusCode::OK).await
+        }
+    }
+}
+
+/// The most recent deliveries, including those still waiting for a retry.
+pub async fn deliveries(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(mut context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let response_format = get_response_format(&headers)?;
+    let client = shared_state.pool.get_client().await?;
+    let deliveries = WebhookDelivery::get_recent(&client, user.id, 100).await?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            let user = User::get_by_id(&client, user.id).await?;
+            let timezone: Tz = user.timezone()?.parse()?;
+            let endpoints = WebhookEndpoint::get_all(&client, user.id).await?;
+            let format = "%b %-d, %-I:%M %p";
+            let deliveries = deliveries
+                .iter()
+                .map(|delivery| {
+                    json!({
+                        "event": delivery.event,
+                        "url": endpoints
+                            .iter()
+                            .find(|endpoint| endpoint.id == delivery.endpoint_id)
+                            .map(|endpoint| endpoint.url.as_str()),
+                        "status": delivery.status,
+                        "attempts": delivery.attempts,
+                        "response_status": delivery.response_status,
+                        "last_error": delivery.last_error,
+                        "created_at": delivery.created_at.with_timezone(&timezone).format(format).to_string(),
+                        "retry_at": (delivery.status == WebhookDeliveryStatus::Pending && delivery.attempts > 0)
+                            .then(|| delivery.next_attempt_at.with_timezone(&timezone).format(format).to_string()),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            context.insert("deliveries".to_string(), to_json(deliveries));
+            context.insert(
+                "partial".to_string(),
+                to_json("preferences/webhook_deliveries"),
+            );
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(generate_response(
+            &response_format,
+            Json(json!({ "deliveries": deliveries })),
+            StatusCode::OK,
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create, delete, deliveries, index};
+    use crate::{
+        models::webhook::{WebhookEndpoint, WebhookEvent},
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        Router,
+        body::{Body, to_bytes},
+        http::{Method, Request, StatusCode},
+        routing::{delete as delete_route, get},
+    };
+    use serde_json::{Value, json};
+    use tower::ServiceExt;
+
+    async fn send(app: &Router, method: Method, uri: &str, form: &str) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Accept", "application/json")
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(Body::from(form.to_string()))
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_manage_endpoints_and_list_deliveries() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+        let user_id = user_extension.id;
+        let app = Router::new()
+            .route("/preferences/webhooks", get(index).post(create))
+            .route("/preferences/webhooks/{id}", delete_route(delete))
+            .route("/preferences/webhooks/deliveries", get(deliveries))
+            .with_state(shared_state)
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let (status, body) = send(
+            &app,
+            Method::POST,
+            "/preferences/webhooks",
+            "url=http%3A%2F%2Fhome.local%2Fhooks&envelope_created=on&per_diem_below_threshold=on&per_diem_threshold=25",
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let endpoint = &body["endpoints"][0];
+        assert_eq!(
+            endpoint["events"],
+            json!(["envelope.created", "per_diem.below_threshold"])
+        );
+        assert_eq!(endpoint["per_diem_threshold"], "25");
+        assert!(endpoint["secret"].as_str().unwrap().starts_with("whsec_"));
+
+        for form in [
+            "url=ftp%3A%2F%2Fhome.local&envelope_created=on",
+            "url=http%3A%2F%2Fhome.local",
+            "url=http%3A%2F%2Fhome.local&per_diem_below_threshold=on",
+        ] {
+            let (status, _) = send(&app, Method::POST, "/preferences/webhooks", form).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+
+        WebhookEndpoint::publish(
+            &client,
+            user_id,
+            WebhookEvent::EnvelopeCreated,
+            json!({ "name": "Gifts" }),
+        )
+        .await
+        .unwrap();
+        let (status, body) = send(&app, Method::GET, "/preferences/webhooks/deliveries", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["deliveries"][0]["event"], "envelope.created");
+        assert_eq!(body["deliveries"][0]["status"], "Pending");
+
+        let uri = format!("/preferences/webhooks/{}", endpoint["id"].as_str().unwrap());
+        let (status, body) = send(&app, Method::DELETE, &uri, "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["endpoints"], json!([]));
+
+        for uri in ["/preferences/webhooks", "/preferences/webhooks/deliveries"] {
+            let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    }
+}
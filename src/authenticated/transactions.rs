@@ -0,0 +1,123 @@
+mod create;
+mod delete;
+mod edit;
+mod index;
+mod new;
+mod update;
+
+use super::UserExtension;
+use crate::{
+    HandlebarsContext, Section, SharedState,
+    errors::AppError,
+    models::{account::Account, envelope::Envelope},
+};
+use axum::{
+    Extension, Router,
+    extract::Request,
+    middleware::{Next, from_fn},
+    response::Response,
+    routing::get,
+};
+use chrono::NaiveDate;
+use handlebars::to_json;
+use rust_database_common::GenericClient;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use uuid::Uuid;
+
+fn schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "account_id": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^([0-9a-fA-F-]{36})?$" },
+                    { "type": "null" }
+                ]
+            },
+            "envelope_id": {
+                "anyOf": [
+                    { "type": "string", "pattern": "^([0-9a-fA-F-]{36})?$" },
+                    { "type": "null" }
+                ]
+            },
+            "amount": { "type": "number", "not": { "const": 0 } },
+            "description": { "type": "string", "minLength": 2 },
+            "occurred_on": { "type": "string", "format": "date" }
+        },
+        "required": [ "amount", "description", "occurred_on" ],
+        "additionalProperties": false
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TransactionForm {
+    account_id: Option<String>,
+    envelope_id: Option<String>,
+    amount: f64,
+    description: String,
+    occurred_on: NaiveDate,
+}
+
+impl TransactionForm {
+    fn account_id(&self) -> Result<Option<Uuid>, AppError> {
+        parse_optional_id(&self.account_id)
+    }
+
+    fn envelope_id(&self) -> Result<Option<Uuid>, AppError> {
+        parse_optional_id(&self.envelope_id)
+    }
+}
+
+fn parse_optional_id(value: &Option<String>) -> Result<Option<Uuid>, AppError> {
+    match value.as_deref() {
+        None | Some("") => Ok(None),
+        Some(id) => Ok(Some(
+            Uuid::parse_str(id).map_err(|e| AppError::InvalidRecord(e.into()))?,
+        )),
+    }
+}
+
+async fn insert_choices(
+    context: &mut HandlebarsContext,
+    client: &impl GenericClient,
+    user_id: Uuid,
+) -> Result<(), AppError> {
+    context.insert(
+        "accounts".to_string(),
+        to_json(Account::get_all(client, user_id).await?),
+    );
+    context.insert(
+        "envelopes".to_string(),
+        to_json(Envelope::get_all(client, user_id).await?),
+    );
+
+    Ok(())
+}
+
+async fn initialize_context(
+    Extension(user_extension): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let mut context = context.clone();
+
+    context.insert("section".to_string(), to_json(Section::Transactions));
+    context.insert("csrf".to_string(), to_json(user_extension.csrf));
+
+    request.extensions_mut().insert(context);
+    next.run(request).await
+}
+
+pub fn transactions_router() -> Router<SharedState> {
+    Router::new()
+        .route("/", get(index::action).post(create::action))
+        .route(
+            "/{id}",
+            get(edit::action).put(update::action).delete(delete::action),
+        )
+        .route("/new", get(new::action))
+        .route("/{id}/delete", get(delete::modal))
+        .route_layer(from_fn(initialize_context))
+}
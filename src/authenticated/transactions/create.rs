@@ -0,0 +1,234 @@
+use super::{TransactionForm, insert_choices, schema};
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::{account::Account, envelope::Envelope, transaction::Transaction},
+    utilities::responses,
+};
+use anyhow::anyhow;
+use axum::{
+    Extension, Form,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use handlebars::to_json;
+use rust_decimal::{Decimal, prelude::FromPrimitive};
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    headers: HeaderMap,
+    Extension(context): Extension<HandlebarsContext>,
+    Form(form): Form<TransactionForm>,
+) -> AppResponse {
+    let mut client = shared_state.pool.get_client().await?;
+    let json = serde_json::to_value(&form)?;
+    let valid = jsonschema::validate(&schema(), &json);
+    let response_format = responses::get_response_format(&headers)?;
+
+    match valid {
+        Ok(_) => {}
+        Err(validation_errors) => {
+            let mut context = context.clone();
+
+            context.insert("errors".to_string(), to_json(validation_errors.to_string()));
+            context.insert("account_id".to_string(), to_json(&form.account_id));
+            context.insert("envelope_id".to_string(), to_json(&form.envelope_id));
+            context.insert("amount".to_string(), to_json(form.amount));
+            context.insert("description".to_string(), to_json(&form.description));
+            context.insert("occurred_on".to_string(), to_json(form.occurred_on));
+
+            match response_format {
+                responses::ResponseFormat::Html => {
+                    insert_choices(&mut context, &client, user.id).await?;
+                    context.insert("partial".to_string(), to_json("transactions/new"));
+                    return Ok(responses::generate_response(
+                        &responses::ResponseFormat::Html,
+                        shared_state.handlebars.render("layout", &context)?,
+                        StatusCode::BAD_REQUEST,
+                    ));
+                }
+                responses::ResponseFormat::Turbo => {
+                    return Ok(responses::generate_response(
+                        &response_format,
+                        shared_state
+                            .handlebars
+                            .render("transactions/_form.turbo", &context)?,
+                        StatusCode::BAD_REQUEST,
+                    ));
+                }
+                responses::ResponseFormat::Json => {
+                    return Ok(responses::api_only());
+                }
+            }
+        }
+    }
+
+    let account_id = form.account_id()?;
+    let envelope_id = form.envelope_id()?;
+    if account_id.is_none() && envelope_id.is_none() {
+        return Err(AppError::InvalidRecord(anyhow!(
+            "a transaction needs an account or an envelope"
+        )));
+    }
+
+    let client = client.transaction().await?;
+
+    if let Some(account_id) = account_id {
+        Account::get_one(&client, account_id, user.id).await?;
+    }
+    if let Some(envelope_id) = envelope_id {
+        Envelope::get_one(&client, envelope_id, user.id).await?;
+    }
+
+    Transaction {
+        id: None,
+        user_id: user.id,
+        account_id,
+        envelope_id,
+        amount: Decimal::from_f64(form.amount).ok_or_else(|| anyhow!("could not parse decimal"))?,
+        description: form.description.to_owned(),
+        occurred_on: form.occurred_on,
+        adjustment: false,
+    }
+    .create(&client)
+    .await?;
+
+    client.commit().await?;
+
+    match response_format {
+        responses::ResponseFormat::Html | responses::ResponseFormat::Turbo => {
+            Ok(Redirect::to("/transactions").into_response())
+        }
+        responses::ResponseFormat::Json => Ok(responses::api_only()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::state_for_tests;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_create_transaction_success() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(500, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let envelope = Envelope {
+            id: None,
+            user_id,
+            name: "groceries".to_string(),
+            amount: Decimal::new(100, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/transactions", post(action))
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let form_data = format!(
+            "account_id={}&envelope_id={}&amount=-25.50&description=market&occurred_on=2024-03-01",
+            account.id.unwrap(),
+            envelope.id.unwrap()
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri("/transactions")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(Body::from(form_data))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get("location").unwrap(), "/transactions");
+
+        let transactions = Transaction::get_all_for_account(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].amount, Decimal::new(-2550, 2));
+
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        let envelope = Envelope::get_one(&client, envelope.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(account.amount, Decimal::new(47450, 2));
+        assert_eq!(envelope.amount, Decimal::new(7450, 2));
+    }
+
+    #[tokio::test]
+    async fn test_create_transaction_validation_error() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(500, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/transactions", post(action))
+            .with_state(shared_state)
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let form_data = format!(
+            "account_id={}&envelope_id=&amount=0&description=market&occurred_on=2024-03-01",
+            account.id.unwrap()
+        );
+        let request = Request::builder()
+            .method("POST")
+            .uri("/transactions")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .header("Accept", "text/vnd.turbo-stream.html")
+            .body(Body::from(form_data))
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "text/vnd.turbo-stream.html"
+        );
+    }
+}
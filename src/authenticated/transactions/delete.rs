@@ -0,0 +1,158 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::transaction::Transaction,
+    utilities::responses::{ResponseFormat, api_only, generate_response, get_response_format},
+};
+use axum::{
+    Extension,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use handlebars::to_json;
+use uuid::Uuid;
+
+pub async fn modal(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let transaction = Transaction::get_one(&client, id, user.id).await?;
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html => {
+            let mut context = context.clone();
+            context.insert(
+                "prompt".to_string(),
+                to_json("Are you sure you want to delete this transaction?"),
+            );
+            context.insert(
+                "action".to_string(),
+                to_json(format!("/transactions/{}", id)),
+            );
+            context.insert("entity".to_string(), to_json(transaction.description));
+            context.insert("partial".to_string(), to_json("delete_confirmation"));
+            Ok(generate_response(
+                &response_format,
+                shared_state
+                    .handlebars
+                    .render("delete_confirmation", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Turbo => Ok(StatusCode::NOT_ACCEPTABLE.into_response()),
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Extension(user): Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let mut client = shared_state.pool.get_client().await?;
+    let client = client.transaction().await?;
+    let transaction = Transaction::get_one(&client, id, user.id).await?;
+
+    transaction.delete(&client).await?;
+    client.commit().await?;
+
+    let response_format = get_response_format(&headers)?;
+    match response_format {
+        ResponseFormat::Html => Ok(StatusCode::NOT_ACCEPTABLE.into_response()),
+        ResponseFormat::Json => Ok(api_only()),
+        ResponseFormat::Turbo => {
+            let mut context = context.clone();
+            context.insert("transaction".to_string(), to_json(&transaction));
+
+            Ok(generate_response(
+                &response_format,
+                shared_state
+                    .handlebars
+                    .render("transactions/delete", &context)?,
+                StatusCode::OK,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::account::Account;
+    use crate::test_utils::state_for_tests;
+    use axum::Router;
+    use axum::body::Body;
+    use axum::http::Request;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_delete_action() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+        let user_id = user_extension.0.id;
+
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(100, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let transaction = Transaction {
+            id: None,
+            user_id,
+            account_id: account.id,
+            envelope_id: None,
+            amount: Decimal::new(40, 0),
+            description: "paycheck".to_string(),
+            occurred_on: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            adjustment: false,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = Router::new()
+            .route("/transactions/{id}", axum::routing::delete(action))
+            .layer(user_extension)
+            .layer(context_extension)
+            .with_state(shared_state.clone());
+
+        let request = Request::builder()
+            .uri(format!("/transactions/{}", transaction.id.unwrap()))
+            .method("DELETE")
+            .header("Accept", "turbo")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let deleted = Transaction::get_one(&client, transaction.id.unwrap(), user_id).await;
+        assert!(deleted.is_err());
+
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(account.amount, Decimal::new(100, 0));
+    }
+}
@@ -0,0 +1,52 @@
+use super::insert_choices;
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::transaction::Transaction,
+    utilities::responses::{ResponseFormat, api_only, generate_response, get_response_format},
+};
+use axum::{
+    Extension,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use handlebars::to_json;
+use uuid::Uuid;
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    user: Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let transaction = Transaction::get_one(&client, id, user.id).await?;
+    let mut context = context.clone();
+    let response_format = get_response_format(&headers)?;
+    context.insert("id".to_string(), to_json(transaction.id));
+    context.insert("account_id".to_string(), to_json(transaction.account_id));
+    context.insert("envelope_id".to_string(), to_json(transaction.envelope_id));
+    context.insert("amount".to_string(), to_json(transaction.amount));
+    context.insert("description".to_string(), to_json(&transaction.description));
+    context.insert(
+        "occurred_on".to_string(),
+        to_json(transaction.occurred_on.format("%Y-%m-%d").to_string()),
+    );
+    match response_format {
+        ResponseFormat::Html => {
+            insert_choices(&mut context, &client, user.id).await?;
+            context.insert("partial".to_string(), to_json("transactions/edit"));
+
+            Ok(generate_response(
+                &response_format,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Turbo => Ok(StatusCode::BAD_REQUEST.into_response()),
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
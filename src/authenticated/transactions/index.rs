@@ -0,0 +1,66 @@
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::{account::Account, envelope::Envelope, transaction::Transaction},
+    utilities::responses::{ResponseFormat, api_only, generate_response, get_response_format},
+};
+use axum::{
+    Extension,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use handlebars::to_json;
+use serde_json::json;
+use std::collections::HashMap;
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    user: Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let mut context = context.clone();
+    let transactions = Transaction::get_all(&client, user.id).await?;
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html | ResponseFormat::Turbo => {
+            let accounts: HashMap<_, _> = Account::get_all(&client, user.id)
+                .await?
+                .into_iter()
+                .map(|account| (account.id, account.name))
+                .collect();
+            let envelopes: HashMap<_, _> = Envelope::get_all(&client, user.id)
+                .await?
+                .into_iter()
+                .map(|envelope| (envelope.id, envelope.name))
+                .collect();
+
+            let rows: Vec<_> = transactions
+                .iter()
+                .map(|transaction| {
+                    json!({
+                        "id": transaction.id,
+                        "occurred_on": transaction.occurred_on,
+                        "description": transaction.description,
+                        "amount": transaction.amount,
+                        "account": transaction.account_id.and_then(|id| accounts.get(&Some(id))),
+                        "envelope": transaction.envelope_id.and_then(|id| envelopes.get(&Some(id))),
+                    })
+                })
+                .collect();
+
+            context.insert("transactions".to_string(), to_json(rows));
+            context.insert("partial".to_string(), to_json("transactions/index"));
+
+            Ok(generate_response(
+                &ResponseFormat::Html,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
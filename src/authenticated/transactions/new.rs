@@ -0,0 +1,67 @@
+use super::insert_choices;
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::AppResponse,
+    models::user::User,
+    utilities::responses::{ResponseFormat, api_only, generate_response, get_response_format},
+};
+use axum::{
+    Extension,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use chrono::Utc;
+use chrono_tz::Tz;
+use handlebars::to_json;
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    headers: HeaderMap,
+    user: Extension<UserExtension>,
+    Extension(context): Extension<HandlebarsContext>,
+) -> AppResponse {
+    let client = shared_state.pool.get_client().await?;
+    let timezone: Tz = User::get_by_id(&client, user.id)
+        .await?
+        .timezone()?
+        .parse()?;
+    let mut context = context.clone();
+    context.insert("account_id".to_string(), to_json(""));
+    context.insert("envelope_id".to_string(), to_json(""));
+    context.insert("amount".to_string(), to_json(""));
+    context.insert("description".to_string(), to_json(""));
+    context.insert(
+        "occurred_on".to_string(),
+        to_json(
+            Utc::now()
+                .with_timezone(&timezone)
+                .date_naive()
+                .format("%Y-%m-%d")
+                .to_string(),
+        ),
+    );
+    insert_choices(&mut context, &client, user.id).await?;
+
+    let response_format = get_response_format(&headers)?;
+
+    match response_format {
+        ResponseFormat::Html => {
+            context.insert("partial".to_string(), to_json("transactions/new"));
+
+            Ok(generate_response(
+                &response_format,
+                shared_state.handlebars.render("layout", &context)?,
+                StatusCode::OK,
+            ))
+        }
+        ResponseFormat::Turbo => Ok(generate_response(
+            &response_format,
+            shared_state
+                .handlebars
+                .render("transactions/new", &context)?,
+            StatusCode::OK,
+        )),
+        ResponseFormat::Json => Ok(api_only()),
+    }
+}
@@ -0,0 +1,188 @@
+use super::{TransactionForm, insert_choices, schema};
+use crate::{
+    HandlebarsContext, SharedState,
+    authenticated::UserExtension,
+    errors::{AppError, AppResponse},
+    models::{account::Account, envelope::Envelope, transaction::Transaction},
+    utilities::responses,
+};
+use anyhow::anyhow;
+use axum::{
+    Extension, Form,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Redirect},
+};
+use handlebars::to_json;
+use rust_decimal::{Decimal, prelude::FromPrimitive};
+use uuid::Uuid;
+
+pub async fn action(
+    shared_state: State<SharedState>,
+    user: Extension<UserExtension>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Extension(context): Extension<HandlebarsContext>,
+    Form(form): Form<TransactionForm>,
+) -> AppResponse {
+    let mut client = shared_state.pool.get_client().await?;
+    let json = serde_json::to_value(&form)?;
+    let valid = jsonschema::validate(&schema(), &json);
+    let response_format = responses::get_response_format(&headers)?;
+
+    match valid {
+        Ok(_) => {}
+        Err(validation_errors) => {
+            let mut context = context.clone();
+
+            context.insert("errors".to_string(), to_json(validation_errors.to_string()));
+            context.insert("id".to_string(), to_json(id));
+            context.insert("account_id".to_string(), to_json(&form.account_id));
+            context.insert("envelope_id".to_string(), to_json(&form.envelope_id));
+            context.insert("amount".to_string(), to_json(form.amount));
+            context.insert("description".to_string(), to_json(&form.description));
+            context.insert("occurred_on".to_string(), to_json(form.occurred_on));
+
+            match response_format {
+                responses::ResponseFormat::Html => {
+                    insert_choices(&mut context, &client, user.id).await?;
+                    context.insert("partial".to_string(), to_json("transactions/edit"));
+                    return Ok(responses::generate_response(
+                        &responses::ResponseFormat::Html,
+                        shared_state.handlebars.render("layout", &context)?,
+                        StatusCode::BAD_REQUEST,
+                    ));
+                }
+                responses::ResponseFormat::Turbo => {
+                    return Ok(responses::generate_response(
+                        &response_format,
+                        shared_state
+                            .handlebars
+                            .render("transactions/_form.turbo", &context)?,
+                        StatusCode::BAD_REQUEST,
+                    ));
+                }
+                responses::ResponseFormat::Json => {
+                    return Ok(responses::api_only());
+                }
+            }
+        }
+    }
+
+    let account_id = form.account_id()?;
+    let envelope_id = form.envelope_id()?;
+    if account_id.is_none() && envelope_id.is_none() {
+        return Err(AppError::InvalidRecord(anyhow!(
+            "a transaction needs an account or an envelope"
+        )));
+    }
+
+    let client = client.transaction().await?;
+
+    if let Some(account_id) = account_id {
+        Account::get_one(&client, account_id, user.id).await?;
+    }
+    if let Some(envelope_id) = envelope_id {
+        Envelope::get_one(&client, envelope_id, user.id).await?;
+    }
+
+    let mut transaction = Transaction::get_one(&client, id, user.id).await?;
+    transaction.account_id = account_id;
+    transaction.envelope_id = envelope_id;
+    transaction.amount =
+        Decimal::from_f64(form.amount).ok_or_else(|| anyhow!("could not parse decimal"))?;
+    transaction.description = form.description.clone();
+    transaction.occurred_on = form.occurred_on;
+    transaction.update(&client).await?;
+
+    client.commit().await?;
+
+    match response_format {
+        responses::ResponseFormat::Html | responses::ResponseFormat::Turbo => {
+            Ok(Redirect::to("/transactions").into_response())
+        }
+        responses::ResponseFormat::Json => Ok(responses::api_only()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::{account::Account, transaction::Transaction},
+        test_utils::state_for_tests,
+    };
+    use axum::http::{Method, Request, StatusCode};
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_update_transaction() {
+        let (shared_state, user_extension, context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(500, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let transaction = Transaction {
+            id: None,
+            user_id,
+            account_id: account.id,
+            envelope_id: None,
+            amount: Decimal::new(-100, 0),
+            description: "rent".to_string(),
+            occurred_on: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            adjustment: false,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(format!("/transactions/{}", transaction.id.unwrap()))
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(format!(
+                "account_id={}&amount=-150&description=rent%20and%20fees&occurred_on=2024-03-02",
+                account.id.unwrap()
+            ))
+            .unwrap();
+
+        let app = axum::Router::new()
+            .route(
+                "/transactions/{id}",
+                axum::routing::post(crate::authenticated::transactions::update::action),
+            )
+            .with_state(shared_state.clone())
+            .layer(user_extension)
+            .layer(context_extension);
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get("location").unwrap(), "/transactions");
+
+        let transaction = Transaction::get_one(&client, transaction.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(transaction.description, "rent and fees");
+        assert_eq!(transaction.amount, Decimal::new(-150, 0));
+
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(account.amount, Decimal::new(350, 0));
+    }
+}
@@ -6,6 +6,7 @@ use axum::{
 mod callback;
 mod client;
 mod login;
+mod providers;
 mod token;
 
 pub fn authentication_router() -> Router<SharedState> {
@@ -1,4 +1,7 @@
-use super::client::get_claims_from_authorization_code;
+use super::{
+    client::get_claims_from_authorization_code,
+    providers::{Provider, providers},
+};
 use crate::{
     SharedState,
     errors::AppError,
@@ -24,14 +27,14 @@ use tracing::error;
 use uuid::Uuid;
 
 #[derive(Debug, Deserialize)]
-pub struct GoogleCallback {
+pub struct Callback {
     code: String,
 }
 
 #[tracing::instrument]
 pub async fn callback(
     shared_state: State<SharedState>,
-    query: Query<GoogleCallback>,
+    query: Query<Callback>,
     jar: SignedCookieJar,
 ) -> Result<(SignedCookieJar, Response), AppError> {
     let nonce_cookie = jar
@@ -45,13 +48,24 @@ pub async fn callback(
 
     // let jar = jar.remove(Cookie::from("redirect_to"));
     let nonce = nonce_cookie.value().to_string();
+    let provider_cookie = jar.get("provider");
+    let provider = Provider::find(
+        providers()?,
+        provider_cookie.as_ref().map(|cookie| cookie.value()),
+    )?;
 
-    let claims = get_claims_from_authorization_code(query.code.clone(), nonce).await?;
+    let claims = get_claims_from_authorization_code(&provider, query.code.clone(), nonce).await?;
     let subject = claims.subject().to_string();
     let email = claims.email().ok_or(anyhow!("could not get email"))?;
     let email = email.to_string();
 
-    let id = create_session(&shared_state.pool.get_client().await?, &subject, &email).await?;
+    let id = create_session(
+        &shared_state.pool.get_client().await?,
+        &provider.issuer,
+        &subject,
+        &email,
+    )
+    .await?;
     let cookie = Cookie::build(("session_id", id.to_string()))
         .expires(None)
         .http_only(true)
@@ -65,11 +79,18 @@ pub async fn callback(
 
 async fn create_session(
     client: &impl GenericClient,
+    issuer: &str,
     subject: &str,
     email: &str,
 ) -> Result<Uuid, AppError> {
     let csrf = Alphanumeric.sample_string(&mut rng(), 32);
-    let user = upsert_subject(client, subject.to_owned(), email.to_owned()).await?;
+    let user = upsert_subject(
+        client,
+        issuer.to_owned(),
+        subject.to_owned(),
+        email.to_owned(),
+    )
+    .await?;
     let expiration = Utc::now()
         .checked_add_days(Days::new(1))
         .ok_or(anyhow!("could not add dates").context("create_session"))?;
@@ -88,15 +109,16 @@ async fn create_session(
 
 async fn upsert_subject(
     client: &impl GenericClient,
+    issuer: String,
     subject: String,
     email: String,
 ) -> Result<User, AppError> {
-    match User::get_by_subject(client, subject.clone()).await {
+    match User::get_by_subject(client, issuer.clone(), subject.clone()).await {
         Ok(user) => Ok(user),
         Err(e) => {
             error!("🚧 {:#?}", e);
 
-            Ok(User::create(client, email, subject).await?)
+            Ok(User::create(client, email, issuer, subject).await?)
         }
     }
 }
@@ -107,14 +129,16 @@ mod tests {
     use crate::{models::user::User, test_utils::state_for_tests};
     use uuid::Uuid;
 
+    const ISSUER: &str = "https://accounts.google.com";
+
     #[tokio::test]
     async fn test_create_session_for_new_user() {
         let uuid = Uuid::new_v4().to_string();
         let (shared_state, _, _) = state_for_tests().await.unwrap();
         let client = shared_state.pool.get_client().await.unwrap();
-        let session = create_session(&client, &uuid, &uuid).await;
+        let session = create_session(&client, ISSUER, &uuid, &uuid).await;
         assert!(session.is_ok());
-        let user = User::get_by_subject(&client, uuid.clone()).await;
+        let user = User::get_by_subject(&client, ISSUER.to_string(), uuid.clone()).await;
         assert!(user.is_ok());
     }
 
@@ -123,11 +147,31 @@ mod tests {
         let uuid = &Uuid::new_v4().to_string();
         let (shared_state, _, _) = state_for_tests().await.unwrap();
         let client = shared_state.pool.get_client().await.unwrap();
-        let user = User::create(&client, uuid.clone(), uuid.clone()).await;
+        let user = User::create(&client, uuid.clone(), ISSUER.to_string(), uuid.clone()).await;
         assert!(user.is_ok());
-        let session = create_session(&client, uuid, uuid).await;
+        let session = create_session(&client, ISSUER, uuid, uuid).await;
         assert!(session.is_ok());
-        let user = User::get_by_subject(&client, uuid.to_string()).await;
+        let user = User::get_by_subject(&client, ISSUER.to_string(), uuid.to_string()).await;
         assert!(user.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_same_subject_at_another_issuer_is_another_user() {
+        let uuid = &Uuid::new_v4().to_string();
+        let (shared_state, _, _) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+        let keycloak = "https://sso.example.com/realms/budget";
+
+        create_session(&client, ISSUER, uuid, uuid).await.unwrap();
+        create_session(&client, keycloak, uuid, uuid).await.unwrap();
+
+        let google_user = User::get_by_subject(&client, ISSUER.to_string(), uuid.clone())
+            .await
+            .unwrap();
+        let keycloak_user = User::get_by_subject(&client, keycloak.to_string(), uuid.clone())
+            .await
+            .unwrap();
+        assert_ne!(google_user.id, keycloak_user.id);
+        assert_eq!(keycloak_user.issuer, keycloak);
+    }
 }
@@ -1,3 +1,4 @@
+use super::providers::{Provider, callback_url};
 use anyhow::{Result, anyhow};
 use openidconnect::{
     AuthorizationCode, ClientId, ClientSecret, EmptyAdditionalClaims, EndpointMaybeSet,
@@ -9,7 +10,6 @@ use openidconnect::{
         CoreRevocableToken, CoreTokenIntrospectionResponse, CoreTokenResponse,
     },
 };
-use std::env;
 
 type OidcClient = openidconnect::Client<
     EmptyAdditionalClaims,
@@ -31,19 +31,18 @@ type OidcClient = openidconnect::Client<
     EndpointMaybeSet,
 >;
 
-pub async fn clients_from_metadata() -> Result<(OidcClient, openidconnect::reqwest::Client)> {
-    let client_id = env::var("GOOGLE_CLIENT_ID")?;
-    let client_secret = env::var("GOOGLE_CLIENT_SECRET")?;
-    let callback_url = env::var("GOOGLE_CALLBACK_URL")?;
-    let issuer_url = IssuerUrl::new("https://accounts.google.com".to_string())?;
+pub async fn clients_from_metadata(
+    provider: &Provider,
+) -> Result<(OidcClient, openidconnect::reqwest::Client)> {
+    let issuer_url = IssuerUrl::new(provider.issuer.clone())?;
     let async_http_client = openidconnect::reqwest::Client::builder().build()?;
     let provider_metadata =
         CoreProviderMetadata::discover_async(issuer_url, &async_http_client).await?;
-    let redirect_uri = RedirectUrl::new(callback_url)?;
+    let redirect_uri = RedirectUrl::new(callback_url()?)?;
     let client = CoreClient::from_provider_metadata(
         provider_metadata,
-        ClientId::new(client_id),
-        Some(ClientSecret::new(client_secret)),
+        ClientId::new(provider.client_id.clone()),
+        provider.client_secret.clone().map(ClientSecret::new),
     );
     let client = client.set_redirect_uri(redirect_uri);
 
@@ -51,10 +50,11 @@ pub async fn clients_from_metadata() -> Result<(OidcClient, openidconnect::reqwe
 }
 
 pub async fn get_claims_from_authorization_code(
+    provider: &Provider,
     code: String,
     nonce: String,
 ) -> Result<IdTokenClaims<EmptyAdditionalClaims, CoreGenderClaim>> {
-    let (oidc_client, http_client) = clients_from_metadata().await?;
+    let (oidc_client, http_client) = clients_from_metadata(provider).await?;
     let token_response = oidc_client
         .exchange_code(AuthorizationCode::new(code))?
         .request_async(&http_client)
@@ -1,4 +1,7 @@
-use super::client::clients_from_metadata;
+use super::{
+    client::clients_from_metadata,
+    providers::{Provider, providers},
+};
 use crate::{
     HandlebarsContext, SharedState,
     errors::{AppError, AppResponse},
@@ -9,17 +12,25 @@ use axum::{
     response::{Html, IntoResponse, Redirect, Response},
 };
 use axum_extra::extract::{
-    SignedCookieJar,
+    Query, SignedCookieJar,
     cookie::{Cookie, SameSite},
 };
 use handlebars::to_json;
 use openidconnect::{CsrfToken, Nonce, Scope, core::CoreAuthenticationFlow};
+use serde::Deserialize;
+use serde_json::json;
 
 pub async fn login(
     state: State<SharedState>,
     Extension(context): Extension<HandlebarsContext>,
 ) -> AppResponse {
     let mut context = context.clone();
+    let providers = providers()?
+        .into_iter()
+        .map(|provider| json!({ "name": provider.name, "label": provider.label }))
+        .collect::<Vec<_>>();
+
+    context.insert("providers".to_string(), to_json(providers));
     context.insert("partial".to_string(), to_json("authentication/login"));
     let handlebars = &state.handlebars;
     let content = handlebars.render("layout", &context)?;
@@ -27,18 +38,29 @@ pub async fn login(
     Ok(Html::from(content).into_response())
 }
 
-pub async fn redirect(jar: SignedCookieJar) -> Result<(SignedCookieJar, Response), AppError> {
-    let (oidc_client, _http_client) = clients_from_metadata().await?;
+#[derive(Debug, Deserialize)]
+pub struct RedirectQuery {
+    provider: Option<String>,
+}
 
-    let (auth_url, _, nonce) = oidc_client
-        .authorize_url(
-            CoreAuthenticationFlow::AuthorizationCode,
-            CsrfToken::new_random,
-            Nonce::new_random,
-        )
-        .add_scope(Scope::new("email".to_string()))
-        .add_scope(Scope::new("openid".to_string()))
-        .url();
+/// Sends the user to the chosen provider, or the first one, remembering which it was for the
+/// callback.
+pub async fn redirect(
+    query: Query<RedirectQuery>,
+    jar: SignedCookieJar,
+) -> Result<(SignedCookieJar, Response), AppError> {
+    let provider = Provider::find(providers()?, query.provider.as_deref())?;
+    let (oidc_client, _http_client) = clients_from_metadata(&provider).await?;
+
+    let mut request = oidc_client.authorize_url(
+        CoreAuthenticationFlow::AuthorizationCode,
+        CsrfToken::new_random,
+        Nonce::new_random,
+    );
+    for scope in &provider.scopes {
+        request = request.add_scope(Scope::new(scope.clone()));
+    }
+    let (auth_url, _, nonce) = request.url();
 
     let cookie = Cookie::build(("nonce", nonce.secret().clone()))
         .expires(None)
@@ -47,9 +69,16 @@ pub async fn redirect(jar: SignedCookieJar) -> Result<(SignedCookieJar, Response
         .same_site(SameSite::Lax)
         .secure(true)
         .build();
+    let provider_cookie = Cookie::build(("provider", provider.name))
+        .expires(None)
+        .http_only(true)
+        .path("/authentication")
+        .same_site(SameSite::Lax)
+        .secure(true)
+        .build();
 
     Ok((
-        jar.add(cookie),
+        jar.add(cookie).add(provider_cookie),
         Redirect::to(auth_url.as_str()).into_response(),
     ))
 }
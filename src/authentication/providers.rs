@@ -0,0 +1,188 @@
+use crate::errors::AppError;
+use anyhow::{Result, anyhow, bail};
+use std::env;
+
+const GOOGLE_ISSUER: &str = "https://accounts.google.com";
+
+/// An OpenID Connect issuer that users can sign in with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Provider {
+    /// Identifies the provider in URLs and cookies.
+    pub name: String,
+    /// Shown on the provider's login button.
+    pub label: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub scopes: Vec<String>,
+    /// Client IDs, such as the iOS app's, whose ID tokens `/authentication/token` accepts.
+    pub token_audiences: Vec<String>,
+}
+
+fn default_scopes() -> Vec<String> {
+    vec!["email".to_string(), "openid".to_string()]
+}
+
+fn words(value: String) -> Vec<String> {
+    value.split_whitespace().map(String::from).collect()
+}
+
+/// The providers named in `OIDC_PROVIDERS`, e.g. `google,keycloak`. Each one is configured by
+/// `OIDC_<NAME>_ISSUER` and `OIDC_<NAME>_CLIENT_ID`, and optionally `_CLIENT_SECRET`, `_LABEL`,
+/// `_SCOPES` and `_TOKEN_AUDIENCES`. Without `OIDC_PROVIDERS`, the `GOOGLE_*` variables configure
+/// Google alone.
+pub fn providers() -> Result<Vec<Provider>> {
+    providers_from(|name| env::var(name).ok())
+}
+
+fn providers_from(var: impl Fn(&str) -> Option<String>) -> Result<Vec<Provider>> {
+    let Some(names) = var("OIDC_PROVIDERS") else {
+        return Ok(vec![Provider {
+            name: "google".to_string(),
+            label: "Google".to_string(),
+            issuer: GOOGLE_ISSUER.to_string(),
+            client_id: var("GOOGLE_CLIENT_ID")
+                .ok_or_else(|| anyhow!("neither OIDC_PROVIDERS nor GOOGLE_CLIENT_ID is set"))?,
+            client_secret: var("GOOGLE_CLIENT_SECRET"),
+            scopes: default_scopes(),
+            token_audiences: var("IOS_CLIENT_ID").into_iter().collect(),
+        }]);
+    };
+
+    let providers = names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let prefix = format!("OIDC_{}", name.to_uppercase().replace('-', "_"));
+            let optional = |key: &str| var(&format!("{prefix}_{key}"));
+            let required =
+                |key: &str| optional(key).ok_or_else(|| anyhow!("{prefix}_{key} is not set"));
+
+            Ok(Provider {
+                name: name.to_string(),
+                label: optional("LABEL").unwrap_or_else(|| name.to_string()),
+                issuer: required("ISSUER")?,
+                client_id: required("CLIENT_ID")?,
+                client_secret: optional("CLIENT_SECRET"),
+                scopes: optional("SCOPES").map(words).unwrap_or_else(default_scopes),
+                token_audiences: optional("TOKEN_AUDIENCES").map(words).unwrap_or_default(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if providers.is_empty() {
+        bail!("OIDC_PROVIDERS names no providers");
+    }
+
+    Ok(providers)
+}
+
+/// Where every provider sends users back to; register it with each of them.
+pub fn callback_url() -> Result<String> {
+    env::var("OIDC_CALLBACK_URL")
+        .or_else(|_| env::var("GOOGLE_CALLBACK_URL"))
+        .map_err(|_| anyhow!("OIDC_CALLBACK_URL is not set"))
+}
+
+impl Provider {
+    /// Issuers that ID tokens from this provider may name; Google also leaves out the scheme.
+    pub fn token_issuers(&self) -> Vec<&str> {
+        let mut issuers = vec![self.issuer.as_str()];
+        if self.issuer == GOOGLE_ISSUER {
+            issuers.push("accounts.google.com");
+        }
+
+        issuers
+    }
+
+    /// The named provider, or the first one when there is no name.
+    pub fn find(providers: Vec<Self>, name: Option<&str>) -> Result<Self, AppError> {
+        let mut providers = providers.into_iter();
+
+        match name {
+            None => providers.next(),
+            Some(name) => providers.find(|provider| provider.name == name),
+        }
+        .ok_or_else(|| AppError::InvalidRecord(anyhow!("unknown provider {name:?}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Provider, providers_from};
+    use std::collections::HashMap;
+
+    fn providers(vars: &[(&str, &str)]) -> anyhow::Result<Vec<Provider>> {
+        let vars: HashMap<_, _> = vars.iter().copied().collect();
+
+        providers_from(|name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn test_google_from_legacy_variables() {
+        assert!(providers(&[]).is_err());
+
+        let providers = providers(&[
+            ("GOOGLE_CLIENT_ID", "web"),
+            ("GOOGLE_CLIENT_SECRET", "secret"),
+            ("IOS_CLIENT_ID", "ios"),
+        ])
+        .unwrap();
+
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].issuer, "https://accounts.google.com");
+        assert_eq!(providers[0].client_secret.as_deref(), Some("secret"));
+        assert_eq!(providers[0].token_audiences, vec!["ios"]);
+        assert_eq!(
+            providers[0].token_issuers(),
+            vec!["https://accounts.google.com", "accounts.google.com"]
+        );
+    }
+
+    #[test]
+    fn test_several_providers() {
+        let providers = providers(&[
+            ("OIDC_PROVIDERS", "google, home-lab"),
+            ("OIDC_GOOGLE_ISSUER", "https://accounts.google.com"),
+            ("OIDC_GOOGLE_CLIENT_ID", "web"),
+            ("OIDC_GOOGLE_LABEL", "Google"),
+            ("OIDC_HOME_LAB_ISSUER", "https://sso.home.lab/realms/budget"),
+            ("OIDC_HOME_LAB_CLIENT_ID", "budget"),
+            ("OIDC_HOME_LAB_SCOPES", "openid email profile"),
+        ])
+        .unwrap();
+
+        assert_eq!(providers.len(), 2);
+        assert_eq!(providers[0].label, "Google");
+        assert_eq!(providers[0].scopes, vec!["email", "openid"]);
+        assert_eq!(providers[1].name, "home-lab");
+        assert_eq!(providers[1].label, "home-lab");
+        assert_eq!(providers[1].client_secret, None);
+        assert_eq!(providers[1].scopes, vec!["openid", "email", "profile"]);
+
+        let home_lab = Provider::find(providers.clone(), Some("home-lab")).unwrap();
+        assert_eq!(home_lab.issuer, "https://sso.home.lab/realms/budget");
+        assert_eq!(
+            home_lab.token_issuers(),
+            vec!["https://sso.home.lab/realms/budget"]
+        );
+        assert_eq!(
+            Provider::find(providers.clone(), None).unwrap().name,
+            "google"
+        );
+        assert!(Provider::find(providers, Some("github")).is_err());
+    }
+
+    #[test]
+    fn test_missing_provider_settings() {
+        let err = providers(&[
+            ("OIDC_PROVIDERS", "keycloak"),
+            ("OIDC_KEYCLOAK_CLIENT_ID", "budget"),
+        ])
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "OIDC_KEYCLOAK_ISSUER is not set");
+        assert!(providers(&[("OIDC_PROVIDERS", " , ")]).is_err());
+    }
+}
@@ -6,7 +6,10 @@ use axum::{
 };
 use axum_extra::extract::{SignedCookieJar, cookie::Cookie};
 use chrono::{Days, Utc};
-use jsonwebtoken::{Algorithm, DecodingKey, Validation, decode, jwk::Jwk};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation, decode, decode_header,
+    jwk::{Jwk, JwkSet},
+};
 use openidconnect::{IssuerUrl, core::CoreProviderMetadata};
 use rand::{
     distr::{Alphanumeric, SampleString},
@@ -15,9 +18,10 @@ use rand::{
 use rust_database_common::GenericClient;
 use serde::Deserialize;
 use serde_json::json;
-use std::env;
+use std::str::FromStr;
 use uuid::Uuid;
 
+use super::providers::{Provider, providers};
 use crate::{
     SharedState,
     errors::{AppError, AppResponse},
@@ -27,6 +31,8 @@ use crate::{
 #[derive(Debug, Deserialize)]
 pub struct Payload {
     id_token: String,
+    /// Defaults to the first provider.
+    provider: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -40,25 +46,31 @@ pub async fn token(
     jar: SignedCookieJar,
     Json(token): Json<Payload>,
 ) -> AppResponse {
-    let issuer_url = IssuerUrl::new("https://accounts.google.com".to_string())?;
+    let provider = Provider::find(providers()?, token.provider.as_deref())?;
+    let issuer_url = IssuerUrl::new(provider.issuer.clone())?;
     let async_http_client = openidconnect::reqwest::Client::builder().build()?;
     let provider_metadata =
         CoreProviderMetadata::discover_async(issuer_url, &async_http_client).await?;
 
-    let keys = provider_metadata.jwks().keys();
-    let key = json!(keys[0].clone());
-    let jwk: Jwk = serde_json::from_value(key)?;
+    let header = decode_header(&token.id_token)?;
+    let keys: JwkSet = serde_json::from_value(json!(provider_metadata.jwks()))?;
+    let jwk = signing_key(&keys, header.kid.as_deref())?;
 
-    let mut validation = Validation::new(Algorithm::RS256);
-    let aud = env::var("IOS_CLIENT_ID")?;
+    let mut validation = Validation::new(key_algorithm(jwk)?);
+    let audiences = if provider.token_audiences.is_empty() {
+        vec![provider.client_id.clone()]
+    } else {
+        provider.token_audiences.clone()
+    };
 
-    validation.set_audience(&[&aud]);
-    validation.set_issuer(&["accounts.google.com", "https://accounts.google.com"]);
+    validation.set_audience(&audiences);
+    validation.set_issuer(&provider.token_issuers());
 
-    let status = decode::<Claims>(&token.id_token, &DecodingKey::from_jwk(&jwk)?, &validation)?;
+    let status = decode::<Claims>(&token.id_token, &DecodingKey::from_jwk(jwk)?, &validation)?;
 
     let id = create_session(
         &shared_state.pool.get_client().await?,
+        &provider.issuer,
         &status.claims.sub,
         &status.claims.email,
     )
@@ -75,14 +87,41 @@ pub async fn token(
     Ok((jar.add(cookie), Html::from("OK")).into_response())
 }
 
+/// The key named by the token's `kid`. A token without one is only accepted when the provider
+/// publishes a single key.
+fn signing_key<'a>(keys: &'a JwkSet, kid: Option<&str>) -> Result<&'a Jwk> {
+    match (kid, keys.keys.as_slice()) {
+        (Some(kid), _) => keys.find(kid),
+        (None, [key]) => Some(key),
+        (None, _) => None,
+    }
+    .ok_or_else(|| anyhow!("no key matches the ID token"))
+}
+
+/// The algorithm the key is published for, rather than the one the token claims; keys that do not
+/// say are taken to be RS256.
+fn key_algorithm(jwk: &Jwk) -> Result<Algorithm> {
+    match &jwk.common.key_algorithm {
+        Some(algorithm) => Ok(Algorithm::from_str(&algorithm.to_string())?),
+        None => Ok(Algorithm::RS256),
+    }
+}
+
 async fn create_session(
     client: &impl GenericClient,
+    issuer: &str,
     subject: &str,
     email: &str,
 ) -> Result<Uuid, AppError> {
     let csrf = Alphanumeric.sample_string(&mut rng(), 32);
 
-    let user = upsert_subject(client, subject.to_owned(), email.to_owned()).await?;
+    let user = upsert_subject(
+        client,
+        issuer.to_owned(),
+        subject.to_owned(),
+        email.to_owned(),
+    )
+    .await?;
 
     let expiration = Utc::now()
         .checked_add_days(Days::new(1))
@@ -101,11 +140,42 @@ async fn create_session(
 
 async fn upsert_subject(
     client: &impl GenericClient,
+    issuer: String,
     subject: String,
     email: String,
 ) -> Result<User, AppError> {
-    match User::get_by_subject(client, subject.clone()).await {
+    match User::get_by_subject(client, issuer.clone(), subject.clone()).await {
         Ok(user) => Ok(user),
-        Err(_) => Ok(User::create(client, email, subject).await?),
+        Err(_) => Ok(User::create(client, email, issuer, subject).await?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{key_algorithm, signing_key};
+    use jsonwebtoken::{Algorithm, jwk::JwkSet};
+    use serde_json::json;
+
+    fn keys(keys: serde_json::Value) -> JwkSet {
+        serde_json::from_value(json!({ "keys": keys })).unwrap()
+    }
+
+    #[test]
+    fn test_signing_key() {
+        let rsa = json!({ "kty": "RSA", "kid": "rsa", "n": "AQAB", "e": "AQAB" });
+        let ec = json!({
+            "kty": "EC", "kid": "ec", "alg": "ES256", "crv": "P-256", "x": "AQAB", "y": "AQAB"
+        });
+        let both = keys(json!([rsa, ec]));
+        let only = keys(json!([rsa]));
+
+        let key = signing_key(&both, Some("ec")).unwrap();
+        assert_eq!(key_algorithm(key).unwrap(), Algorithm::ES256);
+        let key = signing_key(&both, Some("rsa")).unwrap();
+        assert_eq!(key_algorithm(key).unwrap(), Algorithm::RS256);
+
+        assert!(signing_key(&both, Some("other")).is_err());
+        assert!(signing_key(&both, None).is_err());
+        assert!(signing_key(&only, None).is_ok());
     }
 }
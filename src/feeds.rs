@@ -0,0 +1,113 @@
+use crate::{
+    SharedState,
+    errors::AppResponse,
+    models::{calendar::goals_calendar, goal::Goal, user::User},
+};
+use axum::{
+    Router,
+    extract::{Path, State},
+    http::{StatusCode, header},
+    response::IntoResponse,
+    routing::get,
+};
+use chrono::Utc;
+use chrono_tz::Tz;
+
+/// The user's goals as an iCalendar feed. Calendar apps cannot sign in, so the secret token in
+/// the URL stands in for a session.
+async fn calendar(shared_state: State<SharedState>, Path(file): Path<String>) -> AppResponse {
+    let calendar_token = file.strip_suffix(".ics").unwrap_or(&file);
+    let client = shared_state.pool.get_client().await?;
+    let Some(user) = User::get_by_calendar_token(&client, calendar_token).await? else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let timezone: Tz = user.timezone()?.parse()?;
+    let goals = Goal::get_all(&client, user.id).await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CACHE_CONTROL, "private, max-age=3600"),
+        ],
+        goals_calendar(&goals, timezone, Utc::now()),
+    )
+        .into_response())
+}
+
+pub fn feeds_router() -> Router<SharedState> {
+    Router::new().route("/calendar/{file}", get(calendar))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::feeds_router;
+    use crate::{
+        models::{
+            goal::{EnvelopePolicy, Goal, Recurrence},
+            user::User,
+        },
+        test_utils::state_for_tests,
+    };
+    use axum::{
+        body::{Body, to_bytes},
+        http::{Request, StatusCode},
+    };
+    use chrono::{Days, Utc};
+    use rust_decimal::Decimal;
+    use std::str::from_utf8;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_calendar_feed() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+        let user = User::get_by_id(&client, user_extension.0.id).await.unwrap();
+        let calendar_token = format!("token{}", user.id.simple());
+        user.set_calendar_token(&client, Some(&calendar_token))
+            .await
+            .unwrap();
+        Goal {
+            id: None,
+            user_id: user.id,
+            name: "Water bill".to_string(),
+            recurrence: Recurrence::Quarterly,
+            target_date: Utc::now() + Days::new(30),
+            target: Decimal::new(9000, 2),
+            accumulated_amount: Decimal::ZERO,
+            start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let app = feeds_router().with_state(shared_state);
+
+        let request = Request::builder()
+            .uri(format!("/calendar/{calendar_token}.ics"))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["content-type"],
+            "text/calendar; charset=utf-8"
+        );
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let calendar = from_utf8(&body).unwrap();
+        assert!(calendar.contains("SUMMARY:Water bill\r\n"));
+        assert!(calendar.contains("RRULE:FREQ=MONTHLY;INTERVAL=3"));
+
+        let request = Request::builder()
+            .uri("/calendar/revoked.ics")
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -1,2 +1,5 @@
 pub mod clear_sessions;
 pub mod convert_goals;
+pub mod deliver_webhooks;
+pub mod snapshot_dashboards;
+pub mod watch_per_diem;
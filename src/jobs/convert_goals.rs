@@ -1,87 +1,202 @@
 use crate::{
     errors::AppError,
-    models::{envelope::Envelope, goal::Goal, user::User},
+    models::{
+        envelope::Envelope,
+        goal::{EnvelopePolicy, Goal},
+        goal_contribution::{ContributionSource, GoalContribution},
+        income_source::IncomeSource,
+        user::{GoalFunding, User},
+        webhook::{WebhookEndpoint, WebhookEvent},
+    },
     utilities::dates::{TimeUtilities, Times},
 };
 use anyhow::{Result, anyhow};
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use chrono_tz::Tz;
 use rust_database_common::{DatabasePool, GenericClient};
 use rust_decimal::{Decimal, prelude::FromPrimitive};
+use serde::Serialize;
+use serde_json::json;
 use std::str::FromStr;
 use tracing::info;
+use uuid::Uuid;
+
+/// What a conversion run did, or would do when simulated.
+#[derive(Debug, Default, Serialize)]
+pub struct ConversionReport {
+    pub envelopes: Vec<EnvelopeFill>,
+    pub incremented_goals: Vec<Goal>,
+    pub changes: Vec<GoalChange>,
+}
+
+/// An envelope a converted goal was moved into; `amount` is what the envelope gained.
+#[derive(Debug, Serialize)]
+pub struct EnvelopeFill {
+    pub envelope: Envelope,
+    pub created: bool,
+    pub amount: Decimal,
+}
+
+/// A change to a goal's accumulated amount made during the run.
+#[derive(Debug, Serialize)]
+pub struct GoalChange {
+    pub goal_id: Option<Uuid>,
+    pub name: String,
+    pub source: ContributionSource,
+    pub amount: Decimal,
+    pub accumulated_amount: Decimal,
+}
+
+impl GoalChange {
+    fn between(previous: &Goal, current: &Goal, source: ContributionSource) -> Option<Self> {
+        if previous.accumulated_amount == current.accumulated_amount {
+            return None;
+        }
+
+        Some(Self {
+            goal_id: current.id,
+            name: current.name.clone(),
+            source,
+            amount: current.accumulated_amount - previous.accumulated_amount,
+            accumulated_amount: current.accumulated_amount,
+        })
+    }
+}
 
-pub async fn convert_goals(pool: &DatabasePool, time: &impl Times) -> Result<f64, AppError> {
+pub async fn convert_goals(
+    pool: &DatabasePool,
+    time: &impl Times,
+) -> Result<ConversionReport, AppError> {
     let mut manager = pool.get_client().await?;
     let transaction = manager.transaction().await?;
 
-    let results = private_convert_goals(&transaction, time).await?;
+    let report = private_convert_goals(&transaction, time, None).await?;
 
     transaction.commit().await?;
 
-    Ok(results)
+    Ok(report)
+}
+
+/// Runs the conversion for one user as of `time` and rolls it back, reporting what it would
+/// have done.
+pub async fn simulate_convert_goals(
+    pool: &DatabasePool,
+    user_id: Uuid,
+    time: &impl Times,
+) -> Result<ConversionReport, AppError> {
+    let mut manager = pool.get_client().await?;
+    let transaction = manager.transaction().await?;
+
+    let report = private_convert_goals(&transaction, time, Some(user_id)).await?;
+
+    transaction.rollback().await?;
+
+    Ok(report)
 }
 
 async fn private_convert_goals(
     client: &impl GenericClient,
     time: &impl Times,
-) -> Result<f64, AppError> {
+    user_id: Option<Uuid>,
+) -> Result<ConversionReport, AppError> {
     info!("converting goals to envelopes at {}", Utc::now());
 
-    let goals = Goal::get_expired(client, time.now()).await?;
-    for goal in goals {
-        let envelope = Envelope {
-            id: None,
-            name: goal.name.clone(),
-            amount: goal.target,
-            user_id: goal.user_id,
-        };
+    let mut report = ConversionReport::default();
 
-        envelope.create(client).await?;
-        let new_goal = goal.increment()?;
-        new_goal.update(client).await?;
+    let goals = Goal::get_expired(client, time.now(), user_id).await?;
+    for goal in goals {
+        let timezone = User::get_by_id(client, goal.user_id).await?.timezone()?;
+        let filled_on = time
+            .now()
+            .with_timezone(&Tz::from_str(&timezone)?)
+            .date_naive();
+        let fill = fill_envelope(client, &goal, filled_on).await?;
+        let mut new_goal = goal.increment()?;
+        new_goal.envelope_id = fill.envelope.id;
+        let new_goal = new_goal.update(client).await?;
+        GoalContribution::record(client, &goal, &new_goal, ContributionSource::Reset).await?;
+
+        if fill.created {
+            WebhookEndpoint::publish(
+                client,
+                goal.user_id,
+                WebhookEvent::EnvelopeCreated,
+                json!({ "envelope": fill.envelope }),
+            )
+            .await?;
+        }
+        WebhookEndpoint::publish(
+            client,
+            goal.user_id,
+            WebhookEvent::GoalConverted,
+            json!({ "goal": new_goal, "envelope": fill.envelope, "amount": fill.amount }),
+        )
+        .await?;
+
+        report.envelopes.push(fill);
+        report.changes.extend(GoalChange::between(
+            &goal,
+            &new_goal,
+            ContributionSource::Reset,
+        ));
+        report.incremented_goals.push(new_goal);
     }
 
-    let goals = Goal::get_all_unscoped(client).await?;
-    for goal in goals {
-        goal.accumulate(client, time).await?;
+    let goals = Goal::get_all_unscoped(client, user_id).await?;
+    for goal in &goals {
+        let accumulated = goal.accumulate(client, time).await?;
+        report.changes.extend(GoalChange::between(
+            goal,
+            &accumulated,
+            ContributionSource::Accumulation,
+        ));
     }
 
-    let goals = Goal::get_all_unscoped(client).await?;
-    for goal in goals {
-        let user = User::get_by_id(client, goal.user_id).await?;
+    let mut user_ids: Vec<Uuid> = goals.iter().map(|goal| goal.user_id).collect();
+    user_ids.sort();
+    user_ids.dedup();
+
+    for user_id in user_ids {
+        let user = User::get_by_id(client, user_id).await?;
         let timezone = user.timezone()?;
         let time_utilities = TimeUtilities {
             timezone: Tz::from_str(&timezone)?,
+            period: user.budget_period(),
         };
 
-        let length_of_month = time_utilities.length_of_month(time)?;
-        let length_of_month_in_seconds = Decimal::from_i64(length_of_month.num_seconds())
+        let length_of_period = time_utilities.length_of_period(time)?;
+        let length_of_period_in_seconds = Decimal::from_i64(length_of_period.num_seconds())
             .ok_or(anyhow!("could not convert remaining seconds to decimal"))?;
 
         info!(
-            "🚧 length_of_month_in_seconds -> {:#?}",
-            length_of_month_in_seconds
+            "🚧 length_of_period_in_seconds -> {:#?}",
+            length_of_period_in_seconds
         );
 
-        let monthly_income = user.monthly_income()?;
-        let spendable_per_second = monthly_income / length_of_month_in_seconds;
+        let income_per_period = IncomeSource::total_for_period(
+            client,
+            user.id,
+            time_utilities.period,
+            time_utilities.start_of_period(time)?.date_naive(),
+        )
+        .await?;
+        let spendable_per_second = income_per_period / length_of_period_in_seconds;
         info!("🚧 spendable_per_second -> {:#?}", spendable_per_second);
 
-        let remaining_length_of_month = time_utilities.remaining_length_of_month(time)?;
-        let remaining_length_of_month_in_seconds =
-            Decimal::from_i64(remaining_length_of_month.num_seconds())
+        let remaining_length_of_period = time_utilities.remaining_length_of_period(time)?;
+        let remaining_length_of_period_in_seconds =
+            Decimal::from_i64(remaining_length_of_period.num_seconds())
                 .ok_or(anyhow!("could not convert remaining seconds to decimal"))?;
 
         info!(
-            "🚧 remaining_length_of_month_in_seconds -> {:#?}",
-            remaining_length_of_month_in_seconds
+            "🚧 remaining_length_of_period_in_seconds -> {:#?}",
+            remaining_length_of_period_in_seconds
         );
         let remaining_spendable = user.total_balance(client).await?;
         info!("🚧 remaining_spendable -> {:#?}", remaining_spendable);
 
         let remaining_spendable_per_second =
-            remaining_spendable / remaining_length_of_month_in_seconds;
+            remaining_spendable / remaining_length_of_period_in_seconds;
 
         info!(
             "🚧 remaining_spendable_per_second -> {:#?}",
@@ -92,32 +207,136 @@ async fn private_convert_goals(
             remaining_spendable_per_second - spendable_per_second,
         );
         let acceleration_amount =
-            acceleration_amount_per_second * remaining_length_of_month_in_seconds;
+            acceleration_amount_per_second * remaining_length_of_period_in_seconds;
 
         info!("🚧 acceleration_amount -> {:#?}", acceleration_amount);
-        goal.accelerate(client, acceleration_amount).await?;
+
+        let goals = Goal::get_all(client, user.id).await?;
+        let allocations = allocate_surplus(&goals, acceleration_amount, user.goal_funding());
+        for (goal, amount) in goals.iter().zip(allocations) {
+            let accelerated = goal.accelerate(client, amount).await?;
+            report.changes.extend(GoalChange::between(
+                goal,
+                &accelerated,
+                ContributionSource::Acceleration,
+            ));
+        }
     }
 
-    Ok(1.0)
+    Ok(report)
+}
+
+/// Splits `surplus` between `goals`, which must be in priority order. Sequential funding fills
+/// each goal before moving to the next; weighted funding shares the surplus by funding weight and
+/// passes whatever a filled goal cannot take on to the others. No goal receives more than it still
+/// needs, so the allocations never add up to more than `surplus`.
+fn allocate_surplus(goals: &[Goal], surplus: Decimal, funding: GoalFunding) -> Vec<Decimal> {
+    let needs: Vec<Decimal> = goals
+        .iter()
+        .map(|goal| Decimal::max(Decimal::ZERO, goal.target - goal.accumulated_amount))
+        .collect();
+    let mut allocations = vec![Decimal::ZERO; goals.len()];
+    let mut remaining = Decimal::max(Decimal::ZERO, surplus);
+
+    match funding {
+        GoalFunding::Sequential => {
+            for (allocation, need) in allocations.iter_mut().zip(&needs) {
+                *allocation = Decimal::min(*need, remaining);
+                remaining -= *allocation;
+            }
+        }
+        GoalFunding::Weighted => {
+            // Every pass either hands out the rest of the surplus or fills at least one goal.
+            for _ in 0..=goals.len() {
+                let open: Vec<usize> = (0..goals.len())
+                    .filter(|index| allocations[*index] < needs[*index])
+                    .collect();
+                let total_weight: Decimal =
+                    open.iter().map(|index| goals[*index].funding_weight).sum();
+
+                if remaining <= Decimal::ZERO || total_weight <= Decimal::ZERO {
+                    break;
+                }
+
+                let mut given = Decimal::ZERO;
+                for index in open {
+                    let share = remaining * goals[index].funding_weight / total_weight;
+                    let amount = Decimal::min(share, needs[index] - allocations[index]);
+                    allocations[index] += amount;
+                    given += amount;
+                }
+
+                remaining -= given;
+            }
+        }
+    }
+
+    allocations
+}
+
+/// Moves the goal's target into its linked envelope according to the goal's policy, creating
+/// an envelope when there is none to reuse. The move is recorded as an adjustment transaction.
+async fn fill_envelope(
+    client: &impl GenericClient,
+    goal: &Goal,
+    filled_on: NaiveDate,
+) -> Result<EnvelopeFill, AppError> {
+    let linked = match (&goal.envelope_policy, goal.envelope_id) {
+        (EnvelopePolicy::CreateNew, _) | (_, None) => None,
+        (_, Some(envelope_id)) => Some(Envelope::get_one(client, envelope_id, goal.user_id).await?),
+    };
+
+    let (mut envelope, created) = match linked {
+        Some(envelope) => (envelope, false),
+        None => {
+            let envelope = Envelope {
+                id: None,
+                name: goal.name.clone(),
+                amount: Decimal::ZERO,
+                user_id: goal.user_id,
+            }
+            .create(client)
+            .await?;
+            (envelope, true)
+        }
+    };
+
+    let amount = match goal.envelope_policy {
+        EnvelopePolicy::Replace => goal.target - envelope.amount,
+        _ => goal.target,
+    };
+    if amount != Decimal::ZERO {
+        envelope
+            .adjust(client, amount, format!("{} goal", goal.name), filled_on)
+            .await?;
+    }
+
+    Ok(EnvelopeFill {
+        amount,
+        envelope,
+        created,
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use crate::db::database_pool;
-    use crate::jobs::convert_goals::private_convert_goals;
+    use crate::jobs::convert_goals::{allocate_surplus, private_convert_goals};
     use crate::models::account::Account;
     use crate::models::envelope::Envelope;
-    use crate::models::goal::{Goal, Recurrence};
-    use crate::models::user::{Preferences, User};
+    use crate::models::goal::{EnvelopePolicy, Goal, Recurrence};
+    use crate::models::income_source::{IncomeRecurrence, IncomeSource};
+    use crate::models::transaction::Transaction;
+    use crate::models::user::{GoalFunding, User};
     use crate::test_utils::user_for_tests;
     use crate::utilities::dates::Times;
     use chrono::{Days, Duration, TimeZone, Timelike, Utc};
-    use postgres_types::Json;
     use rust_database_common::DatabasePool;
     use rust_database_common::GenericClient;
     use rust_decimal::Decimal;
     use std::env;
     use std::ops::Sub;
+    use uuid::Uuid;
 
     struct MockTimeProvider;
     impl Times for MockTimeProvider {
@@ -148,6 +367,11 @@ mod tests {
             target: Decimal::new(70, 0),
             recurrence: Recurrence::Weekly,
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
 
         let goal = goal.create(client).await.unwrap();
@@ -160,13 +384,81 @@ mod tests {
         )
     }
 
+    fn goal_needing(needed: i64, funding_weight: i64) -> Goal {
+        Goal {
+            id: None,
+            user_id: Uuid::new_v4(),
+            accumulated_amount: Decimal::new(10, 0),
+            name: "allocate".to_owned(),
+            target_date: Utc::now(),
+            target: Decimal::new(10 + needed, 0),
+            recurrence: Recurrence::Monthly,
+            start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::new(funding_weight, 0),
+            target_day: None,
+        }
+    }
+
+    #[test]
+    fn test_allocate_surplus_sequentially() {
+        let goals = [
+            goal_needing(30, 1),
+            goal_needing(50, 1),
+            goal_needing(40, 1),
+        ];
+
+        assert_eq!(
+            allocate_surplus(&goals, Decimal::new(60, 0), GoalFunding::Sequential),
+            [Decimal::new(30, 0), Decimal::new(30, 0), Decimal::ZERO]
+        );
+        assert_eq!(
+            allocate_surplus(&goals, Decimal::new(-5, 0), GoalFunding::Sequential),
+            [Decimal::ZERO, Decimal::ZERO, Decimal::ZERO]
+        );
+    }
+
+    #[test]
+    fn test_allocate_surplus_by_weight() {
+        let goals = [
+            goal_needing(10, 2),
+            goal_needing(100, 1),
+            goal_needing(100, 1),
+        ];
+        let allocations = allocate_surplus(&goals, Decimal::new(80, 0), GoalFunding::Weighted);
+
+        // The first goal would get 40 but only needs 10, so the other 30 is split evenly.
+        assert_eq!(
+            allocations,
+            [
+                Decimal::new(10, 0),
+                Decimal::new(35, 0),
+                Decimal::new(35, 0)
+            ]
+        );
+
+        let allocations = allocate_surplus(&goals, Decimal::new(500, 0), GoalFunding::Weighted);
+        assert_eq!(
+            allocations,
+            [
+                Decimal::new(10, 0),
+                Decimal::new(100, 0),
+                Decimal::new(100, 0)
+            ]
+        );
+    }
+
     // These tests are prone to database deadlocks due to the nature of the tests.
     // Running them sequentially can help avoid deadlocks.
     #[tokio::test]
     async fn test_convert_goalsrunner() {
         test_accelerate_goal().await;
+        test_accelerate_goals_in_priority_order().await;
         test_accumulate_goal().await;
         test_convert_goal_to_envelope().await;
+        test_convert_goal_reuses_envelope().await;
 
         let mut database_pool = database_pool(Some(&env::var("TEST_DATABASE_URL").unwrap()))
             .await
@@ -177,7 +469,7 @@ mod tests {
             .get_client()
             .await
             .unwrap()
-            .execute("TRUNCATE TABLE goals", &[])
+            .execute("TRUNCATE TABLE goals CASCADE", &[])
             .await
             .unwrap();
     }
@@ -194,6 +486,9 @@ mod tests {
             name: "test".to_string(),
             amount: Decimal::new(100, 0),
             debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
         };
 
         account.create(&transaction).await.unwrap();
@@ -203,13 +498,21 @@ mod tests {
 
         goal.update(&transaction).await.unwrap();
 
-        let mut user = user.clone();
-        let mut preferences = Preferences::default();
-        preferences.monthly_income = Some(Decimal::new(3100, 0));
-        user.preferences = Some(Json(preferences));
-        let user = user.update(&transaction).await.unwrap();
+        IncomeSource {
+            id: None,
+            user_id: user.id,
+            name: "salary".to_string(),
+            amount: Decimal::new(3100, 0),
+            recurrence: IncomeRecurrence::Monthly,
+            next_expected_on: time.now().date_naive(),
+        }
+        .create(&transaction)
+        .await
+        .unwrap();
 
-        private_convert_goals(&transaction, &time).await.unwrap();
+        private_convert_goals(&transaction, &time, None)
+            .await
+            .unwrap();
 
         let goal: Goal = transaction
             .query_one(
@@ -226,13 +529,74 @@ mod tests {
         transaction.rollback().await.unwrap();
     }
 
+    async fn test_accelerate_goals_in_priority_order() {
+        let (user, pool, time, goal) = setup().await;
+
+        let mut client = pool.get_client().await.unwrap();
+        let transaction = client.transaction().await.unwrap();
+
+        Account {
+            user_id: user.id,
+            id: None,
+            name: "test".to_string(),
+            amount: Decimal::new(1000, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&transaction)
+        .await
+        .unwrap();
+
+        let mut goal = goal.clone();
+        goal.target_date = time.now().checked_add_days(Days::new(3)).unwrap();
+        let goal = goal.update(&transaction).await.unwrap();
+
+        let mut second_goal = goal.clone();
+        second_goal.name = "second".to_string();
+        second_goal.target = Decimal::new(1000, 0);
+        let second_goal = second_goal.create(&transaction).await.unwrap();
+        assert_eq!(second_goal.priority, goal.priority + 1);
+
+        Goal::reorder(
+            &transaction,
+            user.id,
+            &[second_goal.id.unwrap(), goal.id.unwrap()],
+        )
+        .await
+        .unwrap();
+
+        private_convert_goals(&transaction, &time, None)
+            .await
+            .unwrap();
+
+        let accelerated: Vec<Uuid> = transaction
+            .query(
+                "SELECT DISTINCT goal_id FROM goal_contributions
+                WHERE user_id = $1 AND source = 'Acceleration'",
+                &[&user.id],
+            )
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| row.get("goal_id"))
+            .collect();
+
+        assert_eq!(accelerated, [second_goal.id.unwrap()]);
+
+        transaction.rollback().await.unwrap();
+    }
+
     async fn test_accumulate_goal() {
         let (user, pool, time, _) = setup().await;
 
         let mut client = pool.get_client().await.unwrap();
         let transaction = client.transaction().await.unwrap();
 
-        private_convert_goals(&transaction, &time).await.unwrap();
+        private_convert_goals(&transaction, &time, None)
+            .await
+            .unwrap();
 
         let goal: Goal = transaction
             .query_one(
@@ -255,7 +619,9 @@ mod tests {
         let mut client = pool.get_client().await.unwrap();
         let transaction = client.transaction().await.unwrap();
 
-        private_convert_goals(&transaction, &time).await.unwrap();
+        private_convert_goals(&transaction, &time, None)
+            .await
+            .unwrap();
 
         let envelope = transaction
             .query_one(
@@ -268,6 +634,12 @@ mod tests {
         let envelope: Envelope = envelope.try_into().unwrap();
 
         assert_eq!(envelope.amount, Decimal::new(70, 0));
+        let filled = Transaction::get_all_for_envelope(&transaction, envelope.id.unwrap(), user.id)
+            .await
+            .unwrap();
+        assert_eq!(filled.len(), 1);
+        assert_eq!(filled[0].amount, envelope.amount);
+        assert!(filled[0].adjustment);
 
         let goal: Goal = transaction
             .query_one(
@@ -283,4 +655,53 @@ mod tests {
 
         transaction.rollback().await.unwrap();
     }
+
+    async fn test_convert_goal_reuses_envelope() {
+        let (user, pool, time, goal) = setup().await;
+
+        let mut client = pool.get_client().await.unwrap();
+        let transaction = client.transaction().await.unwrap();
+
+        private_convert_goals(&transaction, &time, None)
+            .await
+            .unwrap();
+
+        let mut goal = Goal::get_one(&transaction, goal.id.unwrap(), user.id)
+            .await
+            .unwrap();
+        assert!(goal.envelope_id.is_some());
+
+        goal.target_date = time.now().sub(Duration::days(1));
+        goal.update(&transaction).await.unwrap();
+        private_convert_goals(&transaction, &time, None)
+            .await
+            .unwrap();
+
+        let envelopes = Envelope::get_all(&transaction, user.id).await.unwrap();
+        assert_eq!(envelopes.len(), 1);
+        assert_eq!(envelopes[0].amount, Decimal::new(140, 0));
+
+        goal.target_date = time.now().sub(Duration::days(1));
+        goal.envelope_policy = EnvelopePolicy::Replace;
+        goal.update(&transaction).await.unwrap();
+        private_convert_goals(&transaction, &time, None)
+            .await
+            .unwrap();
+
+        let envelopes = Envelope::get_all(&transaction, user.id).await.unwrap();
+        assert_eq!(envelopes.len(), 1);
+        assert_eq!(envelopes[0].amount, Decimal::new(70, 0));
+
+        goal.target_date = time.now().sub(Duration::days(1));
+        goal.envelope_policy = EnvelopePolicy::CreateNew;
+        goal.update(&transaction).await.unwrap();
+        private_convert_goals(&transaction, &time, None)
+            .await
+            .unwrap();
+
+        let envelopes = Envelope::get_all(&transaction, user.id).await.unwrap();
+        assert_eq!(envelopes.len(), 2);
+
+        transaction.rollback().await.unwrap();
+    }
 }
@@ -0,0 +1,246 @@
+use crate::{
+    errors::AppError,
+    models::webhook::{WebhookDelivery, WebhookDeliveryStatus, WebhookEndpoint, signature},
+    utilities::dates::Times,
+};
+use chrono::Duration;
+use openidconnect::reqwest::{Client, redirect::Policy};
+use rust_database_common::{DatabasePool, GenericClient};
+use tracing::{error, info};
+use uuid::Uuid;
+
+/// Deliveries are given up on after this many failed attempts.
+const MAX_ATTEMPTS: i32 = 8;
+
+/// How many deliveries one run claims.
+const BATCH: i64 = 50;
+
+/// How long claimed deliveries are held back from other runs; longer than a batch of sends that
+/// each time out.
+const LEASE: Duration = Duration::minutes(15);
+
+/// How long to wait after a failed attempt: a minute after the first, doubling after each one.
+fn backoff(attempts: i32) -> Duration {
+    Duration::minutes(1 << (attempts - 1).clamp(0, MAX_ATTEMPTS))
+}
+
+pub fn http_client() -> Result<Client, AppError> {
+    Ok(Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .redirect(Policy::none())
+        .build()?)
+}
+
+pub async fn deliver_webhooks(pool: &DatabasePool, time: &impl Times) -> Result<(), AppError> {
+    info!("delivering webhooks at {}", time.now());
+    let http = http_client()?;
+    let client = pool.get_client().await?;
+
+    deliver_due(&client, &http, time, None).await?;
+
+    Ok(())
+}
+
+/// Sends each pending delivery that is due, optionally for one endpoint, recording the outcome
+/// and when to try again. Deliveries are claimed before any is sent, so that no transaction or
+/// lock is held while waiting on endpoints; a delivery that cannot be sent or recorded is logged
+/// and left to be retried when its claim runs out.
+pub async fn deliver_due(
+    client: &impl GenericClient,
+    http: &Client,
+    time: &impl Times,
+    endpoint_id: Option<Uuid>,
+) -> Result<Vec<WebhookDelivery>, AppError> {
+    let deliveries =
+        WebhookDelivery::claim_due(client, time.now(), time.now() + LEASE, endpoint_id, BATCH)
+            .await?;
+    let mut attempted = Vec::with_capacity(deliveries.len());
+
+    for mut delivery in deliveries {
+        match attempt(client, http, time, &mut delivery).await {
+            Ok(()) => attempted.push(delivery),
+            Err(err) => error!("could not deliver webhook {}: {:#?}", delivery.id, err),
+        }
+    }
+
+    Ok(attempted)
+}
+
+async fn attempt(
+    client: &impl GenericClient,
+    http: &Client,
+    time: &impl Times,
+    delivery: &mut WebhookDelivery,
+) -> Result<(), AppError> {
+    let endpoint = WebhookEndpoint::get_one(client, delivery.endpoint_id, delivery.user_id).await?;
+    let body = delivery.payload.to_string();
+    let timestamp = time.now().timestamp();
+    let signature = signature(&endpoint.secret, timestamp, &body)?;
+
+    let response = http
+        .post(&endpoint.url)
+        .header("Content-Type", "application/json")
+        .header("X-Simple-Budget-Event", &delivery.event)
+        .header("X-Simple-Budget-Delivery", delivery.id.to_string())
+        .header("X-Simple-Budget-Timestamp", timestamp.to_string())
+        .header("X-Simple-Budget-Signature", format!("sha256={signature}"))
+        .body(body)
+        .send()
+        .await;
+
+    delivery.attempts += 1;
+    match response {
+        Ok(response) if response.status().is_success() => {
+            delivery.status = WebhookDeliveryStatus::Delivered;
+            delivery.response_status = Some(i32::from(response.status().as_u16()));
+            delivery.last_error = None;
+            delivery.delivered_at = Some(time.now());
+        }
+        failure => {
+            match failure {
+                Ok(response) => {
+                    delivery.response_status = Some(i32::from(response.status().as_u16()));
+                    delivery.last_error = Some(format!("endpoint answered {}", response.status()));
+                }
+                Err(err) => {
+                    delivery.response_status = None;
+                    delivery.last_error = Some(err.to_string());
+                }
+            }
+
+            if delivery.attempts >= MAX_ATTEMPTS {
+                delivery.status = WebhookDeliveryStatus::Failed;
+            } else {
+                delivery.next_attempt_at = time.now() + backoff(delivery.attempts);
+            }
+        }
+    }
+
+    delivery.update(client).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_ATTEMPTS, backoff, deliver_due, http_client};
+    use crate::{
+        models::webhook::{WebhookDeliveryStatus, WebhookEndpoint, WebhookEvent, signature},
+        test_utils::state_for_tests,
+        utilities::dates::FixedTime,
+    };
+    use axum::{
+        Router,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+    };
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+    use tokio::net::TcpListener;
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+    /// A local endpoint that fails its first request and accepts the rest.
+    async fn stand_in() -> (String, Received) {
+        let received: Received = Arc::default();
+        let app = Router::new().route(
+            "/hooks",
+            post({
+                let received = received.clone();
+                move |headers: HeaderMap, body: String| async move {
+                    let mut received = received.lock().unwrap();
+                    received.push((headers, body));
+
+                    if received.len() == 1 {
+                        StatusCode::SERVICE_UNAVAILABLE
+                    } else {
+                        StatusCode::NO_CONTENT
+                    }
+                }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        (url, received)
+    }
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), Duration::minutes(1));
+        assert_eq!(backoff(2), Duration::minutes(2));
+        assert_eq!(backoff(MAX_ATTEMPTS), Duration::minutes(128));
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_delivered() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let mut client = shared_state.pool.get_client().await.unwrap();
+        let client = client.transaction().await.unwrap();
+        let (url, received) = stand_in().await;
+        let endpoint = WebhookEndpoint::create(
+            &client,
+            user_extension.id,
+            &url,
+            &[WebhookEvent::EnvelopeCreated],
+            None,
+        )
+        .await
+        .unwrap();
+        WebhookEndpoint::publish(
+            &client,
+            user_extension.id,
+            WebhookEvent::EnvelopeCreated,
+            json!({ "name": "Gifts" }),
+        )
+        .await
+        .unwrap();
+        let http = http_client().unwrap();
+        let now = Utc::now() + Duration::seconds(1);
+
+        let deliveries = deliver_due(&client, &http, &FixedTime(now), Some(endpoint.id))
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len(), 1);
+        let attempted = &deliveries[0];
+        assert_eq!(attempted.status, WebhookDeliveryStatus::Pending);
+        assert_eq!(attempted.attempts, 1);
+        assert_eq!(attempted.response_status, Some(503));
+        assert_eq!(attempted.next_attempt_at, now + Duration::minutes(1));
+
+        let deliveries = deliver_due(&client, &http, &FixedTime(now), Some(endpoint.id))
+            .await
+            .unwrap();
+        assert!(deliveries.is_empty());
+
+        let later = now + Duration::minutes(1);
+        let deliveries = deliver_due(&client, &http, &FixedTime(later), Some(endpoint.id))
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len(), 1);
+        let delivered = &deliveries[0];
+        assert_eq!(delivered.status, WebhookDeliveryStatus::Delivered);
+        assert_eq!(delivered.attempts, 2);
+        assert_eq!(delivered.delivered_at, Some(later));
+
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received.len(), 2);
+        let (headers, body) = &received[1];
+        let timestamp = headers["x-simple-budget-timestamp"].to_str().unwrap();
+        assert_eq!(timestamp, later.timestamp().to_string());
+        assert_eq!(
+            headers["x-simple-budget-signature"].to_str().unwrap(),
+            format!(
+                "sha256={}",
+                signature(&endpoint.secret, later.timestamp(), body).unwrap()
+            )
+        );
+        assert_eq!(headers["x-simple-budget-event"], "envelope.created");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(body).unwrap()["data"]["name"],
+            "Gifts"
+        );
+
+        client.rollback().await.unwrap();
+    }
+}
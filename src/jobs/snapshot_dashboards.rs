@@ -0,0 +1,111 @@
+use crate::{
+    errors::AppError,
+    models::{dashboard_snapshot::DashboardSnapshot, user::User},
+    utilities::dates::Times,
+};
+use chrono_tz::Tz;
+use rust_database_common::{DatabasePool, GenericClient};
+use tracing::{error, info};
+
+/// Records each user's dashboard numbers once per day, on the first run after midnight in the
+/// user's timezone.
+pub async fn snapshot_dashboards(pool: &DatabasePool, time: &impl Times) -> Result<(), AppError> {
+    info!("snapshotting dashboards at {}", time.now());
+    let client = pool.get_client().await?;
+
+    for user in User::get_all(&client).await? {
+        if let Err(err) = snapshot_dashboard(&client, &user, time).await {
+            error!("could not snapshot dashboard for {}: {:#?}", user.id, err);
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn snapshot_dashboard(
+    client: &impl GenericClient,
+    user: &User,
+    time: &impl Times,
+) -> Result<Option<DashboardSnapshot>, AppError> {
+    let timezone: Tz = user.timezone()?.parse()?;
+    let today = time.now().with_timezone(&timezone).date_naive();
+
+    if DashboardSnapshot::exists_for(client, user.id, today).await? {
+        return Ok(None);
+    }
+
+    DashboardSnapshot::measure(client, user, time)
+        .await?
+        .create(client)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::snapshot_dashboard;
+    use crate::{
+        models::{account::Account, dashboard_snapshot::DashboardSnapshot, user::User},
+        test_utils::state_for_tests,
+        utilities::dates::FixedTime,
+    };
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_one_snapshot_per_day() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+        let user = User::get_by_id(&client, user_extension.0.id).await.unwrap();
+
+        Account {
+            id: None,
+            user_id: user.id,
+            name: "checking".to_string(),
+            amount: Decimal::new(3000, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let morning = FixedTime(Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap());
+        let snapshot = snapshot_dashboard(&client, &user, &morning)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            snapshot.snapshot_date,
+            NaiveDate::from_ymd_opt(2024, 4, 1).unwrap()
+        );
+        assert_eq!(snapshot.remaining_total, Decimal::new(3000, 0));
+        assert_eq!(snapshot.per_diem.round_dp(2), Decimal::new(10000, 2));
+
+        let evening = FixedTime(Utc.with_ymd_and_hms(2024, 4, 1, 20, 0, 0).unwrap());
+        assert!(
+            snapshot_dashboard(&client, &user, &evening)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let next_day = FixedTime(Utc.with_ymd_and_hms(2024, 4, 2, 0, 0, 0).unwrap());
+        assert!(
+            snapshot_dashboard(&client, &user, &next_day)
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        let snapshots = DashboardSnapshot::get_all(
+            &client,
+            user.id,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(snapshots.len(), 2);
+    }
+}
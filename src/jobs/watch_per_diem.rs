@@ -0,0 +1,117 @@
+use crate::{
+    authenticated::dashboard::DashboardSummary,
+    errors::AppError,
+    models::{
+        user::User,
+        webhook::{WebhookDelivery, WebhookEndpoint, WebhookEvent},
+    },
+};
+use rust_database_common::{DatabasePool, GenericClient};
+use serde_json::json;
+use tracing::{error, info};
+
+/// Queues `per_diem.below_threshold` for endpoints whose user's per diem has dropped below the
+/// endpoint's threshold since the last run.
+pub async fn watch_per_diem(pool: &DatabasePool) -> Result<(), AppError> {
+    info!("watching per diem thresholds");
+    let client = pool.get_client().await?;
+
+    for endpoint in WebhookEndpoint::get_watching_per_diem(&client).await? {
+        if let Err(err) = check_per_diem(&client, &endpoint).await {
+            error!("could not check per diem for {}: {:#?}", endpoint.id, err);
+        }
+    }
+
+    Ok(())
+}
+
+/// Only the drop is sent; once the per diem is back at the threshold the endpoint is re-armed.
+pub async fn check_per_diem(
+    client: &impl GenericClient,
+    endpoint: &WebhookEndpoint,
+) -> Result<Option<WebhookDelivery>, AppError> {
+    let Some(threshold) = endpoint.per_diem_threshold else {
+        return Ok(None);
+    };
+    let user = User::get_by_id(client, endpoint.user_id).await?;
+    let per_diem = DashboardSummary::measure(&user, client).await?.per_diem;
+    let below = per_diem < threshold;
+
+    if below == endpoint.per_diem_below {
+        return Ok(None);
+    }
+    endpoint.set_per_diem_below(client, below).await?;
+    if !below {
+        return Ok(None);
+    }
+
+    endpoint
+        .enqueue(
+            client,
+            WebhookEvent::PerDiemBelowThreshold,
+            json!({ "per_diem": per_diem, "threshold": threshold }),
+        )
+        .await
+        .map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_per_diem;
+    use crate::{
+        models::{
+            account::Account,
+            webhook::{WebhookEndpoint, WebhookEvent},
+        },
+        test_utils::state_for_tests,
+    };
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_per_diem_drop_is_sent_once() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let mut client = shared_state.pool.get_client().await.unwrap();
+        let client = client.transaction().await.unwrap();
+        let endpoint = WebhookEndpoint::create(
+            &client,
+            user_extension.id,
+            "http://localhost/hooks",
+            &[WebhookEvent::PerDiemBelowThreshold],
+            Some(Decimal::new(10, 0)),
+        )
+        .await
+        .unwrap();
+
+        let delivery = check_per_diem(&client, &endpoint).await.unwrap().unwrap();
+        assert_eq!(delivery.event, "per_diem.below_threshold");
+        assert_eq!(delivery.payload["data"]["threshold"], "10");
+
+        let endpoint = WebhookEndpoint::get_one(&client, endpoint.id, user_extension.id)
+            .await
+            .unwrap();
+        assert!(endpoint.per_diem_below);
+        assert!(check_per_diem(&client, &endpoint).await.unwrap().is_none());
+
+        Account {
+            id: None,
+            user_id: user_extension.id,
+            name: "checking".to_string(),
+            amount: Decimal::new(1_000_000, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        assert!(check_per_diem(&client, &endpoint).await.unwrap().is_none());
+        let endpoint = WebhookEndpoint::get_one(&client, endpoint.id, user_extension.id)
+            .await
+            .unwrap();
+        assert!(!endpoint.per_diem_below);
+
+        client.rollback().await.unwrap();
+    }
+}
@@ -1,12 +1,15 @@
+mod api;
 mod authenticated;
 mod authentication;
 mod db;
 mod errors;
+mod feeds;
 mod jobs;
 mod middleware;
 mod models;
 mod utilities;
 
+use crate::authenticated::live::{LiveUpdate, LiveUpdates};
 use crate::utilities::handlebars::{
     DigestAssetHandlebarsHelper, EqHandlebarsHelper, walk_directory,
 };
@@ -18,7 +21,11 @@ use axum_extra::extract::cookie::Key;
 use chrono::Utc;
 use errors::AppResponse;
 use handlebars::Handlebars;
-use jobs::{clear_sessions::clear_sessions, convert_goals::convert_goals};
+use jobs::{
+    clear_sessions::clear_sessions, convert_goals::convert_goals,
+    deliver_webhooks::deliver_webhooks, snapshot_dashboards::snapshot_dashboards,
+    watch_per_diem::watch_per_diem,
+};
 use rust_database_common::DatabasePool;
 use rust_web_common::telemetry::TelemetryBuilder;
 use serde::Serialize;
@@ -40,6 +47,9 @@ pub enum Section {
     Envelopes,
     Goals,
     Preferences,
+    Transactions,
+    Income,
+    Forecast,
 }
 
 pub type HandlebarsContext = BTreeMap<String, serde_json::Value>;
@@ -49,6 +59,7 @@ pub struct SharedState {
     key: Key,
     pool: DatabasePool,
     handlebars: Handlebars<'static>,
+    live: LiveUpdates,
 }
 
 impl FromRef<SharedState> for Key {
@@ -57,8 +68,8 @@ impl FromRef<SharedState> for Key {
     }
 }
 
-fn start_background_jobs() -> tokio::task::JoinHandle<()> {
-    spawn(async {
+fn start_background_jobs(live: LiveUpdates) -> tokio::task::JoinHandle<()> {
+    spawn(async move {
         let mut interval = interval(Duration::from_millis(60000));
         let database_pool = db::database_pool(None).await.unwrap();
 
@@ -67,10 +78,19 @@ fn start_background_jobs() -> tokio::task::JoinHandle<()> {
         loop {
             interval.tick().await;
 
-            let (_clear_sessions_result, _convert_goals_result) = tokio::join!(
-                clear_sessions(&database_pool),
-                convert_goals(&database_pool, &time)
-            );
+            let (_clear_sessions_result, _convert_goals_result, _snapshot_dashboards_result) =
+                tokio::join!(
+                    clear_sessions(&database_pool),
+                    convert_goals(&database_pool, &time),
+                    snapshot_dashboards(&database_pool, &time)
+                );
+
+            // Runs after the jobs above, so that the events they queue are sent straight away.
+            let _watch_per_diem_result = watch_per_diem(&database_pool).await;
+            let _deliver_webhooks_result = deliver_webhooks(&database_pool, &time).await;
+
+            // Open dashboards count down by the minute, so every one of them is refreshed.
+            live.publish(LiveUpdate::Everyone);
         }
     })
 }
@@ -120,15 +140,19 @@ async fn main() {
         }
     };
 
+    let live = LiveUpdates::default();
     let shared_state = SharedState {
         key,
         pool,
         handlebars,
+        live: live.clone(),
     };
 
     let app = Router::new()
         .merge(authentication::authentication_router())
         .merge(authenticated::authenticated_router(shared_state.clone()))
+        .nest("/api/v1", api::api_router(shared_state.clone()))
+        .merge(feeds::feeds_router())
         .merge(Router::new().route("/healthcheck", get(healthcheck)))
         .with_state(shared_state)
         .layer(from_fn(middleware::secure_headers))
@@ -145,7 +169,7 @@ async fn main() {
     let server_handle = spawn(async {
         axum::serve(listener, app).await.unwrap();
     });
-    let background_jobs = start_background_jobs();
+    let background_jobs = start_background_jobs(live);
 
     let mut signal = signal(SignalKind::terminate()).unwrap();
 
@@ -1,4 +1,18 @@
 pub mod account;
+pub mod account_balance;
+pub mod api_token;
+pub mod archive;
+pub mod calendar;
+pub mod dashboard_snapshot;
+pub mod debt_plan;
 pub mod envelope;
+pub mod forecast;
 pub mod goal;
+pub mod goal_contribution;
+pub mod income_source;
+pub mod ledger;
+pub mod net_worth;
+pub mod statement_import;
+pub mod transaction;
 pub mod user;
+pub mod webhook;
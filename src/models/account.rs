@@ -1,5 +1,6 @@
-use crate::errors::AppError;
+use crate::{errors::AppError, models::transaction::Transaction};
 use anyhow::Result;
+use chrono::NaiveDate;
 use rust_database_common::GenericClient;
 use rust_decimal::Decimal;
 use serde::Deserialize;
@@ -13,6 +14,11 @@ pub struct Account {
     pub name: String,
     pub amount: Decimal,
     pub debt: bool,
+    /// Annual percentage rate on a debt account, as a percentage.
+    pub apr: Option<Decimal>,
+    pub minimum_payment: Option<Decimal>,
+    /// Matches statements imported from the bank to this account.
+    pub account_number: Option<String>,
 }
 
 impl TryInto<Account> for tokio_postgres::Row {
@@ -35,6 +41,15 @@ impl TryInto<Account> for tokio_postgres::Row {
             debt: self
                 .try_get("debt")
                 .map_err(AppError::RecordDeserializationError)?,
+            apr: self
+                .try_get("apr")
+                .map_err(AppError::RecordDeserializationError)?,
+            minimum_payment: self
+                .try_get("minimum_payment")
+                .map_err(AppError::RecordDeserializationError)?,
+            account_number: self
+                .try_get("account_number")
+                .map_err(AppError::RecordDeserializationError)?,
         })
     }
 }
@@ -43,8 +58,8 @@ impl Account {
     pub async fn create(&self, client: &impl GenericClient) -> Result<Self, AppError> {
         let row = client
             .query_one(
-                "INSERT INTO accounts (user_id, name, amount, debt) VALUES ($1, $2, $3, $4) RETURNING id",
-                &[&self.user_id, &self.name, &self.amount, &self.debt],
+                "INSERT INTO accounts (user_id, name, amount, debt, apr, minimum_payment, account_number) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+                &[&self.user_id, &self.name, &self.amount, &self.debt, &self.apr, &self.minimum_payment, &self.account_number],
             )
             .await?;
 
@@ -53,11 +68,44 @@ impl Account {
         Ok(new_account)
     }
 
+    /// Saves everything but the balance, which only transactions move; see `adjust_balance`.
     pub async fn update(&self, client: &impl GenericClient) -> Result<(), AppError> {
-        client.query("UPDATE accounts SET name = $1, amount = $2, debt = $3 WHERE id = $4 AND user_id = $5", &[&self.name, &self.amount, &self.debt, &self.id, &self.user_id]).await?;
+        client.query("UPDATE accounts SET name = $1, debt = $2, apr = $3, minimum_payment = $4, account_number = $5 WHERE id = $6 AND user_id = $7", &[&self.name, &self.debt, &self.apr, &self.minimum_payment, &self.account_number, &self.id, &self.user_id]).await?;
         Ok(())
     }
 
+    /// Moves the balance to `balance` with an adjustment transaction, so the ledger accounts
+    /// for balances set by hand. Update the account first: the transaction follows the saved
+    /// `debt` flag. Returns `false` when the balance is already there.
+    pub async fn adjust_balance(
+        &mut self,
+        client: &impl GenericClient,
+        balance: Decimal,
+        occurred_on: NaiveDate,
+    ) -> Result<bool, AppError> {
+        let change = balance - self.amount;
+        if change == Decimal::ZERO {
+            return Ok(false);
+        }
+
+        Transaction {
+            id: None,
+            user_id: self.user_id,
+            account_id: self.id,
+            envelope_id: None,
+            // Money going out of a debt account increases what is owed.
+            amount: if self.debt { -change } else { change },
+            description: "Balance adjustment".to_string(),
+            occurred_on,
+            adjustment: true,
+        }
+        .create(client)
+        .await?;
+        self.amount = balance;
+
+        Ok(true)
+    }
+
     pub async fn delete(&self, client: &impl GenericClient) -> Result<()> {
         client
             .execute(
@@ -86,6 +134,21 @@ impl Account {
         row.try_into()
     }
 
+    pub async fn get_by_account_number(
+        client: &impl GenericClient,
+        account_number: &str,
+        user_id: Uuid,
+    ) -> Result<Option<Self>, AppError> {
+        client
+            .query_opt(
+                "SELECT accounts.* FROM accounts WHERE user_id = $1 AND account_number = $2",
+                &[&user_id, &account_number],
+            )
+            .await?
+            .map(|row| row.try_into())
+            .transpose()
+    }
+
     pub async fn get_all(
         client: &impl GenericClient,
         user_id: Uuid,
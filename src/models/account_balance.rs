@@ -0,0 +1,89 @@
+use crate::errors::AppError;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// An account's balance as of `recorded_at`. A row is appended whenever the balance or the
+/// debt flag changes.
+#[derive(Serialize, Debug, Clone)]
+pub struct AccountBalance {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub user_id: Uuid,
+    pub amount: Decimal,
+    pub debt: bool,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl TryInto<AccountBalance> for tokio_postgres::Row {
+    type Error = AppError;
+
+    fn try_into(self: tokio_postgres::Row) -> Result<AccountBalance, AppError> {
+        Ok(AccountBalance {
+            id: self
+                .try_get("id")
+                .map_err(AppError::RecordDeserializationError)?,
+            account_id: self
+                .try_get("account_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            user_id: self
+                .try_get("user_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            amount: self
+                .try_get("amount")
+                .map_err(AppError::RecordDeserializationError)?,
+            debt: self
+                .try_get("debt")
+                .map_err(AppError::RecordDeserializationError)?,
+            recorded_at: self
+                .try_get("recorded_at")
+                .map_err(AppError::RecordDeserializationError)?,
+        })
+    }
+}
+
+impl AccountBalance {
+    /// Copies the account's current balance into the history.
+    pub async fn record(
+        client: &impl GenericClient,
+        account_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Self, AppError> {
+        client
+            .query_one(
+                "INSERT INTO account_balances (account_id, user_id, amount, debt)
+                SELECT id, user_id, amount, debt FROM accounts
+                WHERE id = $1 AND user_id = $2
+                RETURNING *",
+                &[&account_id, &user_id],
+            )
+            .await
+            .map_err(AppError::RecordNotFound)?
+            .try_into()
+    }
+
+    pub async fn get_all_until(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        until: DateTime<Utc>,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT account_balances.* FROM account_balances
+                WHERE user_id = $1 AND recorded_at < $2
+                ORDER BY recorded_at",
+                &[&user_id, &until],
+            )
+            .await?;
+
+        let mut balances = Vec::with_capacity(rows.len());
+        for row in rows {
+            balances.push(row.try_into()?);
+        }
+
+        Ok(balances)
+    }
+}
@@ -0,0 +1,173 @@
+use crate::errors::AppError;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use postgres_types::{FromSql, ToSql};
+use rand::{
+    distr::{Alphanumeric, SampleString},
+    rng,
+};
+use rust_database_common::GenericClient;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// What a personal access token may do; `Read` tokens are limited to safe methods.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, FromSql, ToSql, PartialEq)]
+pub enum TokenScope {
+    Read,
+    Write,
+}
+
+/// A personal access token, sent as `Authorization: Bearer <secret>`. Only a SHA-256 hash of
+/// the secret is stored, so it is shown once when the token is created.
+#[derive(Serialize, Debug, Clone)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub scope: TokenScope,
+    #[serde(skip)]
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl TryInto<ApiToken> for tokio_postgres::Row {
+    type Error = AppError;
+
+    fn try_into(self: tokio_postgres::Row) -> Result<ApiToken, AppError> {
+        Ok(ApiToken {
+            id: self
+                .try_get("id")
+                .map_err(AppError::RecordDeserializationError)?,
+            user_id: self
+                .try_get("user_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            name: self
+                .try_get("name")
+                .map_err(AppError::RecordDeserializationError)?,
+            scope: self
+                .try_get("scope")
+                .map_err(AppError::RecordDeserializationError)?,
+            token_hash: self
+                .try_get("token_hash")
+                .map_err(AppError::RecordDeserializationError)?,
+            created_at: self
+                .try_get("created_at")
+                .map_err(AppError::RecordDeserializationError)?,
+            last_used_at: self
+                .try_get("last_used_at")
+                .map_err(AppError::RecordDeserializationError)?,
+        })
+    }
+}
+
+fn hash(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+impl ApiToken {
+    /// Returns the new token along with its secret.
+    pub async fn create(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        name: &str,
+        scope: TokenScope,
+    ) -> Result<(Self, String), AppError> {
+        let secret = format!("sb_{}", Alphanumeric.sample_string(&mut rng(), 40));
+        let token = client
+            .query_one(
+                "INSERT INTO api_tokens (user_id, name, scope, token_hash)
+                VALUES ($1, $2, $3, $4) RETURNING *",
+                &[&user_id, &name, &scope, &hash(&secret)],
+            )
+            .await?
+            .try_into()?;
+
+        Ok((token, secret))
+    }
+
+    /// Finds the token for `secret`, recording that it was used.
+    pub async fn authenticate(client: &impl GenericClient, secret: &str) -> Result<Self, AppError> {
+        client
+            .query_one(
+                "UPDATE api_tokens SET last_used_at = now() WHERE token_hash = $1 RETURNING *",
+                &[&hash(secret)],
+            )
+            .await
+            .map_err(AppError::RecordNotFound)?
+            .try_into()
+    }
+
+    pub async fn get_one(
+        client: &impl GenericClient,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Self, AppError> {
+        client
+            .query_one(
+                "SELECT * FROM api_tokens WHERE id = $1 AND user_id = $2",
+                &[&id, &user_id],
+            )
+            .await
+            .map_err(AppError::RecordNotFound)?
+            .try_into()
+    }
+
+    pub async fn get_all(
+        client: &impl GenericClient,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT * FROM api_tokens WHERE user_id = $1 ORDER BY created_at",
+                &[&user_id],
+            )
+            .await?;
+
+        let mut tokens = Vec::with_capacity(rows.len());
+        for row in rows {
+            tokens.push(row.try_into()?);
+        }
+
+        Ok(tokens)
+    }
+
+    pub async fn delete(&self, client: &impl GenericClient) -> Result<(), AppError> {
+        client
+            .execute(
+                "DELETE FROM api_tokens WHERE id = $1 AND user_id = $2",
+                &[&self.id, &self.user_id],
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApiToken, TokenScope};
+    use crate::test_utils::state_for_tests;
+
+    #[tokio::test]
+    async fn test_authenticate_by_secret() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let (token, secret) = ApiToken::create(&client, user_extension.id, "cli", TokenScope::Read)
+            .await
+            .unwrap();
+        assert!(secret.starts_with("sb_"));
+        assert_ne!(token.token_hash, secret);
+        assert_eq!(token.last_used_at, None);
+
+        let authenticated = ApiToken::authenticate(&client, &secret).await.unwrap();
+        assert_eq!(authenticated.id, token.id);
+        assert!(authenticated.last_used_at.is_some());
+        assert!(ApiToken::authenticate(&client, "sb_wrong").await.is_err());
+
+        token.delete(&client).await.unwrap();
+        assert!(ApiToken::authenticate(&client, &secret).await.is_err());
+    }
+}
@@ -0,0 +1,668 @@
+use crate::{errors::AppError, models::user::Preferences};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use postgres_types::Json;
+use rust_database_common::GenericClient;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Bump whenever the archived tables or columns change, and teach `restore` to read the
+/// versions before it.
+pub const ARCHIVE_VERSION: u64 = 2;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Column {
+    Uuid,
+    Text,
+    /// Archived as strings so amounts keep their exact value.
+    Numeric,
+    Boolean,
+    Integer,
+    IntegerArray,
+    Date,
+    Timestamp,
+    Enum(&'static [&'static str]),
+    /// The `id` of a row in another archived table.
+    Reference(&'static str),
+}
+
+pub struct Table {
+    pub name: &'static str,
+    /// Every column except `user_id`, as `(name, type, nullable)`.
+    pub columns: &'static [(&'static str, Column, bool)],
+}
+
+const RECURRENCE: Column = Column::Enum(&[
+    "Daily",
+    "Weekly",
+    "Monthly",
+    "Quarterly",
+    "Yearly",
+    "Never",
+    "EveryDays",
+    "EveryWeeks",
+    "EveryMonths",
+    "SemiMonthly",
+    "LastDayOfMonth",
+]);
+const ENVELOPE_POLICY: Column = Column::Enum(&["Append", "Replace", "CreateNew"]);
+const INCOME_RECURRENCE: Column = Column::Enum(&[
+    "Once",
+    "Weekly",
+    "Biweekly",
+    "SemiMonthly",
+    "Monthly",
+    "Quarterly",
+    "Yearly",
+]);
+const CONTRIBUTION_SOURCE: Column =
+    Column::Enum(&["Accumulation", "Acceleration", "Manual", "Reset"]);
+
+/// The tables holding a user's data, ordered so that referenced rows are restored first.
+pub const TABLES: &[Table] = &[
+    Table {
+        name: "accounts",
+        columns: &[
+            ("id", Column::Uuid, false),
+            ("name", Column::Text, false),
+            ("amount", Column::Numeric, false),
+            ("debt", Column::Boolean, false),
+            ("apr", Column::Numeric, true),
+            ("minimum_payment", Column::Numeric, true),
+            ("account_number", Column::Text, true),
+        ],
+    },
+    Table {
+        name: "envelopes",
+        columns: &[
+            ("id", Column::Uuid, false),
+            ("name", Column::Text, false),
+            ("amount", Column::Numeric, false),
+        ],
+    },
+    Table {
+        name: "goals",
+        columns: &[
+            ("id", Column::Uuid, false),
+            ("name", Column::Text, false),
+            ("target", Column::Numeric, false),
+            ("target_date", Column::Timestamp, false),
+            ("recurrence", RECURRENCE, false),
+            ("accumulated_amount", Column::Numeric, false),
+            ("start_date", Column::Timestamp, true),
+            ("envelope_id", Column::Reference("envelopes"), true),
+            ("envelope_policy", ENVELOPE_POLICY, false),
+            ("recurrence_interval", Column::Integer, true),
+            ("recurrence_days", Column::IntegerArray, true),
+            ("priority", Column::Integer, false),
+            ("funding_weight", Column::Numeric, false),
+            ("target_day", Column::Integer, true),
+        ],
+    },
+    Table {
+        name: "income_sources",
+        columns: &[
+            ("id", Column::Uuid, false),
+            ("name", Column::Text, false),
+            ("amount", Column::Numeric, false),
+            ("recurrence", INCOME_RECURRENCE, false),
+            ("next_expected_on", Column::Date, false),
+        ],
+    },
+    Table {
+        name: "transactions",
+        columns: &[
+            ("id", Column::Uuid, false),
+            ("account_id", Column::Reference("accounts"), true),
+            ("envelope_id", Column::Reference("envelopes"), true),
+            ("amount", Column::Numeric, false),
+            ("description", Column::Text, false),
+            ("occurred_on", Column::Date, false),
+            ("adjustment", Column::Boolean, false),
+            ("created_at", Column::Timestamp, false),
+        ],
+    },
+    Table {
+        name: "account_balances",
+        columns: &[
+            ("id", Column::Uuid, false),
+            ("account_id", Column::Reference("accounts"), false),
+            ("amount", Column::Numeric, false),
+            ("debt", Column::Boolean, false),
+            ("recorded_at", Column::Timestamp, false),
+        ],
+    },
+    Table {
+        name: "envelope_transfers",
+        columns: &[
+            ("id", Column::Uuid, false),
+            ("from_envelope_id", Column::Reference("envelopes"), false),
+            ("to_envelope_id", Column::Reference("envelopes"), true),
+            ("amount", Column::Numeric, false),
+            ("created_at", Column::Timestamp, false),
+        ],
+    },
+    Table {
+        name: "goal_contributions",
+        columns: &[
+            ("id", Column::Uuid, false),
+            ("goal_id", Column::Reference("goals"), false),
+            ("source", CONTRIBUTION_SOURCE, false),
+            ("amount", Column::Numeric, false),
+            ("accumulated_amount", Column::Numeric, false),
+            ("created_at", Column::Timestamp, false),
+        ],
+    },
+    Table {
+        name: "dashboard_snapshots",
+        columns: &[
+            ("id", Column::Uuid, false),
+            ("snapshot_date", Column::Date, false),
+            ("per_diem", Column::Numeric, false),
+            ("remaining_total", Column::Numeric, false),
+            ("goals_accumulated_per_day", Column::Numeric, false),
+            ("created_at", Column::Timestamp, false),
+        ],
+    },
+];
+
+/// Everything a user owns, as rows keyed by table name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Archive {
+    pub version: u64,
+    pub exported_at: DateTime<Utc>,
+    pub preferences: Option<Value>,
+    pub tables: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreSummary {
+    pub replaced: bool,
+    pub rows: HashMap<&'static str, usize>,
+}
+
+impl Column {
+    fn schema(&self) -> Value {
+        match self {
+            Column::Uuid | Column::Reference(_) => json!({
+                "type": "string",
+                "pattern": "^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$"
+            }),
+            Column::Text => json!({ "type": "string" }),
+            Column::Numeric => json!({ "type": "string", "pattern": "^-?[0-9]+(\\.[0-9]+)?$" }),
+            Column::Boolean => json!({ "type": "boolean" }),
+            Column::Integer => json!({ "type": "integer" }),
+            Column::IntegerArray => json!({ "type": "array", "items": { "type": "integer" } }),
+            Column::Date => json!({ "type": "string", "format": "date" }),
+            Column::Timestamp => json!({ "type": "string", "minLength": 1 }),
+            Column::Enum(values) => json!({ "enum": values }),
+        }
+    }
+}
+
+/// Brings an archive exported by an earlier version up to `ARCHIVE_VERSION`, so it can be
+/// checked against `schema`. Archives it does not recognise are left for the schema to reject.
+pub fn upgrade(archive: &mut Value) {
+    // Version 2 marks ledger entries that set a balance rather than record money moving.
+    if archive["version"] == json!(1) {
+        if let Some(transactions) = archive
+            .get_mut("tables")
+            .and_then(|tables| tables.get_mut("transactions"))
+            .and_then(Value::as_array_mut)
+        {
+            for transaction in transactions.iter_mut().filter_map(Value::as_object_mut) {
+                let adjustment = transaction.get("description")
+                    == Some(&json!("Balance adjustment"))
+                    && transaction.get("envelope_id").is_none_or(Value::is_null);
+                transaction.insert("adjustment".to_string(), json!(adjustment));
+            }
+        }
+        archive["version"] = json!(2);
+    }
+}
+
+/// Built from `TABLES`, so archives are checked against exactly the columns `export` writes.
+pub fn schema() -> Value {
+    let tables = TABLES
+        .iter()
+        .map(|table| {
+            let properties = table
+                .columns
+                .iter()
+                .map(|(name, column, nullable)| {
+                    let schema = if *nullable {
+                        json!({ "anyOf": [column.schema(), { "type": "null" }] })
+                    } else {
+                        column.schema()
+                    };
+                    (name.to_string(), schema)
+                })
+                .collect::<Map<_, _>>();
+            // Nullable columns may be missing, so archives exported before one was added
+            // still restore.
+            let required = table
+                .columns
+                .iter()
+                .filter(|(_, _, nullable)| !nullable)
+                .map(|(name, _, _)| *name)
+                .collect::<Vec<_>>();
+
+            (
+                table.name.to_string(),
+                json!({
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": properties,
+                        "required": required,
+                        "additionalProperties": false
+                    }
+                }),
+            )
+        })
+        .collect::<Map<_, _>>();
+    let table_names = TABLES.iter().map(|table| table.name).collect::<Vec<_>>();
+
+    json!({
+        "type": "object",
+        "properties": {
+            "version": { "const": ARCHIVE_VERSION },
+            "exported_at": { "type": "string", "minLength": 1 },
+            "preferences": { "anyOf": [{ "type": "object" }, { "type": "null" }] },
+            "tables": {
+                "type": "object",
+                "properties": tables,
+                "required": table_names,
+                "additionalProperties": false
+            }
+        },
+        "required": ["version", "exported_at", "preferences", "tables"],
+        "additionalProperties": false
+    })
+}
+
+pub async fn export(client: &impl GenericClient, user_id: Uuid) -> Result<Archive, AppError> {
+    let preferences: Option<Value> = client
+        .query_one("SELECT preferences FROM users WHERE id = $1", &[&user_id])
+        .await
+        .map_err(AppError::RecordNotFound)?
+        .try_get("preferences")
+        .map_err(AppError::RecordDeserializationError)?;
+
+    let mut tables = Map::new();
+    for table in TABLES {
+        let columns = table
+            .columns
+            .iter()
+            .map(|(name, column, _)| match column {
+                Column::Numeric => format!("{name}::text AS {name}"),
+                _ => name.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let rows: Value = client
+            .query_one(
+                &format!(
+                    "SELECT coalesce(jsonb_agg(to_jsonb(archived)), '[]') AS rows FROM (
+                        SELECT {columns} FROM {} WHERE user_id = $1 ORDER BY id
+                    ) archived",
+                    table.name
+                ),
+                &[&user_id],
+            )
+            .await?
+            .try_get("rows")
+            .map_err(AppError::RecordDeserializationError)?;
+
+        tables.insert(table.name.to_string(), rows);
+    }
+
+    Ok(Archive {
+        version: ARCHIVE_VERSION,
+        exported_at: Utc::now(),
+        preferences,
+        tables,
+    })
+}
+
+/// Gives every archived row a new ID, pointing references at the new IDs, so an archive can
+/// be restored next to the data it was exported from.
+fn remap(tables: &mut Map<String, Value>, user_id: Uuid) -> Result<(), AppError> {
+    let mut ids: HashMap<&str, HashMap<String, Value>> = HashMap::new();
+
+    for table in TABLES {
+        let mut table_ids = HashMap::new();
+        let rows = tables
+            .get_mut(table.name)
+            .and_then(Value::as_array_mut)
+            .ok_or_else(|| AppError::InvalidRecord(anyhow!("missing {}", table.name)))?;
+
+        for row in rows.iter_mut() {
+            let row = row
+                .as_object_mut()
+                .ok_or_else(|| AppError::InvalidRecord(anyhow!("invalid {} row", table.name)))?;
+
+            for (name, column, _) in table.columns {
+                let Some(value) = row.get_mut(*name) else {
+                    continue;
+                };
+                match column {
+                    Column::Uuid => {
+                        let id = json!(Uuid::new_v4());
+                        let old = std::mem::replace(value, id.clone());
+                        if table_ids.insert(old.to_string(), id).is_some() {
+                            return Err(AppError::InvalidRecord(anyhow!(
+                                "{} has a repeated ID",
+                                table.name
+                            )));
+                        }
+                    }
+                    Column::Reference(referenced) if !value.is_null() => {
+                        *value = ids
+                            .get(referenced)
+                            .and_then(|referenced| referenced.get(&value.to_string()))
+                            .cloned()
+                            .ok_or_else(|| {
+                                AppError::InvalidRecord(anyhow!(
+                                    "{} refers to a missing row in {}",
+                                    table.name,
+                                    referenced
+                                ))
+                            })?;
+                    }
+                    _ => {}
+                }
+            }
+            row.insert("user_id".to_string(), json!(user_id));
+        }
+
+        ids.insert(table.name, table_ids);
+    }
+
+    Ok(())
+}
+
+/// The schema only checks that archived preferences are an object, and every page reads them
+/// back, so they must be preferences this version understands.
+fn preferences(preferences: Option<Value>) -> Result<Option<Json<Preferences>>, AppError> {
+    let Some(preferences) = preferences else {
+        return Ok(None);
+    };
+    let preferences: Preferences = serde_json::from_value(preferences)
+        .map_err(|e| AppError::InvalidRecord(anyhow!("invalid preferences: {e}")))?;
+
+    if let Some(timezone) = &preferences.timezone {
+        timezone
+            .parse::<Tz>()
+            .map_err(|e| AppError::InvalidRecord(anyhow!("invalid preferences: {e}")))?;
+    }
+
+    Ok(Some(Json(preferences)))
+}
+
+/// Restores a validated archive into the user's data, after deleting it all when `replace`
+/// is set. Preferences are only restored when replacing. Run inside a database transaction.
+pub async fn restore(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    mut archive: Archive,
+    replace: bool,
+) -> Result<RestoreSummary, AppError> {
+    remap(&mut archive.tables, user_id)?;
+    let preferences = preferences(archive.preferences.take())?;
+
+    if replace {
+        for table in TABLES.iter().rev() {
+            client
+                .execute(
+                    &format!("DELETE FROM {} WHERE user_id = $1", table.name),
+                    &[&user_id],
+                )
+                .await?;
+        }
+        client
+            .execute(
+                "UPDATE users SET preferences = $1 WHERE id = $2",
+                &[&preferences, &user_id],
+            )
+            .await?;
+    } else if let Some(accounts) = archive
+        .tables
+        .get_mut("accounts")
+        .and_then(Value::as_array_mut)
+    {
+        // Account numbers are unique per user, so keep the existing account's.
+        let taken = client
+            .query(
+                "SELECT account_number FROM accounts WHERE user_id = $1 AND account_number IS NOT NULL",
+                &[&user_id],
+            )
+            .await?
+            .into_iter()
+            .map(|row| row.try_get::<_, String>("account_number"))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(AppError::RecordDeserializationError)?;
+
+        for account in accounts.iter_mut() {
+            if let Some(account_number) = account.get_mut("account_number")
+                && account_number
+                    .as_str()
+                    .is_some_and(|account_number| taken.iter().any(|taken| taken == account_number))
+            {
+                *account_number = Value::Null;
+            }
+        }
+    }
+
+    let mut rows = HashMap::new();
+    for table in TABLES {
+        let archived = archive.tables.remove(table.name).unwrap_or(json!([]));
+        // Dashboards are snapshotted once a day; keep the existing snapshot for a day.
+        let on_conflict = if table.name == "dashboard_snapshots" {
+            "ON CONFLICT DO NOTHING"
+        } else {
+            ""
+        };
+        let restored = client
+            .execute(
+                &format!(
+                    "INSERT INTO {table} SELECT * FROM jsonb_populate_recordset(NULL::{table}, $1) {on_conflict}",
+                    table = table.name
+                ),
+                &[&archived],
+            )
+            .await?;
+
+        rows.insert(table.name, restored as usize);
+    }
+
+    Ok(RestoreSummary {
+        replaced: replace,
+        rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ARCHIVE_VERSION, Column, TABLES, export, restore, schema, upgrade};
+    use crate::{
+        errors::AppError,
+        models::{account::Account, envelope::Envelope, goal::Goal, transaction::Transaction},
+        test_utils::state_for_tests,
+    };
+    use chrono::NaiveDate;
+    use rust_database_common::GenericClient;
+    use rust_decimal::Decimal;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_tables_match_database() {
+        let (shared_state, _user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        for table in TABLES {
+            let rows = client
+                .query(
+                    "SELECT column_name, is_nullable FROM information_schema.columns
+                    WHERE table_schema = 'public' AND table_name = $1 AND column_name <> 'user_id'
+                    ORDER BY column_name",
+                    &[&table.name],
+                )
+                .await
+                .unwrap();
+            let mut database = rows
+                .iter()
+                .map(|row| {
+                    (
+                        row.get::<_, String>("column_name"),
+                        row.get::<_, String>("is_nullable") == "YES",
+                    )
+                })
+                .collect::<Vec<_>>();
+            let mut archived = table
+                .columns
+                .iter()
+                .map(|(name, _, nullable)| (name.to_string(), *nullable))
+                .collect::<Vec<_>>();
+            database.sort();
+            archived.sort();
+
+            assert_eq!(database, archived, "columns of {}", table.name);
+
+            for (name, column, _) in table.columns {
+                let Column::Enum(values) = column else {
+                    continue;
+                };
+                let labels = client
+                    .query(
+                        "SELECT enumlabel::text AS label FROM pg_enum
+                        INNER JOIN pg_type ON pg_type.oid = pg_enum.enumtypid
+                        INNER JOIN information_schema.columns ON columns.udt_name = pg_type.typname
+                        WHERE columns.table_name = $1 AND columns.column_name = $2
+                        ORDER BY enumsortorder",
+                        &[&table.name, name],
+                    )
+                    .await
+                    .unwrap()
+                    .iter()
+                    .map(|row| row.get::<_, String>("label"))
+                    .collect::<Vec<_>>();
+
+                assert_eq!(&labels, values, "values of {}.{name}", table.name);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_and_restore() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let mut client = shared_state.pool.get_client().await.unwrap();
+        let client = client.transaction().await.unwrap();
+
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(100, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: Some("1234".to_string()),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        let envelope = Envelope {
+            id: None,
+            user_id,
+            name: "groceries".to_string(),
+            amount: Decimal::new(5, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        Transaction {
+            id: None,
+            user_id,
+            account_id: account.id,
+            envelope_id: envelope.id,
+            amount: Decimal::new(-1234567, 5),
+            description: "market".to_string(),
+            occurred_on: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            adjustment: false,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let archive = export(&client, user_id).await.unwrap();
+        assert_eq!(archive.version, ARCHIVE_VERSION);
+        jsonschema::validate(&schema(), &serde_json::to_value(&archive).unwrap()).unwrap();
+
+        let mut earlier = serde_json::to_value(&archive).unwrap();
+        earlier["version"] = json!(1);
+        earlier["tables"]["transactions"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("adjustment");
+        upgrade(&mut earlier);
+        jsonschema::validate(&schema(), &earlier).unwrap();
+        assert_eq!(earlier["tables"]["transactions"][0]["adjustment"], false);
+
+        let summary = restore(&client, user_id, archive.clone(), false)
+            .await
+            .unwrap();
+        assert_eq!(summary.rows["transactions"], 1);
+
+        let accounts = Account::get_all(&client, user_id).await.unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(
+            accounts
+                .iter()
+                .filter(|account| account.account_number.is_some())
+                .count(),
+            1
+        );
+        let transactions = Transaction::get_all(&client, user_id).await.unwrap();
+        assert_eq!(transactions.len(), 2);
+        assert!(
+            transactions
+                .iter()
+                .all(|transaction| transaction.amount == Decimal::new(-1234567, 5))
+        );
+        assert_ne!(transactions[0].account_id, transactions[1].account_id);
+
+        for preferences in [
+            json!({ "goal_header": "nonsense" }),
+            json!({ "timezone": "Mars" }),
+        ] {
+            let mut archive = archive.clone();
+            archive.preferences = Some(preferences);
+            assert!(matches!(
+                restore(&client, user_id, archive, true).await,
+                Err(AppError::InvalidRecord(_))
+            ));
+        }
+
+        restore(&client, user_id, archive, true).await.unwrap();
+        let accounts = Account::get_all(&client, user_id).await.unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].amount, Decimal::new(8765433, 5));
+        assert_eq!(accounts[0].account_number.as_deref(), Some("1234"));
+        assert!(Goal::get_all(&client, user_id).await.unwrap().is_empty());
+
+        let count: i64 = client
+            .query_one(
+                "SELECT count(*) FROM transactions WHERE user_id = $1",
+                &[&user_id],
+            )
+            .await
+            .unwrap()
+            .get(0);
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,337 @@
+use crate::models::goal::{Goal, Recurrence};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+use chrono_tz::Tz;
+
+fn list(days: impl Iterator<Item = u32>) -> String {
+    days.map(|day| day.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Lands on `day`, or on the last day of months shorter than that the way `Recurrence` clamps:
+/// the last of the days from 28 through `day` that the month has.
+fn month_day(day: u32) -> String {
+    if day > 28 {
+        format!(";BYMONTHDAY={};BYSETPOS=-1", list(28..=day))
+    } else {
+        String::new()
+    }
+}
+
+fn interval(count: u32) -> String {
+    if count > 1 {
+        format!(";INTERVAL={count}")
+    } else {
+        String::new()
+    }
+}
+
+/// The RRULE for a goal whose current target falls on `start`, or `None` for goals that do
+/// not recur. Month-based rules land on `day`, which a short month may have clamped `start`
+/// below.
+pub fn rrule(recurrence: &Recurrence, start: NaiveDate, day: u32) -> Option<String> {
+    Some(match recurrence {
+        Recurrence::Never => return None,
+        Recurrence::Daily => "FREQ=DAILY".to_string(),
+        Recurrence::EveryDays(count) => format!("FREQ=DAILY{}", interval(*count)),
+        Recurrence::Weekly => "FREQ=WEEKLY".to_string(),
+        Recurrence::EveryWeeks(count) => format!("FREQ=WEEKLY{}", interval(*count)),
+        Recurrence::Monthly => format!("FREQ=MONTHLY{}", month_day(day)),
+        Recurrence::Quarterly => format!("FREQ=MONTHLY;INTERVAL=3{}", month_day(day)),
+        Recurrence::EveryMonths(count) => {
+            format!("FREQ=MONTHLY{}{}", interval(*count), month_day(day))
+        }
+        Recurrence::Yearly if day > 28 => {
+            format!("FREQ=YEARLY;BYMONTH={}{}", start.month(), month_day(day))
+        }
+        Recurrence::Yearly => "FREQ=YEARLY".to_string(),
+        Recurrence::LastDayOfMonth => "FREQ=MONTHLY;BYMONTHDAY=-1".to_string(),
+        Recurrence::SemiMonthly(first, second) => {
+            let (first, second) = (*first.min(second), *first.max(second));
+            if first <= 28 && second > 28 {
+                let days = std::iter::once(first).chain((28..=second).filter(|day| *day != first));
+                format!("FREQ=MONTHLY;BYMONTHDAY={};BYSETPOS=1,-1", list(days))
+            } else {
+                format!("FREQ=MONTHLY;BYMONTHDAY={first},{second}")
+            }
+        }
+    })
+}
+
+/// Escapes commas, semicolons, backslashes and newlines in a text value.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace(['\r', '\n'], "\\n")
+}
+
+/// Folds a content line so that no line is longer than 75 octets.
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / 74 * 3);
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+
+    folded
+}
+
+/// An iCalendar feed with an all-day event on each goal's target date in `timezone`. Recurring
+/// goals roll over to their next target after that date, so their events repeat.
+pub fn goals_calendar(goals: &[Goal], timezone: Tz, now: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Simple Budget//Goals//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        "X-WR-CALNAME:Goals".to_string(),
+    ];
+
+    for goal in goals {
+        let Some(id) = goal.id else {
+            continue;
+        };
+        let start = goal.target_date.with_timezone(&timezone).date_naive();
+        // Recurrences step in UTC, so the target day only holds where the dates agree.
+        let day = if start == goal.target_date.date_naive() {
+            goal.target_day_of_month()
+        } else {
+            start.day()
+        };
+        let description = match goal.recurrence {
+            Recurrence::Never => format!("Target {:.2}.", goal.target),
+            _ => format!(
+                "Target {:.2}. Rolls over to the next target after this date.",
+                goal.target
+            ),
+        };
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{id}@simple-budget"));
+        lines.push(format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")));
+        lines.push(format!("DTSTART;VALUE=DATE:{}", start.format("%Y%m%d")));
+        if let Some(rrule) = rrule(&goal.recurrence, start, day) {
+            lines.push(format!("RRULE:{rrule}"));
+        }
+        lines.push(format!("SUMMARY:{}", escape(&goal.name)));
+        lines.push(format!("DESCRIPTION:{}", escape(&description)));
+        lines.push("TRANSP:TRANSPARENT".to_string());
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold(line)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{goals_calendar, rrule};
+    use crate::models::goal::{EnvelopePolicy, Goal, Recurrence};
+    use chrono::{Datelike, Months, NaiveDate, NaiveTime, TimeZone, Utc};
+    use chrono_tz::Tz;
+    use rust_decimal::Decimal;
+    use std::{collections::HashMap, str::FromStr};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_rrule_for_each_recurrence() {
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+
+        assert_eq!(rrule(&Recurrence::Never, date(15), 15), None);
+        for (recurrence, day, expected) in [
+            (Recurrence::Daily, 15, "FREQ=DAILY"),
+            (Recurrence::EveryDays(10), 15, "FREQ=DAILY;INTERVAL=10"),
+            (Recurrence::EveryWeeks(2), 15, "FREQ=WEEKLY;INTERVAL=2"),
+            (Recurrence::Monthly, 15, "FREQ=MONTHLY"),
+            (
+                Recurrence::Monthly,
+                31,
+                "FREQ=MONTHLY;BYMONTHDAY=28,29,30,31;BYSETPOS=-1",
+            ),
+            (
+                Recurrence::Quarterly,
+                30,
+                "FREQ=MONTHLY;INTERVAL=3;BYMONTHDAY=28,29,30;BYSETPOS=-1",
+            ),
+            (Recurrence::EveryMonths(1), 15, "FREQ=MONTHLY"),
+            (
+                Recurrence::Yearly,
+                29,
+                "FREQ=YEARLY;BYMONTH=1;BYMONTHDAY=28,29;BYSETPOS=-1",
+            ),
+            (Recurrence::LastDayOfMonth, 31, "FREQ=MONTHLY;BYMONTHDAY=-1"),
+            (
+                Recurrence::SemiMonthly(15, 1),
+                1,
+                "FREQ=MONTHLY;BYMONTHDAY=1,15",
+            ),
+            (
+                Recurrence::SemiMonthly(15, 31),
+                15,
+                "FREQ=MONTHLY;BYMONTHDAY=15,28,29,30,31;BYSETPOS=1,-1",
+            ),
+            (
+                Recurrence::SemiMonthly(28, 30),
+                28,
+                "FREQ=MONTHLY;BYMONTHDAY=28,29,30;BYSETPOS=1,-1",
+            ),
+        ] {
+            assert_eq!(
+                rrule(&recurrence, date(day), day).as_deref(),
+                Some(expected),
+                "{recurrence:?}"
+            );
+        }
+    }
+
+    /// The dates an RRULE of the kind `rrule` writes lands on from `start`, up to `count` of
+    /// them, following RFC 5545: without BYMONTHDAY, months lacking `start`'s day are skipped.
+    fn occurrences(rrule: &str, start: NaiveDate, count: usize) -> Vec<NaiveDate> {
+        let parts = rrule
+            .split(';')
+            .filter_map(|part| part.split_once('='))
+            .collect::<HashMap<_, _>>();
+        let months = match parts["FREQ"] {
+            "MONTHLY" => 1,
+            "YEARLY" => 12,
+            freq => panic!("unexpected FREQ={freq}"),
+        } * parts
+            .get("INTERVAL")
+            .map_or(1, |interval| interval.parse().unwrap());
+        let month_days = parts
+            .get("BYMONTHDAY")
+            .map_or(vec![start.day() as i32], |days| {
+                days.split(',').map(|day| day.parse().unwrap()).collect()
+            });
+
+        let mut first_of_month = start.with_day(1).unwrap();
+        let mut dates = Vec::new();
+        while dates.len() < count {
+            let days = month_days
+                .iter()
+                .filter_map(|day| match *day {
+                    -1 => (first_of_month + Months::new(1)).pred_opt(),
+                    day => first_of_month.with_day(day as u32),
+                })
+                .collect::<Vec<_>>();
+            let chosen = match parts.get("BYSETPOS") {
+                Some(&"-1") => days.last().into_iter().copied().collect(),
+                _ => days,
+            };
+            dates.extend(chosen.into_iter().filter(|date| *date >= start));
+            first_of_month = first_of_month + Months::new(months);
+        }
+        dates.truncate(count);
+
+        dates
+    }
+
+    #[test]
+    fn test_rrule_agrees_with_recurrence() {
+        for (recurrence, target_date, count) in [
+            (Recurrence::Monthly, "2024-01-31", 13),
+            (Recurrence::Monthly, "2024-01-30", 13),
+            (Recurrence::Monthly, "2024-01-15", 13),
+            (Recurrence::Quarterly, "2023-11-30", 5),
+            (Recurrence::EveryMonths(2), "2023-12-31", 7),
+            (Recurrence::Yearly, "2024-02-29", 5),
+            (Recurrence::LastDayOfMonth, "2024-01-31", 13),
+        ] {
+            let mut goal = Goal {
+                id: None,
+                user_id: Uuid::nil(),
+                name: "rent".to_string(),
+                recurrence: recurrence.clone(),
+                target_date: NaiveDate::from_str(target_date)
+                    .unwrap()
+                    .and_time(NaiveTime::MIN)
+                    .and_utc(),
+                target: Decimal::ONE,
+                accumulated_amount: Decimal::ZERO,
+                start_date: None,
+                envelope_id: None,
+                envelope_policy: EnvelopePolicy::Append,
+                priority: 0,
+                funding_weight: Decimal::ONE,
+                target_day: None,
+            };
+            let start = goal.target_date.date_naive();
+            let mut stepped = vec![start];
+            while stepped.len() < count {
+                goal = goal.increment().unwrap();
+                stepped.push(goal.target_date.date_naive());
+            }
+
+            let rule = rrule(&recurrence, start, goal.target_day_of_month()).unwrap();
+            assert_eq!(
+                occurrences(&rule, start, count),
+                stepped,
+                "{recurrence:?} from {target_date}"
+            );
+
+            // Picking the calendar up again from a clamped target keeps to the same dates.
+            let restart = stepped[1];
+            let rule = rrule(&recurrence, restart, goal.target_day_of_month()).unwrap();
+            assert_eq!(
+                occurrences(&rule, restart, count - 1),
+                stepped[1..],
+                "{recurrence:?} from {restart}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_goals_calendar() {
+        let goal = |id, name: &str, recurrence| Goal {
+            id: Some(Uuid::from_u128(id)),
+            user_id: Uuid::from_u128(1),
+            name: name.to_string(),
+            recurrence,
+            target_date: Utc.with_ymd_and_hms(2024, 4, 1, 3, 59, 59).unwrap(),
+            target: Decimal::new(50000, 2),
+            accumulated_amount: Decimal::ZERO,
+            start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        };
+        let goals = [
+            goal(2, "Car insurance, renewal; annual", Recurrence::Yearly),
+            goal(3, &"Long name ".repeat(10), Recurrence::Never),
+        ];
+        let timezone: Tz = "America/New_York".parse().unwrap();
+        let now = Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap();
+
+        let calendar = goals_calendar(&goals, timezone, now);
+
+        assert!(calendar.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(calendar.ends_with("END:VEVENT\r\nEND:VCALENDAR\r\n"));
+        assert!(calendar.contains(
+            "UID:00000000-0000-0000-0000-000000000002@simple-budget\r\n\
+            DTSTAMP:20240301T120000Z\r\n\
+            DTSTART;VALUE=DATE:20240331\r\n\
+            RRULE:FREQ=YEARLY;BYMONTH=3;BYMONTHDAY=28,29,30,31;BYSETPOS=-1\r\n\
+            SUMMARY:Car insurance\\, renewal\\; annual\r\n\
+            DESCRIPTION:Target 500.00. Rolls over to the next target after this date.\r\n"
+        ));
+        assert_eq!(calendar.matches("RRULE:").count(), 1);
+        assert!(calendar.lines().all(|line| line.len() <= 76));
+        assert!(
+            calendar
+                .replace("\r\n ", "")
+                .contains(&format!("SUMMARY:{}\r\n", "Long name ".repeat(10)))
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{
+    errors::AppError,
+    models::{goal::Goal, user::User},
+    utilities::dates::{TimeUtilities, Times},
+};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use rust_database_common::GenericClient;
+use rust_decimal::{Decimal, prelude::FromPrimitive};
+use serde::Serialize;
+use uuid::Uuid;
+
+/// The dashboard's headline numbers for a user, as of one day in their timezone.
+#[derive(Serialize, Debug, Clone)]
+pub struct DashboardSnapshot {
+    pub id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub snapshot_date: NaiveDate,
+    pub per_diem: Decimal,
+    pub remaining_total: Decimal,
+    pub goals_accumulated_per_day: Decimal,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+impl TryInto<DashboardSnapshot> for tokio_postgres::Row {
+    type Error = AppError;
+
+    fn try_into(self: tokio_postgres::Row) -> Result<DashboardSnapshot, AppError> {
+        Ok(DashboardSnapshot {
+            id: self
+                .try_get("id")
+                .map_err(AppError::RecordDeserializationError)?,
+            user_id: self
+                .try_get("user_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            snapshot_date: self
+                .try_get("snapshot_date")
+                .map_err(AppError::RecordDeserializationError)?,
+            per_diem: self
+                .try_get("per_diem")
+                .map_err(AppError::RecordDeserializationError)?,
+            remaining_total: self
+                .try_get("remaining_total")
+                .map_err(AppError::RecordDeserializationError)?,
+            goals_accumulated_per_day: self
+                .try_get("goals_accumulated_per_day")
+                .map_err(AppError::RecordDeserializationError)?,
+            created_at: self
+                .try_get("created_at")
+                .map_err(AppError::RecordDeserializationError)?,
+        })
+    }
+}
+
+impl DashboardSnapshot {
+    /// Computes the numbers the dashboard shows at `time_provider`'s now.
+    pub async fn measure(
+        client: &impl GenericClient,
+        user: &User,
+        time_provider: &impl Times,
+    ) -> Result<Self, AppError> {
+        let timezone: Tz = user.timezone()?.parse()?;
+        let time_utilities = TimeUtilities {
+            timezone,
+            period: user.budget_period(),
+        };
+
+        let goals_accumulated_per_day = Goal::get_all(client, user.id)
+            .await?
+            .iter()
+            .map(|goal| goal.accumulated_per_day())
+            .sum::<Result<Decimal>>()?;
+        let remaining_total = user.total_balance(client).await?;
+        let remaining_seconds = Decimal::from_i64(
+            time_utilities
+                .remaining_length_of_period(time_provider)?
+                .num_seconds(),
+        )
+        .ok_or(anyhow!("could not parse decimal"))?;
+        let per_diem = if remaining_seconds > Decimal::ZERO {
+            remaining_total / remaining_seconds * Decimal::new(86400, 0)
+        } else {
+            remaining_total
+        };
+
+        Ok(Self {
+            id: None,
+            user_id: user.id,
+            snapshot_date: time_provider.now().with_timezone(&timezone).date_naive(),
+            per_diem,
+            remaining_total,
+            goals_accumulated_per_day,
+            created_at: None,
+        })
+    }
+
+    /// Saves the snapshot unless the user already has one for that day.
+    pub async fn create(&self, client: &impl GenericClient) -> Result<Option<Self>, AppError> {
+        let row = client
+            .query_opt(
+                "INSERT INTO dashboard_snapshots (
+                    user_id
+                    , snapshot_date
+                    , per_diem
+                    , remaining_total
+                    , goals_accumulated_per_day
+                ) VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT (user_id, snapshot_date) DO NOTHING
+                RETURNING *",
+                &[
+                    &self.user_id,
+                    &self.snapshot_date,
+                    &self.per_diem,
+                    &self.remaining_total,
+                    &self.goals_accumulated_per_day,
+                ],
+            )
+            .await?;
+
+        row.map(|row| row.try_into()).transpose()
+    }
+
+    pub async fn exists_for(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        snapshot_date: NaiveDate,
+    ) -> Result<bool, AppError> {
+        let row = client
+            .query_opt(
+                "SELECT 1 FROM dashboard_snapshots WHERE user_id = $1 AND snapshot_date = $2",
+                &[&user_id, &snapshot_date],
+            )
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    pub async fn get_all(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        since: NaiveDate,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT dashboard_snapshots.* FROM dashboard_snapshots
+                WHERE user_id = $1 AND snapshot_date >= $2
+                ORDER BY snapshot_date",
+                &[&user_id, &since],
+            )
+            .await?;
+
+        let mut snapshots = Vec::with_capacity(rows.len());
+        for row in rows {
+            snapshots.push(row.try_into()?);
+        }
+
+        Ok(snapshots)
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{errors::AppError, models::account::Account};
+use anyhow::anyhow;
+use chrono::{Months, NaiveDate};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Plans give up on debts that are not paid off within this many months.
+const MAXIMUM_MONTHS: u32 = 600;
+
+/// More than anyone pays toward their debts each month, and small enough to plan with.
+const MAXIMUM_EXTRA: Decimal = Decimal::from_parts(1_000_000_000, 0, 0, false, 0);
+
+/// Refuses extra monthly payments that are negative or too large to plan with.
+pub fn check_extra(extra: Decimal) -> Result<Decimal, AppError> {
+    if extra < Decimal::ZERO {
+        return Err(AppError::InvalidRecord(anyhow!(
+            "extra payment cannot be negative"
+        )));
+    }
+    if extra > MAXIMUM_EXTRA {
+        return Err(AppError::InvalidRecord(anyhow!(
+            "extra payment cannot be more than {}",
+            MAXIMUM_EXTRA
+        )));
+    }
+
+    Ok(extra)
+}
+
+/// Debts that grow for long enough outgrow `Decimal`; there is no plan for those.
+fn checked(value: Option<Decimal>) -> Result<Decimal, AppError> {
+    value.ok_or_else(|| AppError::InvalidRecord(anyhow!("debts are too large to plan")))
+}
+
+/// Which debt gets the money left over after minimum payments: the highest APR first
+/// (avalanche) or the smallest balance first (snowball).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayoffStrategy {
+    Avalanche,
+    Snowball,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DebtPayoff {
+    pub account_id: Option<Uuid>,
+    pub name: String,
+    pub balance: Decimal,
+    /// `None` when the payments never clear the debt.
+    pub paid_off_on: Option<NaiveDate>,
+    pub months: Option<u32>,
+    pub interest: Decimal,
+    pub total_paid: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DebtPlan {
+    pub strategy: PayoffStrategy,
+    pub monthly_payment: Decimal,
+    /// In the order the debts are paid off.
+    pub payoffs: Vec<DebtPayoff>,
+    pub total_interest: Decimal,
+    pub paid_off_on: Option<NaiveDate>,
+    pub months: Option<u32>,
+}
+
+/// Simulates paying `debts` monthly from `start`, accruing a month of interest and then
+/// paying every minimum before `extra` and any freed-up minimums go to the strategy's target.
+pub fn plan(
+    debts: &[Account],
+    extra: Decimal,
+    start: NaiveDate,
+    strategy: PayoffStrategy,
+) -> Result<DebtPlan, AppError> {
+    let debts = debts
+        .iter()
+        .filter(|account| account.debt && account.amount > Decimal::ZERO)
+        .collect::<Vec<_>>();
+    let monthly_rates = debts
+        .iter()
+        .map(|account| account.apr.unwrap_or_default() / Decimal::new(1200, 0))
+        .collect::<Vec<_>>();
+    let minimums = debts
+        .iter()
+        .map(|account| account.minimum_payment.unwrap_or_default())
+        .collect::<Vec<_>>();
+    let monthly_payment = checked(
+        minimums
+            .iter()
+            .try_fold(extra, |total, minimum| total.checked_add(*minimum)),
+    )?;
+
+    let mut balances = debts
+        .iter()
+        .map(|account| account.amount)
+        .collect::<Vec<_>>();
+    let mut payoffs = debts
+        .iter()
+        .map(|account| DebtPayoff {
+            account_id: account.id,
+            name: account.name.clone(),
+            balance: account.amount,
+            paid_off_on: None,
+            months: None,
+            interest: Decimal::ZERO,
+            total_paid: Decimal::ZERO,
+        })
+        .collect::<Vec<_>>();
+
+    for month in 1..=MAXIMUM_MONTHS {
+        let open = (0..debts.len())
+            .filter(|i| balances[*i] > Decimal::ZERO)
+            .collect::<Vec<_>>();
+        if open.is_empty() {
+            break;
+        }
+
+        for i in &open {
+            let interest = checked(balances[*i].checked_mul(monthly_rates[*i]))?.round_dp(2);
+            balances[*i] = checked(balances[*i].checked_add(interest))?;
+            payoffs[*i].interest = checked(payoffs[*i].interest.checked_add(interest))?;
+        }
+
+        let mut remaining = monthly_payment;
+        let mut pay = |i: usize, amount: Decimal, balances: &mut Vec<Decimal>| {
+            let amount = amount.min(balances[i]).min(remaining);
+            balances[i] -= amount;
+            payoffs[i].total_paid += amount;
+            remaining -= amount;
+        };
+
+        for i in &open {
+            pay(*i, minimums[*i], &mut balances);
+        }
+
+        let mut targets = open.clone();
+        targets.sort_by(|a, b| match strategy {
+            PayoffStrategy::Avalanche => monthly_rates[*b]
+                .cmp(&monthly_rates[*a])
+                .then(balances[*a].cmp(&balances[*b])),
+            PayoffStrategy::Snowball => balances[*a]
+                .cmp(&balances[*b])
+                .then(monthly_rates[*b].cmp(&monthly_rates[*a])),
+        });
+        for i in targets {
+            pay(i, Decimal::MAX, &mut balances);
+        }
+
+        let paid_on = start
+            .checked_add_months(Months::new(month))
+            .ok_or_else(|| AppError::Unknown(anyhow!("could not add months")))?;
+        for i in open {
+            if balances[i] == Decimal::ZERO {
+                payoffs[i].paid_off_on = Some(paid_on);
+                payoffs[i].months = Some(month);
+            }
+        }
+    }
+
+    payoffs.sort_by_key(|payoff| payoff.months.unwrap_or(u32::MAX));
+    let finished = payoffs.iter().all(|payoff| payoff.months.is_some());
+
+    let total_interest = checked(payoffs.iter().try_fold(Decimal::ZERO, |total, payoff| {
+        total.checked_add(payoff.interest)
+    }))?;
+
+    Ok(DebtPlan {
+        strategy,
+        monthly_payment,
+        total_interest,
+        paid_off_on: payoffs
+            .last()
+            .and_then(|payoff| payoff.paid_off_on)
+            .filter(|_| finished),
+        months: payoffs
+            .last()
+            .and_then(|payoff| payoff.months)
+            .filter(|_| finished),
+        payoffs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PayoffStrategy, plan};
+    use crate::{errors::AppError, models::account::Account};
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    fn debt(name: &str, amount: i64, apr: i64, minimum: i64) -> Account {
+        Account {
+            id: Some(Uuid::new_v4()),
+            user_id: Uuid::nil(),
+            name: name.to_string(),
+            amount: Decimal::new(amount, 0),
+            debt: true,
+            apr: Some(Decimal::new(apr, 0)),
+            minimum_payment: Some(Decimal::new(minimum, 0)),
+            account_number: None,
+        }
+    }
+
+    #[test]
+    fn test_avalanche_and_snowball_order() {
+        let debts = [debt("card", 3000, 24, 60), debt("loan", 1000, 6, 50)];
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        let avalanche = plan(
+            &debts,
+            Decimal::new(200, 0),
+            start,
+            PayoffStrategy::Avalanche,
+        )
+        .unwrap();
+        let snowball = plan(
+            &debts,
+            Decimal::new(200, 0),
+            start,
+            PayoffStrategy::Snowball,
+        )
+        .unwrap();
+
+        assert_eq!(avalanche.payoffs[0].name, "card");
+        assert_eq!(snowball.payoffs[0].name, "loan");
+        assert!(avalanche.total_interest < snowball.total_interest);
+        assert_eq!(avalanche.monthly_payment, Decimal::new(310, 0));
+
+        for plan in [&avalanche, &snowball] {
+            let paid: Decimal = plan.payoffs.iter().map(|payoff| payoff.total_paid).sum();
+            assert_eq!(paid, Decimal::new(4000, 0) + plan.total_interest);
+            assert!(plan.paid_off_on.is_some());
+        }
+    }
+
+    #[test]
+    fn test_payments_below_interest_never_finish() {
+        let debts = [debt("card", 10000, 24, 100)];
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        let plan = plan(&debts, Decimal::ZERO, start, PayoffStrategy::Avalanche).unwrap();
+
+        assert_eq!(plan.paid_off_on, None);
+        assert_eq!(plan.payoffs[0].paid_off_on, None);
+    }
+
+    #[test]
+    fn test_overflowing_debts_are_rejected() {
+        let debts = [debt("card", 1_000_000_000_000, 100, 0)];
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+
+        assert!(matches!(
+            plan(&debts, Decimal::ZERO, start, PayoffStrategy::Avalanche),
+            Err(AppError::InvalidRecord(_))
+        ));
+        assert!(matches!(
+            plan(
+                &[debt("loan", 1000, 6, 50)],
+                Decimal::MAX,
+                start,
+                PayoffStrategy::Avalanche
+            ),
+            Err(AppError::InvalidRecord(_))
+        ));
+    }
+}
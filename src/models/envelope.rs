@@ -1,8 +1,10 @@
-use crate::errors::AppError;
-use anyhow::Result;
+use crate::{errors::AppError, models::transaction::Transaction};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, NaiveDate, Utc};
 use rust_database_common::GenericClient;
 use rust_decimal::Decimal;
 use serde::Serialize;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(Serialize, Debug, Clone)]
@@ -34,6 +36,67 @@ impl TryInto<Envelope> for tokio_postgres::Row {
     }
 }
 
+#[derive(Serialize, Debug, Clone)]
+pub struct EnvelopeTransfer {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub from_envelope_id: Uuid,
+    pub to_envelope_id: Option<Uuid>,
+    pub amount: Decimal,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryInto<EnvelopeTransfer> for tokio_postgres::Row {
+    type Error = AppError;
+
+    fn try_into(self: tokio_postgres::Row) -> Result<EnvelopeTransfer, AppError> {
+        Ok(EnvelopeTransfer {
+            id: self
+                .try_get("id")
+                .map_err(AppError::RecordDeserializationError)?,
+            user_id: self
+                .try_get("user_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            from_envelope_id: self
+                .try_get("from_envelope_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            to_envelope_id: self
+                .try_get("to_envelope_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            amount: self
+                .try_get("amount")
+                .map_err(AppError::RecordDeserializationError)?,
+            created_at: self
+                .try_get("created_at")
+                .map_err(AppError::RecordDeserializationError)?,
+        })
+    }
+}
+
+impl EnvelopeTransfer {
+    pub async fn get_since(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT * FROM envelope_transfers
+                WHERE user_id = $1 AND created_at >= $2
+                ORDER BY created_at DESC",
+                &[&user_id, &since],
+            )
+            .await?;
+
+        let mut transfers = Vec::with_capacity(rows.len());
+        for row in rows {
+            transfers.push(row.try_into()?);
+        }
+
+        Ok(transfers)
+    }
+}
+
 impl Envelope {
     pub async fn get_one(
         client: &impl GenericClient,
@@ -95,13 +158,598 @@ impl Envelope {
         Ok(new_envelope)
     }
 
+    /// Saves everything but the balance, which only transactions move; see `adjust_balance`.
     pub async fn update(&self, client: &impl GenericClient) -> Result<(), AppError> {
         client
             .query(
-                "UPDATE envelopes SET name = $1, amount = $2 WHERE id = $3 AND user_id = $4",
-                &[&self.name, &self.amount, &self.id, &self.user_id],
+                "UPDATE envelopes SET name = $1 WHERE id = $2 AND user_id = $3",
+                &[&self.name, &self.id, &self.user_id],
             )
             .await?;
         Ok(())
     }
+
+    /// Moves the balance by `change` with an adjustment transaction, for changes that are not
+    /// money spent, so the ledger still accounts for them.
+    pub async fn adjust(
+        &mut self,
+        client: &impl GenericClient,
+        change: Decimal,
+        description: String,
+        occurred_on: NaiveDate,
+    ) -> Result<(), AppError> {
+        Transaction {
+            id: None,
+            user_id: self.user_id,
+            account_id: None,
+            envelope_id: self.id,
+            amount: change,
+            description,
+            occurred_on,
+            adjustment: true,
+        }
+        .create(client)
+        .await?;
+        self.amount += change;
+
+        Ok(())
+    }
+
+    /// Moves the balance to `balance`, like `Account::adjust_balance`. Returns `false` when the
+    /// balance is already there.
+    pub async fn adjust_balance(
+        &mut self,
+        client: &impl GenericClient,
+        balance: Decimal,
+        occurred_on: NaiveDate,
+    ) -> Result<bool, AppError> {
+        let change = balance - self.amount;
+        if change == Decimal::ZERO {
+            return Ok(false);
+        }
+
+        self.adjust(
+            client,
+            change,
+            "Balance adjustment".to_string(),
+            occurred_on,
+        )
+        .await?;
+        self.amount = balance;
+
+        Ok(true)
+    }
+
+    /// Records spending `amount` from this envelope, or fails when it does not hold that much.
+    pub async fn spend(
+        &self,
+        client: &impl GenericClient,
+        amount: Decimal,
+        account_id: Option<Uuid>,
+        description: String,
+        occurred_on: NaiveDate,
+    ) -> Result<Transaction, AppError> {
+        // Checks and takes the money in one statement, like `transfer`, so two spends cannot
+        // both pass the check.
+        let debited = client
+            .execute(
+                "UPDATE envelopes SET amount = amount - $1
+                WHERE id = $2 AND user_id = $3 AND amount >= $1",
+                &[&amount, &self.id, &self.user_id],
+            )
+            .await?;
+        if debited == 0 {
+            return Err(AppError::InvalidRecord(anyhow!(
+                "{} does not have {} to spend",
+                self.name,
+                amount
+            )));
+        }
+
+        Transaction {
+            id: None,
+            user_id: self.user_id,
+            account_id,
+            envelope_id: self.id,
+            amount: -amount,
+            description,
+            occurred_on,
+            adjustment: false,
+        }
+        .create_with_envelope_moved(client)
+        .await
+    }
+
+    pub async fn spent(&self, client: &impl GenericClient) -> Result<Decimal, AppError> {
+        let row = client
+            .query_one(
+                "SELECT coalesce(-sum(amount), 0) AS spent FROM transactions
+                WHERE user_id = $1 AND envelope_id = $2 AND NOT adjustment",
+                &[&self.user_id, &self.id],
+            )
+            .await?;
+
+        Ok(row.try_get("spent")?)
+    }
+
+    /// Moves `amount` out of this envelope into `to`, or back into the unallocated pool when
+    /// `to` is `None`. Runs as a single statement so both balances and the transfer record
+    /// change together.
+    pub async fn transfer(
+        &self,
+        client: &impl GenericClient,
+        to: Option<Uuid>,
+        amount: Decimal,
+    ) -> Result<EnvelopeTransfer, AppError> {
+        if amount <= Decimal::ZERO || to == self.id {
+            return Err(AppError::InvalidRecord(anyhow!(
+                "invalid envelope transfer"
+            )));
+        }
+
+        client
+            .query_opt(
+                "WITH debit AS (
+                    UPDATE envelopes SET amount = amount - $3
+                    WHERE id = $1 AND user_id = $4 AND amount >= $3
+                    AND ($2::uuid IS NULL OR EXISTS (
+                        SELECT 1 FROM envelopes WHERE id = $2 AND user_id = $4
+                    ))
+                    RETURNING id
+                ), credit AS (
+                    UPDATE envelopes SET amount = amount + $3
+                    WHERE id = $2 AND user_id = $4 AND EXISTS (SELECT 1 FROM debit)
+                )
+                INSERT INTO envelope_transfers (user_id, from_envelope_id, to_envelope_id, amount)
+                SELECT $4, id, $2, $3 FROM debit
+                RETURNING *",
+                &[&self.id, &to, &amount, &self.user_id],
+            )
+            .await?
+            .ok_or_else(|| {
+                AppError::InvalidRecord(anyhow!(
+                    "{} does not have {} to transfer",
+                    self.name,
+                    amount
+                ))
+            })?
+            .try_into()
+    }
+
+    /// Folds envelopes that share a name into a single envelope, keeping the one a goal points
+    /// at when there is one. History is re-pointed at the surviving envelope, so run this inside
+    /// a database transaction. Returns the number of envelopes removed.
+    pub async fn merge_duplicates(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        merged_on: NaiveDate,
+    ) -> Result<u64, AppError> {
+        let rows = client
+            .query(
+                "SELECT id, survivor FROM (
+                    SELECT envelopes.id, first_value(envelopes.id) OVER (
+                        PARTITION BY envelopes.name
+                        ORDER BY EXISTS (
+                            SELECT 1 FROM goals WHERE goals.envelope_id = envelopes.id
+                        ) DESC, envelopes.amount DESC, envelopes.id
+                    ) AS survivor
+                    FROM envelopes WHERE envelopes.user_id = $1
+                ) ranked WHERE id <> survivor",
+                &[&user_id],
+            )
+            .await?;
+
+        let mut ids: Vec<Uuid> = Vec::with_capacity(rows.len());
+        let mut survivors: Vec<Uuid> = Vec::with_capacity(rows.len());
+        for row in rows {
+            ids.push(row.try_get("id")?);
+            survivors.push(row.try_get("survivor")?);
+        }
+
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let mapping = "unnest($1::uuid[], $2::uuid[]) AS mapping(id, survivor)";
+
+        // A duplicate's transactions move over with it below. Whatever of its balance they do
+        // not account for, such as what it was created with, is recorded on the survivor, so
+        // the survivor's ledger changes by as much as its balance.
+        client
+            .execute(
+                &format!(
+                    "INSERT INTO transactions (
+                        user_id, envelope_id, amount, description, occurred_on, adjustment
+                    )
+                    SELECT $3, mapping.survivor, envelopes.amount - coalesce(recorded.amount, 0),
+                        'Merged from ' || envelopes.name, $4, true
+                    FROM {mapping}
+                    INNER JOIN envelopes ON envelopes.id = mapping.id
+                    LEFT JOIN (
+                        SELECT envelope_id, sum(amount) AS amount FROM transactions
+                        WHERE envelope_id = ANY($1) GROUP BY envelope_id
+                    ) recorded ON recorded.envelope_id = mapping.id
+                    WHERE envelopes.amount <> coalesce(recorded.amount, 0)"
+                ),
+                &[&ids, &survivors, &user_id, &merged_on],
+            )
+            .await?;
+
+        client
+            .execute(
+                &format!(
+                    "UPDATE envelopes SET amount = envelopes.amount + merged.amount
+                    FROM (
+                        SELECT mapping.survivor, sum(envelopes.amount) AS amount
+                        FROM {mapping} INNER JOIN envelopes ON envelopes.id = mapping.id
+                        GROUP BY mapping.survivor
+                    ) merged
+                    WHERE envelopes.id = merged.survivor"
+                ),
+                &[&ids, &survivors],
+            )
+            .await?;
+
+        client
+            .execute(
+                &format!(
+                    "UPDATE transactions SET envelope_id = mapping.survivor FROM {mapping}
+                    WHERE transactions.envelope_id = mapping.id"
+                ),
+                &[&ids, &survivors],
+            )
+            .await?;
+
+        client
+            .execute(
+                &format!(
+                    "UPDATE goals SET envelope_id = mapping.survivor FROM {mapping}
+                    WHERE goals.envelope_id = mapping.id"
+                ),
+                &[&ids, &survivors],
+            )
+            .await?;
+
+        client
+            .execute(
+                &format!(
+                    "UPDATE envelope_transfers SET from_envelope_id = mapping.survivor FROM {mapping}
+                    WHERE envelope_transfers.from_envelope_id = mapping.id"
+                ),
+                &[&ids, &survivors],
+            )
+            .await?;
+
+        client
+            .execute(
+                &format!(
+                    "UPDATE envelope_transfers SET to_envelope_id = mapping.survivor FROM {mapping}
+                    WHERE envelope_transfers.to_envelope_id = mapping.id"
+                ),
+                &[&ids, &survivors],
+            )
+            .await?;
+
+        client
+            .execute(
+                "DELETE FROM envelope_transfers
+                WHERE user_id = $1 AND from_envelope_id = to_envelope_id",
+                &[&user_id],
+            )
+            .await?;
+
+        Ok(client
+            .execute(
+                "DELETE FROM envelopes WHERE user_id = $1 AND id = ANY($2)",
+                &[&user_id, &ids],
+            )
+            .await?)
+    }
+
+    pub async fn spent_by_envelope(
+        client: &impl GenericClient,
+        user_id: Uuid,
+    ) -> Result<HashMap<Uuid, Decimal>, AppError> {
+        let rows = client
+            .query(
+                "SELECT envelope_id, -sum(amount) AS spent FROM transactions
+                WHERE user_id = $1 AND envelope_id IS NOT NULL AND NOT adjustment
+                GROUP BY envelope_id",
+                &[&user_id],
+            )
+            .await?;
+
+        let mut spent = HashMap::with_capacity(rows.len());
+        for row in rows {
+            spent.insert(row.try_get("envelope_id")?, row.try_get("spent")?);
+        }
+
+        Ok(spent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Envelope, EnvelopeTransfer};
+    use crate::{
+        errors::AppError,
+        models::{
+            account::Account,
+            goal::{EnvelopePolicy, Goal, Recurrence},
+            transaction::Transaction,
+        },
+        test_utils::state_for_tests,
+    };
+    use chrono::{NaiveDate, Utc};
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_spend_draws_down_envelope_and_account() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let mut client = shared_state.pool.get_client().await.unwrap();
+        let client = client.transaction().await.unwrap();
+
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(1000, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let envelope = Envelope {
+            id: None,
+            user_id,
+            name: "groceries".to_string(),
+            amount: Decimal::new(100, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        envelope
+            .spend(
+                &client,
+                Decimal::new(42, 0),
+                account.id,
+                "market".to_string(),
+                date,
+            )
+            .await
+            .unwrap();
+        envelope
+            .spend(
+                &client,
+                Decimal::new(8, 0),
+                None,
+                "bakery".to_string(),
+                date,
+            )
+            .await
+            .unwrap();
+
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        let envelope = Envelope::get_one(&client, envelope.id.unwrap(), user_id)
+            .await
+            .unwrap();
+
+        assert_eq!(account.amount, Decimal::new(958, 0));
+        assert_eq!(envelope.amount, Decimal::new(50, 0));
+        assert_eq!(envelope.spent(&client).await.unwrap(), Decimal::new(50, 0));
+        assert_eq!(
+            Envelope::spent_by_envelope(&client, user_id)
+                .await
+                .unwrap()
+                .get(&envelope.id.unwrap()),
+            Some(&Decimal::new(50, 0))
+        );
+
+        let overspend = envelope
+            .spend(
+                &client,
+                Decimal::new(51, 0),
+                account.id,
+                "market".to_string(),
+                date,
+            )
+            .await;
+        assert!(matches!(overspend, Err(AppError::InvalidRecord(_))));
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(account.amount, Decimal::new(958, 0));
+        assert_eq!(envelope.spent(&client).await.unwrap(), Decimal::new(50, 0));
+
+        client.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_transfer_between_envelopes_and_to_pool() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let groceries = Envelope {
+            id: None,
+            user_id,
+            name: "groceries".to_string(),
+            amount: Decimal::new(100, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+        let dining = Envelope {
+            id: None,
+            user_id,
+            name: "dining".to_string(),
+            amount: Decimal::new(20, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        groceries
+            .transfer(&client, dining.id, Decimal::new(30, 0))
+            .await
+            .unwrap();
+        groceries
+            .transfer(&client, None, Decimal::new(50, 0))
+            .await
+            .unwrap();
+
+        let overdrawn = groceries
+            .transfer(&client, dining.id, Decimal::new(21, 0))
+            .await;
+        assert!(overdrawn.is_err());
+
+        let groceries = Envelope::get_one(&client, groceries.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        let dining = Envelope::get_one(&client, dining.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(groceries.amount, Decimal::new(20, 0));
+        assert_eq!(dining.amount, Decimal::new(50, 0));
+
+        let transfers = EnvelopeTransfer::get_since(&client, user_id, chrono::DateTime::UNIX_EPOCH)
+            .await
+            .unwrap();
+        assert_eq!(transfers.len(), 2);
+        assert!(transfers.iter().any(|t| t.to_envelope_id.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_merge_duplicates_keeps_goal_envelope() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let mut client = shared_state.pool.get_client().await.unwrap();
+        let client = client.transaction().await.unwrap();
+
+        let mut rents = Vec::new();
+        for amount in [100, 200, 300] {
+            rents.push(
+                Envelope {
+                    id: None,
+                    user_id,
+                    name: "rent".to_string(),
+                    amount: Decimal::new(amount, 0),
+                }
+                .create(&client)
+                .await
+                .unwrap(),
+            );
+        }
+        let dining = Envelope {
+            id: None,
+            user_id,
+            name: "dining".to_string(),
+            amount: Decimal::new(20, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let goal = Goal {
+            id: None,
+            user_id,
+            name: "rent".to_string(),
+            recurrence: Recurrence::Monthly,
+            target_date: Utc::now(),
+            target: Decimal::new(100, 0),
+            accumulated_amount: Decimal::ZERO,
+            start_date: None,
+            envelope_id: rents[0].id,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        rents[2]
+            .spend(
+                &client,
+                Decimal::new(50, 0),
+                None,
+                "landlord".to_string(),
+                NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            )
+            .await
+            .unwrap();
+        rents[1]
+            .transfer(&client, rents[0].id, Decimal::new(10, 0))
+            .await
+            .unwrap();
+
+        let today = NaiveDate::from_ymd_opt(2024, 3, 2).unwrap();
+        let merged = Envelope::merge_duplicates(&client, user_id, today)
+            .await
+            .unwrap();
+        assert_eq!(merged, 2);
+
+        let envelopes = Envelope::get_all(&client, user_id).await.unwrap();
+        assert_eq!(envelopes.len(), 2);
+
+        let rent = Envelope::get_one(&client, rents[0].id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(rent.amount, Decimal::new(550, 0));
+        assert_eq!(rent.spent(&client).await.unwrap(), Decimal::new(50, 0));
+        // Everything the merge added beyond the moved spend is recorded: 110 + 440 = 550.
+        let transactions = Transaction::get_all_for_envelope(&client, rent.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(
+            transactions
+                .iter()
+                .map(|transaction| transaction.amount)
+                .sum::<Decimal>(),
+            Decimal::new(440, 0)
+        );
+        assert_eq!(
+            transactions
+                .iter()
+                .filter(|transaction| transaction.adjustment)
+                .count(),
+            2
+        );
+        assert_eq!(
+            Envelope::get_one(&client, dining.id.unwrap(), user_id)
+                .await
+                .unwrap()
+                .amount,
+            Decimal::new(20, 0)
+        );
+
+        let goal = Goal::get_one(&client, goal.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(goal.envelope_id, rent.id);
+
+        let transfers = EnvelopeTransfer::get_since(&client, user_id, chrono::DateTime::UNIX_EPOCH)
+            .await
+            .unwrap();
+        assert!(transfers.is_empty());
+
+        assert_eq!(
+            Envelope::merge_duplicates(&client, user_id, today)
+                .await
+                .unwrap(),
+            0
+        );
+
+        client.rollback().await.unwrap();
+    }
 }
@@ -0,0 +1,194 @@
+use crate::{
+    errors::AppError,
+    models::{goal::Goal, goal::Recurrence, income_source::IncomeSource, user::User},
+    utilities::dates::{FixedTime, Times, end_of_day},
+};
+use anyhow::{Result, anyhow};
+use chrono::{Months, NaiveDate};
+use chrono_tz::Tz;
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+/// The projected spendable balance at the end of one day.
+#[derive(Debug, Clone, Serialize)]
+pub struct ForecastDay {
+    pub date: NaiveDate,
+    pub income: Decimal,
+    /// What goals set aside that day, including the shortfall paid in when a goal converts.
+    pub goals: Decimal,
+    pub conversions: Vec<String>,
+    pub balance: Decimal,
+    pub negative: bool,
+}
+
+/// Walks the user's goals and income forward a day at a time for `months` months from
+/// `time`'s now.
+pub async fn forecast_for(
+    client: &impl GenericClient,
+    user: &User,
+    time: &impl Times,
+    months: u32,
+) -> Result<Vec<ForecastDay>, AppError> {
+    let timezone: Tz = user.timezone()?.parse()?;
+    let start = time.now();
+    let today = start.with_timezone(&timezone).date_naive();
+    let until = today
+        .checked_add_months(Months::new(months))
+        .ok_or_else(|| anyhow!("could not add months"))?;
+
+    Ok(forecast(
+        user.total_balance(client).await?,
+        Goal::get_all(client, user.id).await?,
+        &IncomeSource::get_all(client, user.id).await?,
+        timezone,
+        today,
+        until,
+    )?)
+}
+
+/// Each day, expected income is added first, then goals past their target date convert the
+/// way `convert_goals` would, then every goal accumulates up to the end of the day.
+pub fn forecast(
+    balance: Decimal,
+    mut goals: Vec<Goal>,
+    income_sources: &[IncomeSource],
+    timezone: Tz,
+    today: NaiveDate,
+    until: NaiveDate,
+) -> Result<Vec<ForecastDay>> {
+    let mut balance = balance;
+    let mut next_income = income_sources
+        .iter()
+        .map(|income_source| income_source.next_expected_after(today).map(Some))
+        .collect::<Result<Vec<_>>>()?;
+    let mut days = Vec::new();
+
+    for date in today.iter_days().take_while(|date| *date < until) {
+        let ends_at = end_of_day(timezone, date)?;
+        let mut income = Decimal::ZERO;
+        let mut set_aside = Decimal::ZERO;
+        let mut conversions = Vec::new();
+
+        for (income_source, next) in income_sources.iter().zip(next_income.iter_mut()) {
+            while let Some(expected_on) = next.filter(|expected_on| *expected_on <= date) {
+                income += income_source.amount;
+                *next = following_income(income_source, expected_on)?;
+            }
+        }
+
+        for goal in goals.iter_mut() {
+            while goal.recurrence != Recurrence::Never && goal.target_date < ends_at {
+                set_aside += goal.target - goal.accumulated_amount;
+                conversions.push(goal.name.clone());
+                *goal = goal.increment()?;
+            }
+
+            let accumulated = goal.accumulated_at(&FixedTime(ends_at))?;
+            set_aside += accumulated - goal.accumulated_amount;
+            goal.accumulated_amount = accumulated;
+        }
+
+        balance += income - set_aside;
+        days.push(ForecastDay {
+            date,
+            income,
+            goals: set_aside,
+            conversions,
+            balance,
+            negative: balance < Decimal::ZERO,
+        });
+    }
+
+    Ok(days)
+}
+
+fn following_income(
+    income_source: &IncomeSource,
+    expected_on: NaiveDate,
+) -> Result<Option<NaiveDate>> {
+    let Some(day_after) = expected_on.succ_opt() else {
+        return Ok(None);
+    };
+
+    let next = income_source.next_expected_after(day_after)?;
+
+    Ok(Some(next).filter(|next| *next > expected_on))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::forecast;
+    use crate::models::{
+        goal::{EnvelopePolicy, Goal, Recurrence},
+        income_source::{IncomeRecurrence, IncomeSource},
+    };
+    use chrono::{NaiveDate, NaiveTime};
+    use chrono_tz::Tz;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_forecast_applies_income_goals_and_conversions() {
+        let user_id = Uuid::new_v4();
+        let goal = Goal {
+            id: None,
+            user_id,
+            name: "rent".to_string(),
+            recurrence: Recurrence::Monthly,
+            target_date: date(2024, 1, 10).and_time(NaiveTime::MIN).and_utc(),
+            target: Decimal::new(310, 0),
+            accumulated_amount: Decimal::new(200, 0),
+            start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        };
+        let income = IncomeSource {
+            id: None,
+            user_id,
+            name: "pay".to_string(),
+            amount: Decimal::new(500, 0),
+            recurrence: IncomeRecurrence::Monthly,
+            next_expected_on: date(2023, 12, 15),
+        };
+
+        let days = forecast(
+            Decimal::new(100, 0),
+            vec![goal],
+            &[income],
+            Tz::UTC,
+            date(2024, 1, 1),
+            date(2024, 3, 1),
+        )
+        .unwrap();
+
+        assert_eq!(days.len(), 60);
+
+        // The rent goal started filling on December 10th, so the first day catches up to 230.
+        assert_eq!(days[0].goals.round_dp(6), Decimal::new(30, 0));
+        assert_eq!(days[0].balance.round_dp(6), Decimal::new(70, 0));
+        assert!(!days[7].negative);
+        assert!(days[8].negative);
+
+        // It converts on January 10th and starts filling toward February 10th.
+        assert_eq!(days[9].conversions, ["rent"]);
+        assert_eq!(days[14].income, Decimal::new(500, 0));
+        assert!(!days[14].negative);
+        assert_eq!(days[40].conversions, ["rent"]);
+        assert_eq!(days[45].income, Decimal::new(500, 0));
+
+        let set_aside =
+            Decimal::new(110 + 310, 0) + Decimal::new(310 * 20, 0) / Decimal::new(29, 0);
+        assert_eq!(
+            days.last().unwrap().balance.round_dp(6),
+            (Decimal::new(1100, 0) - set_aside).round_dp(6)
+        );
+    }
+}
@@ -1,13 +1,17 @@
-use crate::{errors::AppError, utilities::dates::Times};
+use crate::{
+    errors::AppError,
+    models::goal_contribution::{ContributionSource, GoalContribution},
+    utilities::dates::Times,
+};
 use anyhow::{Result, anyhow};
-use chrono::{DateTime, Days, Months, TimeDelta, Utc};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, TimeDelta, Utc};
 use postgres_types::{FromSql, ToSql};
 use rust_database_common::GenericClient;
 use rust_decimal::{Decimal, prelude::FromPrimitive};
 use serde::Serialize;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, FromSql, ToSql, PartialEq)]
+#[derive(Debug, Clone, Serialize, PartialEq)]
 pub enum Recurrence {
     Never,
     Daily,
@@ -15,7 +19,31 @@ pub enum Recurrence {
     Monthly,
     Quarterly,
     Yearly,
+    EveryDays(u32),
+    EveryWeeks(u32),
+    EveryMonths(u32),
+    /// Two days of the month; a day past the end of a short month lands on its last day.
+    SemiMonthly(u32, u32),
+    LastDayOfMonth,
+}
+
+/// The `recurrence` column; intervals and days live in their own columns.
+#[derive(Debug, Clone, Copy, FromSql, ToSql, PartialEq)]
+#[postgres(name = "Recurrence")]
+enum RecurrenceKind {
+    Never,
+    Daily,
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+    EveryDays,
+    EveryWeeks,
+    EveryMonths,
+    SemiMonthly,
+    LastDayOfMonth,
 }
+
 #[derive(Debug)]
 pub struct RecurrenceError {}
 
@@ -28,12 +56,228 @@ impl std::str::FromStr for Recurrence {
             "monthly" => Ok(Self::Monthly),
             "quarterly" => Ok(Self::Quarterly),
             "yearly" => Ok(Self::Yearly),
+            "last_day_of_month" => Ok(Self::LastDayOfMonth),
             _ => Err(RecurrenceError {}),
         }
     }
 
     type Err = RecurrenceError;
 }
+
+impl Recurrence {
+    /// The form value for this recurrence, without its interval or days.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Never => "never",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+            Self::Quarterly => "quarterly",
+            Self::Yearly => "yearly",
+            Self::EveryDays(_) => "every_days",
+            Self::EveryWeeks(_) => "every_weeks",
+            Self::EveryMonths(_) => "every_months",
+            Self::SemiMonthly(_, _) => "semi_monthly",
+            Self::LastDayOfMonth => "last_day_of_month",
+        }
+    }
+
+    pub fn interval(&self) -> Option<u32> {
+        match self {
+            Self::EveryDays(interval)
+            | Self::EveryWeeks(interval)
+            | Self::EveryMonths(interval) => Some(*interval),
+            _ => None,
+        }
+    }
+
+    pub fn days(&self) -> Option<(u32, u32)> {
+        match self {
+            Self::SemiMonthly(first, second) => Some((*first, *second)),
+            _ => None,
+        }
+    }
+
+    fn kind(&self) -> RecurrenceKind {
+        match self {
+            Self::Never => RecurrenceKind::Never,
+            Self::Daily => RecurrenceKind::Daily,
+            Self::Weekly => RecurrenceKind::Weekly,
+            Self::Monthly => RecurrenceKind::Monthly,
+            Self::Quarterly => RecurrenceKind::Quarterly,
+            Self::Yearly => RecurrenceKind::Yearly,
+            Self::EveryDays(_) => RecurrenceKind::EveryDays,
+            Self::EveryWeeks(_) => RecurrenceKind::EveryWeeks,
+            Self::EveryMonths(_) => RecurrenceKind::EveryMonths,
+            Self::SemiMonthly(_, _) => RecurrenceKind::SemiMonthly,
+            Self::LastDayOfMonth => RecurrenceKind::LastDayOfMonth,
+        }
+    }
+
+    fn interval_column(&self) -> Option<i32> {
+        self.interval().map(|interval| interval as i32)
+    }
+
+    fn days_column(&self) -> Option<Vec<i32>> {
+        self.days()
+            .map(|(first, second)| vec![first as i32, second as i32])
+    }
+
+    fn from_columns(
+        kind: RecurrenceKind,
+        interval: Option<i32>,
+        days: Option<Vec<i32>>,
+    ) -> Result<Self> {
+        let interval = || {
+            interval
+                .filter(|interval| *interval > 0)
+                .map(|interval| interval as u32)
+                .ok_or_else(|| anyhow!("recurrence is missing its interval"))
+        };
+
+        Ok(match kind {
+            RecurrenceKind::Never => Self::Never,
+            RecurrenceKind::Daily => Self::Daily,
+            RecurrenceKind::Weekly => Self::Weekly,
+            RecurrenceKind::Monthly => Self::Monthly,
+            RecurrenceKind::Quarterly => Self::Quarterly,
+            RecurrenceKind::Yearly => Self::Yearly,
+            RecurrenceKind::EveryDays => Self::EveryDays(interval()?),
+            RecurrenceKind::EveryWeeks => Self::EveryWeeks(interval()?),
+            RecurrenceKind::EveryMonths => Self::EveryMonths(interval()?),
+            RecurrenceKind::SemiMonthly => match days.as_deref() {
+                Some([first, second]) => Self::SemiMonthly(*first as u32, *second as u32),
+                _ => return Err(anyhow!("semi-monthly recurrence needs two days")),
+            },
+            RecurrenceKind::LastDayOfMonth => Self::LastDayOfMonth,
+        })
+    }
+
+    /// The occurrence after `date`, keeping its time of day. Month-based recurrences land on
+    /// `day`, clamped to shorter months, rather than carrying a clamped day forward.
+    pub fn next_after(&self, date: DateTime<Utc>, day: u32) -> Result<DateTime<Utc>> {
+        self.step(date, day, true)
+    }
+
+    pub fn previous_before(&self, date: DateTime<Utc>, day: u32) -> Result<DateTime<Utc>> {
+        self.step(date, day, false)
+    }
+
+    fn step(&self, date: DateTime<Utc>, day: u32, forward: bool) -> Result<DateTime<Utc>> {
+        let days = |count: u64| {
+            if forward {
+                date.checked_add_days(Days::new(count))
+            } else {
+                date.checked_sub_days(Days::new(count))
+            }
+        };
+        let on = |day: NaiveDate| Some(day.and_time(date.time()).and_utc());
+
+        let first_of_month = date
+            .date_naive()
+            .with_day(1)
+            .ok_or_else(|| anyhow!("could not add dates"))?;
+        let months = |count: u32| {
+            if forward {
+                first_of_month.checked_add_months(Months::new(count))
+            } else {
+                first_of_month.checked_sub_months(Months::new(count))
+            }
+            .and_then(|month| day_of_month(month, day))
+            .and_then(on)
+        };
+
+        let stepped = match self {
+            Self::Never => Some(date),
+            Self::Daily => days(1),
+            Self::Weekly => days(7),
+            Self::EveryDays(count) => days(*count as u64),
+            Self::EveryWeeks(count) => days(*count as u64 * 7),
+            Self::Monthly => months(1),
+            Self::Quarterly => months(3),
+            Self::Yearly => months(12),
+            Self::EveryMonths(count) => months(*count),
+            Self::LastDayOfMonth => {
+                // A date before the end of its month steps to that month's end first.
+                let mut month_ends = [
+                    first_of_month - Months::new(1),
+                    first_of_month,
+                    first_of_month + Months::new(1),
+                ]
+                .into_iter()
+                .filter_map(|month| day_of_month(month, 31));
+
+                let today = date.date_naive();
+                if forward {
+                    month_ends.find(|month_end| *month_end > today)
+                } else {
+                    month_ends.rfind(|month_end| *month_end < today)
+                }
+                .and_then(on)
+            }
+            Self::SemiMonthly(first, second) => {
+                let mut candidates = Vec::with_capacity(6);
+                for month in [
+                    first_of_month - Months::new(1),
+                    first_of_month,
+                    first_of_month + Months::new(1),
+                ] {
+                    for day in [first, second] {
+                        candidates.extend(day_of_month(month, *day));
+                    }
+                }
+                candidates.sort();
+
+                let today = date.date_naive();
+                if forward {
+                    candidates.into_iter().find(|candidate| *candidate > today)
+                } else {
+                    candidates.into_iter().rfind(|candidate| *candidate < today)
+                }
+                .and_then(on)
+            }
+        };
+
+        stepped.ok_or_else(|| anyhow!("could not add dates"))
+    }
+}
+
+/// The given day in the month starting at `first_of_month`, clamped to the month's last day.
+fn day_of_month(first_of_month: NaiveDate, day: u32) -> Option<NaiveDate> {
+    let last_day = (first_of_month + Months::new(1)).pred_opt()?;
+    first_of_month.with_day(day.clamp(1, last_day.day()))
+}
+
+#[derive(Debug, Clone, Serialize, FromSql, ToSql, PartialEq)]
+pub enum EnvelopePolicy {
+    Append,
+    Replace,
+    CreateNew,
+}
+
+impl std::str::FromStr for EnvelopePolicy {
+    fn from_str(string: &str) -> Result<Self, RecurrenceError> {
+        match string {
+            "append" => Ok(Self::Append),
+            "replace" => Ok(Self::Replace),
+            "create_new" => Ok(Self::CreateNew),
+            _ => Err(RecurrenceError {}),
+        }
+    }
+
+    type Err = RecurrenceError;
+}
+
+impl std::fmt::Display for EnvelopePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Append => write!(f, "append"),
+            Self::Replace => write!(f, "replace"),
+            Self::CreateNew => write!(f, "create_new"),
+        }
+    }
+}
+
 #[derive(Serialize, Debug, Clone)]
 pub struct Goal {
     pub id: Option<Uuid>,
@@ -44,6 +288,14 @@ pub struct Goal {
     pub target: Decimal,
     pub accumulated_amount: Decimal,
     pub start_date: Option<DateTime<Utc>>,
+    pub envelope_id: Option<Uuid>,
+    pub envelope_policy: EnvelopePolicy,
+    /// Position in the user's funding order, lowest first. Managed by `Goal::reorder`.
+    pub priority: i32,
+    pub funding_weight: Decimal,
+    /// The day of the month that month-based recurrences aim for, which short months clamp
+    /// `target_date` below. `None` aims for `target_date`'s day.
+    pub target_day: Option<u32>,
 }
 
 impl TryInto<Goal> for tokio_postgres::Row {
@@ -60,9 +312,14 @@ impl TryInto<Goal> for tokio_postgres::Row {
             name: self
                 .try_get("name")
                 .map_err(AppError::RecordDeserializationError)?,
-            recurrence: self
-                .try_get("recurrence")
-                .map_err(AppError::RecordDeserializationError)?,
+            recurrence: Recurrence::from_columns(
+                self.try_get("recurrence")
+                    .map_err(AppError::RecordDeserializationError)?,
+                self.try_get("recurrence_interval")
+                    .map_err(AppError::RecordDeserializationError)?,
+                self.try_get("recurrence_days")
+                    .map_err(AppError::RecordDeserializationError)?,
+            )?,
             target_date: self
                 .try_get("target_date")
                 .map_err(AppError::RecordDeserializationError)?,
@@ -75,6 +332,22 @@ impl TryInto<Goal> for tokio_postgres::Row {
             start_date: self
                 .try_get("start_date")
                 .map_err(AppError::RecordDeserializationError)?,
+            envelope_id: self
+                .try_get("envelope_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            envelope_policy: self
+                .try_get("envelope_policy")
+                .map_err(AppError::RecordDeserializationError)?,
+            priority: self
+                .try_get("priority")
+                .map_err(AppError::RecordDeserializationError)?,
+            funding_weight: self
+                .try_get("funding_weight")
+                .map_err(AppError::RecordDeserializationError)?,
+            target_day: self
+                .try_get::<_, Option<i32>>("target_day")
+                .map_err(AppError::RecordDeserializationError)?
+                .map(|day| day as u32),
         })
     }
 }
@@ -87,47 +360,92 @@ impl Goal {
                     user_id
                     , name
                     , recurrence
+                    , recurrence_interval
+                    , recurrence_days
                     , target_date
                     , target
                     , accumulated_amount
                     , start_date
-                ) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+                    , envelope_id
+                    , envelope_policy
+                    , funding_weight
+                    , target_day
+                    , priority
+                ) VALUES (
+                    $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13,
+                    (SELECT COALESCE(MAX(priority) + 1, 0) FROM goals WHERE user_id = $1)
+                ) RETURNING id, priority",
                 &[
                     &self.user_id,
                     &self.name,
-                    &self.recurrence,
+                    &self.recurrence.kind(),
+                    &self.recurrence.interval_column(),
+                    &self.recurrence.days_column(),
                     &self.target_date,
                     &self.target,
                     &Decimal::ZERO,
                     &self.start_date,
+                    &self.envelope_id,
+                    &self.envelope_policy,
+                    &self.funding_weight,
+                    &self.target_day_column(),
                 ],
             )
             .await?;
 
         let mut new_account = self.clone();
         new_account.id = Some(row.try_get("id")?);
+        new_account.priority = row.try_get("priority")?;
 
         Ok(new_account)
     }
 
+    pub fn target_day_of_month(&self) -> u32 {
+        self.target_day.unwrap_or(self.target_date.day())
+    }
+
+    fn target_day_column(&self) -> Option<i32> {
+        self.target_day.map(|day| day as i32)
+    }
+
+    /// Moves the target date, aiming month-based recurrences at its day from now on.
+    pub fn set_target_date(&mut self, target_date: DateTime<Utc>) {
+        if target_date != self.target_date {
+            self.target_date = target_date;
+            self.target_day = None;
+        }
+    }
+
     pub async fn update(&self, client: &impl GenericClient) -> Result<Self, AppError> {
         client
             .execute(
                 "UPDATE goals SET
                     name = $1
                     , recurrence = $2
-                    , target_date = $3
-                    , target = $4
-                    , accumulated_amount = $5
-                    , start_date = $6
-            WHERE id = $7 AND user_id = $8",
+                    , recurrence_interval = $3
+                    , recurrence_days = $4
+                    , target_date = $5
+                    , target = $6
+                    , accumulated_amount = $7
+                    , start_date = $8
+                    , envelope_id = $9
+                    , envelope_policy = $10
+                    , funding_weight = $11
+                    , target_day = $12
+            WHERE id = $13 AND user_id = $14",
                 &[
                     &self.name,
-                    &self.recurrence,
+                    &self.recurrence.kind(),
+                    &self.recurrence.interval_column(),
+                    &self.recurrence.days_column(),
                     &self.target_date,
                     &self.target,
                     &self.accumulated_amount,
                     &self.start_date,
+                    &self.envelope_id,
+                    &self.envelope_policy,
+                    &self.funding_weight,
+                    &self.target_day_column(),
                     &self.id,
                     &self.user_id,
                 ],
@@ -154,6 +472,33 @@ impl Goal {
         Ok(())
     }
 
+    /// Sets the funding order to the order of `ids`. Goals missing from `ids` keep their
+    /// position relative to each other, after the listed ones.
+    pub async fn reorder(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        ids: &[Uuid],
+    ) -> Result<(), AppError> {
+        client
+            .execute(
+                "UPDATE goals SET priority = ordered.priority - 1
+                FROM (
+                    SELECT goals.id, row_number() OVER (
+                        ORDER BY listed.position ASC NULLS LAST, goals.priority, goals.target_date
+                    ) AS priority
+                    FROM goals
+                    LEFT JOIN unnest($2::uuid[]) WITH ORDINALITY AS listed(id, position)
+                        ON listed.id = goals.id
+                    WHERE goals.user_id = $1
+                ) AS ordered
+                WHERE goals.id = ordered.id",
+                &[&user_id, &ids],
+            )
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn get_one(
         client: &impl GenericClient,
         id: Uuid,
@@ -180,7 +525,7 @@ impl Goal {
             .query(
                 "SELECT goals.* FROM goals INNER
             JOIN users ON users.id = goals.user_id WHERE users.id = $1
-            ORDER BY target_date ASC",
+            ORDER BY priority ASC, target_date ASC",
                 &[&user_id],
             )
             .await?;
@@ -193,11 +538,17 @@ impl Goal {
         Ok(goals)
     }
 
-    pub async fn get_all_unscoped(client: &impl GenericClient) -> Result<Vec<Self>, AppError> {
+    /// Every goal, or only `user_id`'s when given, locked for the rest of the transaction.
+    pub async fn get_all_unscoped(
+        client: &impl GenericClient,
+        user_id: Option<Uuid>,
+    ) -> Result<Vec<Self>, AppError> {
         let rows = client
             .query(
-                "SELECT goals.* FROM goals ORDER BY DATE(target_date) ASC, target ASC FOR UPDATE",
-                &[],
+                "SELECT goals.* FROM goals
+                WHERE $1::uuid IS NULL OR user_id = $1
+                ORDER BY DATE(target_date) ASC, target ASC FOR UPDATE",
+                &[&user_id],
             )
             .await?;
 
@@ -212,11 +563,14 @@ impl Goal {
     pub async fn get_expired(
         client: &impl GenericClient,
         cutoff: DateTime<Utc>,
+        user_id: Option<Uuid>,
     ) -> Result<Vec<Self>, AppError> {
         let rows = client
             .query(
-                "SELECT goals.* FROM goals WHERE recurrence <> 'Never' AND target_date < $1",
-                &[&cutoff],
+                "SELECT goals.* FROM goals
+                WHERE recurrence <> 'Never' AND target_date < $1
+                AND ($2::uuid IS NULL OR user_id = $2)",
+                &[&cutoff, &user_id],
             )
             .await?;
 
@@ -232,39 +586,10 @@ impl Goal {
         let mut goal = self.clone();
         goal.accumulated_amount = Decimal::ZERO;
 
-        match self.recurrence {
-            Recurrence::Never => goal.target_date = self.target_date,
-            Recurrence::Daily => {
-                goal.target_date = self
-                    .target_date
-                    .checked_add_days(Days::new(1))
-                    .ok_or_else(|| anyhow!("could not add dates"))?
-            }
-            Recurrence::Weekly => {
-                goal.target_date = self
-                    .target_date
-                    .checked_add_days(Days::new(7))
-                    .ok_or_else(|| anyhow!("could not add dates"))?
-            }
-            Recurrence::Yearly => {
-                goal.target_date = self
-                    .target_date
-                    .checked_add_months(Months::new(12))
-                    .ok_or_else(|| anyhow!("could not add dates"))?
-            }
-            Recurrence::Monthly => {
-                goal.target_date = self
-                    .target_date
-                    .checked_add_months(Months::new(1))
-                    .ok_or_else(|| anyhow!("could not add dates"))?
-            }
-            Recurrence::Quarterly => {
-                goal.target_date = self
-                    .target_date
-                    .checked_add_months(Months::new(3))
-                    .ok_or_else(|| anyhow!("could not add dates"))?
-            }
-        }
+        goal.target_day = Some(self.target_day_of_month());
+        goal.target_date = self
+            .recurrence
+            .next_after(self.target_date, self.target_day_of_month())?;
 
         Ok(goal)
     }
@@ -300,7 +625,10 @@ impl Goal {
             goal.accumulated_amount = goal.target;
         }
 
-        goal.update(client).await
+        let goal = goal.update(client).await?;
+        GoalContribution::record(client, self, &goal, ContributionSource::Acceleration).await?;
+
+        Ok(goal)
     }
 
     pub async fn accumulate(
@@ -308,11 +636,7 @@ impl Goal {
         client: &impl GenericClient,
         time_provider: &impl Times,
     ) -> Result<Self, AppError> {
-        let accumulated_now = self.accumulated_now(time_provider)?;
-        let accumulated_amount = Decimal::min(
-            self.target,
-            Decimal::max(accumulated_now, self.accumulated_amount),
-        );
+        let accumulated_amount = self.accumulated_at(time_provider)?;
 
         let goal = Goal {
             id: self.id,
@@ -323,9 +647,28 @@ impl Goal {
             user_id: self.user_id,
             accumulated_amount,
             start_date: self.start_date,
+            envelope_id: self.envelope_id,
+            envelope_policy: self.envelope_policy.clone(),
+            priority: self.priority,
+            funding_weight: self.funding_weight,
+            target_day: self.target_day,
         };
 
-        goal.update(client).await
+        let goal = goal.update(client).await?;
+        GoalContribution::record(client, self, &goal, ContributionSource::Accumulation).await?;
+
+        Ok(goal)
+    }
+
+    /// What `accumulate` would set the accumulated amount to at `time_provider`'s now. It never
+    /// goes down or past the target.
+    pub fn accumulated_at(&self, time_provider: &impl Times) -> Result<Decimal> {
+        let accumulated_now = self.accumulated_now(time_provider)?;
+
+        Ok(Decimal::min(
+            self.target,
+            Decimal::max(accumulated_now, self.accumulated_amount),
+        ))
     }
 
     fn accumulated_now(&self, time_provider: &impl Times) -> Result<Decimal> {
@@ -364,11 +707,9 @@ impl Goal {
     fn start_at(&self) -> Result<DateTime<Utc>> {
         match self.recurrence {
             Recurrence::Never => self.start_date.ok_or_else(|| anyhow!("missing start date")),
-            Recurrence::Daily => Ok(self.target_date - Days::new(1)),
-            Recurrence::Weekly => Ok(self.target_date - Days::new(7)),
-            Recurrence::Yearly => Ok(self.target_date - Months::new(12)),
-            Recurrence::Monthly => Ok(self.target_date - Months::new(1)),
-            Recurrence::Quarterly => Ok(self.target_date - Months::new(3)),
+            _ => self
+                .recurrence
+                .previous_before(self.target_date, self.target_day_of_month()),
         }
     }
 }
@@ -377,7 +718,7 @@ impl Goal {
 mod tests {
     use std::str::FromStr;
 
-    use super::{Goal, Recurrence};
+    use super::{EnvelopePolicy, Goal, Recurrence};
     use crate::{test_utils::state_for_tests, utilities::dates::Times};
     use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
     use rust_decimal::Decimal;
@@ -408,6 +749,11 @@ mod tests {
             user_id: Uuid::new_v4(),
             target_date,
             start_date: Some(target_date),
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
 
         assert_eq!(goal.accumulated_per_day().unwrap(), Decimal::ZERO);
@@ -432,6 +778,11 @@ mod tests {
             )
             .and_utc(),
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let goal = goal.create(&client).await.unwrap();
         let goal = goal.accumulate(&client, time_provider).await.unwrap();
@@ -458,6 +809,11 @@ mod tests {
             )
             .and_utc(),
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let goal = goal.create(&client).await.unwrap();
         let goal = goal.accumulate(&client, time_provider).await.unwrap();
@@ -484,10 +840,224 @@ mod tests {
             )
             .and_utc(),
             start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
         };
         let goal = goal.create(&client).await.unwrap();
 
         let goal = goal.accumulate(&client, time_provider).await.unwrap();
         assert!(goal.accumulated_amount - Decimal::new(9766, 2) < Decimal::new(3, 1))
     }
+
+    fn goal_with(recurrence: Recurrence, target_date: &str) -> Goal {
+        Goal {
+            id: None,
+            accumulated_amount: Decimal::ZERO,
+            name: "test".to_string(),
+            recurrence,
+            target: Decimal::new(100, 0),
+            user_id: Uuid::new_v4(),
+            target_date: NaiveDateTime::new(
+                NaiveDate::from_str(target_date).unwrap(),
+                NaiveTime::MIN,
+            )
+            .and_utc(),
+            start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        }
+    }
+
+    #[test]
+    fn test_last_day_of_month_does_not_drift() {
+        let mut goal = goal_with(Recurrence::LastDayOfMonth, "2024-01-31");
+        let mut month_ends = Vec::new();
+
+        for _ in 0..12 {
+            goal = goal.increment().unwrap();
+            month_ends.push(goal.target_date.date_naive().to_string());
+        }
+
+        assert_eq!(
+            month_ends,
+            [
+                "2024-02-29",
+                "2024-03-31",
+                "2024-04-30",
+                "2024-05-31",
+                "2024-06-30",
+                "2024-07-31",
+                "2024-08-31",
+                "2024-09-30",
+                "2024-10-31",
+                "2024-11-30",
+                "2024-12-31",
+                "2025-01-31"
+            ]
+        );
+
+        let goal = goal_with(Recurrence::LastDayOfMonth, "2024-03-31");
+        assert_eq!(
+            goal.start_at().unwrap().date_naive(),
+            NaiveDate::from_str("2024-02-29").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_last_day_of_month_from_mid_month() {
+        let date = NaiveDate::from_str("2024-03-15")
+            .unwrap()
+            .and_time(NaiveTime::MIN)
+            .and_utc();
+        let step = |forward: bool| {
+            if forward {
+                Recurrence::LastDayOfMonth.next_after(date, 31)
+            } else {
+                Recurrence::LastDayOfMonth.previous_before(date, 31)
+            }
+            .unwrap()
+            .date_naive()
+            .to_string()
+        };
+
+        assert_eq!(step(true), "2024-03-31");
+        assert_eq!(step(false), "2024-02-29");
+
+        let goal = goal_with(Recurrence::LastDayOfMonth, "2024-03-15")
+            .increment()
+            .unwrap();
+        assert_eq!(goal.target_date.date_naive().to_string(), "2024-03-31");
+    }
+
+    #[test]
+    fn test_month_based_recurrences_do_not_drift() {
+        let targets = |recurrence: Recurrence, target_date: &str, count: usize| {
+            let mut goal = goal_with(recurrence, target_date);
+            (0..count)
+                .map(|_| {
+                    goal = goal.increment().unwrap();
+                    goal.target_date.date_naive().to_string()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            targets(Recurrence::Monthly, "2024-01-31", 3),
+            ["2024-02-29", "2024-03-31", "2024-04-30"]
+        );
+        assert_eq!(
+            targets(Recurrence::Quarterly, "2023-11-30", 2),
+            ["2024-02-29", "2024-05-30"]
+        );
+        assert_eq!(
+            targets(Recurrence::EveryMonths(2), "2023-12-31", 2),
+            ["2024-02-29", "2024-04-30"]
+        );
+        assert_eq!(
+            targets(Recurrence::Yearly, "2024-02-29", 4),
+            ["2025-02-28", "2026-02-28", "2027-02-28", "2028-02-29"]
+        );
+
+        let mut goal = goal_with(Recurrence::Monthly, "2024-01-31")
+            .increment()
+            .unwrap();
+        assert_eq!(goal.target_day, Some(31));
+        assert_eq!(
+            goal.start_at().unwrap().date_naive(),
+            NaiveDate::from_str("2024-01-31").unwrap()
+        );
+
+        goal.set_target_date(goal.target_date);
+        assert_eq!(goal.target_day, Some(31));
+        goal.set_target_date(goal_with(Recurrence::Monthly, "2024-03-15").target_date);
+        assert_eq!(goal.target_day, None);
+        assert_eq!(
+            goal.increment()
+                .unwrap()
+                .target_date
+                .date_naive()
+                .to_string(),
+            "2024-04-15"
+        );
+    }
+
+    #[test]
+    fn test_semi_monthly_clamps_without_drifting() {
+        let mut goal = goal_with(Recurrence::SemiMonthly(15, 31), "2024-01-15");
+        let mut dates = Vec::new();
+
+        for _ in 0..5 {
+            goal = goal.increment().unwrap();
+            dates.push(goal.target_date.date_naive().to_string());
+        }
+
+        assert_eq!(
+            dates,
+            [
+                "2024-01-31",
+                "2024-02-15",
+                "2024-02-29",
+                "2024-03-15",
+                "2024-03-31"
+            ]
+        );
+
+        let goal = goal_with(Recurrence::SemiMonthly(1, 15), "2024-03-01");
+        assert_eq!(
+            goal.start_at().unwrap().date_naive(),
+            NaiveDate::from_str("2024-02-15").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_custom_intervals() {
+        let goal = goal_with(Recurrence::EveryWeeks(2), "2024-01-05");
+        assert_eq!(
+            goal.increment().unwrap().target_date.date_naive(),
+            NaiveDate::from_str("2024-01-19").unwrap()
+        );
+
+        let goal = goal_with(Recurrence::EveryDays(10), "2024-02-25");
+        assert_eq!(
+            goal.increment().unwrap().target_date.date_naive(),
+            NaiveDate::from_str("2024-03-06").unwrap()
+        );
+
+        let goal = goal_with(Recurrence::EveryMonths(2), "2024-01-15");
+        assert_eq!(
+            goal.increment().unwrap().target_date.date_naive(),
+            NaiveDate::from_str("2024-03-15").unwrap()
+        );
+        assert_eq!(
+            goal.start_at().unwrap().date_naive(),
+            NaiveDate::from_str("2023-11-15").unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_custom_recurrence_round_trip() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        for recurrence in [
+            Recurrence::SemiMonthly(1, 15),
+            Recurrence::EveryDays(3),
+            Recurrence::LastDayOfMonth,
+        ] {
+            let mut goal = goal_with(recurrence.clone(), "2024-01-15");
+            goal.user_id = user_extension.0.id;
+            let goal = goal.create(&client).await.unwrap();
+
+            let goal = Goal::get_one(&client, goal.id.unwrap(), user_extension.0.id)
+                .await
+                .unwrap();
+            assert_eq!(goal.recurrence, recurrence);
+        }
+    }
 }
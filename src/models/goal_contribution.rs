@@ -0,0 +1,259 @@
+use crate::{errors::AppError, models::goal::Goal};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use postgres_types::{FromSql, ToSql};
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// What moved a goal's accumulated amount.
+#[derive(Debug, Clone, Copy, Serialize, FromSql, ToSql, PartialEq)]
+pub enum ContributionSource {
+    Accumulation,
+    Acceleration,
+    Manual,
+    Reset,
+}
+
+impl ContributionSource {
+    /// Changes the jobs make every minute, rather than ones a user asked for.
+    fn automatic(&self) -> bool {
+        matches!(self, Self::Accumulation | Self::Acceleration)
+    }
+}
+
+impl std::fmt::Display for ContributionSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Accumulation => write!(f, "Scheduled accumulation"),
+            Self::Acceleration => write!(f, "Acceleration"),
+            Self::Manual => write!(f, "Manual"),
+            Self::Reset => write!(f, "Reset"),
+        }
+    }
+}
+
+/// One change to a goal's accumulated amount; `amount` is the signed change and
+/// `accumulated_amount` the balance after it.
+#[derive(Serialize, Debug, Clone)]
+pub struct GoalContribution {
+    pub id: Uuid,
+    pub goal_id: Uuid,
+    pub user_id: Uuid,
+    pub source: ContributionSource,
+    pub amount: Decimal,
+    pub accumulated_amount: Decimal,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryInto<GoalContribution> for tokio_postgres::Row {
+    type Error = AppError;
+
+    fn try_into(self: tokio_postgres::Row) -> Result<GoalContribution, AppError> {
+        Ok(GoalContribution {
+            id: self
+                .try_get("id")
+                .map_err(AppError::RecordDeserializationError)?,
+            goal_id: self
+                .try_get("goal_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            user_id: self
+                .try_get("user_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            source: self
+                .try_get("source")
+                .map_err(AppError::RecordDeserializationError)?,
+            amount: self
+                .try_get("amount")
+                .map_err(AppError::RecordDeserializationError)?,
+            accumulated_amount: self
+                .try_get("accumulated_amount")
+                .map_err(AppError::RecordDeserializationError)?,
+            created_at: self
+                .try_get("created_at")
+                .map_err(AppError::RecordDeserializationError)?,
+        })
+    }
+}
+
+impl GoalContribution {
+    /// Records the difference between `previous` and `current`, if there is one. The jobs
+    /// accumulate every minute, so automatic changes are folded into the goal's latest
+    /// contribution from the same source and day (in UTC).
+    pub async fn record(
+        client: &impl GenericClient,
+        previous: &Goal,
+        current: &Goal,
+        source: ContributionSource,
+    ) -> Result<Option<Self>, AppError> {
+        if previous.accumulated_amount == current.accumulated_amount {
+            return Ok(None);
+        }
+
+        let Some(goal_id) = current.id else {
+            return Ok(None);
+        };
+        let amount = current.accumulated_amount - previous.accumulated_amount;
+
+        if source.automatic()
+            && let Some(row) = client
+                .query_opt(
+                    "UPDATE goal_contributions SET
+                        amount = goal_contributions.amount + $3
+                        , accumulated_amount = $4
+                    FROM (
+                        SELECT id, created_at FROM goal_contributions
+                        WHERE goal_id = $1 AND source = $2
+                        ORDER BY created_at DESC
+                        LIMIT 1
+                    ) latest
+                    WHERE goal_contributions.id = latest.id
+                        AND (latest.created_at AT TIME ZONE 'UTC')::date
+                            = (clock_timestamp() AT TIME ZONE 'UTC')::date
+                    RETURNING goal_contributions.*",
+                    &[&goal_id, &source, &amount, &current.accumulated_amount],
+                )
+                .await?
+        {
+            return row.try_into().map(Some);
+        }
+
+        client
+            .query_one(
+                "INSERT INTO goal_contributions (
+                    goal_id
+                    , user_id
+                    , source
+                    , amount
+                    , accumulated_amount
+                ) VALUES ($1, $2, $3, $4, $5) RETURNING *",
+                &[
+                    &goal_id,
+                    &current.user_id,
+                    &source,
+                    &amount,
+                    &current.accumulated_amount,
+                ],
+            )
+            .await?
+            .try_into()
+            .map(Some)
+    }
+
+    pub async fn get_all_for_goal(
+        client: &impl GenericClient,
+        goal_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT goal_contributions.* FROM goal_contributions
+                WHERE user_id = $1 AND goal_id = $2
+                ORDER BY created_at DESC",
+                &[&user_id, &goal_id],
+            )
+            .await?;
+
+        let mut contributions = Vec::with_capacity(rows.len());
+        for row in rows {
+            contributions.push(row.try_into()?);
+        }
+
+        Ok(contributions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContributionSource, GoalContribution};
+    use crate::{
+        models::goal::{EnvelopePolicy, Goal, Recurrence},
+        test_utils::state_for_tests,
+    };
+    use chrono::{Duration, Utc};
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_goal_changes_are_recorded() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let mut client = shared_state.pool.get_client().await.unwrap();
+        let client = client.transaction().await.unwrap();
+
+        let goal = Goal {
+            id: None,
+            user_id,
+            name: "vacation".to_string(),
+            recurrence: Recurrence::Monthly,
+            target_date: Utc::now() + Duration::days(10),
+            target: Decimal::new(300, 0),
+            accumulated_amount: Decimal::ZERO,
+            start_date: None,
+            envelope_id: None,
+            envelope_policy: EnvelopePolicy::Append,
+            priority: 0,
+            funding_weight: Decimal::ONE,
+            target_day: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let mut accumulated = goal.clone();
+        accumulated.accumulated_amount = Decimal::new(30, 0);
+        let accumulated = accumulated.update(&client).await.unwrap();
+        GoalContribution::record(
+            &client,
+            &goal,
+            &accumulated,
+            ContributionSource::Accumulation,
+        )
+        .await
+        .unwrap();
+
+        let goal = accumulated
+            .accelerate(&client, Decimal::new(120, 0))
+            .await
+            .unwrap();
+        let goal = goal.accelerate(&client, Decimal::ZERO).await.unwrap();
+
+        let mut accumulated = goal.clone();
+        accumulated.accumulated_amount = Decimal::new(160, 0);
+        let accumulated = accumulated.update(&client).await.unwrap();
+        GoalContribution::record(
+            &client,
+            &goal,
+            &accumulated,
+            ContributionSource::Accumulation,
+        )
+        .await
+        .unwrap();
+        let goal = accumulated;
+
+        let mut reset = goal.clone();
+        reset.accumulated_amount = Decimal::ZERO;
+        let reset = reset.update(&client).await.unwrap();
+        GoalContribution::record(&client, &goal, &reset, ContributionSource::Reset)
+            .await
+            .unwrap();
+
+        let contributions = GoalContribution::get_all_for_goal(&client, goal.id.unwrap(), user_id)
+            .await
+            .unwrap();
+
+        // The day's accumulation and acceleration are kept apart, each folded into its own row.
+        assert_eq!(contributions.len(), 3);
+        assert_eq!(contributions[0].source, ContributionSource::Reset);
+        assert_eq!(contributions[0].amount, Decimal::new(-160, 0));
+        assert_eq!(contributions[0].accumulated_amount, Decimal::ZERO);
+        assert_eq!(contributions[1].source, ContributionSource::Acceleration);
+        assert_eq!(contributions[1].amount, Decimal::new(120, 0));
+        assert_eq!(contributions[1].accumulated_amount, Decimal::new(150, 0));
+        assert_eq!(contributions[2].source, ContributionSource::Accumulation);
+        assert_eq!(contributions[2].amount, Decimal::new(40, 0));
+        assert_eq!(contributions[2].accumulated_amount, Decimal::new(160, 0));
+
+        client.rollback().await.unwrap();
+    }
+}
@@ -0,0 +1,436 @@
+use crate::{errors::AppError, utilities::dates::BudgetPeriod};
+use anyhow::{Result, anyhow};
+use chrono::{Datelike, Days, Months, NaiveDate};
+use postgres_types::{FromSql, ToSql};
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+/// How often an income source pays out. Semi-monthly sources pay on the 15th and on the last
+/// day of the month; `Once` covers irregular income expected on a single date.
+#[derive(Debug, Clone, Copy, Serialize, FromSql, ToSql, PartialEq)]
+pub enum IncomeRecurrence {
+    Once,
+    Weekly,
+    Biweekly,
+    SemiMonthly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+#[derive(Debug)]
+pub struct IncomeRecurrenceError {}
+
+impl std::str::FromStr for IncomeRecurrence {
+    fn from_str(string: &str) -> Result<Self, IncomeRecurrenceError> {
+        match string {
+            "once" => Ok(Self::Once),
+            "weekly" => Ok(Self::Weekly),
+            "biweekly" => Ok(Self::Biweekly),
+            "semi_monthly" => Ok(Self::SemiMonthly),
+            "monthly" => Ok(Self::Monthly),
+            "quarterly" => Ok(Self::Quarterly),
+            "yearly" => Ok(Self::Yearly),
+            _ => Err(IncomeRecurrenceError {}),
+        }
+    }
+
+    type Err = IncomeRecurrenceError;
+}
+
+impl std::fmt::Display for IncomeRecurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Once => write!(f, "once"),
+            Self::Weekly => write!(f, "weekly"),
+            Self::Biweekly => write!(f, "biweekly"),
+            Self::SemiMonthly => write!(f, "semi_monthly"),
+            Self::Monthly => write!(f, "monthly"),
+            Self::Quarterly => write!(f, "quarterly"),
+            Self::Yearly => write!(f, "yearly"),
+        }
+    }
+}
+
+impl IncomeRecurrence {
+    fn occurrences_per_year(&self) -> Decimal {
+        match self {
+            Self::Once => Decimal::ZERO,
+            Self::Weekly => Decimal::new(52, 0),
+            Self::Biweekly => Decimal::new(26, 0),
+            Self::SemiMonthly => Decimal::new(24, 0),
+            Self::Monthly => Decimal::new(12, 0),
+            Self::Quarterly => Decimal::new(4, 0),
+            Self::Yearly => Decimal::ONE,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct IncomeSource {
+    pub id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub name: String,
+    pub amount: Decimal,
+    pub recurrence: IncomeRecurrence,
+    pub next_expected_on: NaiveDate,
+}
+
+impl TryInto<IncomeSource> for tokio_postgres::Row {
+    type Error = AppError;
+
+    fn try_into(self: tokio_postgres::Row) -> Result<IncomeSource, AppError> {
+        Ok(IncomeSource {
+            id: self
+                .try_get("id")
+                .map_err(AppError::RecordDeserializationError)?,
+            user_id: self
+                .try_get("user_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            name: self
+                .try_get("name")
+                .map_err(AppError::RecordDeserializationError)?,
+            amount: self
+                .try_get("amount")
+                .map_err(AppError::RecordDeserializationError)?,
+            recurrence: self
+                .try_get("recurrence")
+                .map_err(AppError::RecordDeserializationError)?,
+            next_expected_on: self
+                .try_get("next_expected_on")
+                .map_err(AppError::RecordDeserializationError)?,
+        })
+    }
+}
+
+impl IncomeSource {
+    pub async fn create(&self, client: &impl GenericClient) -> Result<Self, AppError> {
+        let row = client
+            .query_one(
+                "INSERT INTO income_sources (
+                    user_id
+                    , name
+                    , amount
+                    , recurrence
+                    , next_expected_on
+                ) VALUES ($1, $2, $3, $4, $5) RETURNING id",
+                &[
+                    &self.user_id,
+                    &self.name,
+                    &self.amount,
+                    &self.recurrence,
+                    &self.next_expected_on,
+                ],
+            )
+            .await?;
+
+        let mut new_income_source = self.clone();
+        new_income_source.id = Some(row.try_get("id")?);
+        Ok(new_income_source)
+    }
+
+    pub async fn update(&self, client: &impl GenericClient) -> Result<(), AppError> {
+        client
+            .execute(
+                "UPDATE income_sources SET
+                    name = $1
+                    , amount = $2
+                    , recurrence = $3
+                    , next_expected_on = $4
+                WHERE id = $5 AND user_id = $6",
+                &[
+                    &self.name,
+                    &self.amount,
+                    &self.recurrence,
+                    &self.next_expected_on,
+                    &self.id,
+                    &self.user_id,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, client: &impl GenericClient) -> Result<(), AppError> {
+        client
+            .execute(
+                "DELETE FROM income_sources WHERE user_id = $1 AND id = $2",
+                &[&self.user_id, &self.id],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_one(
+        client: &impl GenericClient,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Self, AppError> {
+        client
+            .query_one(
+                "SELECT income_sources.* FROM income_sources
+                INNER JOIN users ON users.id = income_sources.user_id
+                WHERE users.id = $1 AND income_sources.id = $2",
+                &[&user_id, &id],
+            )
+            .await
+            .map_err(AppError::RecordNotFound)?
+            .try_into()
+    }
+
+    pub async fn get_all(
+        client: &impl GenericClient,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT income_sources.* FROM income_sources
+                WHERE user_id = $1 ORDER BY name",
+                &[&user_id],
+            )
+            .await?;
+
+        let mut income_sources = Vec::with_capacity(rows.len());
+        for row in rows {
+            income_sources.push(row.try_into()?);
+        }
+
+        Ok(income_sources)
+    }
+
+    /// Sums what the sources contribute to the budget period containing `today`.
+    pub async fn total_for_period(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        period: BudgetPeriod,
+        today: NaiveDate,
+    ) -> Result<Decimal, AppError> {
+        let (start, next) = period.bounds(today)?;
+
+        Ok(Self::get_all(client, user_id)
+            .await?
+            .iter()
+            .map(|income_source| income_source.amount_for_period(period, start, next))
+            .sum())
+    }
+
+    /// Recurring income is prorated by how many periods fit in a year, so a biweekly paycheck
+    /// counts the same in every period. One-off income only counts in the period it lands in.
+    pub fn amount_for_period(
+        &self,
+        period: BudgetPeriod,
+        start: NaiveDate,
+        next: NaiveDate,
+    ) -> Decimal {
+        match self.recurrence {
+            IncomeRecurrence::Once => {
+                if self.next_expected_on >= start && self.next_expected_on < next {
+                    self.amount
+                } else {
+                    Decimal::ZERO
+                }
+            }
+            recurrence => {
+                self.amount * recurrence.occurrences_per_year() / period.periods_per_year()
+            }
+        }
+    }
+
+    /// The first expected payment on or after `today`, following the recurrence forward from
+    /// the stored date.
+    pub fn next_expected_after(&self, today: NaiveDate) -> Result<NaiveDate> {
+        let mut date = self.next_expected_on;
+
+        if self.recurrence == IncomeRecurrence::Once {
+            return Ok(date);
+        }
+
+        // Counted from the stored date, so a source on the 31st is paid on each month's last
+        // day rather than drifting to the 28th after February.
+        let months = |count: u32| {
+            self.next_expected_on
+                .checked_add_months(Months::new(count))
+                .ok_or(anyhow!("could not add months"))
+        };
+        let mut steps = 0;
+
+        while date < today {
+            steps += 1;
+            date = match self.recurrence {
+                IncomeRecurrence::Once => date,
+                IncomeRecurrence::Weekly => date + Days::new(7),
+                IncomeRecurrence::Biweekly => date + Days::new(14),
+                IncomeRecurrence::SemiMonthly => {
+                    let fifteenth = date.with_day(15).ok_or(anyhow!("could not parse date"))?;
+                    let last_day = (date.with_day(1).ok_or(anyhow!("could not parse date"))?
+                        + Months::new(1))
+                    .pred_opt()
+                    .ok_or(anyhow!("could not parse date"))?;
+
+                    if date < fifteenth {
+                        fifteenth
+                    } else if date < last_day {
+                        last_day
+                    } else {
+                        fifteenth + Months::new(1)
+                    }
+                }
+                IncomeRecurrence::Monthly => months(steps)?,
+                IncomeRecurrence::Quarterly => months(steps * 3)?,
+                IncomeRecurrence::Yearly => months(steps * 12)?,
+            };
+        }
+
+        Ok(date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IncomeRecurrence, IncomeSource};
+    use crate::{test_utils::state_for_tests, utilities::dates::BudgetPeriod};
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    fn income_source(recurrence: IncomeRecurrence, amount: i64, on: NaiveDate) -> IncomeSource {
+        IncomeSource {
+            id: None,
+            user_id: Uuid::new_v4(),
+            name: "pay".to_string(),
+            amount: Decimal::new(amount, 0),
+            recurrence,
+            next_expected_on: on,
+        }
+    }
+
+    #[test]
+    fn test_amount_for_period_is_prorated() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let (start, next) = (date(2024, 1, 1), date(2024, 2, 1));
+
+        let biweekly = income_source(IncomeRecurrence::Biweekly, 1200, date(2024, 1, 5));
+        assert_eq!(
+            biweekly.amount_for_period(BudgetPeriod::CalendarMonth, start, next),
+            Decimal::new(2600, 0)
+        );
+        assert_eq!(
+            biweekly.amount_for_period(
+                BudgetPeriod::Biweekly {
+                    anchor: date(2024, 1, 5)
+                },
+                start,
+                next
+            ),
+            Decimal::new(1200, 0)
+        );
+
+        let monthly = income_source(IncomeRecurrence::Monthly, 2400, date(2024, 1, 31));
+        assert_eq!(
+            monthly.amount_for_period(BudgetPeriod::SemiMonthly, start, next),
+            Decimal::new(1200, 0)
+        );
+
+        let bonus = income_source(IncomeRecurrence::Once, 500, date(2024, 1, 20));
+        assert_eq!(
+            bonus.amount_for_period(BudgetPeriod::CalendarMonth, start, next),
+            Decimal::new(500, 0)
+        );
+        assert_eq!(
+            bonus.amount_for_period(BudgetPeriod::CalendarMonth, next, date(2024, 3, 1)),
+            Decimal::ZERO
+        );
+    }
+
+    #[test]
+    fn test_next_expected_after() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        let semi_monthly = income_source(IncomeRecurrence::SemiMonthly, 1, date(2024, 1, 15));
+        assert_eq!(
+            semi_monthly.next_expected_after(date(2024, 1, 16)).unwrap(),
+            date(2024, 1, 31)
+        );
+        assert_eq!(
+            semi_monthly.next_expected_after(date(2024, 2, 1)).unwrap(),
+            date(2024, 2, 15)
+        );
+        assert_eq!(
+            semi_monthly.next_expected_after(date(2024, 2, 16)).unwrap(),
+            date(2024, 2, 29)
+        );
+
+        let biweekly = income_source(IncomeRecurrence::Biweekly, 1, date(2024, 1, 5));
+        assert_eq!(
+            biweekly.next_expected_after(date(2024, 1, 20)).unwrap(),
+            date(2024, 2, 2)
+        );
+    }
+
+    #[test]
+    fn test_month_end_does_not_drift() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        let monthly = income_source(IncomeRecurrence::Monthly, 1, date(2024, 1, 31));
+        assert_eq!(
+            monthly.next_expected_after(date(2024, 2, 1)).unwrap(),
+            date(2024, 2, 29)
+        );
+        assert_eq!(
+            monthly.next_expected_after(date(2024, 3, 1)).unwrap(),
+            date(2024, 3, 31)
+        );
+        assert_eq!(
+            monthly.next_expected_after(date(2024, 4, 1)).unwrap(),
+            date(2024, 4, 30)
+        );
+
+        let quarterly = income_source(IncomeRecurrence::Quarterly, 1, date(2023, 11, 30));
+        assert_eq!(
+            quarterly.next_expected_after(date(2024, 3, 1)).unwrap(),
+            date(2024, 5, 30)
+        );
+
+        let yearly = income_source(IncomeRecurrence::Yearly, 1, date(2024, 2, 29));
+        assert_eq!(
+            yearly.next_expected_after(date(2025, 3, 1)).unwrap(),
+            date(2026, 2, 28)
+        );
+        assert_eq!(
+            yearly.next_expected_after(date(2027, 3, 1)).unwrap(),
+            date(2028, 2, 29)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_total_for_period() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        for (recurrence, amount) in [
+            (IncomeRecurrence::Monthly, 3000),
+            (IncomeRecurrence::Weekly, 300),
+        ] {
+            let mut income_source = income_source(recurrence, amount, date(2024, 1, 1));
+            income_source.user_id = user_id;
+            income_source.create(&client).await.unwrap();
+        }
+
+        let total = IncomeSource::total_for_period(
+            &client,
+            user_id,
+            BudgetPeriod::CalendarMonth,
+            date(2024, 1, 10),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(total, Decimal::new(4300, 0));
+    }
+}
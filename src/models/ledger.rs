@@ -0,0 +1,544 @@
+use crate::{
+    errors::AppError,
+    models::{account::Account, envelope::Envelope, goal::Goal, transaction::Transaction},
+};
+use chrono::NaiveDate;
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use uuid::Uuid;
+
+const OPENING_BALANCES: &str = "Equity:Opening-Balances";
+const UNTRACKED: &str = "Equity:Untracked";
+const UNCATEGORIZED_INCOME: &str = "Income:Uncategorized";
+const UNCATEGORIZED_EXPENSES: &str = "Expenses:Uncategorized";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LedgerFormat {
+    #[default]
+    Hledger,
+    Beancount,
+}
+
+impl LedgerFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LedgerFormat::Hledger => "journal",
+            LedgerFormat::Beancount => "beancount",
+        }
+    }
+}
+
+/// Commodities are written the way Beancount spells them, which hledger also accepts.
+pub fn is_valid_commodity(commodity: &str) -> bool {
+    let bytes = commodity.as_bytes();
+    let Some((first, rest)) = bytes.split_first() else {
+        return false;
+    };
+    let last = rest.last().unwrap_or(first);
+
+    bytes.len() <= 24
+        && first.is_ascii_uppercase()
+        && (last.is_ascii_uppercase() || last.is_ascii_digit())
+        && rest
+            .iter()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || b"'._-".contains(c))
+}
+
+/// Everything a plain-text accounting journal for `from` through `to` is built from.
+#[derive(Debug, Clone)]
+pub struct Ledger {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub commodity: String,
+    pub accounts: Vec<Account>,
+    pub envelopes: Vec<Envelope>,
+    pub goals: Vec<Goal>,
+    /// Transactions in the range, oldest first.
+    pub transactions: Vec<Transaction>,
+    /// How far transactions on or after `from` have moved each account, to work back to its
+    /// opening balance.
+    pub moved_since: HashMap<Uuid, Decimal>,
+}
+
+#[derive(Debug)]
+struct Posting {
+    account: String,
+    amount: Decimal,
+    target: Option<Decimal>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    date: NaiveDate,
+    description: String,
+    postings: Vec<Posting>,
+    /// Envelope and goal balances, which only move money between budget accounts.
+    budget: bool,
+}
+
+impl Ledger {
+    pub async fn load(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        from: NaiveDate,
+        to: NaiveDate,
+        commodity: String,
+    ) -> Result<Self, AppError> {
+        let moved_since = client
+            .query(
+                "SELECT account_id, sum(amount) AS moved FROM transactions
+                WHERE user_id = $1 AND account_id IS NOT NULL AND occurred_on >= $2
+                GROUP BY account_id",
+                &[&user_id, &from],
+            )
+            .await?
+            .iter()
+            .map(|row| Ok((row.try_get("account_id")?, row.try_get("moved")?)))
+            .collect::<Result<HashMap<_, _>, AppError>>()?;
+
+        Ok(Ledger {
+            from,
+            to,
+            commodity,
+            accounts: Account::get_all(client, user_id).await?,
+            envelopes: Envelope::get_all(client, user_id).await?,
+            goals: Goal::get_all(client, user_id).await?,
+            transactions: Transaction::get_all_between(client, user_id, from, to).await?,
+            moved_since,
+        })
+    }
+
+    pub fn render(&self, format: LedgerFormat) -> String {
+        let (declared, entries) = self.entries();
+
+        match format {
+            LedgerFormat::Hledger => self.render_hledger(declared, &entries),
+            LedgerFormat::Beancount => self.render_beancount(declared, &entries),
+        }
+    }
+
+    /// Builds the entries shared by both formats, along with every real account they use.
+    fn entries(&self) -> (BTreeSet<String>, Vec<Entry>) {
+        let mut used = HashSet::new();
+        let mut accounts = self.accounts.iter().collect::<Vec<_>>();
+        accounts.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+        let account_names = accounts
+            .iter()
+            .map(|account| {
+                let root = if account.debt {
+                    "Liabilities"
+                } else {
+                    "Assets"
+                };
+                (account.id, unique_name(root, &account.name, &mut used))
+            })
+            .collect::<HashMap<_, _>>();
+        let mut envelopes = self.envelopes.iter().collect::<Vec<_>>();
+        envelopes.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id)));
+        let envelope_names = envelopes
+            .iter()
+            .map(|envelope| {
+                (
+                    envelope.id,
+                    unique_name("Expenses", &envelope.name, &mut used),
+                )
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut declared = account_names
+            .values()
+            .chain(envelope_names.values())
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let mut entries = Vec::new();
+
+        let mut opening = accounts
+            .iter()
+            .filter_map(|account| {
+                let balance = if account.debt {
+                    -account.amount
+                } else {
+                    account.amount
+                };
+                let moved = self
+                    .moved_since
+                    .get(&account.id?)
+                    .copied()
+                    .unwrap_or_default();
+
+                posting(account_names[&account.id].clone(), balance - moved)
+            })
+            .collect::<Vec<_>>();
+        if !opening.is_empty() {
+            let total = opening
+                .iter()
+                .map(|posting| posting.amount)
+                .sum::<Decimal>();
+            opening.extend(posting(OPENING_BALANCES.to_string(), -total));
+            declared.extend(opening.iter().map(|posting| posting.account.clone()));
+            entries.push(Entry {
+                date: self.from,
+                description: "Opening balances".to_string(),
+                postings: opening,
+                budget: false,
+            });
+        }
+
+        for transaction in &self.transactions {
+            let account = transaction
+                .account_id
+                .and_then(|id| account_names.get(&Some(id)))
+                .cloned()
+                .unwrap_or_else(|| UNTRACKED.to_string());
+            let category = match transaction.envelope_id {
+                Some(id) => envelope_names.get(&Some(id)).cloned(),
+                None => None,
+            }
+            .unwrap_or_else(|| {
+                if transaction.amount >= Decimal::ZERO {
+                    UNCATEGORIZED_INCOME.to_string()
+                } else {
+                    UNCATEGORIZED_EXPENSES.to_string()
+                }
+            });
+
+            let postings = posting(account, transaction.amount)
+                .into_iter()
+                .chain(posting(category, -transaction.amount))
+                .collect::<Vec<_>>();
+            if postings.is_empty() {
+                continue;
+            }
+            declared.extend(postings.iter().map(|posting| posting.account.clone()));
+            entries.push(Entry {
+                date: transaction.occurred_on,
+                description: transaction.description.clone(),
+                postings,
+                budget: false,
+            });
+        }
+
+        let mut budget = Vec::new();
+        for envelope in envelopes {
+            let name = unique_name("Budget:Envelopes", &envelope.name, &mut used);
+            budget.extend(posting(name, envelope.amount));
+        }
+        for goal in &self.goals {
+            let name = unique_name("Budget:Goals", &goal.name, &mut used);
+            budget.extend(
+                posting(name, goal.accumulated_amount).map(|posting| Posting {
+                    target: Some(goal.target.round_dp(2)),
+                    ..posting
+                }),
+            );
+        }
+        if !budget.is_empty() {
+            entries.push(Entry {
+                date: self.to,
+                description: "Budget".to_string(),
+                postings: budget,
+                budget: true,
+            });
+        }
+
+        (declared, entries)
+    }
+
+    fn amount(&self, amount: Decimal) -> String {
+        format!("{:.2} {}", amount, self.commodity)
+    }
+
+    fn header(&self) -> String {
+        format!(
+            "; Exported from Simple Budget for {} to {}\n",
+            self.from, self.to
+        )
+    }
+
+    fn render_hledger(&self, mut declared: BTreeSet<String>, entries: &[Entry]) -> String {
+        let mut journal = self.header();
+        journal.push_str(&format!("\ncommodity 1000.00 {}\n\n", self.commodity));
+
+        declared.extend(
+            entries
+                .iter()
+                .filter(|entry| entry.budget)
+                .flat_map(|entry| entry.postings.iter().map(|posting| posting.account.clone())),
+        );
+        for account in &declared {
+            journal.push_str(&format!("account {account}\n"));
+        }
+
+        for entry in entries {
+            let description = entry
+                .description
+                .replace(['\r', '\n'], " ")
+                .replace(';', ",");
+            journal.push_str(&format!("\n{} {}\n", entry.date, description.trim()));
+            if entry.budget {
+                journal
+                    .push_str("    ; Envelope and goal balances when the journal was exported\n");
+            }
+
+            for posting in &entry.postings {
+                let account = if entry.budget {
+                    format!("({})", posting.account)
+                } else {
+                    posting.account.clone()
+                };
+                journal.push_str(&format!("    {account}  {}", self.amount(posting.amount)));
+                if let Some(target) = posting.target {
+                    journal.push_str(&format!("  ; target: {}", self.amount(target)));
+                }
+                journal.push('\n');
+            }
+        }
+
+        journal
+    }
+
+    fn render_beancount(&self, mut declared: BTreeSet<String>, entries: &[Entry]) -> String {
+        let mut journal = self.header();
+        journal.push_str(&format!(
+            "\noption \"operating_currency\" \"{}\"\n\n",
+            self.commodity
+        ));
+
+        for entry in entries.iter().filter(|entry| entry.budget) {
+            declared.extend(
+                entry
+                    .postings
+                    .iter()
+                    .map(|posting| format!("Equity:{}", posting.account)),
+            );
+            declared.insert("Equity:Budget:Unallocated".to_string());
+        }
+        for account in &declared {
+            journal.push_str(&format!(
+                "{} open {account} {}\n",
+                self.from, self.commodity
+            ));
+        }
+
+        for entry in entries {
+            let description = entry
+                .description
+                .replace(['\r', '\n'], " ")
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+            journal.push_str(&format!("\n{} * \"{}\"\n", entry.date, description.trim()));
+            if entry.budget {
+                journal.push_str("  ; Envelope and goal balances when the journal was exported\n");
+            }
+
+            for posting in &entry.postings {
+                let account = if entry.budget {
+                    format!("Equity:{}", posting.account)
+                } else {
+                    posting.account.clone()
+                };
+                journal.push_str(&format!("  {account}  {}\n", self.amount(posting.amount)));
+                if let Some(target) = posting.target {
+                    journal.push_str(&format!("    target: {}\n", self.amount(target)));
+                }
+            }
+            if entry.budget {
+                let total = entry
+                    .postings
+                    .iter()
+                    .map(|posting| posting.amount)
+                    .sum::<Decimal>();
+                journal.push_str(&format!(
+                    "  Equity:Budget:Unallocated  {}\n",
+                    self.amount(-total)
+                ));
+            }
+        }
+
+        journal
+    }
+}
+
+/// A posting of `amount` rounded to cents, or nothing when that is zero.
+fn posting(account: String, amount: Decimal) -> Option<Posting> {
+    let amount = amount.round_dp(2);
+
+    (!amount.is_zero()).then_some(Posting {
+        account,
+        amount,
+        target: None,
+    })
+}
+
+/// Turns `name` into an account name under `root` that both formats accept, numbering it when
+/// another account already has it.
+fn unique_name(root: &str, name: &str, used: &mut HashSet<String>) -> String {
+    let words = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    let component = if words.is_empty() {
+        "Unnamed".to_string()
+    } else {
+        words.join("-")
+    };
+
+    let mut candidate = format!("{root}:{component}");
+    let mut number = 2;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("{root}:{component}-{number}");
+        number += 1;
+    }
+
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ledger, LedgerFormat, is_valid_commodity};
+    use crate::models::{
+        account::Account,
+        envelope::Envelope,
+        goal::{EnvelopePolicy, Goal, Recurrence},
+        transaction::Transaction,
+    };
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use std::collections::HashMap;
+    use uuid::Uuid;
+
+    fn ledger() -> Ledger {
+        let user_id = Uuid::from_u128(1);
+        let checking = Uuid::from_u128(2);
+        let card = Uuid::from_u128(3);
+        let groceries = Uuid::from_u128(4);
+        let date = |day| NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+        let transaction = |account_id, envelope_id, amount, description: &str, day| Transaction {
+            id: None,
+            user_id,
+            account_id,
+            envelope_id,
+            amount: Decimal::new(amount, 2),
+            description: description.to_string(),
+            occurred_on: date(day),
+            adjustment: false,
+        };
+        let account = |id, name: &str, amount, debt| Account {
+            id: Some(id),
+            user_id,
+            name: name.to_string(),
+            amount: Decimal::new(amount, 2),
+            debt,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        };
+
+        Ledger {
+            from: date(1),
+            to: date(31),
+            commodity: "USD".to_string(),
+            accounts: vec![
+                account(checking, "My checking", 245490, false),
+                account(card, "Visa card", 16510, true),
+            ],
+            envelopes: vec![
+                Envelope {
+                    id: Some(groceries),
+                    user_id,
+                    name: "groceries".to_string(),
+                    amount: Decimal::new(25490, 2),
+                },
+                Envelope {
+                    id: Some(Uuid::from_u128(5)),
+                    user_id,
+                    name: "Eating out".to_string(),
+                    amount: Decimal::ZERO,
+                },
+            ],
+            goals: vec![Goal {
+                id: Some(Uuid::from_u128(6)),
+                user_id,
+                name: "Trip to \"Lisbon\"".to_string(),
+                recurrence: Recurrence::Never,
+                target_date: Utc.with_ymd_and_hms(2024, 9, 1, 0, 0, 0).unwrap(),
+                target: Decimal::new(100000, 2),
+                accumulated_amount: Decimal::new(15000, 2),
+                start_date: None,
+                envelope_id: None,
+                envelope_policy: EnvelopePolicy::Append,
+                priority: 0,
+                funding_weight: Decimal::ONE,
+                target_day: None,
+            }],
+            transactions: vec![
+                transaction(Some(checking), None, 200000, "Paycheck", 1),
+                transaction(
+                    Some(checking),
+                    Some(groceries),
+                    -4510,
+                    "Corner grocery; deli",
+                    2,
+                ),
+                transaction(Some(card), None, -6510, "Gas \"Go\"", 4),
+                transaction(None, Some(groceries), -1200, "Farmers market", 9),
+            ],
+            moved_since: HashMap::from([
+                (checking, Decimal::new(195490, 2)),
+                (card, Decimal::new(-6510, 2)),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_render_hledger_journal() {
+        assert_eq!(
+            ledger().render(LedgerFormat::Hledger),
+            include_str!("../../fixtures/ledger/budget.journal")
+        );
+    }
+
+    #[test]
+    fn test_render_beancount_journal() {
+        assert_eq!(
+            ledger().render(LedgerFormat::Beancount),
+            include_str!("../../fixtures/ledger/budget.beancount")
+        );
+    }
+
+    #[test]
+    fn test_render_empty_ledger() {
+        let ledger = Ledger {
+            accounts: Vec::new(),
+            envelopes: Vec::new(),
+            goals: Vec::new(),
+            transactions: Vec::new(),
+            ..ledger()
+        };
+
+        assert_eq!(
+            ledger.render(LedgerFormat::Hledger),
+            "; Exported from Simple Budget for 2024-03-01 to 2024-03-31\n\ncommodity 1000.00 USD\n\n"
+        );
+    }
+
+    #[test]
+    fn test_valid_commodities() {
+        assert!(is_valid_commodity("USD"));
+        assert!(is_valid_commodity("VANGUARD_500"));
+        assert!(!is_valid_commodity("usd"));
+        assert!(!is_valid_commodity("$"));
+        assert!(!is_valid_commodity("USD-"));
+        assert!(!is_valid_commodity(""));
+    }
+}
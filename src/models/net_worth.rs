@@ -0,0 +1,252 @@
+use crate::{
+    errors::AppError,
+    models::{account::Account, account_balance::AccountBalance, user::User},
+    utilities::dates::end_of_day,
+};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc, Weekday};
+use chrono_tz::Tz;
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// More points than a report can usefully show.
+const MAXIMUM_POINTS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Daily,
+    #[default]
+    Weekly,
+    Monthly,
+}
+
+impl Granularity {
+    /// The last day of each day, Monday-to-Sunday week, or calendar month from `from` through
+    /// `to`. The final period is cut short at `to`.
+    pub fn period_ends(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<NaiveDate>> {
+        let mut ends = Vec::new();
+        let mut date = from;
+
+        while date <= to {
+            let end = match self {
+                Self::Daily => date,
+                Self::Weekly => date.week(Weekday::Mon).last_day(),
+                Self::Monthly => (date.with_day(1).ok_or(anyhow!("could not parse date"))?
+                    + Months::new(1))
+                .pred_opt()
+                .ok_or(anyhow!("could not parse date"))?,
+            };
+            let end = end.min(to);
+
+            ends.push(end);
+            date = end.succ_opt().ok_or(anyhow!("could not parse date"))?;
+        }
+
+        Ok(ends)
+    }
+}
+
+/// The dates a report covers, from `from` through `to`. They default to the six months up to
+/// `today`; backwards ranges and ones with too many points are refused.
+pub fn report_range(
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    granularity: Granularity,
+    today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate), AppError> {
+    let to = to.unwrap_or(today);
+    let from = match from {
+        Some(from) => from,
+        None => to
+            .checked_sub_months(Months::new(6))
+            .ok_or_else(|| anyhow!("could not subtract months"))?,
+    };
+
+    if from > to {
+        return Err(AppError::InvalidRecord(anyhow!(
+            "the start of the range must be before the end"
+        )));
+    }
+    if granularity.period_ends(from, to)?.len() > MAXIMUM_POINTS {
+        return Err(AppError::InvalidRecord(anyhow!(
+            "choose a shorter range or a coarser granularity"
+        )));
+    }
+
+    Ok((from, to))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountValue {
+    pub account_id: Uuid,
+    pub name: String,
+    pub amount: Decimal,
+    pub debt: bool,
+}
+
+/// Assets, debts and their difference at the end of `date`.
+#[derive(Debug, Clone, Serialize)]
+pub struct NetWorthPoint {
+    pub date: NaiveDate,
+    pub assets: Decimal,
+    pub debts: Decimal,
+    pub net_worth: Decimal,
+    pub accounts: Vec<AccountValue>,
+}
+
+pub async fn net_worth_for(
+    client: &impl GenericClient,
+    user: &User,
+    from: NaiveDate,
+    to: NaiveDate,
+    granularity: Granularity,
+) -> Result<Vec<NetWorthPoint>, AppError> {
+    let timezone: Tz = user.timezone()?.parse()?;
+    let ends = granularity
+        .period_ends(from, to)?
+        .into_iter()
+        .map(|date| Ok((date, end_of_day(timezone, date)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let Some((_, until)) = ends.last() else {
+        return Ok(Vec::new());
+    };
+
+    let accounts = Account::get_all(client, user.id).await?;
+    let balances = AccountBalance::get_all_until(client, user.id, *until).await?;
+
+    Ok(net_worth(&accounts, &balances, &ends))
+}
+
+/// Each account counts with the last balance recorded before each point in `ends`; accounts
+/// with nothing recorded yet are left out. `balances` must be ordered by `recorded_at`.
+pub fn net_worth(
+    accounts: &[Account],
+    balances: &[AccountBalance],
+    ends: &[(NaiveDate, DateTime<Utc>)],
+) -> Vec<NetWorthPoint> {
+    let mut latest: HashMap<Uuid, &AccountBalance> = HashMap::new();
+    let mut balances = balances.iter().peekable();
+    let mut points = Vec::with_capacity(ends.len());
+
+    for (date, until) in ends {
+        while let Some(balance) = balances.next_if(|balance| balance.recorded_at < *until) {
+            latest.insert(balance.account_id, balance);
+        }
+
+        let accounts = accounts
+            .iter()
+            .filter_map(|account| {
+                let balance = latest.get(&account.id?)?;
+                Some(AccountValue {
+                    account_id: balance.account_id,
+                    name: account.name.clone(),
+                    amount: balance.amount,
+                    debt: balance.debt,
+                })
+            })
+            .collect::<Vec<_>>();
+        let total = |debt: bool| -> Decimal {
+            accounts
+                .iter()
+                .filter(|account| account.debt == debt)
+                .map(|account| account.amount)
+                .sum()
+        };
+        let (assets, debts) = (total(false), total(true));
+
+        points.push(NetWorthPoint {
+            date: *date,
+            assets,
+            debts,
+            net_worth: assets - debts,
+            accounts,
+        });
+    }
+
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Granularity, net_worth};
+    use crate::models::{account::Account, account_balance::AccountBalance};
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    fn date(m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_period_ends() {
+        assert_eq!(
+            Granularity::Weekly
+                .period_ends(date(1, 3), date(1, 16))
+                .unwrap(),
+            [date(1, 7), date(1, 14), date(1, 16)]
+        );
+        assert_eq!(
+            Granularity::Monthly
+                .period_ends(date(1, 15), date(3, 10))
+                .unwrap(),
+            [date(1, 31), date(2, 29), date(3, 10)]
+        );
+        assert_eq!(
+            Granularity::Daily
+                .period_ends(date(1, 1), date(1, 3))
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_net_worth_uses_latest_balance() {
+        let user_id = Uuid::new_v4();
+        let account = |name: &str, debt| Account {
+            id: Some(Uuid::new_v4()),
+            user_id,
+            name: name.to_string(),
+            amount: Decimal::ZERO,
+            debt,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        };
+        let (checking, card) = (account("checking", false), account("card", true));
+        let balance = |account: &Account, amount, day| AccountBalance {
+            id: Uuid::new_v4(),
+            account_id: account.id.unwrap(),
+            user_id,
+            amount: Decimal::new(amount, 0),
+            debt: account.debt,
+            recorded_at: Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap(),
+        };
+        let balances = [
+            balance(&checking, 1000, 1),
+            balance(&card, 300, 2),
+            balance(&checking, 1200, 2),
+            balance(&card, 100, 5),
+        ];
+        let ends = [1, 4, 5].map(|day| {
+            (
+                date(1, day),
+                Utc.with_ymd_and_hms(2024, 1, day + 1, 0, 0, 0).unwrap(),
+            )
+        });
+
+        let points = net_worth(&[checking, card], &balances, &ends);
+
+        assert_eq!(points[0].net_worth, Decimal::new(1000, 0));
+        assert_eq!(points[0].accounts.len(), 1);
+        assert_eq!(points[1].assets, Decimal::new(1200, 0));
+        assert_eq!(points[1].debts, Decimal::new(300, 0));
+        assert_eq!(points[2].net_worth, Decimal::new(1100, 0));
+        assert_eq!(points[2].accounts[1].amount, Decimal::new(100, 0));
+    }
+}
@@ -0,0 +1,189 @@
+pub mod csv;
+pub mod ofx;
+
+use crate::{
+    errors::AppError,
+    models::{account::Account, transaction::Transaction},
+};
+use anyhow::anyhow;
+use chrono::NaiveDate;
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// One line of a bank statement. Rows with an `amount` become transactions.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatementRow {
+    pub occurred_on: NaiveDate,
+    pub description: String,
+    pub amount: Option<Decimal>,
+    pub balance: Option<Decimal>,
+    pub duplicate: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub duplicates: usize,
+    pub balance: Decimal,
+}
+
+/// Flags rows matching a transaction already on the account by date, amount and description.
+/// Each existing transaction matches at most one row, so repeated purchases within a new
+/// statement are kept.
+pub fn mark_duplicates(rows: &mut [StatementRow], existing: &[Transaction]) {
+    let mut remaining: HashMap<(NaiveDate, Decimal, &str), usize> = HashMap::new();
+    for transaction in existing {
+        *remaining
+            .entry((
+                transaction.occurred_on,
+                transaction.amount.normalize(),
+                transaction.description.trim(),
+            ))
+            .or_default() += 1;
+    }
+
+    for row in rows.iter_mut() {
+        let Some(amount) = row.amount else {
+            continue;
+        };
+
+        if let Some(count) = remaining
+            .get_mut(&(row.occurred_on, amount.normalize(), row.description.trim()))
+            .filter(|count| **count > 0)
+        {
+            *count -= 1;
+            row.duplicate = true;
+        }
+    }
+}
+
+/// The balance on the most recent row. Statements may list rows oldest or newest first;
+/// rows sharing the latest date are taken in the statement's own order.
+pub fn closing_balance(rows: &[StatementRow]) -> Option<Decimal> {
+    let newest_first = match (rows.first(), rows.last()) {
+        (Some(first), Some(last)) => first.occurred_on > last.occurred_on,
+        _ => false,
+    };
+    let mut closing: Option<&StatementRow> = None;
+
+    for row in rows.iter().filter(|row| row.balance.is_some()) {
+        closing = match closing {
+            Some(current)
+                if current.occurred_on > row.occurred_on
+                    || (newest_first && current.occurred_on == row.occurred_on) =>
+            {
+                Some(current)
+            }
+            _ => Some(row),
+        };
+    }
+
+    closing.and_then(|row| row.balance)
+}
+
+/// Records each non-duplicate row with an amount as a transaction on `account`, then adjusts
+/// the account to the statement's closing `balance` as of its date when there is one. Run
+/// inside a database transaction.
+pub async fn import(
+    client: &impl GenericClient,
+    account: &Account,
+    rows: &[StatementRow],
+    balance: Option<(Decimal, NaiveDate)>,
+) -> Result<ImportSummary, AppError> {
+    let account_id = account.id.ok_or_else(|| anyhow!("account was not saved"))?;
+    let mut imported = 0;
+
+    for row in rows.iter().filter(|row| !row.duplicate) {
+        let Some(amount) = row.amount else {
+            continue;
+        };
+
+        Transaction {
+            id: None,
+            user_id: account.user_id,
+            account_id: Some(account_id),
+            envelope_id: None,
+            amount,
+            description: row.description.trim().to_string(),
+            occurred_on: row.occurred_on,
+            adjustment: false,
+        }
+        .create(client)
+        .await?;
+        imported += 1;
+    }
+
+    let mut account = Account::get_one(client, account_id, account.user_id).await?;
+    if let Some((balance, balance_on)) = balance {
+        account.adjust_balance(client, balance, balance_on).await?;
+    }
+
+    Ok(ImportSummary {
+        imported,
+        duplicates: rows.iter().filter(|row| row.duplicate).count(),
+        balance: account.amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StatementRow, closing_balance, mark_duplicates};
+    use crate::models::transaction::Transaction;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+    use uuid::Uuid;
+
+    fn row(day: u32, description: &str, amount: i64, balance: Option<i64>) -> StatementRow {
+        StatementRow {
+            occurred_on: NaiveDate::from_ymd_opt(2024, 3, day).unwrap(),
+            description: description.to_string(),
+            amount: Some(Decimal::new(amount, 2)),
+            balance: balance.map(|balance| Decimal::new(balance, 2)),
+            duplicate: false,
+        }
+    }
+
+    #[test]
+    fn test_mark_duplicates_matches_each_transaction_once() {
+        let existing = Transaction {
+            id: Some(Uuid::new_v4()),
+            user_id: Uuid::nil(),
+            account_id: None,
+            envelope_id: None,
+            amount: Decimal::new(-4500, 3),
+            description: "Coffee".to_string(),
+            occurred_on: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+            adjustment: false,
+        };
+        let mut rows = [
+            row(1, "Coffee ", -450, None),
+            row(1, "Coffee", -450, None),
+            row(2, "Coffee", -450, None),
+        ];
+
+        mark_duplicates(&mut rows, &[existing]);
+
+        assert_eq!(rows.map(|row| row.duplicate), [true, false, false]);
+    }
+
+    #[test]
+    fn test_closing_balance_uses_latest_row() {
+        let oldest_first = [
+            row(1, "a", -100, Some(900)),
+            row(3, "b", -100, Some(800)),
+            row(3, "c", -100, Some(700)),
+            row(4, "d", 0, None),
+        ];
+        let newest_first = [
+            row(3, "c", -100, Some(700)),
+            row(3, "b", -100, Some(800)),
+            row(1, "a", -100, Some(900)),
+        ];
+
+        assert_eq!(closing_balance(&oldest_first), Some(Decimal::new(700, 2)));
+        assert_eq!(closing_balance(&newest_first), Some(Decimal::new(700, 2)));
+        assert_eq!(closing_balance(&oldest_first[3..]), None);
+    }
+}
@@ -0,0 +1,200 @@
+use super::StatementRow;
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+pub const DATE_FORMATS: [&str; 4] = ["%Y-%m-%d", "%m/%d/%Y", "%d/%m/%Y", "%d.%m.%Y"];
+
+/// How to read a statement. Columns are zero-based.
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub date_column: usize,
+    pub description_column: usize,
+    pub amount_column: Option<usize>,
+    pub balance_column: Option<usize>,
+    pub date_format: String,
+    pub delimiter: u8,
+    pub decimal_comma: bool,
+    pub has_header: bool,
+    /// For statements that list money out as positive amounts.
+    pub flip_signs: bool,
+}
+
+/// Reads every row of `input`, reporting all the rows that could not be read at once.
+pub fn parse(input: &str, options: &CsvOptions) -> Result<Vec<StatementRow>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .has_headers(options.has_header)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(input.as_bytes());
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+
+    for record in reader.records() {
+        let record = record?;
+        let line = record
+            .position()
+            .map(|position| position.line())
+            .unwrap_or_default();
+
+        match parse_record(&record, options) {
+            Ok(row) => rows.push(row),
+            Err(e) => errors.push(format!("line {line}: {e}")),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(anyhow!(errors.join("\n")));
+    }
+    if rows.is_empty() {
+        return Err(anyhow!("the file has no rows to import"));
+    }
+
+    Ok(rows)
+}
+
+fn parse_record(record: &csv::StringRecord, options: &CsvOptions) -> Result<StatementRow> {
+    let column = |index: usize| {
+        record
+            .get(index)
+            .ok_or_else(|| anyhow!("there is no column {}", index + 1))
+    };
+
+    let date = column(options.date_column)?;
+    let occurred_on = NaiveDate::parse_from_str(date, &options.date_format)
+        .map_err(|_| anyhow!("could not read the date \"{date}\""))?;
+    let description = column(options.description_column)?.to_string();
+    if description.is_empty() {
+        return Err(anyhow!("the description is empty"));
+    }
+
+    let amount = match options.amount_column {
+        Some(index) => parse_amount(column(index)?, options.decimal_comma)?
+            .map(|amount| if options.flip_signs { -amount } else { amount }),
+        None => None,
+    };
+    let balance = match options.balance_column {
+        Some(index) => parse_amount(column(index)?, options.decimal_comma)?,
+        None => None,
+    };
+    if amount.is_none() && balance.is_none() {
+        return Err(anyhow!("the row has no amount or balance"));
+    }
+
+    Ok(StatementRow {
+        occurred_on,
+        description,
+        amount,
+        balance,
+        duplicate: false,
+    })
+}
+
+/// Accepts currency symbols, thousands separators, and negatives written with a leading or
+/// trailing minus sign or in parentheses. Empty cells have no amount.
+pub fn parse_amount(value: &str, decimal_comma: bool) -> Result<Option<Decimal>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let negative = value.starts_with('-')
+        || value.ends_with('-')
+        || (value.starts_with('(') && value.ends_with(')'));
+    let (decimal, thousands) = if decimal_comma {
+        (',', '.')
+    } else {
+        ('.', ',')
+    };
+    let digits = value
+        .chars()
+        .filter(|c| *c != thousands)
+        .filter_map(|c| match c {
+            '0'..='9' => Some(c),
+            c if c == decimal => Some('.'),
+            _ => None,
+        })
+        .collect::<String>();
+
+    let amount =
+        Decimal::from_str(&digits).map_err(|_| anyhow!("could not read the amount \"{value}\""))?;
+
+    Ok(Some(if negative { -amount } else { amount }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CsvOptions, parse, parse_amount};
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    fn options() -> CsvOptions {
+        CsvOptions {
+            date_column: 0,
+            description_column: 1,
+            amount_column: Some(2),
+            balance_column: Some(3),
+            date_format: "%m/%d/%Y".to_string(),
+            delimiter: b',',
+            decimal_comma: false,
+            has_header: true,
+            flip_signs: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_amount_formats() {
+        let amount = |value, decimal_comma| parse_amount(value, decimal_comma).unwrap();
+
+        assert_eq!(amount("$1,234.50", false), Some(Decimal::new(123450, 2)));
+        assert_eq!(amount("(12.00)", false), Some(Decimal::new(-1200, 2)));
+        assert_eq!(amount("12.00-", false), Some(Decimal::new(-1200, 2)));
+        assert_eq!(amount("-1.234,5 €", true), Some(Decimal::new(-12345, 1)));
+        assert_eq!(amount("", false), None);
+        assert!(parse_amount("n/a", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_statement() {
+        let input = "Date,Description,Amount,Balance\n\
+            03/01/2024,Paycheck,\"2,000.00\",\"2,500.00\"\n\
+            03/02/2024,\"Grocer, Inc\",-45.10,2454.90\n";
+
+        let rows = parse(input, &options()).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[1].occurred_on,
+            NaiveDate::from_ymd_opt(2024, 3, 2).unwrap()
+        );
+        assert_eq!(rows[1].description, "Grocer, Inc");
+        assert_eq!(rows[1].amount, Some(Decimal::new(-4510, 2)));
+        assert_eq!(rows[1].balance, Some(Decimal::new(245490, 2)));
+    }
+
+    #[test]
+    fn test_parse_reports_every_bad_row() {
+        let input = "2024-03-01;Coffee;4,50\n03/02/2024;Lunch;12,00\n03/03/2024;;1,00\n";
+        let options = CsvOptions {
+            balance_column: None,
+            delimiter: b';',
+            decimal_comma: true,
+            has_header: false,
+            flip_signs: true,
+            ..options()
+        };
+
+        let error = parse(input, &options).unwrap_err().to_string();
+
+        assert_eq!(
+            error,
+            "line 1: could not read the date \"2024-03-01\"\nline 3: the description is empty"
+        );
+
+        let rows = parse("03/02/2024;Lunch;12,00\n", &options).unwrap();
+        assert_eq!(rows[0].amount, Some(Decimal::new(-1200, 2)));
+    }
+}
@@ -0,0 +1,282 @@
+use super::StatementRow;
+use anyhow::{Result, anyhow};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::str::FromStr;
+
+/// A bank or credit card statement from an OFX or QFX file.
+#[derive(Debug, Clone, Serialize)]
+pub struct OfxStatement {
+    pub account_number: String,
+    pub currency: Option<String>,
+    /// As the bank reports it, so amounts owed on credit cards are negative.
+    pub ledger_balance: Option<Decimal>,
+    pub balance_on: Option<NaiveDate>,
+    pub rows: Vec<StatementRow>,
+}
+
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    value: Option<String>,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.child(name)?.value.as_deref()
+    }
+
+    fn find_all<'a>(&'a self, name: &str, found: &mut Vec<&'a Element>) {
+        for child in &self.children {
+            if child.name == name {
+                found.push(child);
+            } else {
+                child.find_all(name, found);
+            }
+        }
+    }
+}
+
+enum Token<'a> {
+    Open(&'a str),
+    Close(&'a str),
+    Text(&'a str),
+}
+
+/// Splits the body after the headers into tags and text. OFX 1.x files are SGML and may leave
+/// the closing tags of values out; OFX 2.x files are XML and close everything.
+fn tokenize(input: &str) -> Result<Vec<Token<'_>>> {
+    let start = input
+        .find("<OFX>")
+        .ok_or_else(|| anyhow!("this is not an OFX file"))?;
+    let mut rest = &input[start..];
+    let mut tokens = Vec::new();
+
+    while let Some(open) = rest.find('<') {
+        let text = rest[..open].trim();
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+
+        let close = rest[open..]
+            .find('>')
+            .ok_or_else(|| anyhow!("a tag is not closed"))?
+            + open;
+        let tag = rest[open + 1..close].trim();
+        if let Some(name) = tag.strip_prefix('/') {
+            tokens.push(Token::Close(name.trim()));
+        } else if let Some(name) = tag.strip_suffix('/') {
+            tokens.push(Token::Open(name.trim()));
+            tokens.push(Token::Close(name.trim()));
+        } else if !tag.starts_with('?') && !tag.starts_with('!') {
+            tokens.push(Token::Open(tag));
+        }
+
+        rest = &rest[close + 1..];
+    }
+
+    Ok(tokens)
+}
+
+fn decode(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Builds the element tree. A tag directly followed by text holds a value; any other tag
+/// holds the elements up to its closing tag.
+fn parse_tree(input: &str) -> Result<Element> {
+    let mut tokens = tokenize(input)?.into_iter().peekable();
+    let mut stack = vec![Element::default()];
+
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Open(name) => {
+                let Some(Token::Text(text)) = tokens.peek() else {
+                    stack.push(Element {
+                        name: name.to_string(),
+                        ..Default::default()
+                    });
+                    continue;
+                };
+                let value = decode(text);
+                tokens.next();
+                tokens.next_if(|token| matches!(token, Token::Close(close) if *close == name));
+
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(Element {
+                        name: name.to_string(),
+                        value: Some(value),
+                        children: Vec::new(),
+                    });
+                }
+            }
+            Token::Close(name) => {
+                if !stack[1..].iter().any(|element| element.name == name) {
+                    continue;
+                }
+                while stack.len() > 1 {
+                    let element = stack.pop().ok_or_else(|| anyhow!("unbalanced tags"))?;
+                    let closed = element.name == name;
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(element);
+                    }
+                    if closed {
+                        break;
+                    }
+                }
+            }
+            Token::Text(_) => {}
+        }
+    }
+
+    while stack.len() > 1 {
+        let element = stack.pop().ok_or_else(|| anyhow!("unbalanced tags"))?;
+        if let Some(parent) = stack.last_mut() {
+            parent.children.push(element);
+        }
+    }
+
+    stack.pop().ok_or_else(|| anyhow!("unbalanced tags"))
+}
+
+/// OFX dates start with `YYYYMMDD`; the time and zone that may follow are ignored.
+fn parse_date(value: &str) -> Result<NaiveDate> {
+    value
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(|| anyhow!("could not read the date \"{value}\""))
+}
+
+fn parse_amount(value: &str) -> Result<Decimal> {
+    Decimal::from_str(&value.trim().replace(',', "."))
+        .map_err(|_| anyhow!("could not read the amount \"{value}\""))
+}
+
+/// Reads every bank and credit card statement in an OFX 1.x or 2.x file.
+pub fn parse(input: &str) -> Result<Vec<OfxStatement>> {
+    let tree = parse_tree(input)?;
+    let mut found = Vec::new();
+    tree.find_all("STMTRS", &mut found);
+    tree.find_all("CCSTMTRS", &mut found);
+
+    let statements = found
+        .into_iter()
+        .map(parse_statement)
+        .collect::<Result<Vec<_>>>()?;
+    if statements.is_empty() {
+        return Err(anyhow!("the file has no statements"));
+    }
+
+    Ok(statements)
+}
+
+fn parse_statement(statement: &Element) -> Result<OfxStatement> {
+    let account_number = statement
+        .child("BANKACCTFROM")
+        .or_else(|| statement.child("CCACCTFROM"))
+        .and_then(|account| account.value("ACCTID"))
+        .ok_or_else(|| anyhow!("a statement has no account number"))?;
+    let ledger = statement.child("LEDGERBAL");
+
+    let mut transactions = Vec::new();
+    if let Some(list) = statement.child("BANKTRANLIST") {
+        list.find_all("STMTTRN", &mut transactions);
+    }
+    let rows = transactions
+        .into_iter()
+        .map(|transaction| {
+            let description = transaction
+                .value("NAME")
+                .or_else(|| transaction.value("MEMO"))
+                .or_else(|| transaction.value("TRNTYPE"))
+                .unwrap_or_default();
+
+            Ok(StatementRow {
+                occurred_on: parse_date(
+                    transaction
+                        .value("DTPOSTED")
+                        .ok_or_else(|| anyhow!("a transaction has no date"))?,
+                )?,
+                description: description.to_string(),
+                amount: Some(parse_amount(
+                    transaction
+                        .value("TRNAMT")
+                        .ok_or_else(|| anyhow!("a transaction has no amount"))?,
+                )?),
+                balance: None,
+                duplicate: false,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(OfxStatement {
+        account_number: account_number.to_string(),
+        currency: statement.value("CURDEF").map(str::to_string),
+        ledger_balance: ledger
+            .and_then(|ledger| ledger.value("BALAMT"))
+            .map(parse_amount)
+            .transpose()?,
+        balance_on: ledger
+            .and_then(|ledger| ledger.value("DTASOF"))
+            .map(parse_date)
+            .transpose()?,
+        rows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_parse_sgml_statement() {
+        let statements = parse(include_str!("../../../fixtures/ofx/checking.ofx")).unwrap();
+
+        assert_eq!(statements.len(), 1);
+        let statement = &statements[0];
+        assert_eq!(statement.account_number, "000123456789");
+        assert_eq!(statement.currency.as_deref(), Some("USD"));
+        assert_eq!(statement.ledger_balance, Some(Decimal::new(233490, 2)));
+        assert_eq!(statement.balance_on, NaiveDate::from_ymd_opt(2024, 3, 5));
+        assert_eq!(statement.rows.len(), 3);
+        assert_eq!(statement.rows[0].amount, Some(Decimal::new(200000, 2)));
+        assert_eq!(statement.rows[1].description, "CORNER GROCERY & DELI");
+        assert_eq!(statement.rows[2].description, "CHECK 1042");
+        assert_eq!(
+            statement.rows[2].occurred_on,
+            NaiveDate::from_ymd_opt(2024, 3, 4).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_xml_statement() {
+        let statements = parse(include_str!("../../../fixtures/ofx/credit_card.qfx")).unwrap();
+
+        assert_eq!(statements.len(), 1);
+        let statement = &statements[0];
+        assert_eq!(statement.account_number, "4111111111111111");
+        assert_eq!(statement.ledger_balance, Some(Decimal::new(-51235, 2)));
+        assert_eq!(statement.rows.len(), 2);
+        assert_eq!(statement.rows[0].description, "GAS & GO #12");
+        assert_eq!(statement.rows[1].amount, Some(Decimal::new(30000, 2)));
+    }
+
+    #[test]
+    fn test_parse_rejects_other_files() {
+        assert!(parse("Date,Description,Amount\n").is_err());
+        assert!(parse("<OFX><SIGNONMSGSRSV1></SIGNONMSGSRSV1></OFX>").is_err());
+    }
+}
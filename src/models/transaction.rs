@@ -0,0 +1,458 @@
+use crate::{errors::AppError, models::user::User, utilities::dates::end_of_day};
+use anyhow::{Result, anyhow};
+use chrono::{NaiveDate, TimeDelta, Utc};
+use chrono_tz::Tz;
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::cmp::min;
+use uuid::Uuid;
+
+/// Signed ledger entry; writes also move the account and envelope balances, so run them
+/// inside a database transaction.
+#[derive(Serialize, Debug, Clone)]
+pub struct Transaction {
+    pub id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub envelope_id: Option<Uuid>,
+    pub amount: Decimal,
+    pub description: String,
+    pub occurred_on: NaiveDate,
+    /// Set on entries that move a balance to a figure given by hand or by the app, such as a
+    /// typed-over balance or a goal filling its envelope, rather than money spent or received.
+    pub adjustment: bool,
+}
+
+impl TryInto<Transaction> for tokio_postgres::Row {
+    type Error = AppError;
+
+    fn try_into(self: tokio_postgres::Row) -> Result<Transaction, AppError> {
+        Ok(Transaction {
+            id: self
+                .try_get("id")
+                .map_err(AppError::RecordDeserializationError)?,
+            user_id: self
+                .try_get("user_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            account_id: self
+                .try_get("account_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            envelope_id: self
+                .try_get("envelope_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            amount: self
+                .try_get("amount")
+                .map_err(AppError::RecordDeserializationError)?,
+            description: self
+                .try_get("description")
+                .map_err(AppError::RecordDeserializationError)?,
+            occurred_on: self
+                .try_get("occurred_on")
+                .map_err(AppError::RecordDeserializationError)?,
+            adjustment: self
+                .try_get("adjustment")
+                .map_err(AppError::RecordDeserializationError)?,
+        })
+    }
+}
+
+impl Transaction {
+    pub async fn create(&self, client: &impl GenericClient) -> Result<Self, AppError> {
+        let transaction = self.insert(client).await?;
+        self.apply(client, self.amount).await?;
+
+        Ok(transaction)
+    }
+
+    /// Like `create`, for callers that have already moved the envelope balance themselves,
+    /// such as `Envelope::spend`'s guarded debit.
+    pub async fn create_with_envelope_moved(
+        &self,
+        client: &impl GenericClient,
+    ) -> Result<Self, AppError> {
+        let transaction = self.insert(client).await?;
+        self.apply_to_account(client, self.amount).await?;
+
+        Ok(transaction)
+    }
+
+    async fn insert(&self, client: &impl GenericClient) -> Result<Self, AppError> {
+        let row = client
+            .query_one(
+                "INSERT INTO transactions (
+                    user_id
+                    , account_id
+                    , envelope_id
+                    , amount
+                    , description
+                    , occurred_on
+                    , adjustment
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+                &[
+                    &self.user_id,
+                    &self.account_id,
+                    &self.envelope_id,
+                    &self.amount,
+                    &self.description,
+                    &self.occurred_on,
+                    &self.adjustment,
+                ],
+            )
+            .await?;
+
+        let mut new_transaction = self.clone();
+        new_transaction.id = Some(row.try_get("id")?);
+        Ok(new_transaction)
+    }
+
+    pub async fn update(&self, client: &impl GenericClient) -> Result<Self, AppError> {
+        let id = self.id.ok_or(anyhow!("missing ID for update"))?;
+        let previous = Transaction::get_one(client, id, self.user_id).await?;
+
+        client
+            .execute(
+                "UPDATE transactions SET
+                    account_id = $1
+                    , envelope_id = $2
+                    , amount = $3
+                    , description = $4
+                    , occurred_on = $5
+                WHERE id = $6 AND user_id = $7",
+                &[
+                    &self.account_id,
+                    &self.envelope_id,
+                    &self.amount,
+                    &self.description,
+                    &self.occurred_on,
+                    &self.id,
+                    &self.user_id,
+                ],
+            )
+            .await?;
+
+        previous.apply(client, -previous.amount).await?;
+        self.apply(client, self.amount).await?;
+
+        Ok(self.clone())
+    }
+
+    pub async fn delete(&self, client: &impl GenericClient) -> Result<(), AppError> {
+        client
+            .execute(
+                "DELETE FROM transactions WHERE user_id = $1 AND id = $2",
+                &[&self.user_id, &self.id],
+            )
+            .await?;
+
+        self.apply(client, -self.amount).await?;
+
+        Ok(())
+    }
+
+    pub async fn get_one(
+        client: &impl GenericClient,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Self, AppError> {
+        client
+            .query_one(
+                "SELECT transactions.* FROM transactions
+                INNER JOIN users ON users.id = transactions.user_id
+                WHERE users.id = $1 AND transactions.id = $2",
+                &[&user_id, &id],
+            )
+            .await
+            .map_err(AppError::RecordNotFound)?
+            .try_into()
+    }
+
+    pub async fn get_all(
+        client: &impl GenericClient,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT transactions.* FROM transactions INNER
+            JOIN users ON users.id = transactions.user_id WHERE users.id = $1
+            ORDER BY occurred_on DESC, created_at DESC",
+                &[&user_id],
+            )
+            .await?;
+
+        let mut transactions = Vec::with_capacity(rows.len());
+        for row in rows {
+            transactions.push(row.try_into()?);
+        }
+
+        Ok(transactions)
+    }
+
+    /// Transactions that occurred from `from` through `to`, oldest first.
+    pub async fn get_all_between(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT transactions.* FROM transactions
+                WHERE user_id = $1 AND occurred_on BETWEEN $2 AND $3
+                ORDER BY occurred_on ASC, created_at ASC",
+                &[&user_id, &from, &to],
+            )
+            .await?;
+
+        let mut transactions = Vec::with_capacity(rows.len());
+        for row in rows {
+            transactions.push(row.try_into()?);
+        }
+
+        Ok(transactions)
+    }
+
+    pub async fn get_all_for_account(
+        client: &impl GenericClient,
+        account_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT transactions.* FROM transactions
+                WHERE user_id = $1 AND account_id = $2
+                ORDER BY occurred_on DESC, created_at DESC",
+                &[&user_id, &account_id],
+            )
+            .await?;
+
+        let mut transactions = Vec::with_capacity(rows.len());
+        for row in rows {
+            transactions.push(row.try_into()?);
+        }
+
+        Ok(transactions)
+    }
+
+    pub async fn get_all_for_envelope(
+        client: &impl GenericClient,
+        envelope_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT transactions.* FROM transactions
+                WHERE user_id = $1 AND envelope_id = $2
+                ORDER BY occurred_on DESC, created_at DESC",
+                &[&user_id, &envelope_id],
+            )
+            .await?;
+
+        let mut transactions = Vec::with_capacity(rows.len());
+        for row in rows {
+            transactions.push(row.try_into()?);
+        }
+
+        Ok(transactions)
+    }
+
+    /// Moves the account and envelope balances by `amount`. Debt accounts track what is
+    /// owed, so money going out of a debt account increases its balance. The new account
+    /// balance is added to its history.
+    async fn apply(&self, client: &impl GenericClient, amount: Decimal) -> Result<(), AppError> {
+        self.apply_to_account(client, amount).await?;
+
+        if let Some(envelope_id) = self.envelope_id {
+            client
+                .execute(
+                    "UPDATE envelopes SET amount = amount + $1 WHERE id = $2 AND user_id = $3",
+                    &[&amount, &envelope_id, &self.user_id],
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the account balance by `amount` and files the change in its history at the end of
+    /// `occurred_on` in the user's timezone, or now for entries dated today or later. History
+    /// recorded after that moves by the same amount, so backdated and imported entries show up
+    /// on the day they happened.
+    async fn apply_to_account(
+        &self,
+        client: &impl GenericClient,
+        amount: Decimal,
+    ) -> Result<(), AppError> {
+        if let Some(account_id) = self.account_id {
+            let timezone: Tz = User::get_by_id(client, self.user_id)
+                .await?
+                .timezone()?
+                .parse()?;
+            let recorded_at = min(
+                end_of_day(timezone, self.occurred_on)? - TimeDelta::microseconds(1),
+                Utc::now(),
+            );
+
+            client
+                .execute(
+                    "WITH updated AS (
+                        UPDATE accounts
+                        SET amount = amount + CASE WHEN debt THEN -$1::numeric ELSE $1::numeric END
+                        WHERE id = $2 AND user_id = $3
+                        RETURNING id, user_id, debt,
+                            CASE WHEN debt THEN -$1::numeric ELSE $1::numeric END AS change
+                    ), shifted AS (
+                        UPDATE account_balances
+                        SET amount = account_balances.amount + updated.change
+                        FROM updated
+                        WHERE account_balances.account_id = updated.id
+                            AND account_balances.recorded_at > $4
+                    )
+                    INSERT INTO account_balances (account_id, user_id, amount, debt, recorded_at)
+                    SELECT updated.id, updated.user_id,
+                        COALESCE(earlier.amount, 0) + updated.change,
+                        COALESCE(earlier.debt, updated.debt), $4
+                    FROM updated
+                    LEFT JOIN LATERAL (
+                        SELECT amount, debt FROM account_balances
+                        WHERE account_id = updated.id AND recorded_at <= $4
+                        ORDER BY recorded_at DESC
+                        LIMIT 1
+                    ) earlier ON true",
+                    &[&amount, &account_id, &self.user_id, &recorded_at],
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Transaction;
+    use crate::{
+        models::{account::Account, envelope::Envelope},
+        test_utils::state_for_tests,
+    };
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_transactions_move_balances() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let mut client = shared_state.pool.get_client().await.unwrap();
+        let client = client.transaction().await.unwrap();
+
+        let account = Account {
+            id: None,
+            user_id,
+            name: "checking".to_string(),
+            amount: Decimal::new(1000, 0),
+            debt: false,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let envelope = Envelope {
+            id: None,
+            user_id,
+            name: "groceries".to_string(),
+            amount: Decimal::new(200, 0),
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let transaction = Transaction {
+            id: None,
+            user_id,
+            account_id: account.id,
+            envelope_id: envelope.id,
+            amount: Decimal::new(-42, 0),
+            description: "market".to_string(),
+            occurred_on: NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            adjustment: false,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        let envelope = Envelope::get_one(&client, envelope.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(account.amount, Decimal::new(958, 0));
+        assert_eq!(envelope.amount, Decimal::new(158, 0));
+
+        let mut transaction = transaction.clone();
+        transaction.amount = Decimal::new(-50, 0);
+        transaction.envelope_id = None;
+        let transaction = transaction.update(&client).await.unwrap();
+
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        let envelope = Envelope::get_one(&client, envelope.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(account.amount, Decimal::new(950, 0));
+        assert_eq!(envelope.amount, Decimal::new(200, 0));
+
+        transaction.delete(&client).await.unwrap();
+
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(account.amount, Decimal::new(1000, 0));
+
+        client.rollback().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_transactions_against_debt_accounts() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let user_id = user_extension.0.id;
+        let client = shared_state.pool.get_client().await.unwrap();
+
+        let account = Account {
+            id: None,
+            user_id,
+            name: "credit card".to_string(),
+            amount: Decimal::new(300, 0),
+            debt: true,
+            apr: None,
+            minimum_payment: None,
+            account_number: None,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        Transaction {
+            id: None,
+            user_id,
+            account_id: account.id,
+            envelope_id: None,
+            amount: Decimal::new(-25, 0),
+            description: "coffee".to_string(),
+            occurred_on: NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+            adjustment: false,
+        }
+        .create(&client)
+        .await
+        .unwrap();
+
+        let account = Account::get_one(&client, account.id.unwrap(), user_id)
+            .await
+            .unwrap();
+        assert_eq!(account.amount, Decimal::new(325, 0));
+    }
+}
@@ -6,7 +6,7 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::errors::AppError;
+use crate::{errors::AppError, utilities::dates::BudgetPeriod};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum GoalHeader {
@@ -15,12 +15,22 @@ pub enum GoalHeader {
     PerDay,
 }
 
+/// How surplus income is shared between goals: in priority order, filling each goal before
+/// the next, or split by each goal's funding weight.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum GoalFunding {
+    #[default]
+    Sequential,
+    Weighted,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Preferences {
     pub timezone: Option<String>,
     pub goal_header: Option<GoalHeader>,
     pub forecast_offset: Option<i64>,
-    pub monthly_income: Option<Decimal>,
+    pub budget_period: Option<BudgetPeriod>,
+    pub goal_funding: Option<GoalFunding>,
 }
 
 impl Preferences {
@@ -29,7 +39,8 @@ impl Preferences {
             timezone: Some("UTC".to_owned()),
             goal_header: Some(GoalHeader::Accumulated),
             forecast_offset: Some(1),
-            monthly_income: Some(Decimal::ZERO),
+            budget_period: Some(BudgetPeriod::CalendarMonth),
+            goal_funding: Some(GoalFunding::Sequential),
         }
     }
 
@@ -40,14 +51,16 @@ impl Preferences {
             .ok_or(anyhow!("failure fetching timezone"))
     }
 
-    pub fn monthly_income(&self) -> Result<Decimal> {
-        self.monthly_income
-            .or(Some(Decimal::ZERO))
-            .ok_or(anyhow!("failure fetching monthly income"))
+    pub fn budget_period(&self) -> BudgetPeriod {
+        self.budget_period.unwrap_or_default()
+    }
+
+    pub fn goal_funding(&self) -> GoalFunding {
+        self.goal_funding.unwrap_or_default()
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Session {
     pub id: Option<Uuid>,
     pub user_id: Uuid,
@@ -80,7 +93,10 @@ impl Session {
     pub async fn get_by_id(client: &impl GenericClient, id: &str) -> Result<Self> {
         let id = Uuid::parse_str(id)?;
         client
-            .query_one("SELECT * FROM sessions WHERE id = $1 AND expiration > NOW()", &[&id])
+            .query_one(
+                "SELECT * FROM sessions WHERE id = $1 AND expiration > NOW()",
+                &[&id],
+            )
             .await?
             .try_into()
     }
@@ -103,8 +119,12 @@ impl Session {
 pub struct User {
     pub id: Uuid,
     pub email: String,
+    /// Users are identified by the issuer of their ID token together with its subject.
+    pub issuer: String,
     pub subject: String,
     pub preferences: Option<Json<Preferences>>,
+    /// Secret in the URL of the user's goal calendar feed; `None` when there is no feed.
+    pub calendar_token: Option<String>,
 }
 
 impl TryInto<User> for tokio_postgres::Row {
@@ -118,12 +138,18 @@ impl TryInto<User> for tokio_postgres::Row {
             email: self
                 .try_get("email")
                 .map_err(AppError::RecordDeserializationError)?,
+            issuer: self
+                .try_get("issuer")
+                .map_err(AppError::RecordDeserializationError)?,
             subject: self
                 .try_get("subject")
                 .map_err(AppError::RecordDeserializationError)?,
             preferences: self
                 .try_get("preferences")
                 .map_err(AppError::RecordDeserializationError)?,
+            calendar_token: self
+                .try_get("calendar_token")
+                .map_err(AppError::RecordDeserializationError)?,
         })
     }
 }
@@ -181,12 +207,13 @@ impl User {
     pub async fn create(
         client: &impl GenericClient,
         email: String,
+        issuer: String,
         subject: String,
     ) -> Result<Self, AppError> {
         let id = client
             .query_one(
-                "INSERT INTO users (email, subject) VALUES ($1, $2) RETURNING id",
-                &[&email, &subject],
+                "INSERT INTO users (email, issuer, subject) VALUES ($1, $2, $3) RETURNING id",
+                &[&email, &issuer, &subject],
             )
             .await?;
 
@@ -210,10 +237,14 @@ impl User {
 
     pub async fn get_by_subject(
         client: &impl GenericClient,
+        issuer: String,
         subject: String,
     ) -> Result<Self, AppError> {
         client
-            .query_one("SELECT * FROM users WHERE subject = $1", &[&subject])
+            .query_one(
+                "SELECT * FROM users WHERE issuer = $1 AND subject = $2",
+                &[&issuer, &subject],
+            )
             .await?
             .try_into()
     }
@@ -225,6 +256,47 @@ impl User {
             .try_into()
     }
 
+    pub async fn get_by_calendar_token(
+        client: &impl GenericClient,
+        calendar_token: &str,
+    ) -> Result<Option<Self>, AppError> {
+        client
+            .query_opt(
+                "SELECT * FROM users WHERE calendar_token = $1",
+                &[&calendar_token],
+            )
+            .await?
+            .map(TryInto::try_into)
+            .transpose()
+    }
+
+    /// Replaces the calendar feed's token, so any earlier feed URL stops working. Passing
+    /// `None` turns the feed off.
+    pub async fn set_calendar_token(
+        &self,
+        client: &impl GenericClient,
+        calendar_token: Option<&str>,
+    ) -> Result<Self, AppError> {
+        client
+            .query_one(
+                "UPDATE users SET calendar_token = $1 WHERE id = $2 RETURNING *",
+                &[&calendar_token, &self.id],
+            )
+            .await?
+            .try_into()
+    }
+
+    pub async fn get_all(client: &impl GenericClient) -> Result<Vec<Self>, AppError> {
+        let rows = client.query("SELECT * FROM users ORDER BY id", &[]).await?;
+
+        let mut users = Vec::with_capacity(rows.len());
+        for row in rows {
+            users.push(row.try_into()?);
+        }
+
+        Ok(users)
+    }
+
     pub fn timezone(&self) -> Result<String> {
         match &self.preferences {
             Some(Json(preferences)) => preferences.timezone(),
@@ -232,10 +304,17 @@ impl User {
         }
     }
 
-    pub fn monthly_income(&self) -> Result<Decimal> {
+    pub fn budget_period(&self) -> BudgetPeriod {
+        match &self.preferences {
+            Some(Json(preferences)) => preferences.budget_period(),
+            None => BudgetPeriod::default(),
+        }
+    }
+
+    pub fn goal_funding(&self) -> GoalFunding {
         match &self.preferences {
-            Some(Json(preferences)) => preferences.monthly_income(),
-            None => Ok(Decimal::ZERO),
+            Some(Json(preferences)) => preferences.goal_funding(),
+            None => GoalFunding::default(),
         }
     }
 }
@@ -253,7 +332,14 @@ mod tests {
 
         // A legitimate user.
         let subject = uuid::Uuid::new_v4().to_string();
-        let user = User::create(&client, subject.clone(), subject).await.unwrap();
+        let user = User::create(
+            &client,
+            subject.clone(),
+            "https://issuer.example.com".to_string(),
+            subject,
+        )
+        .await
+        .unwrap();
 
         // A session that expired 2 days ago.
         let expiration = Utc::now().checked_sub_days(Days::new(2)).unwrap();
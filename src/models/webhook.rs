@@ -0,0 +1,455 @@
+use crate::errors::AppError;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use postgres_types::{FromSql, ToSql};
+use rand::{
+    distr::{Alphanumeric, SampleString},
+    rng,
+};
+use rust_database_common::GenericClient;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::{Value, json};
+use sha2::Sha256;
+use uuid::Uuid;
+
+/// A budget event that webhook endpoints can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WebhookEvent {
+    GoalConverted,
+    EnvelopeCreated,
+    EnvelopeDeleted,
+    PerDiemBelowThreshold,
+}
+
+impl WebhookEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::GoalConverted => "goal.converted",
+            Self::EnvelopeCreated => "envelope.created",
+            Self::EnvelopeDeleted => "envelope.deleted",
+            Self::PerDiemBelowThreshold => "per_diem.below_threshold",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, FromSql, ToSql, PartialEq)]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+/// Hex HMAC-SHA256 of `"{timestamp}.{body}"`, so that receivers can check both where a delivery
+/// came from and that it is not being replayed.
+pub fn signature(secret: &str, timestamp: i64, body: &str) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|_| anyhow!("could not create a signing key"))?;
+    mac.update(format!("{timestamp}.{body}").as_bytes());
+
+    Ok(format!("{:x}", mac.finalize().into_bytes()))
+}
+
+/// A URL that is sent the events it subscribes to. `per_diem_below` remembers whether the per
+/// diem was last seen under `per_diem_threshold`, so the event is sent once per drop.
+#[derive(Serialize, Debug, Clone)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub url: String,
+    pub secret: String,
+    pub events: Vec<String>,
+    pub per_diem_threshold: Option<Decimal>,
+    pub per_diem_below: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryInto<WebhookEndpoint> for tokio_postgres::Row {
+    type Error = AppError;
+
+    fn try_into(self: tokio_postgres::Row) -> Result<WebhookEndpoint, AppError> {
+        Ok(WebhookEndpoint {
+            id: self
+                .try_get("id")
+                .map_err(AppError::RecordDeserializationError)?,
+            user_id: self
+                .try_get("user_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            url: self
+                .try_get("url")
+                .map_err(AppError::RecordDeserializationError)?,
+            secret: self
+                .try_get("secret")
+                .map_err(AppError::RecordDeserializationError)?,
+            events: self
+                .try_get("events")
+                .map_err(AppError::RecordDeserializationError)?,
+            per_diem_threshold: self
+                .try_get("per_diem_threshold")
+                .map_err(AppError::RecordDeserializationError)?,
+            per_diem_below: self
+                .try_get("per_diem_below")
+                .map_err(AppError::RecordDeserializationError)?,
+            created_at: self
+                .try_get("created_at")
+                .map_err(AppError::RecordDeserializationError)?,
+        })
+    }
+}
+
+fn payload(event: WebhookEvent, data: Value) -> Value {
+    json!({
+        "id": Uuid::new_v4(),
+        "type": event.name(),
+        "created_at": Utc::now(),
+        "data": data,
+    })
+}
+
+impl WebhookEndpoint {
+    pub async fn create(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        url: &str,
+        events: &[WebhookEvent],
+        per_diem_threshold: Option<Decimal>,
+    ) -> Result<Self, AppError> {
+        let secret = format!("whsec_{}", Alphanumeric.sample_string(&mut rng(), 32));
+        let events: Vec<&str> = events.iter().map(WebhookEvent::name).collect();
+
+        client
+            .query_one(
+                "INSERT INTO webhook_endpoints (user_id, url, secret, events, per_diem_threshold)
+                VALUES ($1, $2, $3, $4, $5) RETURNING *",
+                &[&user_id, &url, &secret, &events, &per_diem_threshold],
+            )
+            .await?
+            .try_into()
+    }
+
+    pub async fn get_one(
+        client: &impl GenericClient,
+        id: Uuid,
+        user_id: Uuid,
+    ) -> Result<Self, AppError> {
+        client
+            .query_one(
+                "SELECT * FROM webhook_endpoints WHERE id = $1 AND user_id = $2",
+                &[&id, &user_id],
+            )
+            .await
+            .map_err(AppError::RecordNotFound)?
+            .try_into()
+    }
+
+    pub async fn get_all(
+        client: &impl GenericClient,
+        user_id: Uuid,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT * FROM webhook_endpoints WHERE user_id = $1 ORDER BY created_at",
+                &[&user_id],
+            )
+            .await?;
+
+        let mut endpoints = Vec::with_capacity(rows.len());
+        for row in rows {
+            endpoints.push(row.try_into()?);
+        }
+
+        Ok(endpoints)
+    }
+
+    /// Endpoints of every user that want to hear when the per diem drops below their threshold.
+    pub async fn get_watching_per_diem(client: &impl GenericClient) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT * FROM webhook_endpoints
+                WHERE per_diem_threshold IS NOT NULL AND $1 = ANY(events)",
+                &[&WebhookEvent::PerDiemBelowThreshold.name()],
+            )
+            .await?;
+
+        let mut endpoints = Vec::with_capacity(rows.len());
+        for row in rows {
+            endpoints.push(row.try_into()?);
+        }
+
+        Ok(endpoints)
+    }
+
+    pub async fn set_per_diem_below(
+        &self,
+        client: &impl GenericClient,
+        per_diem_below: bool,
+    ) -> Result<(), AppError> {
+        client
+            .execute(
+                "UPDATE webhook_endpoints SET per_diem_below = $1 WHERE id = $2",
+                &[&per_diem_below, &self.id],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, client: &impl GenericClient) -> Result<(), AppError> {
+        client
+            .execute(
+                "DELETE FROM webhook_endpoints WHERE id = $1 AND user_id = $2",
+                &[&self.id, &self.user_id],
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Queues `event` for this endpoint alone.
+    pub async fn enqueue(
+        &self,
+        client: &impl GenericClient,
+        event: WebhookEvent,
+        data: Value,
+    ) -> Result<WebhookDelivery, AppError> {
+        client
+            .query_one(
+                "INSERT INTO webhook_deliveries (endpoint_id, user_id, event, payload)
+                VALUES ($1, $2, $3, $4) RETURNING *",
+                &[
+                    &self.id,
+                    &self.user_id,
+                    &event.name(),
+                    &payload(event, data),
+                ],
+            )
+            .await?
+            .try_into()
+    }
+
+    /// Queues `event` for every endpoint of the user that subscribes to it. Call it in the same
+    /// transaction as the change, so that the event is sent exactly when the change is committed.
+    pub async fn publish(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        event: WebhookEvent,
+        data: Value,
+    ) -> Result<u64, AppError> {
+        Ok(client
+            .execute(
+                "INSERT INTO webhook_deliveries (endpoint_id, user_id, event, payload)
+                SELECT id, user_id, $2, $3 FROM webhook_endpoints
+                WHERE user_id = $1 AND $2 = ANY(events)",
+                &[&user_id, &event.name(), &payload(event, data)],
+            )
+            .await?)
+    }
+}
+
+/// One event queued for one endpoint, kept after it is sent as the delivery log.
+#[derive(Serialize, Debug, Clone)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub user_id: Uuid,
+    pub event: String,
+    pub payload: Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl TryInto<WebhookDelivery> for tokio_postgres::Row {
+    type Error = AppError;
+
+    fn try_into(self: tokio_postgres::Row) -> Result<WebhookDelivery, AppError> {
+        Ok(WebhookDelivery {
+            id: self
+                .try_get("id")
+                .map_err(AppError::RecordDeserializationError)?,
+            endpoint_id: self
+                .try_get("endpoint_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            user_id: self
+                .try_get("user_id")
+                .map_err(AppError::RecordDeserializationError)?,
+            event: self
+                .try_get("event")
+                .map_err(AppError::RecordDeserializationError)?,
+            payload: self
+                .try_get("payload")
+                .map_err(AppError::RecordDeserializationError)?,
+            status: self
+                .try_get("status")
+                .map_err(AppError::RecordDeserializationError)?,
+            attempts: self
+                .try_get("attempts")
+                .map_err(AppError::RecordDeserializationError)?,
+            next_attempt_at: self
+                .try_get("next_attempt_at")
+                .map_err(AppError::RecordDeserializationError)?,
+            response_status: self
+                .try_get("response_status")
+                .map_err(AppError::RecordDeserializationError)?,
+            last_error: self
+                .try_get("last_error")
+                .map_err(AppError::RecordDeserializationError)?,
+            created_at: self
+                .try_get("created_at")
+                .map_err(AppError::RecordDeserializationError)?,
+            delivered_at: self
+                .try_get("delivered_at")
+                .map_err(AppError::RecordDeserializationError)?,
+        })
+    }
+}
+
+impl WebhookDelivery {
+    /// Claims the pending deliveries that are due, optionally for one endpoint, by moving their
+    /// next attempt to `lease_until`. Other runs skip them until then, and a run that dies before
+    /// recording an outcome leaves them to be retried once the lease is up.
+    pub async fn claim_due(
+        client: &impl GenericClient,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        endpoint_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "UPDATE webhook_deliveries SET next_attempt_at = $2
+                WHERE id IN (
+                    SELECT id FROM webhook_deliveries
+                    WHERE status = 'Pending' AND next_attempt_at <= $1
+                        AND ($3::uuid IS NULL OR endpoint_id = $3)
+                    ORDER BY next_attempt_at LIMIT $4
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING *",
+                &[&now, &lease_until, &endpoint_id, &limit],
+            )
+            .await?;
+
+        let mut deliveries = Vec::with_capacity(rows.len());
+        for row in rows {
+            deliveries.push(row.try_into()?);
+        }
+
+        Ok(deliveries)
+    }
+
+    pub async fn get_recent(
+        client: &impl GenericClient,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Self>, AppError> {
+        let rows = client
+            .query(
+                "SELECT * FROM webhook_deliveries WHERE user_id = $1
+                ORDER BY created_at DESC LIMIT $2",
+                &[&user_id, &limit],
+            )
+            .await?;
+
+        let mut deliveries = Vec::with_capacity(rows.len());
+        for row in rows {
+            deliveries.push(row.try_into()?);
+        }
+
+        Ok(deliveries)
+    }
+
+    pub async fn update(&self, client: &impl GenericClient) -> Result<(), AppError> {
+        client
+            .execute(
+                "UPDATE webhook_deliveries SET
+                    status = $1
+                    , attempts = $2
+                    , next_attempt_at = $3
+                    , response_status = $4
+                    , last_error = $5
+                    , delivered_at = $6
+                WHERE id = $7",
+                &[
+                    &self.status,
+                    &self.attempts,
+                    &self.next_attempt_at,
+                    &self.response_status,
+                    &self.last_error,
+                    &self.delivered_at,
+                    &self.id,
+                ],
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WebhookEndpoint, WebhookEvent, signature};
+    use crate::test_utils::state_for_tests;
+    use serde_json::json;
+
+    #[test]
+    fn test_signature() {
+        // echo -n '1700000000.{}' | openssl dgst -sha256 -hmac secret
+        assert_eq!(
+            signature("secret", 1_700_000_000, "{}").unwrap(),
+            "b8569b78799ff9e3cbff0fc2d63a33a2b57f3282abd07c37ae5e8e7d79a5f163"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish_to_subscribed_endpoints() {
+        let (shared_state, user_extension, _context_extension) = state_for_tests().await.unwrap();
+        let mut client = shared_state.pool.get_client().await.unwrap();
+        let client = client.transaction().await.unwrap();
+        let user_id = user_extension.id;
+
+        let subscribed = WebhookEndpoint::create(
+            &client,
+            user_id,
+            "http://localhost/hooks",
+            &[WebhookEvent::EnvelopeCreated, WebhookEvent::EnvelopeDeleted],
+            None,
+        )
+        .await
+        .unwrap();
+        WebhookEndpoint::create(
+            &client,
+            user_id,
+            "http://localhost/goals",
+            &[WebhookEvent::GoalConverted],
+            None,
+        )
+        .await
+        .unwrap();
+
+        let queued = WebhookEndpoint::publish(
+            &client,
+            user_id,
+            WebhookEvent::EnvelopeCreated,
+            json!({ "name": "Gifts" }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(queued, 1);
+
+        let deliveries = super::WebhookDelivery::get_recent(&client, user_id, 10)
+            .await
+            .unwrap();
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].endpoint_id, subscribed.id);
+        assert_eq!(deliveries[0].payload["type"], "envelope.created");
+        assert_eq!(deliveries[0].payload["data"]["name"], "Gifts");
+
+        client.rollback().await.unwrap();
+    }
+}
@@ -5,17 +5,23 @@ use crate::{
     authenticated::UserExtension,
     errors::AppError,
     models::user::Preferences,
-    utilities::handlebars::{DigestAssetHandlebarsHelper, walk_directory},
+    utilities::handlebars::{DigestAssetHandlebarsHelper, EqHandlebarsHelper, walk_directory},
 };
 
 #[cfg(test)]
 use crate::{SharedState, db::database_pool, models::user::User};
 use anyhow::{Result, anyhow};
-use axum::Extension;
+use axum::{
+    Extension, Router,
+    body::{Body, to_bytes},
+    http::{Method, Request, StatusCode},
+};
 use axum_extra::extract::cookie::Key;
 use handlebars::Handlebars;
 use postgres_types::Json;
 use rust_database_common::GenericClient;
+use serde_json::Value;
+use tower::ServiceExt;
 
 pub async fn state_for_tests() -> Result<(
     SharedState,
@@ -35,6 +41,7 @@ pub async fn state_for_tests() -> Result<(
             key: String::from_str("test")?,
         }),
     );
+    handlebars.register_helper("eq", Box::new(EqHandlebarsHelper {}));
 
     for template in walk_directory("./templates").unwrap() {
         let name = template
@@ -53,6 +60,7 @@ pub async fn state_for_tests() -> Result<(
         key: Key::generate(),
         pool,
         handlebars,
+        live: Default::default(),
     };
 
     Ok((
@@ -69,6 +77,7 @@ pub async fn user_for_tests(
     let user = User::create(
         client,
         uuid::Uuid::new_v4().to_string(),
+        "https://issuer.example.com".to_string(),
         uuid::Uuid::new_v4().to_string(),
     )
     .await?;
@@ -95,3 +104,24 @@ async fn user_extension_for_tests(
         csrf: "test".to_owned(),
     }))
 }
+
+/// Sends `body` as JSON, when given, and decodes the JSON response; empty bodies decode as null.
+pub async fn api_request(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let request = Request::builder().method(method).uri(uri);
+    let request = match body {
+        Some(body) => request
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => request.body(Body::empty()),
+    };
+    let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+}
@@ -1,3 +1,4 @@
+pub mod charts;
 pub mod dates;
 pub mod handlebars;
 pub mod responses;
@@ -0,0 +1,66 @@
+/// Maps series of values onto a `WIDTH` by `HEIGHT` SVG viewBox. The vertical range always
+/// includes zero so the zero line can be drawn.
+pub struct Scale {
+    low: f64,
+    high: f64,
+    step: f64,
+}
+
+pub const WIDTH: f64 = 600.0;
+pub const HEIGHT: f64 = 200.0;
+
+impl Scale {
+    pub fn new(series: &[&[f64]]) -> Self {
+        let values = series.iter().flat_map(|values| values.iter().copied());
+        let (low, high) = values.fold((0.0, 0.0), |(low, high): (f64, f64), value| {
+            (low.min(value), high.max(value))
+        });
+        let count = series.iter().map(|values| values.len()).max().unwrap_or(0);
+
+        Self {
+            low,
+            high: if high > low { high } else { low + 1.0 },
+            step: WIDTH / (count.max(2) - 1) as f64,
+        }
+    }
+
+    pub fn x(&self, index: usize) -> String {
+        format!("{:.1}", index as f64 * self.step)
+    }
+
+    pub fn y(&self, value: f64) -> String {
+        format!(
+            "{:.1}",
+            HEIGHT - (value - self.low) / (self.high - self.low) * HEIGHT
+        )
+    }
+
+    /// The `points` attribute of a polyline through `values`.
+    pub fn points(&self, values: &[f64]) -> String {
+        values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| format!("{},{}", self.x(index), self.y(*value)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Scale;
+
+    #[test]
+    fn test_scale_includes_zero() {
+        let scale = Scale::new(&[&[50.0, 100.0, -100.0]]);
+
+        assert_eq!(
+            scale.points(&[50.0, 100.0, -100.0]),
+            "0.0,50.0 300.0,0.0 600.0,200.0"
+        );
+        assert_eq!(scale.y(0.0), "100.0");
+
+        let flat = Scale::new(&[&[]]);
+        assert_eq!(flat.y(0.0), "200.0");
+    }
+}
@@ -1,6 +1,11 @@
 use anyhow::{Result, anyhow};
-use chrono::{DateTime, Datelike, Duration, Months, TimeDelta, TimeZone, Utc};
+use chrono::{
+    DateTime, Datelike, Days, Duration, Months, NaiveDate, NaiveTime, TimeDelta, TimeZone, Utc,
+    Weekday,
+};
 use chrono_tz::Tz;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 
 pub trait Times {
     fn now(&self) -> DateTime<Utc>;
@@ -8,47 +13,105 @@ pub trait Times {
 
 pub struct TimeProvider;
 
+/// The stretch of time a budget is spread across. Semi-monthly periods run from the 1st
+/// through the 15th and from the 16th through the end of the month; weekly periods start on
+/// Monday.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum BudgetPeriod {
+    #[default]
+    CalendarMonth,
+    SemiMonthly,
+    Biweekly {
+        anchor: NaiveDate,
+    },
+    Weekly,
+}
+
+impl BudgetPeriod {
+    pub fn periods_per_year(&self) -> Decimal {
+        match self {
+            Self::CalendarMonth => Decimal::new(12, 0),
+            Self::SemiMonthly => Decimal::new(24, 0),
+            Self::Biweekly { .. } => Decimal::new(26, 0),
+            Self::Weekly => Decimal::new(52, 0),
+        }
+    }
+
+    /// Returns the first day of the period containing `date` and the first day of the next one.
+    pub fn bounds(&self, date: NaiveDate) -> Result<(NaiveDate, NaiveDate)> {
+        let first_of_month = date.with_day(1).ok_or(anyhow!("could not parse date"))?;
+
+        let (start, next) = match self {
+            Self::CalendarMonth => (first_of_month, first_of_month + Months::new(1)),
+            Self::SemiMonthly => {
+                let sixteenth = first_of_month + Days::new(15);
+                if date < sixteenth {
+                    (first_of_month, sixteenth)
+                } else {
+                    (sixteenth, first_of_month + Months::new(1))
+                }
+            }
+            Self::Biweekly { anchor } => {
+                let periods = (date - *anchor).num_days().div_euclid(14);
+                let start = *anchor + Duration::days(periods * 14);
+                (start, start + Days::new(14))
+            }
+            Self::Weekly => {
+                let start = date.week(Weekday::Mon).first_day();
+                (start, start + Days::new(7))
+            }
+        };
+
+        Ok((start, next))
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct TimeUtilities {
     pub timezone: Tz,
+    pub period: BudgetPeriod,
 }
 
 impl TimeUtilities {
-    pub fn length_of_month(self, time_provider: &impl Times) -> Result<TimeDelta> {
-        let start_of_month = self.start_of_month(time_provider)?;
-        let end_of_month = self.end_of_month(time_provider)?;
-        Ok(end_of_month - start_of_month)
+    pub fn length_of_period(self, time_provider: &impl Times) -> Result<TimeDelta> {
+        let start_of_period = self.start_of_period(time_provider)?;
+        let end_of_period = self.end_of_period(time_provider)?;
+        Ok(end_of_period - start_of_period)
     }
 
-    pub fn remaining_length_of_month(self, time_provider: &impl Times) -> Result<TimeDelta> {
+    pub fn remaining_length_of_period(self, time_provider: &impl Times) -> Result<TimeDelta> {
         let now = time_provider.now().with_timezone(&self.timezone);
-        let end_of_month = self.end_of_month(time_provider)?;
-        Ok(end_of_month - now)
+        let end_of_period = self.end_of_period(time_provider)?;
+        Ok(end_of_period - now)
     }
 
-    fn start_of_month(&self, time: &impl Times) -> Result<DateTime<Tz>> {
-        let now = time.now().with_timezone(&self.timezone);
-
-        let start_of_month = self
-            .timezone
-            .with_ymd_and_hms(now.year(), now.month(), 1, 0, 0, 0);
-
-        start_of_month
-            .single()
-            .ok_or(anyhow!("error parsing datetime"))
+    pub fn start_of_period(&self, time: &impl Times) -> Result<DateTime<Tz>> {
+        let (start, _) = self.local_bounds(time)?;
+        Ok(start)
     }
 
-    fn end_of_month(&self, time: &impl Times) -> Result<DateTime<Tz>> {
-        let start_of_month = self.start_of_month(time)?;
-        let next_month = start_of_month
-            .checked_add_months(Months::new(1))
-            .ok_or(anyhow!("could not parse date"))?;
+    fn end_of_period(&self, time: &impl Times) -> Result<DateTime<Tz>> {
+        let (_, next) = self.local_bounds(time)?;
 
-        let end_of_month = next_month
+        let end_of_period = next
             .checked_sub_signed(Duration::seconds(1))
             .ok_or(anyhow!("could not parse date"))?;
 
-        Ok(end_of_month)
+        Ok(end_of_period)
+    }
+
+    fn local_bounds(&self, time: &impl Times) -> Result<(DateTime<Tz>, DateTime<Tz>)> {
+        let now = time.now().with_timezone(&self.timezone);
+        let (start, next) = self.period.bounds(now.date_naive())?;
+
+        Ok((self.midnight(start)?, self.midnight(next)?))
+    }
+
+    fn midnight(&self, date: NaiveDate) -> Result<DateTime<Tz>> {
+        self.timezone
+            .with_ymd_and_hms(date.year(), date.month(), date.day(), 0, 0, 0)
+            .single()
+            .ok_or(anyhow!("error parsing datetime"))
     }
 }
 
@@ -58,10 +121,32 @@ impl Times for TimeProvider {
     }
 }
 
+/// A clock stopped at a given instant, for previewing what would happen at that time.
+pub struct FixedTime(pub DateTime<Utc>);
+
+impl Times for FixedTime {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+/// The instant `date` ends in `timezone`, which is midnight at the start of the next day.
+pub fn end_of_day(timezone: Tz, date: NaiveDate) -> Result<DateTime<Utc>> {
+    let next_day = date
+        .checked_add_days(Days::new(1))
+        .ok_or_else(|| anyhow!("could not add days"))?;
+
+    Ok(timezone
+        .from_local_datetime(&next_day.and_time(NaiveTime::MIN))
+        .earliest()
+        .ok_or_else(|| anyhow!("could not construct datetime"))?
+        .with_timezone(&Utc))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use chrono::{TimeDelta, TimeZone, Timelike, Utc};
+    use chrono::{NaiveDate, TimeDelta, TimeZone, Timelike, Utc};
 
     #[test]
     fn test_remaining_seconds_on_second_to_last_day() {
@@ -77,9 +162,10 @@ mod test {
 
         let time_utilities = TimeUtilities {
             timezone: chrono_tz::Tz::UTC,
+            period: BudgetPeriod::CalendarMonth,
         };
         let sec = time_utilities
-            .remaining_length_of_month(&MockTimeProvider {})
+            .remaining_length_of_period(&MockTimeProvider {})
             .unwrap();
 
         assert_eq!(sec, TimeDelta::seconds(172799))
@@ -99,11 +185,85 @@ mod test {
 
         let time_utilities = TimeUtilities {
             timezone: chrono_tz::Tz::UTC,
+            period: BudgetPeriod::CalendarMonth,
         };
         let sec = time_utilities
-            .remaining_length_of_month(&MockTimeProvider {})
+            .remaining_length_of_period(&MockTimeProvider {})
             .unwrap();
 
         assert_eq!(sec, TimeDelta::seconds(86399))
     }
+
+    #[test]
+    fn test_period_bounds() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+        assert_eq!(
+            BudgetPeriod::CalendarMonth
+                .bounds(date(2024, 2, 10))
+                .unwrap(),
+            (date(2024, 2, 1), date(2024, 3, 1))
+        );
+        assert_eq!(
+            BudgetPeriod::SemiMonthly.bounds(date(2024, 2, 15)).unwrap(),
+            (date(2024, 2, 1), date(2024, 2, 16))
+        );
+        assert_eq!(
+            BudgetPeriod::SemiMonthly.bounds(date(2024, 2, 16)).unwrap(),
+            (date(2024, 2, 16), date(2024, 3, 1))
+        );
+        assert_eq!(
+            BudgetPeriod::Weekly.bounds(date(2024, 1, 14)).unwrap(),
+            (date(2024, 1, 8), date(2024, 1, 15))
+        );
+
+        let biweekly = BudgetPeriod::Biweekly {
+            anchor: date(2024, 1, 5),
+        };
+        assert_eq!(
+            biweekly.bounds(date(2024, 1, 18)).unwrap(),
+            (date(2024, 1, 5), date(2024, 1, 19))
+        );
+        assert_eq!(
+            biweekly.bounds(date(2024, 1, 19)).unwrap(),
+            (date(2024, 1, 19), date(2024, 2, 2))
+        );
+        assert_eq!(
+            biweekly.bounds(date(2023, 12, 31)).unwrap(),
+            (date(2023, 12, 22), date(2024, 1, 5))
+        );
+    }
+
+    #[test]
+    fn test_remaining_seconds_in_biweekly_period() {
+        struct MockTimeProvider;
+        impl Times for MockTimeProvider {
+            fn now(&self) -> chrono::DateTime<chrono::Utc> {
+                Utc.with_ymd_and_hms(2024, 1, 18, 0, 0, 0)
+                    .unwrap()
+                    .with_nanosecond(0)
+                    .unwrap()
+            }
+        }
+
+        let time_utilities = TimeUtilities {
+            timezone: chrono_tz::Tz::UTC,
+            period: BudgetPeriod::Biweekly {
+                anchor: NaiveDate::from_ymd_opt(2024, 1, 5).unwrap(),
+            },
+        };
+
+        assert_eq!(
+            time_utilities
+                .remaining_length_of_period(&MockTimeProvider {})
+                .unwrap(),
+            TimeDelta::seconds(86399)
+        );
+        assert_eq!(
+            time_utilities
+                .length_of_period(&MockTimeProvider {})
+                .unwrap(),
+            TimeDelta::seconds(14 * 86400 - 1)
+        );
+    }
 }
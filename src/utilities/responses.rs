@@ -47,3 +47,13 @@ pub fn generate_response<T: IntoResponse>(
         ResponseFormat::Html => (status, Html::from(content)).into_response(),
     }
 }
+
+/// The answer to JSON requests for pages whose JSON lives under `/api/v1`; the `Link` header
+/// points clients at the API's OpenAPI document.
+pub fn api_only() -> Response {
+    (
+        StatusCode::NOT_ACCEPTABLE,
+        [("link", "</api/v1/openapi.json>; rel=\"service-desc\"")],
+    )
+        .into_response()
+}